@@ -0,0 +1,209 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use std::collections::HashMap;
+use std::env;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::room::JoinError;
+use crate::room_config::RoomConfig;
+
+/// Hash a room password for storage in [`RoomConfig::password_hash`]
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash password: {}", e))
+}
+
+/// Check a password against a PHC-format hash. The comparison runs in constant time.
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    let parsed = match PasswordHash::new(hash) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            warn!("Ignoring malformed room password hash: {}", e);
+            return false;
+        }
+    };
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok()
+}
+
+/// How many failed password attempts are tolerated before joins are refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    /// Failures allowed within `window` before locking out
+    pub max_failures: u32,
+    /// Period over which failures are counted
+    pub window: Duration,
+    /// How long joins are refused once locked out
+    pub lockout: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            window: Duration::from_secs(5 * 60),
+            lockout: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl LockoutPolicy {
+    /// Load the policy from `JOIN_LOCKOUT_MAX_FAILURES`, `JOIN_LOCKOUT_WINDOW` and
+    /// `JOIN_LOCKOUT_DURATION` (both in seconds)
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+            env::var(name).ok().and_then(|value| value.parse().ok())
+        }
+
+        let defaults = Self::default();
+        Self {
+            max_failures: parse("JOIN_LOCKOUT_MAX_FAILURES")
+                .filter(|failures| *failures > 0)
+                .unwrap_or(defaults.max_failures),
+            window: parse("JOIN_LOCKOUT_WINDOW")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.window),
+            lockout: parse("JOIN_LOCKOUT_DURATION")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.lockout),
+        }
+    }
+}
+
+/// Who a failed attempt is attributed to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Subject {
+    User(u32),
+    Ip(IpAddr),
+}
+
+#[derive(Debug)]
+struct FailureRecord {
+    failures: u32,
+    first_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// Checks room passwords and locks out users and addresses that keep guessing.
+///
+/// Failures are tracked per room, separately for the user and the client IP, so
+/// rotating accounts from one address is locked out as well.
+#[derive(Debug, Default)]
+pub struct RoomAccessGuard {
+    policy: LockoutPolicy,
+    failures: Mutex<HashMap<(String, Subject), FailureRecord>>,
+}
+
+impl RoomAccessGuard {
+    pub fn new(policy: LockoutPolicy) -> Self {
+        Self {
+            policy,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn policy(&self) -> LockoutPolicy {
+        self.policy
+    }
+
+    /// Decide whether `user_id` may enter a room configured with `config`
+    pub async fn check(
+        &self,
+        room_name: &str,
+        config: &RoomConfig,
+        user_id: u32,
+        password: Option<&str>,
+        client_ip: Option<IpAddr>,
+    ) -> Result<(), JoinError> {
+        let Some(hash) = config.password_hash.clone() else {
+            return Ok(());
+        };
+
+        let subjects = subjects(user_id, client_ip);
+        if let Some(retry_after) = self.locked_for(room_name, &subjects) {
+            return Err(JoinError::LockedOut { retry_after });
+        }
+
+        let Some(password) = password.map(str::to_string) else {
+            return Err(JoinError::PasswordRequired);
+        };
+
+        // Argon2 is deliberately slow; keep it off the async workers
+        let valid = tokio::task::spawn_blocking(move || verify_password(&hash, &password))
+            .await
+            .unwrap_or(false);
+
+        if valid {
+            self.clear(room_name, user_id);
+            Ok(())
+        } else {
+            warn!(
+                "Invalid password for room {} from user {} ({:?})",
+                room_name, user_id, client_ip
+            );
+            self.record_failure(room_name, &subjects);
+            Err(JoinError::InvalidPassword)
+        }
+    }
+
+    fn locked_for(&self, room_name: &str, subjects: &[Subject]) -> Option<Duration> {
+        let now = Instant::now();
+        let failures = self.failures.lock().unwrap();
+        subjects
+            .iter()
+            .filter_map(|subject| failures.get(&(room_name.to_string(), subject.clone())))
+            .filter_map(|record| record.locked_until)
+            .filter(|until| *until > now)
+            .max()
+            .map(|until| until - now)
+    }
+
+    fn record_failure(&self, room_name: &str, subjects: &[Subject]) {
+        let now = Instant::now();
+        let mut failures = self.failures.lock().unwrap();
+
+        // Forget failures that no longer count towards a lockout
+        failures.retain(|_, record| match record.locked_until {
+            Some(until) => until > now,
+            None => now.duration_since(record.first_failure) < self.policy.window,
+        });
+
+        for subject in subjects {
+            let record = failures
+                .entry((room_name.to_string(), subject.clone()))
+                .or_insert(FailureRecord {
+                    failures: 0,
+                    first_failure: now,
+                    locked_until: None,
+                });
+            record.failures += 1;
+            if record.failures >= self.policy.max_failures {
+                warn!("Locking out {:?} from room {}", subject, room_name);
+                record.locked_until = Some(now + self.policy.lockout);
+            }
+        }
+    }
+
+    /// Forget the user's failures. The address keeps its record, so one known
+    /// password cannot reset the guessing of every account behind it.
+    fn clear(&self, room_name: &str, user_id: u32) {
+        self.failures
+            .lock()
+            .unwrap()
+            .remove(&(room_name.to_string(), Subject::User(user_id)));
+    }
+}
+
+fn subjects(user_id: u32, client_ip: Option<IpAddr>) -> Vec<Subject> {
+    let mut subjects = vec![Subject::User(user_id)];
+    subjects.extend(client_ip.map(Subject::Ip));
+    subjects
+}
@@ -0,0 +1,418 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use tracing::warn;
+
+/// Rooms whose timelines are kept in memory before the least recently active is dropped
+pub const MAX_TIMELINE_ROOMS: usize = 1024;
+
+/// Room files the writer keeps open before closing the least recently written
+pub const MAX_OPEN_FILES: usize = 256;
+
+/// Signaling message relayed between participants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignalKind {
+    Offer,
+    Answer,
+    IceCandidate,
+}
+
+impl SignalKind {
+    /// Type of the client message carrying the signal
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignalKind::Offer => "offer",
+            SignalKind::Answer => "answer",
+            SignalKind::IceCandidate => "ice-candidate",
+        }
+    }
+}
+
+/// Something that happened in a room, as recorded in the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AuditEvent {
+    #[serde(rename = "joined")]
+    Joined {
+        #[serde(rename = "userId")]
+        user_id: u32,
+        username: String,
+    },
+    #[serde(rename = "left")]
+    Left {
+        #[serde(rename = "userId")]
+        user_id: u32,
+    },
+    /// An offer, answer or candidate was relayed; `to_user_id` is unset for broadcasts
+    #[serde(rename = "signal")]
+    Signal {
+        kind: SignalKind,
+        #[serde(rename = "fromUserId")]
+        from_user_id: u32,
+        #[serde(rename = "toUserId", default, skip_serializing_if = "Option::is_none")]
+        to_user_id: Option<u32>,
+        /// Bytes of SDP or candidate text
+        size: usize,
+        /// The SDP or candidate itself, only kept when `AUDIT_LOG_INCLUDE_SDP` is set
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        body: Option<String>,
+    },
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "roomName")]
+    pub room_name: String,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// Where room events are recorded and how much is kept
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// Directory holding one JSONL file per room; nothing is written when unset
+    pub dir: Option<PathBuf>,
+    /// Size at which a room's file is rotated
+    pub max_file_bytes: u64,
+    /// Files kept per room, counting the one being written
+    pub max_files: usize,
+    /// Record SDP and candidate bodies, not just their size
+    pub include_sdp: bool,
+    /// Events per room kept in memory for [`AuditLog::timeline`]
+    pub timeline_capacity: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+            include_sdp: false,
+            timeline_capacity: 500,
+        }
+    }
+}
+
+impl AuditConfig {
+    /// Load audit configuration from the environment.
+    ///
+    /// `AUDIT_LOG_DIR` enables the log, `AUDIT_LOG_MAX_BYTES` and `AUDIT_LOG_MAX_FILES`
+    /// control rotation, `AUDIT_LOG_INCLUDE_SDP` records message bodies and
+    /// `AUDIT_TIMELINE_CAPACITY` bounds the in-memory timeline of each room.
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+            env::var(name).ok().and_then(|value| value.parse().ok())
+        }
+
+        let defaults = Self::default();
+        Self {
+            dir: env::var("AUDIT_LOG_DIR")
+                .ok()
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+            max_file_bytes: parse("AUDIT_LOG_MAX_BYTES")
+                .filter(|bytes| *bytes > 0)
+                .unwrap_or(defaults.max_file_bytes),
+            max_files: parse("AUDIT_LOG_MAX_FILES")
+                .filter(|files| *files > 0)
+                .unwrap_or(defaults.max_files),
+            include_sdp: parse("AUDIT_LOG_INCLUDE_SDP").unwrap_or(defaults.include_sdp),
+            timeline_capacity: parse("AUDIT_TIMELINE_CAPACITY")
+                .unwrap_or(defaults.timeline_capacity),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+}
+
+/// File name for a room's log; characters outside `[A-Za-z0-9._-]`, such as the
+/// slash in breakout room names, become underscores
+pub fn log_file_name(room_name: &str) -> String {
+    let stem: String = room_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.jsonl", stem)
+}
+
+/// Recent events of one room
+#[derive(Debug, Default)]
+struct Timeline {
+    events: VecDeque<AuditRecord>,
+    /// Value of the recorder's counter when the room last had an event
+    last_active: u64,
+}
+
+#[derive(Debug, Default)]
+struct Recorder {
+    timelines: HashMap<String, Timeline>,
+    counter: u64,
+}
+
+enum WriterCommand {
+    Append(AuditRecord),
+    /// Answered once everything sent before it is written
+    Flush(mpsc::Sender<()>),
+}
+
+#[derive(Debug)]
+struct AuditLogInner {
+    config: AuditConfig,
+    recorder: Mutex<Recorder>,
+    /// Feeds the writer thread, which owns the files; unset without a directory
+    writer: Option<mpsc::Sender<WriterCommand>>,
+}
+
+/// Records joins, leaves and signaling metadata per room to rotating JSONL files
+/// and keeps each room's recent events for [`AuditLog::timeline`].
+/// Cheap to clone; every clone writes to the same files.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    inner: Arc<AuditLogInner>,
+}
+
+impl AuditLog {
+    /// Create the log directory, if any, and start recording
+    pub fn open(config: AuditConfig) -> Result<Self, String> {
+        let writer = match &config.dir {
+            Some(dir) => {
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+                let (tx, rx) = mpsc::channel();
+                let writer = AuditWriter {
+                    dir: dir.clone(),
+                    max_file_bytes: config.max_file_bytes,
+                    max_files: config.max_files,
+                    files: HashMap::new(),
+                    counter: 0,
+                };
+                thread::Builder::new()
+                    .name("audit-writer".to_string())
+                    .spawn(move || writer.run(rx))
+                    .map_err(|e| format!("Failed to start the audit writer: {}", e))?;
+                Some(tx)
+            }
+            None => None,
+        };
+        Ok(Self {
+            inner: Arc::new(AuditLogInner {
+                config,
+                recorder: Mutex::new(Recorder::default()),
+                writer,
+            }),
+        })
+    }
+
+    /// Record a relayed offer, answer or candidate, keeping its body only if configured
+    pub fn record_signal(
+        &self,
+        room_name: &str,
+        kind: SignalKind,
+        from_user_id: u32,
+        to_user_id: Option<u32>,
+        body: &str,
+    ) {
+        self.record(
+            room_name,
+            AuditEvent::Signal {
+                kind,
+                from_user_id,
+                to_user_id,
+                size: body.len(),
+                body: self.inner.config.include_sdp.then(|| body.to_string()),
+            },
+        );
+    }
+
+    pub fn record(&self, room_name: &str, event: AuditEvent) {
+        let record = AuditRecord {
+            timestamp: Utc::now(),
+            room_name: room_name.to_string(),
+            event,
+        };
+        let config = &self.inner.config;
+        // Held while handing the record over, so the file matches the timeline's order
+        let mut recorder = self.inner.recorder.lock().unwrap();
+
+        if let Some(writer) = &self.inner.writer {
+            let _ = writer.send(WriterCommand::Append(record.clone()));
+        }
+        if config.timeline_capacity == 0 {
+            return;
+        }
+
+        recorder.counter += 1;
+        let counter = recorder.counter;
+        if !recorder.timelines.contains_key(room_name)
+            && recorder.timelines.len() >= MAX_TIMELINE_ROOMS
+        {
+            let stalest = recorder
+                .timelines
+                .iter()
+                .min_by_key(|(_, timeline)| timeline.last_active)
+                .map(|(name, _)| name.clone());
+            if let Some(stalest) = stalest {
+                recorder.timelines.remove(&stalest);
+            }
+        }
+        let timeline = recorder.timelines.entry(room_name.to_string()).or_default();
+        timeline.last_active = counter;
+        timeline.events.push_back(record);
+        while timeline.events.len() > config.timeline_capacity {
+            timeline.events.pop_front();
+        }
+    }
+
+    /// Wait until every event recorded so far is in the room files
+    pub fn flush(&self) {
+        let Some(writer) = &self.inner.writer else {
+            return;
+        };
+        let (tx, rx) = mpsc::channel();
+        if writer.send(WriterCommand::Flush(tx)).is_ok() {
+            let _ = rx.recv();
+        }
+    }
+
+    /// The room's most recent events, oldest first, at most `limit` of them
+    pub fn timeline(&self, room_name: &str, limit: usize) -> Vec<AuditRecord> {
+        let recorder = self.inner.recorder.lock().unwrap();
+        let Some(timeline) = recorder.timelines.get(room_name) else {
+            return Vec::new();
+        };
+        let skip = timeline.events.len().saturating_sub(limit);
+        timeline.events.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// A room file held open by the writer
+struct OpenLog {
+    file: fs::File,
+    size: u64,
+    /// Value of the writer's counter when the file was last written
+    last_used: u64,
+}
+
+/// Appends records to their room files on its own thread, keeping the files open
+/// and rotating them as they fill
+struct AuditWriter {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    max_files: usize,
+    files: HashMap<String, OpenLog>,
+    counter: u64,
+}
+
+impl AuditWriter {
+    /// Write until every [`AuditLog`] clone is dropped
+    fn run(mut self, commands: mpsc::Receiver<WriterCommand>) {
+        for command in commands {
+            match command {
+                WriterCommand::Append(record) => {
+                    if let Err(e) = self.append(&record) {
+                        warn!(
+                            "Failed to write audit log for room {}: {}",
+                            record.room_name, e
+                        );
+                    }
+                }
+                WriterCommand::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+
+    /// Append `record` to its room's file, rotating first if the line would overflow it
+    fn append(&mut self, record: &AuditRecord) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+        line.push('\n');
+
+        let name = log_file_name(&record.room_name);
+        let size = match self.files.get(&name) {
+            Some(open) => open.size,
+            None => fs::metadata(self.dir.join(&name)).map_or(0, |metadata| metadata.len()),
+        };
+        if size > 0 && size + line.len() as u64 > self.max_file_bytes {
+            self.files.remove(&name);
+            rotate(&self.dir, &name, self.max_files)?;
+        }
+
+        let open = self.file(name)?;
+        open.file.write_all(line.as_bytes())?;
+        open.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// The room's open file, opening it and closing the stalest if too many are open
+    fn file(&mut self, name: String) -> std::io::Result<&mut OpenLog> {
+        self.counter += 1;
+        if !self.files.contains_key(&name) && self.files.len() >= MAX_OPEN_FILES {
+            let stalest = self
+                .files
+                .iter()
+                .min_by_key(|(_, open)| open.last_used)
+                .map(|(name, _)| name.clone());
+            if let Some(stalest) = stalest {
+                self.files.remove(&stalest);
+            }
+        }
+        let open = match self.files.entry(name) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.dir.join(entry.key()))?;
+                let size = file.metadata()?.len();
+                entry.insert(OpenLog {
+                    file,
+                    size,
+                    last_used: 0,
+                })
+            }
+        };
+        open.last_used = self.counter;
+        Ok(open)
+    }
+}
+
+/// Shift `room.jsonl` to `room.jsonl.1`, `room.jsonl.1` to `room.jsonl.2` and so on,
+/// dropping the oldest so at most `max_files` remain
+fn rotate(dir: &Path, name: &str, max_files: usize) -> std::io::Result<()> {
+    let rotated = |index: usize| dir.join(format!("{}.{}", name, index));
+    if max_files <= 1 {
+        return fs::remove_file(dir.join(name));
+    }
+
+    let oldest = rotated(max_files - 1);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for index in (1..max_files - 1).rev() {
+        let from = rotated(index);
+        if from.exists() {
+            fs::rename(from, rotated(index + 1))?;
+        }
+    }
+    fs::rename(dir.join(name), rotated(1))
+}
@@ -1,7 +1,12 @@
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 use tracing::{debug, error};
 
+use crate::moderation::RoomRole;
+use crate::tenant::validate_tenant_id;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: u32, // subject (user ID as number)
@@ -10,10 +15,48 @@ pub struct Claims {
     pub exp: usize, // expiration
 }
 
+/// Optional claims granting the bearer roles in specific rooms
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenGrants {
+    /// Room name to role; the key `*` applies to every room
+    #[serde(default)]
+    pub room_roles: HashMap<String, RoomRole>,
+    /// Longest session, in seconds, of the rooms the bearer opens
+    #[serde(default)]
+    pub max_duration_secs: Option<u64>,
+}
+
+impl TokenGrants {
+    pub fn role_for(&self, room_name: &str) -> RoomRole {
+        self.room_roles
+            .get(room_name)
+            .or_else(|| self.room_roles.get("*"))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn max_duration(&self) -> Option<Duration> {
+        self.max_duration_secs.map(Duration::from_secs)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenClaims {
+    #[serde(flatten)]
+    claims: Claims,
+    #[serde(flatten)]
+    grants: TokenGrants,
+    /// Namespace for every room the bearer uses
+    #[serde(default)]
+    tenant: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct AuthenticatedUser {
     pub user_id: u32,
     pub username: String,
+    /// Tenant from the token's `tenant` claim; the user only sees that tenant's rooms
+    pub tenant: Option<String>,
 }
 
 pub struct JwtValidator {
@@ -34,17 +77,36 @@ impl JwtValidator {
     }
 
     pub fn validate_token(&self, token: &str) -> Result<AuthenticatedUser, String> {
+        self.validate_token_with_grants(token).map(|(user, _)| user)
+    }
+
+    /// Validate a token, also returning the room roles it grants
+    pub fn validate_token_with_grants(
+        &self,
+        token: &str,
+    ) -> Result<(AuthenticatedUser, TokenGrants), String> {
         debug!("Validating JWT token");
 
-        match decode::<Claims>(token, &self.secret, &self.validation) {
+        match decode::<TokenClaims>(token, &self.secret, &self.validation) {
             Ok(token_data) => {
-                let claims = token_data.claims;
+                let TokenClaims {
+                    claims,
+                    grants,
+                    tenant,
+                } = token_data.claims;
+                if let Some(tenant) = &tenant {
+                    validate_tenant_id(tenant).map_err(|e| format!("Invalid token: {}", e))?;
+                }
                 debug!("Token validated for user: {}", claims.username);
 
-                Ok(AuthenticatedUser {
-                    user_id: claims.sub,
-                    username: claims.username,
-                })
+                Ok((
+                    AuthenticatedUser {
+                        user_id: claims.sub,
+                        username: claims.username,
+                        tenant,
+                    },
+                    grants,
+                ))
             }
             Err(e) => {
                 error!("JWT validation failed: {}", e);
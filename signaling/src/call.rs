@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::messages::{CallEndReason, Participant, ServerMessage};
+use crate::room::RoomParticipant;
+use crate::tenant::TENANT_SEPARATOR;
+
+/// How long an invitation rings when `CALL_RING_TIMEOUT_SECS` is unset
+pub const DEFAULT_RING_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long the room of an accepted call waits for its first participant
+pub const CALL_ROOM_TTL: Duration = Duration::from_secs(60);
+
+/// Settings for calls placed directly between users
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallConfig {
+    /// Unanswered invitations end after this long
+    pub ring_timeout: Duration,
+}
+
+impl Default for CallConfig {
+    fn default() -> Self {
+        Self {
+            ring_timeout: DEFAULT_RING_TIMEOUT,
+        }
+    }
+}
+
+impl CallConfig {
+    /// Load the configuration from `CALL_RING_TIMEOUT_SECS`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            ring_timeout: env::var("CALL_RING_TIMEOUT_SECS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|secs| *secs > 0)
+                .map_or(defaults.ring_timeout, Duration::from_secs),
+        }
+    }
+}
+
+/// Identifies a user across tenants, as in `acme::42`
+pub fn user_key(tenant: Option<&str>, user_id: u32) -> String {
+    qualify(tenant, user_id)
+}
+
+fn qualify(tenant: Option<&str>, name: impl fmt::Display) -> String {
+    match tenant {
+        Some(tenant) => format!("{}{}{}", tenant, TENANT_SEPARATOR, name),
+        None => name.to_string(),
+    }
+}
+
+/// Every authenticated connection on this server, so that events addressed to a
+/// user reach each of their devices
+#[derive(Debug, Clone, Default)]
+pub struct UserConnections {
+    connections: Arc<RwLock<HashMap<Uuid, RoomParticipant>>>,
+}
+
+impl UserConnections {
+    pub async fn add(&self, participant: RoomParticipant) {
+        self.connections
+            .write()
+            .await
+            .insert(participant.connection_id, participant);
+    }
+
+    pub async fn remove(&self, connection_id: Uuid) -> Option<RoomParticipant> {
+        self.connections.write().await.remove(&connection_id)
+    }
+
+    pub async fn get(&self, connection_id: Uuid) -> Option<RoomParticipant> {
+        self.connections.read().await.get(&connection_id).cloned()
+    }
+
+    pub async fn is_online(&self, tenant: Option<&str>, user_id: u32) -> bool {
+        self.connections
+            .read()
+            .await
+            .values()
+            .any(|participant| is_user(participant, tenant, user_id))
+    }
+
+    /// Send `message` to every connection of the user, returning how many it reached
+    pub async fn send(&self, tenant: Option<&str>, user_id: u32, message: &ServerMessage) -> usize {
+        let Ok(json) = serde_json::to_string(message) else {
+            return 0;
+        };
+        let connections = self.connections.read().await;
+        let mut delivered = 0;
+        for participant in connections
+            .values()
+            .filter(|participant| is_user(participant, tenant, user_id))
+        {
+            match participant.sender.try_send(Message::Text(json.clone())) {
+                Ok(()) => delivered += 1,
+                Err(e) => warn!("Failed to send message to user {}: {}", user_id, e),
+            }
+        }
+        delivered
+    }
+}
+
+fn is_user(participant: &RoomParticipant, tenant: Option<&str>, user_id: u32) -> bool {
+    participant.user.user_id == user_id && participant.user.tenant.as_deref() == tenant
+}
+
+/// An unanswered call from one user to another in the same tenant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallInvitation {
+    pub call_id: Uuid,
+    pub caller: Participant,
+    pub callee_id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+impl CallInvitation {
+    pub fn new(caller: &AuthenticatedUser, callee_id: u32) -> Self {
+        Self {
+            call_id: Uuid::new_v4(),
+            caller: Participant {
+                user_id: caller.user_id,
+                username: caller.username.clone(),
+            },
+            callee_id,
+            tenant: caller.tenant.clone(),
+        }
+    }
+
+    /// Private room the two users meet in once the call is accepted
+    pub fn room_name(&self) -> String {
+        qualify(
+            self.tenant.as_deref(),
+            format_args!("call-{}", self.call_id),
+        )
+    }
+
+    /// Event ringing the callee
+    pub fn incoming(&self) -> ServerMessage {
+        ServerMessage::IncomingCall {
+            call_id: self.call_id,
+            from: self.caller.clone(),
+        }
+    }
+
+    /// The caller and the callee
+    pub fn user_ids(&self) -> [u32; 2] {
+        [self.caller.user_id, self.callee_id]
+    }
+
+    /// Check that `user` may give `response`: the callee accepts or declines and
+    /// the caller cancels. Anyone else is told there is no such call.
+    pub fn check_response(
+        &self,
+        user: &AuthenticatedUser,
+        response: CallResponse,
+    ) -> Result<(), CallError> {
+        let answering = match response {
+            CallResponse::Accept | CallResponse::Decline => self.callee_id,
+            CallResponse::Cancel => self.caller.user_id,
+        };
+        if user.user_id == answering && user.tenant == self.tenant {
+            Ok(())
+        } else {
+            Err(CallError::NotFound)
+        }
+    }
+
+    /// Event telling both users how the call was answered
+    pub fn outcome(&self, response: CallResponse) -> ServerMessage {
+        let reason = match response {
+            CallResponse::Accept => {
+                return ServerMessage::CallAccepted {
+                    call_id: self.call_id,
+                    room_name: self.room_name(),
+                    by_user_id: self.callee_id,
+                }
+            }
+            CallResponse::Decline => CallEndReason::Declined,
+            CallResponse::Cancel => CallEndReason::Cancelled,
+        };
+        self.ended(reason)
+    }
+
+    pub fn ended(&self, reason: CallEndReason) -> ServerMessage {
+        ServerMessage::CallEnded {
+            call_id: self.call_id,
+            reason,
+        }
+    }
+}
+
+/// How a ringing call was answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallResponse {
+    Accept,
+    Decline,
+    /// The caller hung up before the callee answered
+    Cancel,
+}
+
+/// Why a call could not be placed or answered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// The callee has no open connection
+    UserOffline,
+    /// The call was already answered, timed out, or is not the user's to answer
+    NotFound,
+    /// The caller rang themselves
+    SelfCall,
+    Failed(String),
+}
+
+impl CallError {
+    /// Error code sent to the client alongside the message
+    pub fn code(&self) -> u32 {
+        match self {
+            CallError::UserOffline => 404,
+            CallError::NotFound => 404,
+            CallError::SelfCall => 400,
+            CallError::Failed(_) => 500,
+        }
+    }
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::UserOffline => write!(f, "User is not online"),
+            CallError::NotFound => write!(f, "No such call"),
+            CallError::SelfCall => write!(f, "You cannot call yourself"),
+            CallError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
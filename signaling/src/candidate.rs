@@ -0,0 +1,347 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::sdp::SessionDescription;
+use crate::tenant::tenant_of;
+
+/// ICE candidate type (RFC 8445 section 5.1.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateType {
+    Host,
+    ServerReflexive,
+    PeerReflexive,
+    Relay,
+}
+
+/// The parts of an ICE candidate attribute the filtering policy looks at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub foundation: String,
+    pub component: u32,
+    pub transport: String,
+    pub priority: u32,
+    /// IP address or mDNS hostname
+    pub address: String,
+    pub port: u16,
+    pub candidate_type: CandidateType,
+}
+
+impl Candidate {
+    /// Parse `candidate:<foundation> <component> <transport> <priority> <address> <port> typ <type> ...`,
+    /// with or without the `a=` prefix used inside SDP
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.strip_prefix("a=").unwrap_or(value);
+        let mut parts = value.strip_prefix("candidate:")?.split_whitespace();
+
+        let foundation = parts.next()?.to_string();
+        let component = parts.next()?.parse().ok()?;
+        let transport = parts.next()?.to_string();
+        let priority = parts.next()?.parse().ok()?;
+        let address = parts.next()?.to_string();
+        let port = parts.next()?.parse().ok()?;
+        if parts.next()? != "typ" {
+            return None;
+        }
+        let candidate_type = match parts.next()? {
+            "host" => CandidateType::Host,
+            "srflx" => CandidateType::ServerReflexive,
+            "prflx" => CandidateType::PeerReflexive,
+            "relay" => CandidateType::Relay,
+            _ => return None,
+        };
+
+        Some(Self {
+            foundation,
+            component,
+            transport,
+            priority,
+            address,
+            port,
+            candidate_type,
+        })
+    }
+
+    /// Whether the address is an mDNS name that hides the host's IP
+    pub fn is_mdns(&self) -> bool {
+        self.address.ends_with(".local")
+    }
+
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.address.parse().ok()
+    }
+}
+
+/// An IPv4 or IPv6 network such as `10.0.0.0/8`, written as a string in configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, address: &IpAddr) -> bool {
+        match (self.network, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(*address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(*address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (network, prefix_len) = match value.split_once('/') {
+            Some((network, prefix_len)) => (network, Some(prefix_len)),
+            None => (value, None),
+        };
+        let network: IpAddr = network
+            .parse()
+            .map_err(|_| format!("Invalid network address in CIDR: {}", value))?;
+        let max_prefix_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_prefix_len)
+                .ok_or_else(|| format!("Invalid prefix length in CIDR: {}", value))?,
+            None => max_prefix_len,
+        };
+
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl TryFrom<String> for Cidr {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Cidr> for String {
+    fn from(cidr: Cidr) -> Self {
+        cidr.to_string()
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// Why a candidate was not relayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterReason {
+    /// The candidate could not be parsed
+    Malformed,
+    /// The room only relays TURN candidates
+    NotRelay,
+    /// A host candidate exposing a raw IP address
+    HostAddress,
+    /// The address matches the deny list or is missing from the allow list
+    AddressBlocked,
+}
+
+/// Per-room rules for which ICE candidates may reach other participants
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CandidatePolicy {
+    /// Only relay candidates obtained from a TURN server
+    pub relay_only: bool,
+    /// Drop host candidates that carry an IP address rather than an mDNS name,
+    /// and blank the related address of reflexive and relay candidates
+    pub drop_host_candidates: bool,
+    /// When non-empty, candidate addresses must fall inside one of these networks
+    pub allow_cidrs: Vec<Cidr>,
+    /// Candidate addresses inside these networks are dropped
+    pub deny_cidrs: Vec<Cidr>,
+}
+
+impl CandidatePolicy {
+    /// Whether the policy lets every candidate through unchanged
+    pub fn is_empty(&self) -> bool {
+        !self.relay_only
+            && !self.drop_host_candidates
+            && self.allow_cidrs.is_empty()
+            && self.deny_cidrs.is_empty()
+    }
+
+    /// Check a candidate attribute, returning the (possibly rewritten) value to relay.
+    ///
+    /// The empty end-of-candidates marker always passes.
+    pub fn apply(&self, value: &str) -> Result<String, FilterReason> {
+        if self.is_empty() || value.is_empty() {
+            return Ok(value.to_string());
+        }
+
+        let candidate = Candidate::parse(value).ok_or(FilterReason::Malformed)?;
+
+        if self.relay_only && candidate.candidate_type != CandidateType::Relay {
+            return Err(FilterReason::NotRelay);
+        }
+        if self.drop_host_candidates
+            && candidate.candidate_type == CandidateType::Host
+            && !candidate.is_mdns()
+        {
+            return Err(FilterReason::HostAddress);
+        }
+
+        match candidate.ip() {
+            Some(ip) => {
+                if self.deny_cidrs.iter().any(|cidr| cidr.contains(&ip)) {
+                    return Err(FilterReason::AddressBlocked);
+                }
+                if !self.allow_cidrs.is_empty()
+                    && !self.allow_cidrs.iter().any(|cidr| cidr.contains(&ip))
+                {
+                    return Err(FilterReason::AddressBlocked);
+                }
+            }
+            // mDNS names cannot be checked against an allow list
+            None if !self.allow_cidrs.is_empty() => return Err(FilterReason::AddressBlocked),
+            None => {}
+        }
+
+        if self.drop_host_candidates || self.relay_only {
+            Ok(hide_related_address(value))
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    /// Apply the policy to every `a=candidate` line in an SDP, recording drops in `stats`
+    pub fn filter_description(
+        &self,
+        description: &mut SessionDescription,
+        stats: &CandidateFilterStats,
+    ) {
+        if self.is_empty() {
+            return;
+        }
+        description.retain_candidates(|value| {
+            let result = self.apply(value);
+            stats.record(&result);
+            result.ok()
+        });
+    }
+}
+
+/// Replace `raddr`/`rport`, which reveal the host's local address, with placeholders
+fn hide_related_address(value: &str) -> String {
+    let mut parts: Vec<&str> = value.split(' ').collect();
+    for i in 0..parts.len().saturating_sub(1) {
+        match parts[i] {
+            "raddr" => parts[i + 1] = "0.0.0.0",
+            "rport" => parts[i + 1] = "0",
+            _ => {}
+        }
+    }
+    parts.join(" ")
+}
+
+/// Counters of candidates inspected and dropped by candidate policies
+#[derive(Debug, Default)]
+pub struct CandidateFilterStats {
+    inspected: AtomicU64,
+    malformed: AtomicU64,
+    not_relay: AtomicU64,
+    host_address: AtomicU64,
+    address_blocked: AtomicU64,
+}
+
+/// Point-in-time copy of [`CandidateFilterStats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CandidateFilterSnapshot {
+    pub inspected: u64,
+    pub malformed: u64,
+    pub not_relay: u64,
+    pub host_address: u64,
+    pub address_blocked: u64,
+}
+
+impl CandidateFilterSnapshot {
+    pub fn dropped(&self) -> u64 {
+        self.malformed + self.not_relay + self.host_address + self.address_blocked
+    }
+}
+
+impl CandidateFilterStats {
+    pub fn record<T>(&self, result: &Result<T, FilterReason>) {
+        self.inspected.fetch_add(1, Ordering::Relaxed);
+        let counter = match result {
+            Ok(_) => return,
+            Err(FilterReason::Malformed) => &self.malformed,
+            Err(FilterReason::NotRelay) => &self.not_relay,
+            Err(FilterReason::HostAddress) => &self.host_address,
+            Err(FilterReason::AddressBlocked) => &self.address_blocked,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CandidateFilterSnapshot {
+        CandidateFilterSnapshot {
+            inspected: self.inspected.load(Ordering::Relaxed),
+            malformed: self.malformed.load(Ordering::Relaxed),
+            not_relay: self.not_relay.load(Ordering::Relaxed),
+            host_address: self.host_address.load(Ordering::Relaxed),
+            address_blocked: self.address_blocked.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// [`CandidateFilterStats`] kept apart for each tenant, so one tenant's traffic
+/// never shows up in another's counters
+#[derive(Debug, Default)]
+pub struct TenantCandidateStats {
+    tenants: RwLock<HashMap<Option<String>, Arc<CandidateFilterStats>>>,
+}
+
+impl TenantCandidateStats {
+    /// Counters of the tenant owning `room_name`
+    pub fn for_room(&self, room_name: &str) -> Arc<CandidateFilterStats> {
+        let tenant = tenant_of(room_name);
+        if let Some(stats) = self
+            .tenants
+            .read()
+            .unwrap()
+            .get(&tenant.map(str::to_string))
+        {
+            return Arc::clone(stats);
+        }
+        let mut tenants = self.tenants.write().unwrap();
+        Arc::clone(tenants.entry(tenant.map(str::to_string)).or_default())
+    }
+
+    /// Counters of `tenant`, or of the rooms outside any tenant when `None`
+    pub fn snapshot(&self, tenant: Option<&str>) -> CandidateFilterSnapshot {
+        self.tenants
+            .read()
+            .unwrap()
+            .get(&tenant.map(str::to_string))
+            .map(|stats| stats.snapshot())
+            .unwrap_or_default()
+    }
+}
@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use futures_util::StreamExt;
 use redis::{AsyncCommands, Client as RedisClient};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -10,8 +10,530 @@ use tokio_tungstenite::tungstenite::Message;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::messages::{Participant, ServerMessage};
-use crate::room::{LocalRoomManager, RoomManagerTrait, RoomParticipant};
+use crate::access::{self, LockoutPolicy};
+use crate::audit::{AuditEvent, AuditLog, SignalKind};
+use crate::auth::AuthenticatedUser;
+use crate::call::{
+    user_key, CallError, CallInvitation, CallResponse, UserConnections, CALL_ROOM_TTL,
+};
+use crate::directory::RoomDirectory;
+use crate::mesh::{PeerPair, NEGOTIATION_TIMEOUT};
+use crate::messages::{
+    CallEndReason, DeliveryFailure, LeaveReason, Participant, RoomCloseReason, RoomSummary,
+    ServerMessage,
+};
+use crate::moderation::{
+    authorize, validate_breakouts, ModerationError, ModeratorAction, RoomRole,
+};
+use crate::room::{
+    breakout_room_name, close_replaced, DeliveryError, JoinError, JoinOutcome, JoinRequest,
+    LocalRoomManager, RoomManagerTrait, RoomOwnership, RoomParticipant, RECENTLY_DEPARTED,
+};
+use crate::room_config::{
+    HostTransferPolicy, MeshPolicy, OverflowPolicy, RejoinPolicy, RoomConfig, RoomConfigStore,
+    RoomVisibility,
+};
+use crate::room_state::{RoomStateEntry, RoomStateError, RoomStateMap, RoomStateUpdate, MAX_KEYS};
+use crate::schedule::{RoomDeadline, CLOSING_WARNINGS};
+use crate::tenant::{local_room_name, same_tenant, tenant_of};
+use crate::webhook::{WebhookDispatcher, WebhookEvent};
+
+/// Claims a slot in `rooms:{room}:participants` if the room has capacity, otherwise
+/// applies the overflow policy. Runs atomically so concurrent joins on different
+/// nodes cannot overfill a room.
+///
+/// KEYS: participants hash, spectators set, waitlist list
+/// ARGV: user id, node id, capacity (-1 for unlimited), overflow policy, waitlist entry
+const RESERVE_SLOT_SCRIPT: &str = r#"
+if redis.call('HEXISTS', KEYS[1], ARGV[1]) == 1 then
+    redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+    return {'joined', 0}
+end
+local capacity = tonumber(ARGV[3])
+local active = redis.call('HLEN', KEYS[1]) - redis.call('SCARD', KEYS[2])
+if capacity < 0 or active < capacity then
+    redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+    return {'joined', 0}
+end
+if ARGV[4] == 'spectator' then
+    redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+    redis.call('SADD', KEYS[2], ARGV[1])
+    return {'spectator', 0}
+end
+if ARGV[4] == 'waitlist' then
+    return {'waitlisted', redis.call('RPUSH', KEYS[3], ARGV[5])}
+end
+return {'full', capacity}
+"#;
+
+/// Removes a user from a room if they are still connected through `node id`, so
+/// that a rejoin through another server is left alone. Returns 1 if removed.
+///
+/// KEYS: participants hash, spectators set, roles hash
+/// ARGV: user id, node id
+const REAP_PARTICIPANT_SCRIPT: &str = r#"
+if redis.call('HGET', KEYS[1], ARGV[1]) ~= ARGV[2] then
+    return 0
+end
+redis.call('HDEL', KEYS[1], ARGV[1])
+redis.call('SREM', KEYS[2], ARGV[1])
+redis.call('HDEL', KEYS[3], ARGV[1])
+return 1
+"#;
+
+/// Moves waitlist entries into the participants hash while the room has capacity,
+/// returning the admitted entries.
+///
+/// KEYS: participants hash, spectators set, waitlist list
+/// ARGV: capacity (-1 for unlimited)
+const ADMIT_WAITLIST_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local admitted = {}
+while capacity < 0 or redis.call('HLEN', KEYS[1]) - redis.call('SCARD', KEYS[2]) < capacity do
+    local entry = redis.call('LPOP', KEYS[3])
+    if not entry then
+        break
+    end
+    local decoded = cjson.decode(entry)
+    redis.call('HSET', KEYS[1], tostring(decoded.userId), decoded.nodeId)
+    table.insert(admitted, entry)
+end
+return admitted
+"#;
+
+/// Records a joining user in `rooms:{room}:joined` and makes them host if they claim
+/// the room, or if the role is vacant and the policy lets them take it. Returns
+/// whether the host changed and the previous host ('' for none).
+///
+/// KEYS: host hash (`owner`, `host`), joined sorted set
+/// ARGV: user id, join time in ms, claims host ('1' or '0'), transfer policy
+const SETTLE_HOST_SCRIPT: &str = r#"
+local user = ARGV[1]
+redis.call('ZADD', KEYS[2], 'NX', ARGV[2], user)
+if ARGV[3] == '1' then
+    redis.call('HSET', KEYS[1], 'owner', user)
+end
+local owner = redis.call('HGET', KEYS[1], 'owner')
+if not owner then
+    redis.call('HSET', KEYS[1], 'owner', user)
+    owner = user
+end
+local host = redis.call('HGET', KEYS[1], 'host')
+if host == user then
+    return {0, ''}
+end
+local takes_vacancy = not host and (owner == user or ARGV[4] == 'longest-present')
+if ARGV[3] ~= '1' and not takes_vacancy then
+    return {0, ''}
+end
+redis.call('HSET', KEYS[1], 'host', user)
+return {1, host or ''}
+"#;
+
+/// Forgets a departed user's join time and, if they were host, hands the role on
+/// according to the policy. Returns whether the host changed and the new host
+/// ('' for none).
+///
+/// KEYS: host hash, joined sorted set, participants hash, spectators set, roles hash
+/// ARGV: departed user id, transfer policy
+const REASSIGN_HOST_SCRIPT: &str = r#"
+redis.call('ZREM', KEYS[2], ARGV[1])
+if redis.call('HLEN', KEYS[3]) == 0 then
+    redis.call('DEL', KEYS[1], KEYS[2])
+    return {0, ''}
+end
+if redis.call('HGET', KEYS[1], 'host') ~= ARGV[1] then
+    return {0, ''}
+end
+local successor = ''
+if ARGV[2] == 'longest-present' then
+    local fallback = ''
+    for _, member in ipairs(redis.call('ZRANGE', KEYS[2], 0, -1)) do
+        local present = redis.call('HEXISTS', KEYS[3], member) == 1
+        if present and redis.call('HGET', KEYS[5], member) ~= 'spectator' then
+            if redis.call('SISMEMBER', KEYS[4], member) == 0 then
+                successor = member
+                break
+            end
+            if fallback == '' then
+                fallback = member
+            end
+        end
+    end
+    if successor == '' then
+        successor = fallback
+    end
+end
+if successor == '' then
+    redis.call('HDEL', KEYS[1], 'host')
+else
+    redis.call('HSET', KEYS[1], 'host', successor)
+end
+return {1, successor}
+"#;
+
+/// Counts a failed password attempt against each subject, starting the count's
+/// window on the first failure and holding the count for the lockout once it
+/// reaches the limit.
+///
+/// KEYS: `lockout:{room}:user:{id}` and, if known, `lockout:{room}:ip:{ip}`
+/// ARGV: max failures, window in ms, lockout in ms
+const RECORD_FAILURE_SCRIPT: &str = r#"
+local locked = 0
+for _, key in ipairs(KEYS) do
+    local failures = redis.call('INCR', key)
+    if failures == 1 then
+        redis.call('PEXPIRE', key, ARGV[2])
+    end
+    if failures == tonumber(ARGV[1]) then
+        redis.call('PEXPIRE', key, ARGV[3])
+        locked = 1
+    end
+end
+return locked
+"#;
+
+/// Applies a versioned change to one key of a room's shared state. Versions come
+/// from a per-room counter so they never repeat. Returns the outcome and either the
+/// new version or, on conflict, the key's current one.
+///
+/// KEYS: state hash (key to value JSON), versions hash (key to version), counter
+/// ARGV: key, expected version (-1 for unconditional), value JSON ('' to delete),
+/// key limit
+const UPDATE_STATE_SCRIPT: &str = r#"
+local current = tonumber(redis.call('HGET', KEYS[2], ARGV[1]) or '0')
+local expected = tonumber(ARGV[2])
+if expected >= 0 and expected ~= current then
+    return {'conflict', current}
+end
+if ARGV[3] == '' then
+    if current == 0 then
+        return {'missing', 0}
+    end
+    redis.call('HDEL', KEYS[1], ARGV[1])
+    redis.call('HDEL', KEYS[2], ARGV[1])
+    return {'ok', redis.call('INCR', KEYS[3])}
+end
+if current == 0 and redis.call('HLEN', KEYS[1]) >= tonumber(ARGV[4]) then
+    return {'full', 0}
+end
+local version = redis.call('INCR', KEYS[3])
+redis.call('HSET', KEYS[1], ARGV[1], ARGV[3])
+redis.call('HSET', KEYS[2], ARGV[1], version)
+return {'ok', version}
+"#;
+
+/// Hash of the rooms defined through [`RoomManagerTrait::create_room`], by name,
+/// holding each room's JSON configuration
+const ROOM_DEFINITIONS_KEY: &str = "rooms:definitions";
+
+/// Set of the rooms with someone in them, for the room directory
+const LIVE_ROOMS_KEY: &str = "rooms:live";
+
+/// Every Redis key holding state for `room_id`
+fn room_keys(room_id: &str) -> Vec<String> {
+    [
+        "participants",
+        "spectators",
+        "waitlist",
+        "roles",
+        "bans",
+        "locked",
+        "host",
+        "joined",
+        "lobby",
+        "breakouts",
+        "state",
+        "state_versions",
+        "state_version",
+        "invited",
+        "deadline",
+        "mesh",
+        "departed",
+    ]
+    .iter()
+    .map(|suffix| format!("rooms:{}:{}", room_id, suffix))
+    .collect()
+}
+
+fn host_transfer_policy_name(policy: HostTransferPolicy) -> &'static str {
+    match policy {
+        HostTransferPolicy::LongestPresent => "longest-present",
+        HostTransferPolicy::Vacant => "vacant",
+    }
+}
+
+/// A queued join stored in `rooms:{room}:waitlist`, or a knock stored in
+/// `rooms:{room}:lobby`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WaitlistEntry {
+    user_id: u32,
+    username: String,
+    node_id: String,
+    connection_id: Uuid,
+    /// Knocked with the spectator role, which the user keeps once admitted
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    spectator: bool,
+    /// Session limit from the user's token, applied if their admission opens the room
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_duration_secs: Option<u64>,
+}
+
+/// A user on this node waiting for a slot in a full room or to be admitted from a lobby
+#[derive(Debug, Clone)]
+struct WaitingParticipant {
+    room_id: String,
+    participant: RoomParticipant,
+    /// Serialized [`WaitlistEntry`], needed to remove it from the Redis list
+    entry: String,
+}
+
+type LocalWaitlist = Arc<RwLock<HashMap<u32, WaitingParticipant>>>;
+
+/// A planned pair in `rooms:{room}:mesh`, keyed by [`PeerPair::key`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MeshEntry {
+    #[serde(flatten)]
+    pair: PeerPair,
+    instructed_at: DateTime<Utc>,
+}
+
+/// Events telling each peer of `pair` to connect to the other
+fn mesh_instructions(room_id: &str, pair: &PeerPair) -> Vec<ClusterMessage> {
+    pair.instructions(room_id)
+        .into_iter()
+        .map(|(user_id, message)| ClusterMessage::RoomEvent {
+            room_id: room_id.to_string(),
+            message,
+            target_user: Some(user_id),
+            exclude_user: None,
+        })
+        .collect()
+}
+
+/// Room deadlines a node has a close timer for
+type ScheduledCloses = Arc<std::sync::Mutex<HashSet<(String, DateTime<Utc>)>>>;
+
+/// Warns and closes this node's share of a room as its deadline, shared through
+/// `rooms:{room}:deadline`, approaches. Every node holding participants of the room
+/// runs one, so each closes the connections it holds.
+#[derive(Clone)]
+struct RoomCloser {
+    redis_client: RedisClient,
+    node_id: String,
+    local_connections: Arc<RwLock<HashMap<u32, RoomParticipant>>>,
+    local_waitlist: LocalWaitlist,
+    local_lobby: LocalWaitlist,
+    room_configs: Arc<RoomConfigStore>,
+    webhooks: Option<WebhookDispatcher>,
+    audit: Option<AuditLog>,
+    /// Deadlines this node has a timer for
+    scheduled: ScheduledCloses,
+}
+
+impl RoomCloser {
+    /// Start a timer for the room's stored deadline, unless this node has one
+    async fn schedule(&self, conn: &mut redis::aio::MultiplexedConnection, room_id: &str) {
+        let stored: Option<String> = conn
+            .get(format!("rooms:{}:deadline", room_id))
+            .await
+            .unwrap_or(None);
+        let Some(deadline) =
+            stored.and_then(|stored| serde_json::from_str::<RoomDeadline>(&stored).ok())
+        else {
+            return;
+        };
+        if !self
+            .scheduled
+            .lock()
+            .unwrap()
+            .insert((room_id.to_string(), deadline.at))
+        {
+            return;
+        }
+        tokio::spawn(self.clone().run(room_id.to_string(), deadline));
+    }
+
+    async fn run(self, room_id: String, deadline: RoomDeadline) {
+        let closes_at = deadline.instant();
+        for warning in CLOSING_WARNINGS {
+            let Some(warn_at) = closes_at.checked_sub(warning) else {
+                continue;
+            };
+            if warn_at <= tokio::time::Instant::now() {
+                continue;
+            }
+            tokio::time::sleep_until(warn_at).await;
+            if !self.is_current(&room_id, deadline).await {
+                return self.finish(&room_id, deadline);
+            }
+            let closing = ServerMessage::RoomClosing {
+                room_name: room_id.clone(),
+                closes_in_seconds: warning.as_secs(),
+                reason: deadline.reason,
+            };
+            for participant in self.local_participants(&room_id).await {
+                send_to(&participant, &closing);
+            }
+        }
+
+        tokio::time::sleep_until(closes_at).await;
+        if self.is_current(&room_id, deadline).await {
+            self.close(&room_id, deadline.reason).await;
+        }
+        self.finish(&room_id, deadline);
+    }
+
+    fn finish(&self, room_id: &str, deadline: RoomDeadline) {
+        self.scheduled
+            .lock()
+            .unwrap()
+            .remove(&(room_id.to_string(), deadline.at));
+    }
+
+    /// Whether the room is still in the session the deadline was set for
+    async fn is_current(&self, room_id: &str, deadline: RoomDeadline) -> bool {
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return false;
+        };
+        let stored: Option<String> = conn
+            .get(format!("rooms:{}:deadline", room_id))
+            .await
+            .unwrap_or(None);
+        stored
+            .and_then(|stored| serde_json::from_str::<RoomDeadline>(&stored).ok())
+            .is_some_and(|stored| stored == deadline)
+    }
+
+    /// Participants of the room connected to this node
+    async fn local_participants(&self, room_id: &str) -> Vec<RoomParticipant> {
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return Vec::new();
+        };
+        let participants: HashMap<String, String> = conn
+            .hgetall(format!("rooms:{}:participants", room_id))
+            .await
+            .unwrap_or_default();
+        let connections = self.local_connections.read().await;
+        participants
+            .into_iter()
+            .filter(|(_, node_id)| *node_id == self.node_id)
+            .filter_map(|(user_id, _)| user_id.parse::<u32>().ok())
+            .filter_map(|user_id| connections.get(&user_id).cloned())
+            .collect()
+    }
+
+    /// Remove this node's participants and waiting users from the room, telling
+    /// them why it closed, and release the room once every node has done so
+    async fn close(&self, room_id: &str, reason: RoomCloseReason) {
+        let participants = self.local_participants(room_id).await;
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+        info!(
+            "Cluster: Closing room {} for {} local participants: {:?}",
+            room_id,
+            participants.len(),
+            reason
+        );
+        let closed = ServerMessage::RoomClosed {
+            room_name: room_id.to_string(),
+            reason,
+        };
+
+        let server_key = format!("servers:{}:connections", self.node_id);
+        for participant in participants {
+            let user_id = participant.user.user_id.to_string();
+            let _: Result<(), _> = redis::pipe()
+                .hdel(format!("rooms:{}:participants", room_id), &user_id)
+                .ignore()
+                .srem(format!("rooms:{}:spectators", room_id), &user_id)
+                .ignore()
+                .hdel(format!("rooms:{}:roles", room_id), &user_id)
+                .ignore()
+                .hdel(&server_key, &user_id)
+                .ignore()
+                .query_async(&mut conn)
+                .await;
+            record_departure(&mut conn, room_id, participant.user.user_id).await;
+            self.local_connections
+                .write()
+                .await
+                .remove(&participant.user.user_id);
+            ClusterRoomManager::report_left(
+                &self.redis_client,
+                &self.room_configs,
+                self.webhooks.as_ref(),
+                self.audit.as_ref(),
+                room_id,
+                participant.user.user_id,
+            )
+            .await;
+            send_to(&participant, &closed);
+        }
+
+        for waiting in [&self.local_waitlist, &self.local_lobby] {
+            let mut waiting = waiting.write().await;
+            let user_ids: Vec<u32> = waiting
+                .iter()
+                .filter(|(_, entry)| entry.room_id == room_id)
+                .map(|(user_id, _)| *user_id)
+                .collect();
+            for user_id in user_ids {
+                let Some(entry) = waiting.remove(&user_id) else {
+                    continue;
+                };
+                let _: Result<(), _> = redis::pipe()
+                    .lrem(format!("rooms:{}:waitlist", room_id), 1, &entry.entry)
+                    .ignore()
+                    .hdel(format!("rooms:{}:lobby", room_id), user_id.to_string())
+                    .ignore()
+                    .hdel(format!("rooms:{}:roles", room_id), user_id.to_string())
+                    .ignore()
+                    .query_async(&mut conn)
+                    .await;
+                send_to(&entry.participant, &closed);
+            }
+        }
+
+        let remaining: i64 = conn
+            .hlen(format!("rooms:{}:participants", room_id))
+            .await
+            .unwrap_or(1);
+        if remaining == 0 {
+            let _: Result<(), _> = conn.srem(LIVE_ROOMS_KEY, room_id).await;
+            ClusterRoomManager::release_empty_room(&mut conn, &self.room_configs, room_id).await;
+        }
+    }
+}
+
+fn send_to(participant: &RoomParticipant, message: &ServerMessage) {
+    if let Ok(json_message) = serde_json::to_string(message) {
+        let _ = participant.sender.try_send(Message::Text(json_message));
+    }
+}
+
+/// Remember in `rooms:{room}:departed` that the user left the room, keeping the
+/// last [`RECENTLY_DEPARTED`] users
+async fn record_departure(
+    conn: &mut redis::aio::MultiplexedConnection,
+    room_id: &str,
+    user_id: u32,
+) {
+    let departed_key = format!("rooms:{}:departed", room_id);
+    let user_id = user_id.to_string();
+    let _: Result<(), _> = redis::pipe()
+        .lrem(&departed_key, 0, &user_id)
+        .ignore()
+        .rpush(&departed_key, &user_id)
+        .ignore()
+        .ltrim(&departed_key, -(RECENTLY_DEPARTED as isize), -1)
+        .ignore()
+        .query_async(conn)
+        .await;
+}
 
 /// Represents connection information stored in Redis
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +560,8 @@ pub enum ClusterMessage {
         room_id: String,
         user_id: u32,
         target_server: Option<String>,
+        #[serde(default)]
+        reason: Option<LeaveReason>,
     },
     /// WebRTC signaling message - route to specific user
     WebRTCSignal {
@@ -46,6 +570,27 @@ pub enum ClusterMessage {
         to_user: u32,
         signal_type: String,
         signal_data: String,
+        /// Media stream identification of an ICE candidate
+        #[serde(default)]
+        sdp_mid: Option<String>,
+        /// Media line index of an ICE candidate
+        #[serde(default)]
+        sdp_mline_index: Option<u32>,
+        /// Server the recipient is registered on, which reports a failed delivery
+        #[serde(default)]
+        target_server: Option<String>,
+        /// Server holding the sender's connection, told about a failed delivery
+        #[serde(default)]
+        origin_server: Option<String>,
+    },
+    /// A routed signal did not reach its recipient - handled by the sender's server
+    SignalUndelivered {
+        room_id: String,
+        from_user: u32,
+        to_user: u32,
+        signal_type: String,
+        reason: DeliveryFailure,
+        target_server: String,
     },
     /// Server heartbeat for failure detection
     ServerHeartbeat {
@@ -53,6 +598,37 @@ pub enum ClusterMessage {
         timestamp: u64,
         connection_count: usize,
     },
+    /// A waitlisted or knocking user was given a slot - handled by the server holding their connection
+    WaitlistAdmitted {
+        room_id: String,
+        user_id: u32,
+        participants: Vec<Participant>,
+        target_server: String,
+        #[serde(default)]
+        spectator: bool,
+    },
+    /// A user admitted from the lobby found the room full and joined its waitlist
+    LobbyWaitlisted {
+        room_id: String,
+        user_id: u32,
+        position: usize,
+        target_server: String,
+    },
+    /// A user was removed by a moderator - handled by the server holding their connection
+    Removed {
+        room_id: String,
+        user_id: u32,
+        /// Event delivered to the removed user
+        message: ServerMessage,
+    },
+    /// Event for the room's members on every server, or only for `target_user`
+    RoomEvent {
+        room_id: String,
+        message: ServerMessage,
+        target_user: Option<u32>,
+        #[serde(default)]
+        exclude_user: Option<u32>,
+    },
     /// Request for room participants list
     ParticipantsRequest {
         room_id: String,
@@ -64,6 +640,26 @@ pub enum ClusterMessage {
         participants: Vec<Participant>,
         target_server: String,
     },
+    /// A room was defined, or its definition dropped when `config` is `None`
+    RoomDefined {
+        room_id: String,
+        config: Option<RoomConfig>,
+    },
+    /// Event for every connection of a user, on whichever servers they are connected
+    UserEvent {
+        tenant: Option<String>,
+        user_id: u32,
+        message: ServerMessage,
+    },
+    /// A rejoin took over the user's place in a room; the server holding the old
+    /// connection closes it
+    ConnectionReplaced {
+        node_id: String,
+        user_id: u32,
+        connection_id: Uuid,
+    },
+    /// A listed room changed; every server tells its directory subscribers
+    DirectoryUpdate { summary: RoomSummary },
 }
 
 /// Redis-based clustered room manager
@@ -76,6 +672,22 @@ pub struct ClusterRoomManager {
     node_id: String,
     /// Local connections (user_id -> connection info)
     local_connections: Arc<RwLock<HashMap<u32, RoomParticipant>>>,
+    /// Local users queued for a full room (user_id -> waiting participant)
+    local_waitlist: LocalWaitlist,
+    /// Local users waiting in a room's lobby (user_id -> knocking participant)
+    local_lobby: LocalWaitlist,
+    /// Every authenticated connection on this server, shared with the local manager
+    users: UserConnections,
+    /// Directory subscribers on this server, shared with the local manager
+    directory: RoomDirectory,
+    /// Per-room configuration, kept in step with the room definitions in Redis
+    room_configs: Arc<RoomConfigStore>,
+    /// Receives the membership changes this server makes
+    webhooks: Option<WebhookDispatcher>,
+    /// Records the membership changes this server makes
+    audit: Option<AuditLog>,
+    /// Room deadlines this server has a close timer for
+    scheduled_closes: ScheduledCloses,
     /// Health status
     redis_healthy: Arc<RwLock<bool>>,
 }
@@ -85,6 +697,15 @@ impl ClusterRoomManager {
     pub async fn new(
         redis_url: &str,
         node_id: String,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::with_room_configs(redis_url, node_id, Arc::new(RoomConfigStore::default())).await
+    }
+
+    /// Create a cluster room manager that applies the given per-room configuration
+    pub async fn with_room_configs(
+        redis_url: &str,
+        node_id: String,
+        room_configs: Arc<RoomConfigStore>,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let redis_client = RedisClient::open(redis_url)?;
 
@@ -93,11 +714,29 @@ impl ClusterRoomManager {
         let _: String = redis::cmd("PING").query_async(&mut conn).await?;
         info!("Successfully connected to Redis cluster coordinator");
 
+        // Rooms defined on other servers apply here too
+        let definitions: HashMap<String, String> = conn.hgetall(ROOM_DEFINITIONS_KEY).await?;
+        for (room_id, config) in definitions {
+            match serde_json::from_str::<RoomConfig>(&config) {
+                Ok(config) => room_configs.set(room_id, config).await,
+                Err(e) => warn!("Ignoring invalid definition of room {}: {}", room_id, e),
+            }
+        }
+
+        let local_manager = LocalRoomManager::with_room_configs(Arc::clone(&room_configs));
         let manager = Self {
-            local_manager: LocalRoomManager::new(),
+            users: local_manager.users(),
+            directory: local_manager.directory(),
+            local_manager,
             redis_client,
             node_id: node_id.clone(),
             local_connections: Arc::new(RwLock::new(HashMap::new())),
+            local_waitlist: Arc::new(RwLock::new(HashMap::new())),
+            local_lobby: Arc::new(RwLock::new(HashMap::new())),
+            room_configs,
+            webhooks: None,
+            audit: None,
+            scheduled_closes: Arc::default(),
             redis_healthy: Arc::new(RwLock::new(true)),
         };
 
@@ -109,14 +748,207 @@ impl ClusterRoomManager {
         Ok(manager)
     }
 
+    /// Failed password attempts are counted in Redis, so a lockout holds on every
+    /// server; each server counts on its own while Redis is unavailable
+    pub fn with_lockout_policy(mut self, policy: LockoutPolicy) -> Self {
+        self.local_manager = std::mem::take(&mut self.local_manager).with_lockout_policy(policy);
+        self
+    }
+
+    /// Send room lifecycle events to `webhooks`, including while Redis is unavailable
+    pub fn with_webhooks(mut self, webhooks: WebhookDispatcher) -> Self {
+        self.local_manager =
+            std::mem::take(&mut self.local_manager).with_webhooks(webhooks.clone());
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Record who joins and leaves rooms through this server in `audit`, including
+    /// while Redis is unavailable
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.local_manager = std::mem::take(&mut self.local_manager).with_audit_log(audit.clone());
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Report a user joining a room, with the room's size in Redis
+    async fn emit_joined(&self, room_id: &str, user_id: u32, username: String) {
+        if let Some(audit) = &self.audit {
+            audit.record(
+                room_id,
+                AuditEvent::Joined {
+                    user_id,
+                    username: username.clone(),
+                },
+            );
+        }
+        self.update_directory(room_id).await;
+        let Some(webhooks) = &self.webhooks else {
+            return;
+        };
+        let (participant_count, spectator_count) =
+            Self::counts_in_redis(&self.redis_client, room_id).await;
+        if participant_count == 1 && !self.local_manager.room_config(room_id).await.persistent {
+            webhooks.emit(WebhookEvent::RoomCreated {
+                room_name: room_id.to_string(),
+            });
+        }
+        webhooks.emit(WebhookEvent::UserJoined {
+            room_name: room_id.to_string(),
+            user_id,
+            username,
+            participant_count,
+            spectator_count,
+        });
+    }
+
+    /// Report a user leaving a room, and the room emptying if they were the last
+    async fn emit_left(&self, room_id: &str, user_id: u32) {
+        Self::report_left(
+            &self.redis_client,
+            &self.room_configs,
+            self.webhooks.as_ref(),
+            self.audit.as_ref(),
+            room_id,
+            user_id,
+        )
+        .await;
+    }
+
+    async fn report_left(
+        redis_client: &RedisClient,
+        room_configs: &RoomConfigStore,
+        webhooks: Option<&WebhookDispatcher>,
+        audit: Option<&AuditLog>,
+        room_id: &str,
+        user_id: u32,
+    ) {
+        if let Some(audit) = audit {
+            audit.record(room_id, AuditEvent::Left { user_id });
+        }
+        Self::announce_in_directory(redis_client, room_configs, room_id).await;
+        let Some(webhooks) = webhooks else {
+            return;
+        };
+        let (participant_count, spectator_count) =
+            Self::counts_in_redis(redis_client, room_id).await;
+        webhooks.emit(WebhookEvent::UserLeft {
+            room_name: room_id.to_string(),
+            user_id,
+            participant_count,
+            spectator_count,
+        });
+        if participant_count == 0 {
+            webhooks.emit(WebhookEvent::RoomEmptied {
+                room_name: room_id.to_string(),
+            });
+        }
+    }
+
+    /// Everyone in the room, and how many of them are spectators
+    async fn counts_in_redis(redis_client: &RedisClient, room_id: &str) -> (usize, usize) {
+        let Ok(mut conn) = redis_client.get_multiplexed_async_connection().await else {
+            return (0, 0);
+        };
+        redis::pipe()
+            .hlen(format!("rooms:{}:participants", room_id))
+            .scard(format!("rooms:{}:spectators", room_id))
+            .query_async(&mut conn)
+            .await
+            .unwrap_or((0, 0))
+    }
+
+    async fn update_directory(&self, room_id: &str) {
+        Self::announce_in_directory(&self.redis_client, &self.room_configs, room_id).await;
+    }
+
+    /// Keep the room in the live room set while someone is in it, and tell the
+    /// directory subscribers on every server about the change
+    async fn announce_in_directory(
+        redis_client: &RedisClient,
+        room_configs: &RoomConfigStore,
+        room_id: &str,
+    ) {
+        let Ok(mut conn) = redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+        let (participant_count, _) = Self::counts_in_redis(redis_client, room_id).await;
+        let result: redis::RedisResult<()> = if participant_count > 0 {
+            conn.sadd(LIVE_ROOMS_KEY, room_id).await
+        } else {
+            conn.srem(LIVE_ROOMS_KEY, room_id).await
+        };
+        if let Err(e) = result {
+            warn!("Failed to update live rooms in Redis: {}", e);
+        }
+
+        let Some(summary) =
+            Self::listed_summary_in_redis(redis_client, room_configs, room_id).await
+        else {
+            return;
+        };
+        let Ok(message_json) = serde_json::to_string(&ClusterMessage::DirectoryUpdate { summary })
+        else {
+            return;
+        };
+        if let Err(e) = conn
+            .publish::<_, _, ()>("cluster:messages", message_json)
+            .await
+        {
+            warn!(
+                "Failed to publish directory update for room {}: {}",
+                room_id, e
+            );
+        }
+    }
+
+    /// Directory entry for the room, unless it is unlisted or private
+    async fn listed_summary_in_redis(
+        redis_client: &RedisClient,
+        room_configs: &RoomConfigStore,
+        room_id: &str,
+    ) -> Option<RoomSummary> {
+        if room_configs.get(room_id).await.visibility != RoomVisibility::Public {
+            return None;
+        }
+        let mut conn = redis_client.get_multiplexed_async_connection().await.ok()?;
+        let private: bool = conn
+            .exists(format!("rooms:{}:invited", room_id))
+            .await
+            .unwrap_or(false);
+        if private {
+            return None;
+        }
+
+        let (participant_count, spectator_count) =
+            Self::counts_in_redis(redis_client, room_id).await;
+        let participants = if participant_count > 0 {
+            Self::participants_in_redis(redis_client, room_id).await
+        } else {
+            Vec::new()
+        };
+        Some(RoomSummary {
+            room_name: room_id.to_string(),
+            participant_count,
+            spectator_count,
+            participants,
+        })
+    }
+
     /// Start Redis pub/sub listener for cluster messages
     async fn start_pubsub_listener(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let conn = self.redis_client.get_async_connection().await?;
-        let mut pubsub = conn.into_pubsub();
+        let mut pubsub = self.redis_client.get_async_pubsub().await?;
         pubsub.subscribe("cluster:messages").await?;
 
         let local_connections = Arc::clone(&self.local_connections);
+        let local_waitlist = Arc::clone(&self.local_waitlist);
+        let local_lobby = Arc::clone(&self.local_lobby);
+        let users = self.users.clone();
+        let directory = self.directory.clone();
+        let room_configs = Arc::clone(&self.room_configs);
+        let redis_client = self.redis_client.clone();
         let node_id = self.node_id.clone();
+        let closer = self.closer();
 
         tokio::spawn(async move {
             info!("Started cluster message listener for node: {}", node_id);
@@ -124,8 +956,19 @@ impl ClusterRoomManager {
             while let Some(msg) = pubsub.on_message().next().await {
                 if let Ok(payload) = msg.get_payload::<String>() {
                     if let Ok(cluster_msg) = serde_json::from_str::<ClusterMessage>(&payload) {
-                        Self::handle_cluster_message(cluster_msg, &local_connections, &node_id)
-                            .await;
+                        Self::handle_cluster_message(
+                            cluster_msg,
+                            &local_connections,
+                            &local_waitlist,
+                            &local_lobby,
+                            &users,
+                            &directory,
+                            &room_configs,
+                            &redis_client,
+                            &node_id,
+                            &closer,
+                        )
+                        .await;
                     }
                 }
             }
@@ -137,10 +980,18 @@ impl ClusterRoomManager {
     }
 
     /// Handle incoming cluster messages
+    #[allow(clippy::too_many_arguments)]
     async fn handle_cluster_message(
         message: ClusterMessage,
         local_connections: &Arc<RwLock<HashMap<u32, RoomParticipant>>>,
+        local_waitlist: &LocalWaitlist,
+        local_lobby: &LocalWaitlist,
+        users: &UserConnections,
+        directory: &RoomDirectory,
+        room_configs: &RoomConfigStore,
+        redis_client: &RedisClient,
         node_id: &str,
+        closer: &RoomCloser,
     ) {
         match message {
             ClusterMessage::UserJoined {
@@ -163,18 +1014,23 @@ impl ClusterRoomManager {
 
                 // Notify local users in this room about new participant
                 let server_message = ServerMessage::UserJoined {
-                    room_name: room_id,
+                    room_name: room_id.clone(),
                     user: Participant { user_id, username },
                 };
 
-                Self::broadcast_to_local_room_participants(&server_message, local_connections)
-                    .await;
+                Self::broadcast_to_local_room_participants(
+                    &room_id,
+                    &server_message,
+                    local_connections,
+                )
+                .await;
             }
 
             ClusterMessage::UserLeft {
                 room_id,
                 user_id,
                 target_server,
+                reason,
             } => {
                 if let Some(target) = target_server {
                     if target != node_id {
@@ -188,107 +1044,422 @@ impl ClusterRoomManager {
                 );
 
                 let server_message = ServerMessage::UserLeft {
-                    room_name: room_id,
+                    room_name: room_id.clone(),
                     user_id,
+                    reason,
                 };
 
-                Self::broadcast_to_local_room_participants(&server_message, local_connections)
-                    .await;
+                Self::broadcast_to_local_room_participants(
+                    &room_id,
+                    &server_message,
+                    local_connections,
+                )
+                .await;
             }
 
             ClusterMessage::WebRTCSignal {
+                room_id,
                 from_user,
                 to_user,
                 signal_type,
                 signal_data,
-                ..
+                sdp_mid,
+                sdp_mline_index,
+                target_server,
+                origin_server,
             } => {
+                // Only the server the recipient is registered on answers for them
+                let undelivered = |reason: DeliveryFailure| {
+                    origin_server
+                        .clone()
+                        .filter(|_| target_server.as_deref() == Some(node_id))
+                        .map(|origin_server| ClusterMessage::SignalUndelivered {
+                            room_id: room_id.clone(),
+                            from_user,
+                            to_user,
+                            signal_type: signal_type.clone(),
+                            reason,
+                            target_server: origin_server,
+                        })
+                };
+
                 // Deliver signal to local user if they're connected to this server
                 let connections = local_connections.read().await;
-                if let Some(participant) = connections.get(&to_user) {
-                    debug!(
-                        "Cluster: Delivering WebRTC signal from {} to {} on this server",
-                        from_user, to_user
-                    );
+                let Some(participant) = connections.get(&to_user).filter(|participant| {
+                    same_tenant(participant.user.tenant.as_deref(), &room_id)
+                }) else {
+                    drop(connections);
+                    if let Some(undelivered) = undelivered(DeliveryFailure::RecipientGone) {
+                        Self::report_undelivered(redis_client, &undelivered).await;
+                    }
+                    return;
+                };
+                debug!(
+                    "Cluster: Delivering WebRTC signal from {} to {} on this server",
+                    from_user, to_user
+                );
 
-                    let message = match signal_type.as_str() {
-                        "offer" => ServerMessage::Offer {
-                            room_name: "cluster".to_string(), // TODO: pass actual room name
-                            from_user_id: from_user,
-                            sdp: signal_data,
-                        },
-                        "answer" => ServerMessage::Answer {
-                            room_name: "cluster".to_string(),
-                            from_user_id: from_user,
-                            sdp: signal_data,
-                        },
-                        "ice-candidate" => ServerMessage::IceCandidate {
-                            room_name: "cluster".to_string(),
-                            from_user_id: from_user,
-                            candidate: signal_data,
-                            sdp_mid: None,
-                            sdp_mline_index: None,
-                        },
-                        _ => {
-                            warn!("Unknown signal type: {}", signal_type);
-                            return;
-                        }
-                    };
+                let message = match signal_type.as_str() {
+                    "offer" => ServerMessage::Offer {
+                        room_name: room_id.clone(),
+                        from_user_id: from_user,
+                        sdp: signal_data,
+                    },
+                    "answer" => ServerMessage::Answer {
+                        room_name: room_id.clone(),
+                        from_user_id: from_user,
+                        sdp: signal_data,
+                    },
+                    "ice-candidate" => ServerMessage::IceCandidate {
+                        room_name: room_id.clone(),
+                        from_user_id: from_user,
+                        candidate: signal_data,
+                        sdp_mid,
+                        sdp_mline_index,
+                    },
+                    _ => {
+                        warn!("Unknown signal type: {}", signal_type);
+                        return;
+                    }
+                };
 
-                    if let Ok(json_message) = serde_json::to_string(&message) {
-                        let websocket_message = Message::Text(json_message);
-                        if let Err(e) = participant.sender.send(websocket_message) {
-                            warn!(
-                                "Failed to deliver cluster WebRTC signal to user {}: {}",
-                                to_user, e
-                            );
-                        }
+                if let Err(DeliveryError::Undelivered(reason)) = participant.deliver(&message) {
+                    warn!(
+                        "Failed to deliver cluster WebRTC signal to user {}: {:?}",
+                        to_user, reason
+                    );
+                    drop(connections);
+                    if let Some(undelivered) = undelivered(reason) {
+                        Self::report_undelivered(redis_client, &undelivered).await;
                     }
                 }
             }
 
-            _ => {
-                // Handle other message types as needed
-                debug!("Received unhandled cluster message type");
-            }
-        }
-    }
-
-    /// Broadcast message to all local participants
-    async fn broadcast_to_local_room_participants(
-        message: &ServerMessage,
-        local_connections: &Arc<RwLock<HashMap<u32, RoomParticipant>>>,
-    ) {
-        let connections = local_connections.read().await;
-
-        if let Ok(json_message) = serde_json::to_string(message) {
-            let websocket_message = Message::Text(json_message);
-
-            for (user_id, participant) in connections.iter() {
-                if let Err(e) = participant.sender.send(websocket_message.clone()) {
-                    warn!(
-                        "Failed to broadcast cluster message to local user {}: {}",
-                        user_id, e
+            ClusterMessage::SignalUndelivered {
+                room_id,
+                from_user,
+                to_user,
+                signal_type,
+                reason,
+                target_server,
+            } => {
+                if target_server != node_id {
+                    return;
+                }
+                let connections = local_connections.read().await;
+                if let Some(participant) = connections
+                    .get(&from_user)
+                    .filter(|participant| same_tenant(participant.user.tenant.as_deref(), &room_id))
+                {
+                    send_to(
+                        participant,
+                        &ServerMessage::DeliveryFailed {
+                            room_name: room_id,
+                            target_user_id: to_user,
+                            message_type: signal_type,
+                            reason,
+                        },
                     );
                 }
             }
-        }
-    }
 
-    /// Start heartbeat mechanism for failure detection
-    async fn start_heartbeat(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let redis_client = self.redis_client.clone();
-        let node_id = self.node_id.clone();
-        let local_connections = Arc::clone(&self.local_connections);
+            ClusterMessage::WaitlistAdmitted {
+                room_id,
+                user_id,
+                participants,
+                target_server,
+                spectator,
+            } => {
+                if target_server != node_id {
+                    return;
+                }
 
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(10));
+                let waiting = match Self::take_waiting(local_waitlist, &room_id, user_id).await {
+                    Some(waiting) => Some(waiting),
+                    None => Self::take_waiting(local_lobby, &room_id, user_id).await,
+                };
 
-            loop {
-                interval.tick().await;
+                let Ok(mut conn) = redis_client.get_multiplexed_async_connection().await else {
+                    warn!("Failed to connect to Redis to admit user {}", user_id);
+                    return;
+                };
 
-                match redis_client.get_multiplexed_async_connection().await {
-                    Ok(mut conn) => {
+                let Some(waiting) = waiting else {
+                    // The user disconnected after the slot was claimed; give it back
+                    let room_key = format!("rooms:{}:participants", room_id);
+                    let _: Result<(), _> = conn.hdel(&room_key, user_id.to_string()).await;
+                    return;
+                };
+
+                let participant = waiting.participant;
+                if let Err(e) =
+                    Self::record_connection(&mut conn, node_id, &room_id, &participant).await
+                {
+                    warn!("Failed to record admitted user {} in Redis: {}", user_id, e);
+                }
+                local_connections
+                    .write()
+                    .await
+                    .insert(user_id, participant.clone());
+                // This node may hold no one else in the room to close at its deadline
+                closer.schedule(&mut conn, &room_id).await;
+
+                info!(
+                    "Cluster: User {} admitted to room {} from the waitlist or lobby",
+                    user_id, room_id
+                );
+                let ownership = Self::ownership_in_redis(&mut conn, &room_id).await;
+                let state = Self::state_in_redis(&mut conn, &room_id).await;
+                let joined = ServerMessage::RoomJoined {
+                    room_name: room_id,
+                    user_id,
+                    participants,
+                    spectator,
+                    owner_user_id: ownership.owner,
+                    host_user_id: ownership.host,
+                    state,
+                };
+                if let Ok(json_message) = serde_json::to_string(&joined) {
+                    let _ = participant.sender.try_send(Message::Text(json_message));
+                }
+            }
+
+            ClusterMessage::LobbyWaitlisted {
+                room_id,
+                user_id,
+                position,
+                target_server,
+            } => {
+                if target_server != node_id {
+                    return;
+                }
+                let Some(waiting) = Self::take_waiting(local_lobby, &room_id, user_id).await else {
+                    return;
+                };
+
+                let message = ServerMessage::Waitlisted {
+                    room_name: room_id,
+                    position,
+                };
+                if let Ok(json_message) = serde_json::to_string(&message) {
+                    let _ = waiting
+                        .participant
+                        .sender
+                        .try_send(Message::Text(json_message));
+                }
+                local_waitlist.write().await.insert(user_id, waiting);
+            }
+
+            ClusterMessage::Removed {
+                room_id,
+                user_id,
+                message,
+            } => {
+                let recipient = if let Some(knocking) =
+                    Self::take_waiting(local_lobby, &room_id, user_id).await
+                {
+                    // The moderator's node already dropped the knock from Redis
+                    knocking.participant
+                } else if let Some(waiting) =
+                    Self::take_waiting(local_waitlist, &room_id, user_id).await
+                {
+                    if let Ok(mut conn) = redis_client.get_multiplexed_async_connection().await {
+                        let waitlist_key = format!("rooms:{}:waitlist", room_id);
+                        let _: Result<(), _> = conn.lrem(&waitlist_key, 1, &waiting.entry).await;
+                    }
+                    waiting.participant
+                } else {
+                    match local_connections.write().await.remove(&user_id) {
+                        Some(participant) => participant,
+                        None => return,
+                    }
+                };
+
+                info!(
+                    "Cluster: User {} removed from room {} by a moderator",
+                    user_id, room_id
+                );
+                if let Ok(json_message) = serde_json::to_string(&message) {
+                    let _ = recipient.sender.try_send(Message::Text(json_message));
+                }
+            }
+
+            ClusterMessage::RoomDefined { room_id, config } => match config {
+                Some(config) => room_configs.set(room_id, config).await,
+                None => {
+                    room_configs.remove(&room_id).await;
+                }
+            },
+
+            ClusterMessage::UserEvent {
+                tenant,
+                user_id,
+                message,
+            } => {
+                users.send(tenant.as_deref(), user_id, &message).await;
+            }
+
+            ClusterMessage::ConnectionReplaced {
+                node_id: target_node,
+                user_id,
+                connection_id,
+            } => {
+                if target_node == node_id {
+                    let mut connections = local_connections.write().await;
+                    if connections
+                        .get(&user_id)
+                        .is_some_and(|p| p.connection_id == connection_id)
+                    {
+                        connections.remove(&user_id);
+                    }
+                    drop(connections);
+                    if let Some(stale) = users.get(connection_id).await {
+                        close_replaced(&stale.sender);
+                    }
+                }
+            }
+
+            ClusterMessage::DirectoryUpdate { summary } => directory.publish(&summary),
+
+            ClusterMessage::RoomEvent {
+                room_id,
+                message,
+                target_user,
+                exclude_user,
+            } => {
+                let members: Vec<u32> = match target_user {
+                    Some(user_id) => vec![user_id],
+                    None => {
+                        let Ok(mut conn) = redis_client.get_multiplexed_async_connection().await
+                        else {
+                            warn!("Failed to connect to Redis to deliver a room event");
+                            return;
+                        };
+                        let room_key = format!("rooms:{}:participants", room_id);
+                        let user_ids: Vec<String> = conn.hkeys(&room_key).await.unwrap_or_default();
+                        user_ids.iter().filter_map(|id| id.parse().ok()).collect()
+                    }
+                };
+
+                let Ok(json_message) = serde_json::to_string(&message) else {
+                    return;
+                };
+                // User ids are only unique within a tenant, so a local connection with
+                // the same id from another tenant must not receive the event
+                let connections = local_connections.read().await;
+                for user_id in members.into_iter().filter(|id| Some(*id) != exclude_user) {
+                    if let Some(participant) = connections.get(&user_id).filter(|participant| {
+                        same_tenant(participant.user.tenant.as_deref(), &room_id)
+                    }) {
+                        if let Err(e) = participant
+                            .sender
+                            .try_send(Message::Text(json_message.clone()))
+                        {
+                            warn!("Failed to deliver room event to user {}: {}", user_id, e);
+                        }
+                    }
+                }
+            }
+
+            _ => {
+                // Handle other message types as needed
+                debug!("Received unhandled cluster message type");
+            }
+        }
+    }
+
+    /// Remove a local user's queued join or knock for `room_id`
+    async fn take_waiting(
+        waiting: &LocalWaitlist,
+        room_id: &str,
+        user_id: u32,
+    ) -> Option<WaitingParticipant> {
+        let mut waiting = waiting.write().await;
+        match waiting.get(&user_id) {
+            Some(entry) if entry.room_id == room_id => waiting.remove(&user_id),
+            _ => None,
+        }
+    }
+
+    /// Why a user missing from the room's participants cannot be reached: gone if
+    /// they are among its recently departed users, otherwise unknown
+    async fn missing_recipient(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_id: &str,
+        user_id: u32,
+    ) -> DeliveryFailure {
+        let departed: Vec<String> = conn
+            .lrange(format!("rooms:{}:departed", room_id), 0, -1)
+            .await
+            .unwrap_or_default();
+        if departed.contains(&user_id.to_string()) {
+            DeliveryFailure::RecipientGone
+        } else {
+            DeliveryFailure::UnknownRecipient
+        }
+    }
+
+    /// Tell the sender's server that a routed signal could not be delivered here
+    async fn report_undelivered(redis_client: &RedisClient, undelivered: &ClusterMessage) {
+        let Ok(message_json) = serde_json::to_string(undelivered) else {
+            return;
+        };
+        match redis_client.get_multiplexed_async_connection().await {
+            Ok(mut conn) => {
+                if let Err(e) = conn
+                    .publish::<_, _, ()>("cluster:messages", message_json)
+                    .await
+                {
+                    warn!("Failed to report an undelivered signal: {}", e);
+                }
+            }
+            Err(e) => warn!(
+                "Failed to connect to Redis to report an undelivered signal: {}",
+                e
+            ),
+        }
+    }
+
+    /// Broadcast message to all local participants
+    async fn broadcast_to_local_room_participants(
+        room_id: &str,
+        message: &ServerMessage,
+        local_connections: &Arc<RwLock<HashMap<u32, RoomParticipant>>>,
+    ) {
+        let connections = local_connections.read().await;
+
+        if let Ok(json_message) = serde_json::to_string(message) {
+            let websocket_message = Message::Text(json_message);
+
+            let tenant_connections = connections.iter().filter(|(_, participant)| {
+                same_tenant(participant.user.tenant.as_deref(), room_id)
+            });
+            for (user_id, participant) in tenant_connections {
+                if let Err(e) = participant.sender.try_send(websocket_message.clone()) {
+                    warn!(
+                        "Failed to broadcast cluster message to local user {}: {}",
+                        user_id, e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Start heartbeat mechanism for failure detection
+    async fn start_heartbeat(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let redis_client = self.redis_client.clone();
+        let node_id = self.node_id.clone();
+        let local_connections = Arc::clone(&self.local_connections);
+        let room_configs = Arc::clone(&self.room_configs);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(10));
+
+            loop {
+                interval.tick().await;
+
+                match redis_client.get_multiplexed_async_connection().await {
+                    Ok(mut conn) => {
                         let connection_count = local_connections.read().await.len();
 
                         let timestamp = Utc::now().timestamp() as u64;
@@ -318,6 +1489,9 @@ impl ClusterRoomManager {
                             "Sent heartbeat for node {} with {} connections",
                             node_id, connection_count
                         );
+
+                        Self::reap_lost_nodes(&mut conn, &room_configs, &node_id).await;
+                        Self::retry_stalled_pairs(&mut conn, &node_id).await;
                     }
                     Err(e) => {
                         warn!("Failed to connect to Redis for heartbeat: {}", e);
@@ -360,107 +1534,2160 @@ impl ClusterRoomManager {
         });
     }
 
-    /// Add user to Redis room registry
-    async fn register_user_in_redis(
+    /// Claim a slot for the user in the Redis room registry, applying the room's
+    /// capacity and overflow policy
+    async fn reserve_slot_in_redis(
         &self,
         room_id: &str,
-        user_id: u32,
-        username: &str,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        participant: &RoomParticipant,
+        role: RoomRole,
+        config: &RoomConfig,
+        max_duration: Option<Duration>,
+    ) -> Result<(String, i64, String), Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.redis_client.get_multiplexed_async_connection().await?;
 
-        // Add user to room participants list in Redis
-        let room_key = format!("rooms:{}:participants", room_id);
-        let _: () = conn
-            .hset(&room_key, user_id.to_string(), &self.node_id)
-            .await?;
+        let entry = self.waitlist_entry(participant, role, max_duration)?;
+        let (status, value) = Self::claim_slot_in_redis(
+            &mut conn,
+            room_id,
+            participant.user.user_id,
+            &self.node_id,
+            &entry,
+            role,
+            config,
+        )
+        .await?;
 
-        // Add connection to this server's connection list
-        let server_key = format!("servers:{}:connections", self.node_id);
+        if status == "joined" || status == "spectator" {
+            Self::record_connection(&mut conn, &self.node_id, room_id, participant).await?;
+        }
+
+        Ok((status, value, entry))
+    }
+
+    /// Run [`RESERVE_SLOT_SCRIPT`] for a user connected to `node_id`
+    async fn claim_slot_in_redis(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_id: &str,
+        user_id: u32,
+        node_id: &str,
+        entry: &str,
+        role: RoomRole,
+        config: &RoomConfig,
+    ) -> redis::RedisResult<(String, i64)> {
+        let capacity = config.max_participants.map_or(-1, |max| max as i64);
+        let overflow = match config.overflow {
+            OverflowPolicy::Reject => "reject",
+            OverflowPolicy::Waitlist => "waitlist",
+            OverflowPolicy::Spectator => "spectator",
+        };
+        // Spectators by role never take a slot, as if the room were always full
+        let (capacity, overflow) = if role.is_spectator() {
+            (0, "spectator")
+        } else {
+            (capacity, overflow)
+        };
+
+        redis::Script::new(RESERVE_SLOT_SCRIPT)
+            .key(format!("rooms:{}:participants", room_id))
+            .key(format!("rooms:{}:spectators", room_id))
+            .key(format!("rooms:{}:waitlist", room_id))
+            .arg(user_id)
+            .arg(node_id)
+            .arg(capacity)
+            .arg(overflow)
+            .arg(entry)
+            .invoke_async(conn)
+            .await
+    }
+
+    fn waitlist_entry(
+        &self,
+        participant: &RoomParticipant,
+        role: RoomRole,
+        max_duration: Option<Duration>,
+    ) -> serde_json::Result<String> {
+        serde_json::to_string(&WaitlistEntry {
+            user_id: participant.user.user_id,
+            username: participant.user.username.clone(),
+            node_id: self.node_id.clone(),
+            connection_id: participant.connection_id,
+            spectator: role.is_spectator(),
+            max_duration_secs: max_duration.map(|d| d.as_secs()),
+        })
+    }
+
+    /// Add the connection to this server's connection list
+    async fn record_connection(
+        conn: &mut redis::aio::MultiplexedConnection,
+        node_id: &str,
+        room_id: &str,
+        participant: &RoomParticipant,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let server_key = format!("servers:{}:connections", node_id);
         let connection_info = ConnectionInfo {
-            user_id,
-            username: username.to_string(),
+            user_id: participant.user.user_id,
+            username: participant.user.username.clone(),
             room_id: room_id.to_string(),
             connected_at: Utc::now(),
-            connection_id: Uuid::new_v4(), // This should match the actual connection_id
+            connection_id: participant.connection_id,
         };
 
         let connection_json = serde_json::to_string(&connection_info)?;
         let _: () = conn
-            .hset(&server_key, user_id.to_string(), connection_json)
+            .hset(
+                &server_key,
+                participant.user.user_id.to_string(),
+                connection_json,
+            )
             .await?;
 
         Ok(())
     }
 
-    /// Remove user from Redis room registry
-    async fn unregister_user_from_redis(
+    /// Fill free slots in a room from its Redis waitlist, notifying the nodes that
+    /// hold the admitted users
+    async fn admit_from_redis_waitlist(&self, room_id: &str) {
+        let config = self.local_manager.room_config(room_id).await;
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+
+        let admitted: Vec<String> = match redis::Script::new(ADMIT_WAITLIST_SCRIPT)
+            .key(format!("rooms:{}:participants", room_id))
+            .key(format!("rooms:{}:spectators", room_id))
+            .key(format!("rooms:{}:waitlist", room_id))
+            .arg(config.max_participants.map_or(-1, |max| max as i64))
+            .invoke_async(&mut conn)
+            .await
+        {
+            Ok(admitted) => admitted,
+            Err(e) => {
+                warn!("Failed to admit waitlisted users to {}: {}", room_id, e);
+                return;
+            }
+        };
+
+        for entry in admitted {
+            let Ok(entry) = serde_json::from_str::<WaitlistEntry>(&entry) else {
+                continue;
+            };
+            let host_changed = self
+                .settle_host_in_redis(room_id, entry.user_id, false, config.host_transfer)
+                .await;
+            let participants = self
+                .get_existing_participants_from_redis(room_id)
+                .await
+                .into_iter()
+                .filter(|p| p.user_id != entry.user_id)
+                .collect();
+
+            let messages = [
+                ClusterMessage::WaitlistAdmitted {
+                    room_id: room_id.to_string(),
+                    user_id: entry.user_id,
+                    participants,
+                    target_server: entry.node_id,
+                    spectator: false,
+                },
+                ClusterMessage::UserJoined {
+                    room_id: room_id.to_string(),
+                    user_id: entry.user_id,
+                    username: entry.username.clone(),
+                    target_server: None,
+                },
+            ];
+            self.emit_joined(room_id, entry.user_id, entry.username)
+                .await;
+            let host_changed = host_changed.map(|message| ClusterMessage::RoomEvent {
+                room_id: room_id.to_string(),
+                message,
+                target_user: None,
+                exclude_user: Some(entry.user_id),
+            });
+            for message in messages.into_iter().chain(host_changed) {
+                if let Ok(message_json) = serde_json::to_string(&message) {
+                    if let Err(e) = conn
+                        .publish::<_, _, ()>("cluster:messages", message_json)
+                        .await
+                    {
+                        warn!("Failed to publish waitlist admission: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Record a user's arrival and give them the host role if they are entitled to
+    /// it, returning the `host-changed` event for the rest of the room
+    async fn settle_host_in_redis(
         &self,
         room_id: &str,
         user_id: u32,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let mut conn = self.redis_client.get_multiplexed_async_connection().await?;
+        claims_host: bool,
+        policy: HostTransferPolicy,
+    ) -> Option<ServerMessage> {
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .ok()?;
+        let result: Result<(i64, String), _> = redis::Script::new(SETTLE_HOST_SCRIPT)
+            .key(format!("rooms:{}:host", room_id))
+            .key(format!("rooms:{}:joined", room_id))
+            .arg(user_id)
+            .arg(Utc::now().timestamp_millis())
+            .arg(if claims_host { "1" } else { "0" })
+            .arg(host_transfer_policy_name(policy))
+            .invoke_async(&mut conn)
+            .await;
 
-        // Remove from room participants
-        let room_key = format!("rooms:{}:participants", room_id);
-        let _: () = conn.hdel(&room_key, user_id.to_string()).await?;
+        match result {
+            Ok((1, previous)) => {
+                info!("Cluster: User {} is now host of room {}", user_id, room_id);
+                Some(ServerMessage::HostChanged {
+                    room_name: room_id.to_string(),
+                    host_user_id: Some(user_id),
+                    previous_host_user_id: previous.parse().ok(),
+                })
+            }
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Failed to update the host of room {}: {}", room_id, e);
+                None
+            }
+        }
+    }
 
-        // Remove from server connections
-        let server_key = format!("servers:{}:connections", self.node_id);
-        let _: () = conn.hdel(&server_key, user_id.to_string()).await?;
+    /// Hand the host role on if the departed user held it, telling the room
+    async fn reassign_host_in_redis(&self, room_id: &str, user_id: u32) {
+        let config = self.local_manager.room_config(room_id).await;
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+        if let Some(message) =
+            Self::hand_on_host(&mut conn, room_id, user_id, config.host_transfer).await
+        {
+            if let Err(e) = self.publish(&message).await {
+                warn!("{}", e);
+            }
+        }
+    }
 
-        Ok(())
+    /// Remove participants whose server stopped sending heartbeats, telling the
+    /// rest of their rooms that they left because of a server shutdown
+    async fn reap_lost_nodes(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_configs: &RoomConfigStore,
+        node_id: &str,
+    ) {
+        let rooms: Vec<String> = match conn.smembers(LIVE_ROOMS_KEY).await {
+            Ok(rooms) => rooms,
+            Err(e) => {
+                warn!("Failed to read live rooms from Redis: {}", e);
+                return;
+            }
+        };
+
+        let mut alive: HashMap<String, bool> = HashMap::new();
+        for room_id in rooms {
+            let participants: HashMap<String, String> = conn
+                .hgetall(format!("rooms:{}:participants", room_id))
+                .await
+                .unwrap_or_default();
+            for (user_id, user_node) in participants {
+                let Ok(user_id) = user_id.parse::<u32>() else {
+                    continue;
+                };
+                if user_node == node_id {
+                    continue;
+                }
+                let is_alive = match alive.get(&user_node) {
+                    Some(is_alive) => *is_alive,
+                    None => {
+                        let is_alive = conn
+                            .exists(format!("servers:{}:heartbeat", user_node))
+                            .await
+                            .unwrap_or(true);
+                        alive.insert(user_node.clone(), is_alive);
+                        is_alive
+                    }
+                };
+                if !is_alive {
+                    Self::reap_participant(conn, room_configs, &room_id, user_id, &user_node).await;
+                }
+            }
+        }
     }
 
-    /// Get existing participants from Redis
-    async fn get_existing_participants_from_redis(&self, room_id: &str) -> Vec<Participant> {
-        match self.redis_client.get_multiplexed_async_connection().await {
-            Ok(mut conn) => {
-                let room_key = format!("rooms:{}:participants", room_id);
+    async fn reap_participant(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_configs: &RoomConfigStore,
+        room_id: &str,
+        user_id: u32,
+        lost_node: &str,
+    ) {
+        let spectator = Self::role_in_redis(conn, room_id, user_id)
+            .await
+            .is_spectator();
+        let reaped: i64 = match redis::Script::new(REAP_PARTICIPANT_SCRIPT)
+            .key(format!("rooms:{}:participants", room_id))
+            .key(format!("rooms:{}:spectators", room_id))
+            .key(format!("rooms:{}:roles", room_id))
+            .arg(user_id)
+            .arg(lost_node)
+            .invoke_async(conn)
+            .await
+        {
+            Ok(reaped) => reaped,
+            Err(e) => {
+                warn!(
+                    "Failed to reap user {} from room {}: {}",
+                    user_id, room_id, e
+                );
+                return;
+            }
+        };
+        if reaped == 0 {
+            return;
+        }
+        record_departure(conn, room_id, user_id).await;
+        info!(
+            "Cluster: Removed user {} from room {} after server {} was lost",
+            user_id, room_id, lost_node
+        );
+        let _: Result<(), _> = conn
+            .hdel(
+                format!("servers:{}:connections", lost_node),
+                user_id.to_string(),
+            )
+            .await;
 
-                match conn.hgetall::<_, HashMap<String, String>>(&room_key).await {
-                    Ok(participants_map) => {
-                        let mut participants = Vec::new();
+        let mut messages = Vec::new();
+        if !spectator {
+            messages.push(ClusterMessage::UserLeft {
+                room_id: room_id.to_string(),
+                user_id,
+                target_server: None,
+                reason: Some(LeaveReason::ServerShutdown),
+            });
+        }
+        let policy = room_configs.get(room_id).await.host_transfer;
+        messages.extend(Self::hand_on_host(conn, room_id, user_id, policy).await);
+        for message in messages {
+            if let Ok(message_json) = serde_json::to_string(&message) {
+                let _: Result<(), _> = conn.publish("cluster:messages", message_json).await;
+            }
+        }
 
-                        for (user_id_str, server_node) in participants_map {
-                            if let Ok(user_id) = user_id_str.parse::<u32>() {
-                                // Get username from server's connection list
-                                if let Ok(username) =
-                                    self.get_username_from_server(&server_node, user_id).await
-                                {
-                                    participants.push(Participant { user_id, username });
-                                }
-                            }
-                        }
+        let remaining: i64 = conn
+            .hlen(format!("rooms:{}:participants", room_id))
+            .await
+            .unwrap_or(1);
+        if remaining == 0 {
+            let _: Result<(), _> = conn.srem(LIVE_ROOMS_KEY, room_id).await;
+        }
+        Self::release_empty_room(conn, room_configs, room_id).await;
+    }
+
+    /// Re-issue the planned pairs whose initiator is on this server and that did not
+    /// complete within [`NEGOTIATION_TIMEOUT`], and drop the pairs of users who left
+    async fn retry_stalled_pairs(conn: &mut redis::aio::MultiplexedConnection, node_id: &str) {
+        let rooms: Vec<String> = match conn.smembers(LIVE_ROOMS_KEY).await {
+            Ok(rooms) => rooms,
+            Err(e) => {
+                warn!("Failed to read live rooms from Redis: {}", e);
+                return;
+            }
+        };
+
+        let now = Utc::now();
+        for room_id in rooms {
+            let mesh_key = format!("rooms:{}:mesh", room_id);
+            let entries: HashMap<String, String> =
+                conn.hgetall(&mesh_key).await.unwrap_or_default();
+            if entries.is_empty() {
+                continue;
+            }
+            let participants: HashMap<String, String> = conn
+                .hgetall(format!("rooms:{}:participants", room_id))
+                .await
+                .unwrap_or_default();
+
+            for (key, entry) in entries {
+                let Ok(mut entry) = serde_json::from_str::<MeshEntry>(&entry) else {
+                    continue;
+                };
+                let initiator_node = participants.get(&entry.pair.initiator.to_string());
+                let Some(initiator_node) = initiator_node
+                    .filter(|_| participants.contains_key(&entry.pair.responder.to_string()))
+                else {
+                    let _: Result<(), _> = conn.hdel(&mesh_key, &key).await;
+                    continue;
+                };
+                // The initiator's server looks after the pair
+                let stalled = now
+                    .signed_duration_since(entry.instructed_at)
+                    .to_std()
+                    .is_ok_and(|elapsed| elapsed >= NEGOTIATION_TIMEOUT);
+                if initiator_node != node_id || !entry.pair.is_pending() || !stalled {
+                    continue;
+                }
+
+                let retried = entry.pair.retry();
+                entry.instructed_at = now;
+                if let Ok(entry_json) = serde_json::to_string(&entry) {
+                    let _: Result<(), _> = conn.hset(&mesh_key, &key, entry_json).await;
+                }
+                if !retried {
+                    warn!(
+                        "Users {} and {} in room {} did not connect after {} attempts",
+                        entry.pair.initiator, entry.pair.responder, room_id, entry.pair.attempt
+                    );
+                    continue;
+                }
+                for message in mesh_instructions(&room_id, &entry.pair) {
+                    if let Ok(message_json) = serde_json::to_string(&message) {
+                        let _: Result<(), _> = conn.publish("cluster:messages", message_json).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pair a user who just got into the room with everyone in it, on any server,
+    /// storing the pairs in `rooms:{room}:mesh` and telling both peers of each
+    async fn plan_mesh_in_redis(&self, room_id: &str, user_id: u32) {
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+        let parse = |ids: Vec<String>| -> Vec<u32> {
+            ids.iter().filter_map(|id| id.parse().ok()).collect()
+        };
+        let participants = parse(
+            conn.hkeys(format!("rooms:{}:participants", room_id))
+                .await
+                .unwrap_or_default(),
+        );
+        if !participants.contains(&user_id) {
+            return;
+        }
+        let mut spectators: HashSet<u32> = parse(
+            conn.smembers(format!("rooms:{}:spectators", room_id))
+                .await
+                .unwrap_or_default(),
+        )
+        .into_iter()
+        .collect();
+        let roles: HashMap<String, String> = conn
+            .hgetall(format!("rooms:{}:roles", room_id))
+            .await
+            .unwrap_or_default();
+        spectators.extend(
+            roles
+                .iter()
+                .filter(|(_, role)| role.as_str() == RoomRole::Spectator.as_str())
+                .filter_map(|(id, _)| id.parse::<u32>().ok()),
+        );
+
+        let spectator = spectators.contains(&user_id);
+        let mut peers: Vec<u32> = participants
+            .into_iter()
+            .filter(|peer| *peer != user_id && !(spectator && spectators.contains(peer)))
+            .collect();
+        peers.sort_unstable();
+
+        let mesh_key = format!("rooms:{}:mesh", room_id);
+        let now = Utc::now();
+        for peer in peers {
+            let entry = MeshEntry {
+                pair: PeerPair::new(user_id, peer),
+                instructed_at: now,
+            };
+            let Ok(entry_json) = serde_json::to_string(&entry) else {
+                continue;
+            };
+            if let Err(e) = conn
+                .hset::<_, _, _, ()>(&mesh_key, PeerPair::key(user_id, peer), entry_json)
+                .await
+            {
+                warn!("Failed to plan pair in room {}: {}", room_id, e);
+                continue;
+            }
+            for message in mesh_instructions(room_id, &entry.pair) {
+                if let Err(e) = self.publish(&message).await {
+                    warn!("{}", e);
+                }
+            }
+        }
+    }
+
+    /// Advance the negotiation of the pair stored for `from_user_id` and `to_user_id`
+    async fn record_signal_in_redis(
+        &self,
+        room_id: &str,
+        from_user_id: u32,
+        to_user_id: u32,
+        kind: SignalKind,
+    ) {
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+        let mesh_key = format!("rooms:{}:mesh", room_id);
+        let key = PeerPair::key(from_user_id, to_user_id);
+        let stored: Option<String> = conn.hget(&mesh_key, &key).await.unwrap_or(None);
+        let Some(mut entry) =
+            stored.and_then(|stored| serde_json::from_str::<MeshEntry>(&stored).ok())
+        else {
+            return;
+        };
+        if !entry.pair.record(from_user_id, to_user_id, kind) {
+            return;
+        }
+        if let Ok(entry_json) = serde_json::to_string(&entry) {
+            let _: Result<(), _> = conn.hset(&mesh_key, &key, entry_json).await;
+        }
+    }
+
+    /// Share the room's deadline when this join opened it, and make sure this server
+    /// warns and closes its participants as the deadline approaches
+    async fn schedule_close_in_redis(
+        &self,
+        room_id: &str,
+        config: &RoomConfig,
+        max_duration: Option<Duration>,
+        opened: bool,
+    ) {
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+        let deadline_key = format!("rooms:{}:deadline", room_id);
+        if opened {
+            let deadline = RoomDeadline::for_session(config, max_duration, Utc::now());
+            if let Some(deadline_json) = deadline.and_then(|d| serde_json::to_string(&d).ok()) {
+                let _: Result<bool, _> = conn.set_nx(&deadline_key, deadline_json).await;
+            }
+        }
+        self.closer().schedule(&mut conn, room_id).await;
+    }
+
+    fn closer(&self) -> RoomCloser {
+        RoomCloser {
+            redis_client: self.redis_client.clone(),
+            node_id: self.node_id.clone(),
+            local_connections: Arc::clone(&self.local_connections),
+            local_waitlist: Arc::clone(&self.local_waitlist),
+            local_lobby: Arc::clone(&self.local_lobby),
+            room_configs: Arc::clone(&self.room_configs),
+            webhooks: self.webhooks.clone(),
+            audit: self.audit.clone(),
+            scheduled: Arc::clone(&self.scheduled_closes),
+        }
+    }
+
+    /// Run [`REASSIGN_HOST_SCRIPT`] for a departed user, returning the event that
+    /// tells the room if the host changed
+    async fn hand_on_host(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_id: &str,
+        user_id: u32,
+        policy: HostTransferPolicy,
+    ) -> Option<ClusterMessage> {
+        let result: Result<(i64, String), _> = redis::Script::new(REASSIGN_HOST_SCRIPT)
+            .key(format!("rooms:{}:host", room_id))
+            .key(format!("rooms:{}:joined", room_id))
+            .key(format!("rooms:{}:participants", room_id))
+            .key(format!("rooms:{}:spectators", room_id))
+            .key(format!("rooms:{}:roles", room_id))
+            .arg(user_id)
+            .arg(host_transfer_policy_name(policy))
+            .invoke_async(conn)
+            .await;
+
+        match result {
+            Ok((1, successor)) => Some(ClusterMessage::RoomEvent {
+                room_id: room_id.to_string(),
+                message: ServerMessage::HostChanged {
+                    room_name: room_id.to_string(),
+                    host_user_id: successor.parse().ok(),
+                    previous_host_user_id: Some(user_id),
+                },
+                target_user: None,
+                exclude_user: None,
+            }),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Failed to reassign the host of room {}: {}", room_id, e);
+                None
+            }
+        }
+    }
+
+    async fn ownership_in_redis(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_id: &str,
+    ) -> RoomOwnership {
+        let host_key = format!("rooms:{}:host", room_id);
+        let (owner, host): (Option<u32>, Option<u32>) = redis::cmd("HMGET")
+            .arg(&host_key)
+            .arg("owner")
+            .arg("host")
+            .query_async(conn)
+            .await
+            .unwrap_or_default();
+        RoomOwnership { owner, host }
+    }
+
+    async fn state_in_redis(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_id: &str,
+    ) -> RoomStateMap {
+        let state_key = format!("rooms:{}:state", room_id);
+        let versions_key = format!("rooms:{}:state_versions", room_id);
+        let values: HashMap<String, String> = conn.hgetall(&state_key).await.unwrap_or_default();
+        let versions: HashMap<String, u64> = conn.hgetall(&versions_key).await.unwrap_or_default();
+        values
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let value = serde_json::from_str(&value).ok()?;
+                let version = versions.get(&key).copied().unwrap_or_default();
+                Some((key, RoomStateEntry { value, version }))
+            })
+            .collect()
+    }
+
+    /// Apply a shared state update in Redis and tell the room on every server
+    async fn update_state_in_redis(
+        &self,
+        room_id: &str,
+        user_id: u32,
+        update: RoomStateUpdate,
+    ) -> Result<u64, RoomStateError> {
+        update.validate()?;
+        let failed = |e: redis::RedisError| RoomStateError::Failed(format!("Redis error: {}", e));
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(failed)?;
+        let room_key = format!("rooms:{}:participants", room_id);
+        let present: bool = conn
+            .hexists(&room_key, user_id.to_string())
+            .await
+            .map_err(failed)?;
+        if !present {
+            return Err(RoomStateError::NotInRoom);
+        }
+
+        let value_json = update
+            .value
+            .as_ref()
+            .map_or(String::new(), |value| value.to_string());
+        let (outcome, version): (String, u64) = redis::Script::new(UPDATE_STATE_SCRIPT)
+            .key(format!("rooms:{}:state", room_id))
+            .key(format!("rooms:{}:state_versions", room_id))
+            .key(format!("rooms:{}:state_version", room_id))
+            .arg(&update.key)
+            .arg(update.expected_version.map_or(-1, |version| version as i64))
+            .arg(value_json)
+            .arg(MAX_KEYS)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(failed)?;
+        match outcome.as_str() {
+            "conflict" => return Err(RoomStateError::VersionConflict { current: version }),
+            "missing" => return Err(RoomStateError::KeyNotFound),
+            "full" => return Err(RoomStateError::TooManyKeys),
+            _ => {}
+        }
+
+        self.publish(&ClusterMessage::RoomEvent {
+            room_id: room_id.to_string(),
+            message: ServerMessage::RoomStateChanged {
+                room_name: room_id.to_string(),
+                key: update.key,
+                value: update.value,
+                version,
+                by_user_id: user_id,
+            },
+            target_user: None,
+            exclude_user: None,
+        })
+        .await
+        .map_err(RoomStateError::Failed)?;
+        Ok(version)
+    }
+
+    /// Hand a rejoining user's place in the room to their new connection and have
+    /// the server holding the old one close it. `None` when the user has no other
+    /// connection in the room.
+    async fn replace_in_redis(
+        &self,
+        room_id: &str,
+        participant: &RoomParticipant,
+    ) -> Option<JoinOutcome> {
+        let user_id = participant.user.user_id;
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .ok()?;
+        let room_key = format!("rooms:{}:participants", room_id);
+        let stale_node: String = conn
+            .hget::<_, _, Option<String>>(&room_key, user_id.to_string())
+            .await
+            .ok()??;
+        let stale_key = format!("servers:{}:connections", stale_node);
+        let stale = conn
+            .hget::<_, _, Option<String>>(&stale_key, user_id.to_string())
+            .await
+            .ok()?
+            .and_then(|json| serde_json::from_str::<ConnectionInfo>(&json).ok())
+            .filter(|info| info.room_id == room_id)?;
+        if stale.connection_id == participant.connection_id {
+            return None;
+        }
+
+        // The old connection must not take the room with it when it closes
+        if stale_node != self.node_id {
+            let _: Result<(), _> = conn.hdel(&stale_key, user_id.to_string()).await;
+        }
+        if let Err(e) = conn
+            .hset::<_, _, _, ()>(&room_key, user_id.to_string(), &self.node_id)
+            .await
+        {
+            warn!("Failed to replace user {} in Redis: {}", user_id, e);
+            return None;
+        }
+        if let Err(e) =
+            Self::record_connection(&mut conn, &self.node_id, room_id, participant).await
+        {
+            warn!("Failed to record connection in Redis: {}", e);
+        }
+        self.local_connections
+            .write()
+            .await
+            .insert(user_id, participant.clone());
+        info!(
+            "Cluster: User {} rejoined room {}, replacing connection {} on {}",
+            user_id, room_id, stale.connection_id, stale_node
+        );
+
+        let replaced = ClusterMessage::ConnectionReplaced {
+            node_id: stale_node,
+            user_id,
+            connection_id: stale.connection_id,
+        };
+        if let Err(e) = self.publish(&replaced).await {
+            warn!("{}", e);
+        }
+        let role = Self::role_in_redis(&mut conn, room_id, user_id).await;
+        if !role.is_spectator() {
+            let event = ClusterMessage::RoomEvent {
+                room_id: room_id.to_string(),
+                message: ServerMessage::ParticipantReplaced {
+                    room_name: room_id.to_string(),
+                    user_id,
+                },
+                target_user: None,
+                exclude_user: Some(user_id),
+            };
+            if let Err(e) = self.publish(&event).await {
+                warn!("{}", e);
+            }
+        }
+        if role.can_moderate() {
+            self.send_knocks_in_redis(room_id, participant).await;
+        }
+
+        let existing_participants: Vec<Participant> = self
+            .get_existing_participants_from_redis(room_id)
+            .await
+            .into_iter()
+            .filter(|p| p.user_id != user_id)
+            .collect();
+        let spectator: bool = conn
+            .sismember(format!("rooms:{}:spectators", room_id), user_id.to_string())
+            .await
+            .unwrap_or(false);
+        Some(if spectator {
+            JoinOutcome::Spectating(existing_participants)
+        } else {
+            JoinOutcome::Joined(existing_participants)
+        })
+    }
+
+    /// Put a user in the room's Redis lobby and tell the moderators on every server
+    async fn knock_in_redis(
+        &self,
+        room_id: &str,
+        participant: RoomParticipant,
+        role: RoomRole,
+        timeout: Duration,
+        max_duration: Option<Duration>,
+    ) -> Result<JoinOutcome, JoinError> {
+        let user_id = participant.user.user_id;
+        let entry = self.waitlist_entry(&participant, role, max_duration);
+        let conn = self.redis_client.get_multiplexed_async_connection().await;
+        let (Ok(entry), Ok(mut conn)) = (entry, conn) else {
+            warn!("Failed to register knock in Redis, using the local lobby");
+            return self
+                .local_manager
+                .add_participant(room_id.to_string(), participant, role, max_duration)
+                .await;
+        };
+
+        let lobby_key = format!("rooms:{}:lobby", room_id);
+        let added: bool = conn
+            .hset_nx(&lobby_key, user_id.to_string(), &entry)
+            .await
+            .unwrap_or(false);
+        if !added {
+            return Err(JoinError::AlreadyInRoom);
+        }
+
+        info!(
+            "Cluster: User {} is waiting in the lobby of room {}",
+            user_id, room_id
+        );
+        let knock = ServerMessage::Knock {
+            room_name: room_id.to_string(),
+            user: Participant {
+                user_id,
+                username: participant.user.username.clone(),
+            },
+        };
+        Self::notify_moderators_in_redis(&mut conn, room_id, knock).await;
+        self.expire_knock_in_redis(room_id, &participant, timeout);
+        self.local_lobby.write().await.insert(
+            user_id,
+            WaitingParticipant {
+                room_id: room_id.to_string(),
+                participant,
+                entry,
+            },
+        );
+        Ok(JoinOutcome::InLobby)
+    }
+
+    /// Refuse the join if the knock is still unanswered after `timeout`
+    fn expire_knock_in_redis(
+        &self,
+        room_id: &str,
+        participant: &RoomParticipant,
+        timeout: Duration,
+    ) {
+        let redis_client = self.redis_client.clone();
+        let local_lobby = Arc::clone(&self.local_lobby);
+        let room_id = room_id.to_string();
+        let user_id = participant.user.user_id;
+        let connection_id = participant.connection_id;
+
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let still_waiting = local_lobby
+                .read()
+                .await
+                .get(&user_id)
+                .is_some_and(|waiting| {
+                    waiting.room_id == room_id && waiting.participant.connection_id == connection_id
+                });
+            if !still_waiting {
+                return;
+            }
+            let Ok(mut conn) = redis_client.get_multiplexed_async_connection().await else {
+                return;
+            };
+
+            // A moderator may have answered on another server in the meantime
+            let lobby_key = format!("rooms:{}:lobby", room_id);
+            let removed: i64 = conn
+                .hdel(&lobby_key, user_id.to_string())
+                .await
+                .unwrap_or(0);
+            if removed == 0 {
+                return;
+            }
+            let Some(waiting) = Self::take_waiting(&local_lobby, &room_id, user_id).await else {
+                return;
+            };
+
+            info!(
+                "Cluster: Knock by user {} on room {} timed out",
+                user_id, room_id
+            );
+            let message = JoinError::LobbyTimeout.to_server_message(&room_id);
+            if let Ok(json_message) = serde_json::to_string(&message) {
+                let _ = waiting
+                    .participant
+                    .sender
+                    .try_send(Message::Text(json_message));
+            }
+            let resolved = ServerMessage::KnockResolved {
+                room_name: room_id.clone(),
+                user_id,
+                admitted: false,
+            };
+            Self::notify_moderators_in_redis(&mut conn, &room_id, resolved).await;
+        });
+    }
+
+    /// Deliver `message` to the room's host and moderators on every server
+    async fn notify_moderators_in_redis(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_id: &str,
+        message: ServerMessage,
+    ) {
+        let roles_key = format!("rooms:{}:roles", room_id);
+        let roles: HashMap<String, String> = conn.hgetall(&roles_key).await.unwrap_or_default();
+        let host = Self::ownership_in_redis(conn, room_id).await.host;
+
+        let mut moderators: Vec<u32> = roles
+            .iter()
+            .filter(|(_, role)| RoomRole::parse(role).is_some_and(|role| role.can_moderate()))
+            .filter_map(|(user_id, _)| user_id.parse().ok())
+            .chain(host)
+            .collect();
+        moderators.sort_unstable();
+        moderators.dedup();
+
+        for user_id in moderators {
+            let event = ClusterMessage::RoomEvent {
+                room_id: room_id.to_string(),
+                message: message.clone(),
+                target_user: Some(user_id),
+                exclude_user: None,
+            };
+            if let Ok(message_json) = serde_json::to_string(&event) {
+                if let Err(e) = conn
+                    .publish::<_, _, ()>("cluster:messages", message_json)
+                    .await
+                {
+                    warn!("Failed to notify moderators of room {}: {}", room_id, e);
+                }
+            }
+        }
+    }
+
+    /// Send the knocks still waiting in the lobby to a moderator who just joined
+    async fn send_knocks_in_redis(&self, room_id: &str, participant: &RoomParticipant) {
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+        let lobby_key = format!("rooms:{}:lobby", room_id);
+        let entries: Vec<String> = conn.hvals(&lobby_key).await.unwrap_or_default();
+        for entry in entries {
+            let Ok(entry) = serde_json::from_str::<WaitlistEntry>(&entry) else {
+                continue;
+            };
+            let knock = ServerMessage::Knock {
+                room_name: room_id.to_string(),
+                user: Participant {
+                    user_id: entry.user_id,
+                    username: entry.username,
+                },
+            };
+            if let Ok(json_message) = serde_json::to_string(&knock) {
+                let _ = participant.sender.try_send(Message::Text(json_message));
+            }
+        }
+    }
+
+    /// Withdraw a local user's knock, returning whether they were in a lobby
+    async fn leave_redis_lobby(
+        &self,
+        user_id: u32,
+        matches: impl Fn(&WaitingParticipant) -> bool,
+    ) -> bool {
+        let knocking = {
+            let mut lobby = self.local_lobby.write().await;
+            match lobby.get(&user_id) {
+                Some(knocking) if matches(knocking) => lobby.remove(&user_id),
+                _ => None,
+            }
+        };
+        let Some(knocking) = knocking else {
+            return false;
+        };
+
+        if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
+            let lobby_key = format!("rooms:{}:lobby", knocking.room_id);
+            let removed: i64 = conn
+                .hdel(&lobby_key, user_id.to_string())
+                .await
+                .unwrap_or(0);
+            if removed > 0 {
+                let resolved = ServerMessage::KnockResolved {
+                    room_name: knocking.room_id.clone(),
+                    user_id,
+                    admitted: false,
+                };
+                Self::notify_moderators_in_redis(&mut conn, &knocking.room_id, resolved).await;
+            }
+        }
+        debug!(
+            "Cluster: User {} left the lobby of room {}",
+            user_id, knocking.room_id
+        );
+        true
+    }
+
+    /// Claim a slot for a user admitted from the lobby and tell the server holding
+    /// their connection how it went
+    async fn admit_knock_in_redis(
+        &self,
+        room_id: &str,
+        entry: &str,
+    ) -> Result<(), ModerationError> {
+        let failed =
+            |e: &dyn std::fmt::Display| ModerationError::Failed(format!("Redis error: {}", e));
+        let knock: WaitlistEntry = serde_json::from_str(entry).map_err(|e| failed(&e))?;
+        let config = self.local_manager.room_config(room_id).await;
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| failed(&e))?;
+        let role = if knock.spectator {
+            RoomRole::Spectator
+        } else {
+            RoomRole::Participant
+        };
+        let (status, value) = Self::claim_slot_in_redis(
+            &mut conn,
+            room_id,
+            knock.user_id,
+            &knock.node_id,
+            entry,
+            role,
+            &config,
+        )
+        .await
+        .map_err(|e| failed(&e))?;
+        if matches!(status.as_str(), "joined" | "spectator") {
+            // The admission may be what opened the room's session
+            let max_duration = knock.max_duration_secs.map(Duration::from_secs);
+            self.schedule_close_in_redis(room_id, &config, max_duration, true)
+                .await;
+        }
+
+        let message = match status.as_str() {
+            "joined" | "spectator" if knock.spectator => {
+                let roles_key = format!("rooms:{}:roles", room_id);
+                let _: () = conn
+                    .hset(&roles_key, knock.user_id.to_string(), role.as_str())
+                    .await
+                    .map_err(|e| failed(&e))?;
+                let participants = self.get_existing_participants_from_redis(room_id).await;
+                self.emit_joined(room_id, knock.user_id, knock.username)
+                    .await;
+                ClusterMessage::WaitlistAdmitted {
+                    room_id: room_id.to_string(),
+                    user_id: knock.user_id,
+                    participants,
+                    target_server: knock.node_id,
+                    spectator: true,
+                }
+            }
+            "joined" | "spectator" => {
+                let host_changed = self
+                    .settle_host_in_redis(room_id, knock.user_id, false, config.host_transfer)
+                    .await;
+                let participants = self
+                    .get_existing_participants_from_redis(room_id)
+                    .await
+                    .into_iter()
+                    .filter(|p| p.user_id != knock.user_id)
+                    .collect();
+                self.publish(&ClusterMessage::WaitlistAdmitted {
+                    room_id: room_id.to_string(),
+                    user_id: knock.user_id,
+                    participants,
+                    target_server: knock.node_id,
+                    spectator: status == "spectator",
+                })
+                .await
+                .map_err(ModerationError::Failed)?;
+                self.publish(&ClusterMessage::UserJoined {
+                    room_id: room_id.to_string(),
+                    user_id: knock.user_id,
+                    username: knock.username.clone(),
+                    target_server: None,
+                })
+                .await
+                .map_err(ModerationError::Failed)?;
+                self.emit_joined(room_id, knock.user_id, knock.username)
+                    .await;
+                let Some(host_changed) = host_changed else {
+                    return Ok(());
+                };
+                ClusterMessage::RoomEvent {
+                    room_id: room_id.to_string(),
+                    message: host_changed,
+                    target_user: None,
+                    exclude_user: Some(knock.user_id),
+                }
+            }
+            "waitlisted" => ClusterMessage::LobbyWaitlisted {
+                room_id: room_id.to_string(),
+                user_id: knock.user_id,
+                position: value as usize,
+                target_server: knock.node_id,
+            },
+            _ => ClusterMessage::Removed {
+                room_id: room_id.to_string(),
+                user_id: knock.user_id,
+                message: JoinError::RoomFull {
+                    capacity: value as usize,
+                }
+                .to_server_message(room_id),
+            },
+        };
+        self.publish(&message)
+            .await
+            .map_err(ModerationError::Failed)
+    }
+
+    /// Drop a local user's queued join, returning whether they were waiting
+    async fn leave_redis_waitlist(
+        &self,
+        user_id: u32,
+        matches: impl Fn(&WaitingParticipant) -> bool,
+    ) -> bool {
+        let waiting = {
+            let mut waitlist = self.local_waitlist.write().await;
+            match waitlist.get(&user_id) {
+                Some(waiting) if matches(waiting) => waitlist.remove(&user_id),
+                _ => None,
+            }
+        };
+        let Some(waiting) = waiting else {
+            return false;
+        };
+
+        if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
+            let waitlist_key = format!("rooms:{}:waitlist", waiting.room_id);
+            let _: Result<(), _> = conn.lrem(&waitlist_key, 1, &waiting.entry).await;
+            let roles_key = format!("rooms:{}:roles", waiting.room_id);
+            let _: Result<(), _> = conn.hdel(&roles_key, user_id.to_string()).await;
+        }
+        debug!(
+            "Cluster: User {} left the waitlist of room {}",
+            user_id, waiting.room_id
+        );
+        true
+    }
+
+    /// Remove user from Redis room registry
+    async fn unregister_user_from_redis(
+        &self,
+        room_id: &str,
+        user_id: u32,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.redis_client.get_multiplexed_async_connection().await?;
+
+        // Remove from room participants
+        let room_key = format!("rooms:{}:participants", room_id);
+        let _: () = conn.hdel(&room_key, user_id.to_string()).await?;
+        let spectators_key = format!("rooms:{}:spectators", room_id);
+        let _: () = conn.srem(&spectators_key, user_id.to_string()).await?;
+        let roles_key = format!("rooms:{}:roles", room_id);
+        let _: () = conn.hdel(&roles_key, user_id.to_string()).await?;
+        record_departure(&mut conn, room_id, user_id).await;
+
+        // Remove from server connections
+        let server_key = format!("servers:{}:connections", self.node_id);
+        let _: () = conn.hdel(&server_key, user_id.to_string()).await?;
+
+        Ok(())
+    }
+
+    /// Get existing participants from Redis, leaving out spectators by role
+    async fn get_existing_participants_from_redis(&self, room_id: &str) -> Vec<Participant> {
+        Self::participants_in_redis(&self.redis_client, room_id).await
+    }
+
+    async fn participants_in_redis(redis_client: &RedisClient, room_id: &str) -> Vec<Participant> {
+        match redis_client.get_multiplexed_async_connection().await {
+            Ok(mut conn) => {
+                let room_key = format!("rooms:{}:participants", room_id);
+                let roles_key = format!("rooms:{}:roles", room_id);
+                let roles: HashMap<String, String> =
+                    conn.hgetall(&roles_key).await.unwrap_or_default();
+
+                match conn.hgetall::<_, HashMap<String, String>>(&room_key).await {
+                    Ok(participants_map) => {
+                        let mut participants = Vec::new();
+
+                        let announced = participants_map.into_iter().filter(|(user_id, _)| {
+                            roles.get(user_id).map(String::as_str)
+                                != Some(RoomRole::Spectator.as_str())
+                        });
+                        for (user_id_str, server_node) in announced {
+                            if let Ok(user_id) = user_id_str.parse::<u32>() {
+                                // Get username from server's connection list
+                                if let Ok(username) = Self::get_username_from_server(
+                                    redis_client,
+                                    &server_node,
+                                    user_id,
+                                )
+                                .await
+                                {
+                                    participants.push(Participant { user_id, username });
+                                }
+                            }
+                        }
+
+                        participants
+                    }
+                    Err(e) => {
+                        warn!("Failed to get room participants from Redis: {}", e);
+                        Vec::new()
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to connect to Redis: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Get username from a server's connection list
+    async fn get_username_from_server(
+        redis_client: &RedisClient,
+        server_node: &str,
+        user_id: u32,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = redis_client.get_multiplexed_async_connection().await?;
+        let server_key = format!("servers:{}:connections", server_node);
+
+        let connection_json: String = conn.hget(&server_key, user_id.to_string()).await?;
+        let connection_info: ConnectionInfo = serde_json::from_str(&connection_json)?;
+
+        Ok(connection_info.username)
+    }
+
+    /// Publish a message to every server in the cluster
+    async fn publish(&self, message: &ClusterMessage) -> Result<(), String> {
+        let failed =
+            |e: &dyn std::fmt::Display| format!("Failed to publish cluster message: {}", e);
+        let message_json = serde_json::to_string(message).map_err(|e| failed(&e))?;
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| failed(&e))?;
+        conn.publish::<_, _, ()>("cluster:messages", message_json)
+            .await
+            .map_err(|e| failed(&e))
+    }
+
+    /// Deliver `message` to every connection of the call's caller and callee
+    async fn publish_to_call(
+        conn: &mut redis::aio::MultiplexedConnection,
+        invitation: &CallInvitation,
+        message: &ServerMessage,
+    ) {
+        for user_id in invitation.user_ids() {
+            let event = ClusterMessage::UserEvent {
+                tenant: invitation.tenant.clone(),
+                user_id,
+                message: message.clone(),
+            };
+            if let Ok(message_json) = serde_json::to_string(&event) {
+                if let Err(e) = conn
+                    .publish::<_, _, ()>("cluster:messages", message_json)
+                    .await
+                {
+                    warn!("Failed to publish call {}: {}", invitation.call_id, e);
+                }
+            }
+        }
+    }
+
+    /// End the call if it is still ringing after `timeout`, wherever it was rung from
+    fn expire_call_in_redis(&self, invitation: &CallInvitation, timeout: Duration) {
+        let redis_client = self.redis_client.clone();
+        let invitation = invitation.clone();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let Ok(mut conn) = redis_client.get_multiplexed_async_connection().await else {
+                return;
+            };
+            let call_key = format!("calls:{}", invitation.call_id);
+            let removed: i64 = conn.del(&call_key).await.unwrap_or(0);
+            if removed == 0 {
+                return;
+            }
+
+            info!(
+                "Cluster: Call {} from user {} timed out",
+                invitation.call_id, invitation.caller.user_id
+            );
+            let ended = invitation.ended(CallEndReason::Timeout);
+            Self::publish_to_call(&mut conn, &invitation, &ended).await;
+        });
+    }
+
+    /// Refuse joins from banned users and, while the room is locked, non-moderators
+    async fn check_room_restrictions_in_redis(
+        &self,
+        room_id: &str,
+        user_id: u32,
+        role: RoomRole,
+    ) -> Result<(), JoinError> {
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return Ok(());
+        };
+
+        let bans_key = format!("rooms:{}:bans", room_id);
+        let expires: Option<i64> = conn
+            .hget(&bans_key, user_id.to_string())
+            .await
+            .unwrap_or(None);
+        match expires {
+            Some(0) => return Err(JoinError::Banned { remaining: None }),
+            Some(expires) if expires > Utc::now().timestamp() => {
+                let remaining = (expires - Utc::now().timestamp()) as u64;
+                return Err(JoinError::Banned {
+                    remaining: Some(Duration::from_secs(remaining)),
+                });
+            }
+            Some(_) => {
+                let _: Result<(), _> = conn.hdel(&bans_key, user_id.to_string()).await;
+            }
+            None => {}
+        }
+
+        let invited_key = format!("rooms:{}:invited", room_id);
+        let (private, invited): (bool, bool) = redis::pipe()
+            .exists(&invited_key)
+            .sismember(&invited_key, user_id.to_string())
+            .query_async(&mut conn)
+            .await
+            .unwrap_or((false, false));
+        if private && !invited {
+            return Err(JoinError::NotInvited);
+        }
+
+        let locked_key = format!("rooms:{}:locked", room_id);
+        let locked: bool = conn.exists(&locked_key).await.unwrap_or(false);
+        if locked && !role.can_moderate() {
+            return Err(JoinError::RoomLocked);
+        }
+        Ok(())
+    }
+
+    /// Role recorded for the user when they joined
+    async fn role_in_redis(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_id: &str,
+        user_id: u32,
+    ) -> RoomRole {
+        let host_key = format!("rooms:{}:host", room_id);
+        let host: Option<u32> = conn.hget(&host_key, "host").await.unwrap_or(None);
+        if host == Some(user_id) {
+            return RoomRole::Host;
+        }
+
+        let roles_key = format!("rooms:{}:roles", room_id);
+        conn.hget::<_, _, Option<String>>(&roles_key, user_id.to_string())
+            .await
+            .ok()
+            .flatten()
+            .and_then(|role| RoomRole::parse(&role))
+            .unwrap_or_default()
+    }
+
+    /// Apply a moderator action through Redis so it takes effect on every server
+    async fn moderate_in_redis(
+        &self,
+        room_id: &str,
+        actor_id: u32,
+        action: ModeratorAction,
+    ) -> Result<(), ModerationError> {
+        let failed = |e: redis::RedisError| ModerationError::Failed(format!("Redis error: {}", e));
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(failed)?;
+        let room_key = format!("rooms:{}:participants", room_id);
+
+        let actor_present: bool = conn
+            .hexists(&room_key, actor_id.to_string())
+            .await
+            .map_err(failed)?;
+        if !actor_present {
+            return Err(ModerationError::NotInRoom);
+        }
+        let actor_role = Self::role_in_redis(&mut conn, room_id, actor_id).await;
+        let target_role = match action.target() {
+            Some(target) => Self::role_in_redis(&mut conn, room_id, target).await,
+            None => RoomRole::Participant,
+        };
+        authorize(&action, actor_role, |_| target_role)?;
+
+        let target_present = match action.target() {
+            Some(target) => conn
+                .hexists(&room_key, target.to_string())
+                .await
+                .map_err(failed)?,
+            None => false,
+        };
+
+        match action {
+            ModeratorAction::Kick { target, reason } => {
+                if !target_present {
+                    return Err(ModerationError::TargetNotFound);
+                }
+                info!("User {} kicked {} from room {}", actor_id, target, room_id);
+                let kicked = ServerMessage::Kicked {
+                    room_name: room_id.to_string(),
+                    by_user_id: actor_id,
+                    reason,
+                };
+                self.remove_from_redis_room(room_id, target, kicked, LeaveReason::Kicked)
+                    .await
+            }
+            ModeratorAction::Ban {
+                target,
+                duration,
+                reason,
+            } => {
+                info!(
+                    "User {} banned {} from room {} for {:?}",
+                    actor_id, target, room_id, duration
+                );
+                let bans_key = format!("rooms:{}:bans", room_id);
+                let expires = duration.map_or(0, |duration| {
+                    Utc::now().timestamp() + duration.as_secs() as i64
+                });
+                let _: () = conn
+                    .hset(&bans_key, target.to_string(), expires)
+                    .await
+                    .map_err(failed)?;
+
+                let banned = ServerMessage::Banned {
+                    room_name: room_id.to_string(),
+                    by_user_id: actor_id,
+                    reason,
+                    duration_seconds: duration.map(|duration| duration.as_secs()),
+                };
+                if target_present {
+                    return self
+                        .remove_from_redis_room(room_id, target, banned, LeaveReason::Banned)
+                        .await;
+                }
+
+                // Drop the user's queued join or knock, if any
+                let waitlist_key = format!("rooms:{}:waitlist", room_id);
+                let entries: Vec<String> =
+                    conn.lrange(&waitlist_key, 0, -1).await.map_err(failed)?;
+                let waiting = entries.iter().any(|entry| {
+                    serde_json::from_str::<WaitlistEntry>(entry)
+                        .is_ok_and(|entry| entry.user_id == target)
+                });
+                let lobby_key = format!("rooms:{}:lobby", room_id);
+                let knocking: i64 = conn
+                    .hdel(&lobby_key, target.to_string())
+                    .await
+                    .map_err(failed)?;
+                if knocking > 0 {
+                    let resolved = ServerMessage::KnockResolved {
+                        room_name: room_id.to_string(),
+                        user_id: target,
+                        admitted: false,
+                    };
+                    Self::notify_moderators_in_redis(&mut conn, room_id, resolved).await;
+                }
+                if waiting || knocking > 0 {
+                    self.publish(&ClusterMessage::Removed {
+                        room_id: room_id.to_string(),
+                        user_id: target,
+                        message: banned,
+                    })
+                    .await
+                    .map_err(ModerationError::Failed)?;
+                }
+                Ok(())
+            }
+            ModeratorAction::RequestMute { target, media } => {
+                if !target_present {
+                    return Err(ModerationError::TargetNotFound);
+                }
+                self.publish(&ClusterMessage::RoomEvent {
+                    room_id: room_id.to_string(),
+                    message: ServerMessage::MuteRequested {
+                        room_name: room_id.to_string(),
+                        by_user_id: actor_id,
+                        media,
+                    },
+                    target_user: Some(target),
+                    exclude_user: None,
+                })
+                .await
+                .map_err(ModerationError::Failed)
+            }
+            ModeratorAction::TransferHost { target } => {
+                if !target_present {
+                    return Err(ModerationError::TargetNotFound);
+                }
+                info!(
+                    "Cluster: User {} handed host of room {} to {}",
+                    actor_id, room_id, target
+                );
+                let host_key = format!("rooms:{}:host", room_id);
+                let _: () = conn.hset(&host_key, "host", target).await.map_err(failed)?;
+                self.publish(&ClusterMessage::RoomEvent {
+                    room_id: room_id.to_string(),
+                    message: ServerMessage::HostChanged {
+                        room_name: room_id.to_string(),
+                        host_user_id: Some(target),
+                        previous_host_user_id: Some(actor_id),
+                    },
+                    target_user: None,
+                    exclude_user: None,
+                })
+                .await
+                .map_err(ModerationError::Failed)
+            }
+            ModeratorAction::Promote { target } => {
+                let node_id: Option<String> = conn
+                    .hget(&room_key, target.to_string())
+                    .await
+                    .map_err(failed)?;
+                let Some(node_id) = node_id else {
+                    return Err(ModerationError::TargetNotFound);
+                };
+                if !target_role.is_spectator() {
+                    return Err(ModerationError::Invalid(format!(
+                        "User {} is not a spectator",
+                        target
+                    )));
+                }
+                let _: () = conn
+                    .hdel(format!("rooms:{}:roles", room_id), target.to_string())
+                    .await
+                    .map_err(failed)?;
+                let _: () = conn
+                    .srem(format!("rooms:{}:spectators", room_id), target.to_string())
+                    .await
+                    .map_err(failed)?;
+                info!(
+                    "Cluster: User {} promoted spectator {} in room {}",
+                    actor_id, target, room_id
+                );
+
+                let config = self.local_manager.room_config(room_id).await;
+                let host_changed = self
+                    .settle_host_in_redis(room_id, target, false, config.host_transfer)
+                    .await;
+                let joined = Self::get_username_from_server(&self.redis_client, &node_id, target)
+                    .await
+                    .ok()
+                    .map(|username| ServerMessage::UserJoined {
+                        room_name: room_id.to_string(),
+                        user: Participant {
+                            user_id: target,
+                            username,
+                        },
+                    });
+                let promoted = ServerMessage::Promoted {
+                    room_name: room_id.to_string(),
+                    by_user_id: actor_id,
+                };
+                let events = [(promoted, Some(target), None)]
+                    .into_iter()
+                    .chain(joined.map(|message| (message, None, Some(target))))
+                    .chain(host_changed.map(|message| (message, None, None)));
+                for (message, target_user, exclude_user) in events {
+                    self.publish(&ClusterMessage::RoomEvent {
+                        room_id: room_id.to_string(),
+                        message,
+                        target_user,
+                        exclude_user,
+                    })
+                    .await
+                    .map_err(ModerationError::Failed)?;
+                }
+                self.update_directory(room_id).await;
+                Ok(())
+            }
+            ModeratorAction::Admit { target } => {
+                let lobby_key = format!("rooms:{}:lobby", room_id);
+                let entry: Option<String> = conn
+                    .hget(&lobby_key, target.to_string())
+                    .await
+                    .map_err(failed)?;
+                let removed: i64 = conn
+                    .hdel(&lobby_key, target.to_string())
+                    .await
+                    .map_err(failed)?;
+                let Some(entry) = entry.filter(|_| removed > 0) else {
+                    return Err(ModerationError::TargetNotFound);
+                };
+
+                if let Err(e) = self.local_manager.check_schedule(room_id).await {
+                    // Outside the room's window the knock is refused instead
+                    let resolved = ServerMessage::KnockResolved {
+                        room_name: room_id.to_string(),
+                        user_id: target,
+                        admitted: false,
+                    };
+                    Self::notify_moderators_in_redis(&mut conn, room_id, resolved).await;
+                    self.publish(&ClusterMessage::Removed {
+                        room_id: room_id.to_string(),
+                        user_id: target,
+                        message: e.to_server_message(room_id),
+                    })
+                    .await
+                    .map_err(ModerationError::Failed)?;
+                    self.release_room_in_redis(room_id).await;
+                    return Ok(());
+                }
+
+                info!(
+                    "Cluster: User {} admitted {} to room {}",
+                    actor_id, target, room_id
+                );
+                let resolved = ServerMessage::KnockResolved {
+                    room_name: room_id.to_string(),
+                    user_id: target,
+                    admitted: true,
+                };
+                Self::notify_moderators_in_redis(&mut conn, room_id, resolved).await;
+                self.admit_knock_in_redis(room_id, &entry).await
+            }
+            ModeratorAction::Deny { target, reason } => {
+                let lobby_key = format!("rooms:{}:lobby", room_id);
+                let removed: i64 = conn
+                    .hdel(&lobby_key, target.to_string())
+                    .await
+                    .map_err(failed)?;
+                if removed == 0 {
+                    return Err(ModerationError::TargetNotFound);
+                }
+
+                info!(
+                    "Cluster: User {} denied {} entry to room {}",
+                    actor_id, target, room_id
+                );
+                let resolved = ServerMessage::KnockResolved {
+                    room_name: room_id.to_string(),
+                    user_id: target,
+                    admitted: false,
+                };
+                Self::notify_moderators_in_redis(&mut conn, room_id, resolved).await;
+                self.publish(&ClusterMessage::Removed {
+                    room_id: room_id.to_string(),
+                    user_id: target,
+                    message: JoinError::LobbyDenied { reason }.to_server_message(room_id),
+                })
+                .await
+                .map_err(ModerationError::Failed)
+            }
+            ModeratorAction::OpenBreakouts { breakouts } => {
+                let members: Vec<String> = conn.hkeys(&room_key).await.map_err(failed)?;
+                let members: HashSet<u32> =
+                    members.iter().filter_map(|id| id.parse().ok()).collect();
+                validate_breakouts(&breakouts, |user_id| members.contains(&user_id))?;
+
+                let breakouts_key = format!("rooms:{}:breakouts", room_id);
+                for assignment in breakouts {
+                    let name = breakout_room_name(room_id, &assignment.name);
+                    let added: i64 = conn.sadd(&breakouts_key, &name).await.map_err(failed)?;
+                    if added > 0 {
+                        info!("Cluster: Opened breakout room {}", name);
+                    }
+                    for user_id in assignment.user_ids {
+                        self.move_in_redis(room_id, &name, user_id).await?;
+                    }
+                }
+                Ok(())
+            }
+            ModeratorAction::CloseBreakouts => {
+                let breakouts_key = format!("rooms:{}:breakouts", room_id);
+                let names: Vec<String> = conn.smembers(&breakouts_key).await.map_err(failed)?;
+                for name in names {
+                    let breakout_key = format!("rooms:{}:participants", name);
+                    let user_ids: Vec<String> = conn.hkeys(&breakout_key).await.map_err(failed)?;
+                    let mut user_ids: Vec<u32> =
+                        user_ids.iter().filter_map(|id| id.parse().ok()).collect();
+                    user_ids.sort_unstable();
+                    for user_id in user_ids {
+                        self.move_in_redis(&name, room_id, user_id).await?;
+                    }
+                    info!("Cluster: Closed breakout room {}", name);
+                }
+                let _: () = conn.del(&breakouts_key).await.map_err(failed)?;
+                Ok(())
+            }
+            ModeratorAction::Lock { locked } => {
+                info!("User {} set room {} locked={}", actor_id, room_id, locked);
+                let locked_key = format!("rooms:{}:locked", room_id);
+                if locked {
+                    let _: () = conn.set(&locked_key, actor_id).await.map_err(failed)?;
+                } else {
+                    let _: () = conn.del(&locked_key).await.map_err(failed)?;
+                }
+                self.publish(&ClusterMessage::RoomEvent {
+                    room_id: room_id.to_string(),
+                    message: ServerMessage::RoomLocked {
+                        room_name: room_id.to_string(),
+                        locked,
+                        by_user_id: actor_id,
+                    },
+                    target_user: None,
+                    exclude_user: None,
+                })
+                .await
+                .map_err(ModerationError::Failed)
+            }
+        }
+    }
+
+    /// Take a participant out of the Redis room registry on behalf of a moderator,
+    /// notifying them and the remaining participants
+    async fn remove_from_redis_room(
+        &self,
+        room_id: &str,
+        user_id: u32,
+        message: ServerMessage,
+        reason: LeaveReason,
+    ) -> Result<(), ModerationError> {
+        let failed = |e: redis::RedisError| ModerationError::Failed(format!("Redis error: {}", e));
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(failed)?;
+
+        let room_key = format!("rooms:{}:participants", room_id);
+        let node_id: Option<String> = conn
+            .hget(&room_key, user_id.to_string())
+            .await
+            .map_err(failed)?;
+        let spectator = Self::role_in_redis(&mut conn, room_id, user_id)
+            .await
+            .is_spectator();
+        let _: () = conn
+            .hdel(&room_key, user_id.to_string())
+            .await
+            .map_err(failed)?;
+        let spectators_key = format!("rooms:{}:spectators", room_id);
+        let _: () = conn
+            .srem(&spectators_key, user_id.to_string())
+            .await
+            .map_err(failed)?;
+        let roles_key = format!("rooms:{}:roles", room_id);
+        let _: () = conn
+            .hdel(&roles_key, user_id.to_string())
+            .await
+            .map_err(failed)?;
+        record_departure(&mut conn, room_id, user_id).await;
+        if let Some(node_id) = node_id {
+            let server_key = format!("servers:{}:connections", node_id);
+            let _: () = conn
+                .hdel(&server_key, user_id.to_string())
+                .await
+                .map_err(failed)?;
+        }
+
+        self.publish(&ClusterMessage::Removed {
+            room_id: room_id.to_string(),
+            user_id,
+            message,
+        })
+        .await
+        .map_err(ModerationError::Failed)?;
+        if !spectator {
+            self.publish(&ClusterMessage::RoomEvent {
+                room_id: room_id.to_string(),
+                message: ServerMessage::UserLeft {
+                    room_name: room_id.to_string(),
+                    user_id,
+                    reason: Some(reason),
+                },
+                target_user: None,
+                exclude_user: None,
+            })
+            .await
+            .map_err(ModerationError::Failed)?;
+        }
+
+        self.emit_left(room_id, user_id).await;
+        self.reassign_host_in_redis(room_id, user_id).await;
+        self.admit_from_redis_waitlist(room_id).await;
+        self.release_room_in_redis(room_id).await;
+        Ok(())
+    }
+
+    /// Move a participant between rooms in the Redis registry, keeping their server
+    /// and role (the host role stays behind), and tell both rooms
+    async fn move_in_redis(
+        &self,
+        from_room: &str,
+        to_room: &str,
+        user_id: u32,
+    ) -> Result<(), ModerationError> {
+        let failed = |e: redis::RedisError| ModerationError::Failed(format!("Redis error: {}", e));
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(failed)?;
+
+        let from_key = format!("rooms:{}:participants", from_room);
+        let node_id: Option<String> = conn
+            .hget(&from_key, user_id.to_string())
+            .await
+            .map_err(failed)?;
+        let Some(node_id) = node_id else {
+            return Ok(());
+        };
+        let role = Self::role_in_redis(&mut conn, from_room, user_id)
+            .await
+            .min(RoomRole::Moderator);
+
+        let _: () = conn
+            .hdel(&from_key, user_id.to_string())
+            .await
+            .map_err(failed)?;
+        let _: () = conn
+            .srem(
+                format!("rooms:{}:spectators", from_room),
+                user_id.to_string(),
+            )
+            .await
+            .map_err(failed)?;
+        let _: () = conn
+            .hdel(format!("rooms:{}:roles", from_room), user_id.to_string())
+            .await
+            .map_err(failed)?;
+        let _: () = conn
+            .zrem(format!("rooms:{}:joined", from_room), user_id.to_string())
+            .await
+            .map_err(failed)?;
+        record_departure(&mut conn, from_room, user_id).await;
+
+        let _: () = conn
+            .hset(
+                format!("rooms:{}:participants", to_room),
+                user_id.to_string(),
+                &node_id,
+            )
+            .await
+            .map_err(failed)?;
+        if role != RoomRole::Participant {
+            let _: () = conn
+                .hset(
+                    format!("rooms:{}:roles", to_room),
+                    user_id.to_string(),
+                    role.as_str(),
+                )
+                .await
+                .map_err(failed)?;
+        }
+        if role.is_spectator() {
+            let _: () = conn
+                .sadd(format!("rooms:{}:spectators", to_room), user_id.to_string())
+                .await
+                .map_err(failed)?;
+        }
+        let server_key = format!("servers:{}:connections", node_id);
+        let connection: Option<String> = conn
+            .hget(&server_key, user_id.to_string())
+            .await
+            .map_err(failed)?;
+        let mut connection = connection
+            .and_then(|connection| serde_json::from_str::<ConnectionInfo>(&connection).ok());
+        if let Some(connection) = connection.as_mut() {
+            connection.room_id = to_room.to_string();
+            if let Ok(connection_json) = serde_json::to_string(connection) {
+                let _: () = conn
+                    .hset(&server_key, user_id.to_string(), connection_json)
+                    .await
+                    .map_err(failed)?;
+            }
+        }
+        info!(
+            "Cluster: Moved user {} from room {} to {}",
+            user_id, from_room, to_room
+        );
+
+        if !role.is_spectator() {
+            self.publish(&ClusterMessage::RoomEvent {
+                room_id: from_room.to_string(),
+                message: ServerMessage::UserLeft {
+                    room_name: from_room.to_string(),
+                    user_id,
+                    reason: Some(LeaveReason::Moved),
+                },
+                target_user: None,
+                exclude_user: None,
+            })
+            .await
+            .map_err(ModerationError::Failed)?;
+        }
+        self.emit_left(from_room, user_id).await;
+        self.reassign_host_in_redis(from_room, user_id).await;
+        self.admit_from_redis_waitlist(from_room).await;
+        self.release_room_in_redis(from_room).await;
+
+        // Spectators arrive unannounced and never take the host role
+        let config = self.local_manager.room_config(to_room).await;
+        let host_changed = if role.is_spectator() {
+            None
+        } else {
+            self.settle_host_in_redis(to_room, user_id, false, config.host_transfer)
+                .await
+        };
+        let participants = self
+            .get_existing_participants_from_redis(to_room)
+            .await
+            .into_iter()
+            .filter(|p| p.user_id != user_id)
+            .collect();
+        let ownership = Self::ownership_in_redis(&mut conn, to_room).await;
+        let username = connection.map(|connection| connection.username);
+        if let Some(username) = &username {
+            self.emit_joined(to_room, user_id, username.clone()).await;
+        }
+
+        let joined =
+            username
+                .filter(|_| !role.is_spectator())
+                .map(|username| ServerMessage::UserJoined {
+                    room_name: to_room.to_string(),
+                    user: Participant { user_id, username },
+                });
+        let moved = ServerMessage::MovedToRoom {
+            from_room_name: from_room.to_string(),
+            room_name: to_room.to_string(),
+            participants,
+            owner_user_id: ownership.owner,
+            host_user_id: ownership.host,
+            state: Self::state_in_redis(&mut conn, to_room).await,
+        };
+        let events = joined
+            .into_iter()
+            .chain(host_changed)
+            .map(|message| (message, None, Some(user_id)))
+            .chain([(moved, Some(user_id), None)]);
+        for (message, target_user, exclude_user) in events {
+            self.publish(&ClusterMessage::RoomEvent {
+                room_id: to_room.to_string(),
+                message,
+                target_user,
+                exclude_user,
+            })
+            .await
+            .map_err(ModerationError::Failed)?;
+        }
+
+        // A moderator arriving takes over the room's pending knocks
+        if role.can_moderate() {
+            let lobby_key = format!("rooms:{}:lobby", to_room);
+            let entries: Vec<String> = conn.hvals(&lobby_key).await.unwrap_or_default();
+            for entry in entries {
+                let Ok(entry) = serde_json::from_str::<WaitlistEntry>(&entry) else {
+                    continue;
+                };
+                let knock = ServerMessage::Knock {
+                    room_name: to_room.to_string(),
+                    user: Participant {
+                        user_id: entry.user_id,
+                        username: entry.username,
+                    },
+                };
+                self.publish(&ClusterMessage::RoomEvent {
+                    room_id: to_room.to_string(),
+                    message: knock,
+                    target_user: Some(user_id),
+                    exclude_user: None,
+                })
+                .await
+                .map_err(ModerationError::Failed)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Clear any pending expiry on the room's keys now that it is in use again
+    async fn keep_room_in_redis(&self, room_id: &str) {
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+        let mut pipe = redis::pipe();
+        for key in room_keys(room_id) {
+            pipe.persist(key).ignore();
+        }
+        if let Err(e) = pipe.query_async::<_, ()>(&mut conn).await {
+            warn!("Failed to keep room {}: {}", room_id, e);
+        }
+    }
+
+    /// Check the room's password, counting failures in Redis under
+    /// `lockout:{room}:user:{id}` and `lockout:{room}:ip:{ip}`
+    async fn check_access_in_redis(
+        &self,
+        room_name: &str,
+        user_id: u32,
+        request: &JoinRequest,
+    ) -> Result<(), JoinError> {
+        let config = self.local_manager.room_config(room_name).await;
+        let Some(hash) = config.password_hash.clone() else {
+            return Ok(());
+        };
+        let conn = if self.is_redis_healthy().await {
+            self.redis_client
+                .get_multiplexed_async_connection()
+                .await
+                .ok()
+        } else {
+            None
+        };
+        let Some(mut conn) = conn else {
+            return self
+                .local_manager
+                .check_access(room_name, user_id, request)
+                .await;
+        };
+
+        let policy = self.local_manager.lockout_policy();
+        let user_key = format!("lockout:{}:user:{}", room_name, user_id);
+        let mut keys = vec![user_key.clone()];
+        keys.extend(
+            request
+                .client_ip
+                .map(|ip| format!("lockout:{}:ip:{}", room_name, ip)),
+        );
+
+        let mut pipe = redis::pipe();
+        for key in &keys {
+            pipe.get(key).pttl(key);
+        }
+        let counts: Vec<(Option<u32>, i64)> = pipe.query_async(&mut conn).await.unwrap_or_default();
+        let retry_after = counts
+            .into_iter()
+            .filter(|(failures, ttl)| failures.unwrap_or(0) >= policy.max_failures && *ttl > 0)
+            .map(|(_, ttl)| Duration::from_millis(ttl as u64))
+            .max();
+        if let Some(retry_after) = retry_after {
+            return Err(JoinError::LockedOut { retry_after });
+        }
+
+        let Some(password) = request.password.clone() else {
+            return Err(JoinError::PasswordRequired);
+        };
+        // Argon2 is deliberately slow; keep it off the async workers
+        let valid = tokio::task::spawn_blocking(move || access::verify_password(&hash, &password))
+            .await
+            .unwrap_or(false);
+
+        if valid {
+            // The address keeps its count, like the local guard
+            let _: Result<(), _> = conn.del(&user_key).await;
+            return Ok(());
+        }
+        warn!(
+            "Cluster: Invalid password for room {} from user {} ({:?})",
+            room_name, user_id, request.client_ip
+        );
+        let script = redis::Script::new(RECORD_FAILURE_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        for key in &keys {
+            invocation.key(key);
+        }
+        let locked: Result<i64, _> = invocation
+            .arg(policy.max_failures)
+            .arg(policy.window.as_millis() as u64)
+            .arg(policy.lockout.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await;
+        match locked {
+            Ok(1) => warn!(
+                "Cluster: Locking out user {} ({:?}) from room {}",
+                user_id, request.client_ip, room_name
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to record password failure in Redis: {}", e),
+        }
+        Err(JoinError::InvalidPassword)
+    }
 
-                        participants
-                    }
-                    Err(e) => {
-                        warn!("Failed to get room participants from Redis: {}", e);
-                        Vec::new()
-                    }
+    /// Delete the state of a room nobody is in, or let it expire after the room's
+    /// empty-room TTL. Persistent rooms are kept. Releasing a breakout room
+    /// detaches it from its parent, which is then checked in turn.
+    async fn release_room_in_redis(&self, room_id: &str) {
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return;
+        };
+        Self::release_empty_room(&mut conn, &self.room_configs, room_id).await;
+    }
+
+    async fn release_empty_room(
+        conn: &mut redis::aio::MultiplexedConnection,
+        room_configs: &RoomConfigStore,
+        room_id: &str,
+    ) {
+        let mut room_id = room_id.to_string();
+        loop {
+            let config = room_configs.get(&room_id).await;
+            let occupied: Result<(i64, i64, i64, i64), _> = redis::pipe()
+                .hlen(format!("rooms:{}:participants", room_id))
+                .hlen(format!("rooms:{}:lobby", room_id))
+                .llen(format!("rooms:{}:waitlist", room_id))
+                .scard(format!("rooms:{}:breakouts", room_id))
+                .query_async(conn)
+                .await;
+            match occupied {
+                Ok((0, 0, 0, 0)) => {}
+                Ok(_) => return,
+                Err(e) => {
+                    warn!("Failed to check whether room {} is empty: {}", room_id, e);
+                    return;
                 }
             }
-            Err(e) => {
-                warn!("Failed to connect to Redis: {}", e);
-                Vec::new()
+            // The session ends with the last participant
+            let _: Result<(), _> = conn.del(format!("rooms:{}:deadline", room_id)).await;
+            if config.persistent {
+                return;
             }
-        }
-    }
-
-    /// Get username from a server's connection list
-    async fn get_username_from_server(
-        &self,
-        server_node: &str,
-        user_id: u32,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        let mut conn = self.redis_client.get_multiplexed_async_connection().await?;
-        let server_key = format!("servers:{}:connections", server_node);
 
-        let connection_json: String = conn.hget(&server_key, user_id.to_string()).await?;
-        let connection_info: ConnectionInfo = serde_json::from_str(&connection_json)?;
+            let mut pipe = redis::pipe();
+            match config.empty_ttl() {
+                Some(ttl) => {
+                    for key in room_keys(&room_id) {
+                        pipe.expire(key, ttl.as_secs().max(1) as i64).ignore();
+                    }
+                }
+                None => {
+                    pipe.del(room_keys(&room_id)).ignore();
+                }
+            }
+            if let Err(e) = pipe.query_async::<_, ()>(conn).await {
+                warn!("Failed to release room {}: {}", room_id, e);
+                return;
+            }
+            debug!("Cluster: Released empty room {}", room_id);
 
-        Ok(connection_info.username)
+            let Some((parent_id, _)) = room_id.rsplit_once('/') else {
+                return;
+            };
+            let parent_id = parent_id.to_string();
+            let detached: i64 = conn
+                .srem(format!("rooms:{}:breakouts", parent_id), &room_id)
+                .await
+                .unwrap_or(0);
+            if detached == 0 {
+                return;
+            }
+            room_id = parent_id;
+        }
     }
 
     /// Check if Redis is healthy and we can use cluster mode
@@ -471,16 +3698,22 @@ impl ClusterRoomManager {
 
 #[async_trait::async_trait]
 impl RoomManagerTrait for ClusterRoomManager {
-    async fn join_room(
+    async fn join_room_with(
         &self,
         room_name: String,
         participant: RoomParticipant,
-    ) -> Result<Vec<Participant>, String> {
-        // Always add to local connections first
-        {
-            let mut connections = self.local_connections.write().await;
-            connections.insert(participant.user.user_id, participant.clone());
-        }
+        request: JoinRequest,
+    ) -> Result<JoinOutcome, JoinError> {
+        let user_id = participant.user.user_id;
+        self.local_manager.check_tenant(&room_name, &participant)?;
+        self.check_access_in_redis(&room_name, user_id, &request)
+            .await?;
+        self.local_manager.check_ban(&room_name, user_id)?;
+        self.local_manager.check_schedule(&room_name).await?;
+        let role = self
+            .local_manager
+            .effective_role(&room_name, user_id, &request)
+            .await;
 
         if self.is_redis_healthy().await {
             // Cluster mode: use Redis for coordination
@@ -489,21 +3722,119 @@ impl RoomManagerTrait for ClusterRoomManager {
                 participant.user.user_id, room_name
             );
 
+            if self.local_waitlist.read().await.contains_key(&user_id)
+                || self.local_lobby.read().await.contains_key(&user_id)
+            {
+                return Err(JoinError::AlreadyInRoom);
+            }
+            let config = self.local_manager.room_config(&room_name).await;
+            if config.rejoin == RejoinPolicy::Replace {
+                if let Some(outcome) = self.replace_in_redis(&room_name, &participant).await {
+                    self.schedule_close_in_redis(&room_name, &config, request.max_duration, false)
+                        .await;
+                    return Ok(outcome);
+                }
+            }
+            self.check_room_restrictions_in_redis(&room_name, user_id, role)
+                .await?;
+            self.keep_room_in_redis(&room_name).await;
+
+            // Moderators and the room's owner skip the lobby
+            if config.lobby
+                && !role.can_moderate()
+                && self.room_ownership(&room_name).await.owner != Some(user_id)
+            {
+                return self
+                    .knock_in_redis(
+                        &room_name,
+                        participant,
+                        role,
+                        config.lobby_timeout(),
+                        request.max_duration,
+                    )
+                    .await;
+            }
+
             // Get existing participants from Redis first
             let existing_participants = self.get_existing_participants_from_redis(&room_name).await;
 
-            // Register this user in Redis
-            if let Err(e) = self
-                .register_user_in_redis(
+            // Claim a slot for this user in Redis
+            let reserved = self
+                .reserve_slot_in_redis(
                     &room_name,
-                    participant.user.user_id,
-                    &participant.user.username,
+                    &participant,
+                    role,
+                    &config,
+                    request.max_duration,
                 )
-                .await
+                .await;
+            // The host role itself lives in `rooms:{room}:host`
+            let stored_role = role.min(RoomRole::Moderator);
+            if stored_role != RoomRole::Participant
+                && matches!(&reserved, Ok((status, _, _)) if status != "full")
+            {
+                if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
+                    let roles_key = format!("rooms:{}:roles", room_name);
+                    let _: Result<(), _> = conn
+                        .hset(&roles_key, user_id.to_string(), stored_role.as_str())
+                        .await;
+                }
+            }
+            let spectator = match reserved {
+                Ok((status, _, _)) if status == "joined" => false,
+                Ok((status, _, _)) if status == "spectator" => true,
+                Ok((status, position, entry)) if status == "waitlisted" => {
+                    self.local_waitlist.write().await.insert(
+                        participant.user.user_id,
+                        WaitingParticipant {
+                            room_id: room_name.clone(),
+                            participant,
+                            entry,
+                        },
+                    );
+                    return Ok(JoinOutcome::Waitlisted {
+                        position: position as usize,
+                    });
+                }
+                Ok((_, capacity, _)) => {
+                    return Err(JoinError::RoomFull {
+                        capacity: capacity as usize,
+                    });
+                }
+                Err(e) => {
+                    warn!("Failed to register user in Redis: {}", e);
+                    // Fall back to local mode for this operation
+                    self.local_connections
+                        .write()
+                        .await
+                        .insert(participant.user.user_id, participant.clone());
+                    return self
+                        .local_manager
+                        .add_participant(room_name, participant, role, request.max_duration)
+                        .await;
+                }
+            };
+
             {
-                warn!("Failed to register user in Redis: {}", e);
-                // Fall back to local mode for this operation
-                return self.local_manager.join_room(room_name, participant).await;
+                let mut connections = self.local_connections.write().await;
+                connections.insert(participant.user.user_id, participant.clone());
+            }
+            self.schedule_close_in_redis(
+                &room_name,
+                &config,
+                request.max_duration,
+                existing_participants.is_empty(),
+            )
+            .await;
+            if role.is_spectator() {
+                // Spectators join unannounced and never take the host role
+                info!(
+                    "Cluster: User {} ({}) is spectating room {}",
+                    user_id, participant.user.username, room_name
+                );
+                self.emit_joined(&room_name, user_id, participant.user.username.clone())
+                    .await;
+                return Ok(JoinOutcome::Spectating(existing_participants));
             }
 
             // Notify other servers about the new user
@@ -529,15 +3860,50 @@ impl RoomManagerTrait for ClusterRoomManager {
                 "Cluster: User {} ({}) joined room {} via Redis coordination",
                 participant.user.user_id, participant.user.username, room_name
             );
+            self.emit_joined(&room_name, user_id, participant.user.username.clone())
+                .await;
+
+            if let Some(host_changed) = self
+                .settle_host_in_redis(
+                    &room_name,
+                    user_id,
+                    role == RoomRole::Host,
+                    config.host_transfer,
+                )
+                .await
+            {
+                let message = ClusterMessage::RoomEvent {
+                    room_id: room_name.clone(),
+                    message: host_changed,
+                    target_user: None,
+                    exclude_user: Some(user_id),
+                };
+                if let Err(e) = self.publish(&message).await {
+                    warn!("{}", e);
+                }
+            }
+            if role.can_moderate() || self.room_ownership(&room_name).await.host == Some(user_id) {
+                self.send_knocks_in_redis(&room_name, &participant).await;
+            }
 
-            Ok(existing_participants)
+            if spectator {
+                Ok(JoinOutcome::Spectating(existing_participants))
+            } else {
+                Ok(JoinOutcome::Joined(existing_participants))
+            }
         } else {
             // Fallback to local mode
             debug!(
                 "Local mode: User {} joining room {} (Redis unavailable)",
                 participant.user.user_id, room_name
             );
-            self.local_manager.join_room(room_name, participant).await
+            {
+                let mut connections = self.local_connections.write().await;
+                connections.insert(participant.user.user_id, participant.clone());
+            }
+            self.local_manager
+                .add_participant(room_name, participant, role, request.max_duration)
+                .await
         }
     }
 
@@ -552,25 +3918,32 @@ impl RoomManagerTrait for ClusterRoomManager {
             // Cluster mode: use Redis for coordination
             debug!("Cluster mode: User {} leaving room {}", user_id, room_name);
 
+            if self
+                .leave_redis_waitlist(user_id, |waiting| waiting.room_id == room_name)
+                .await
+                || self
+                    .leave_redis_lobby(user_id, |knocking| knocking.room_id == room_name)
+                    .await
+            {
+                return Ok(());
+            }
+
+            let spectator = self.room_role(room_name, user_id).await.is_spectator();
             if let Err(e) = self.unregister_user_from_redis(room_name, user_id).await {
                 warn!("Failed to unregister user from Redis: {}", e);
             }
+            self.emit_left(room_name, user_id).await;
 
-            // Notify other servers about user leaving
-            let leave_message = ClusterMessage::UserLeft {
-                room_id: room_name.to_string(),
-                user_id,
-                target_server: None,
-            };
-
-            if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
-                if let Ok(message_json) = serde_json::to_string(&leave_message) {
-                    if let Err(e) = conn
-                        .publish::<_, _, ()>("cluster:messages", message_json)
-                        .await
-                    {
-                        warn!("Failed to publish leave message: {}", e);
-                    }
+            // Notify other servers about user leaving, unless nobody was told they joined
+            if !spectator {
+                let leave_message = ClusterMessage::UserLeft {
+                    room_id: room_name.to_string(),
+                    user_id,
+                    target_server: None,
+                    reason: Some(LeaveReason::Left),
+                };
+                if let Err(e) = self.publish(&leave_message).await {
+                    warn!("{}", e);
                 }
             }
 
@@ -578,6 +3951,9 @@ impl RoomManagerTrait for ClusterRoomManager {
                 "Cluster: User {} left room {} via Redis coordination",
                 user_id, room_name
             );
+            self.reassign_host_in_redis(room_name, user_id).await;
+            self.admit_from_redis_waitlist(room_name).await;
+            self.release_room_in_redis(room_name).await;
             Ok(())
         } else {
             // Fallback to local mode
@@ -613,92 +3989,106 @@ impl RoomManagerTrait for ClusterRoomManager {
         room_name: &str,
         target_user_id: u32,
         message: ServerMessage,
-    ) -> Result<(), String> {
+    ) -> Result<(), DeliveryError> {
         if self.is_redis_healthy().await {
-            // Check if user is connected locally first
-            let connections = self.local_connections.read().await;
-            if connections.contains_key(&target_user_id) {
-                drop(connections);
-                return self
-                    .local_manager
-                    .send_to_user_in_room(room_name, target_user_id, message)
-                    .await;
-            }
-            drop(connections);
-
-            // User not local, find which server has them and route via Redis
+            // Find which server has the user, and route via Redis unless it is this one
             match self.redis_client.get_multiplexed_async_connection().await {
                 Ok(mut conn) => {
                     let room_key = format!("rooms:{}:participants", room_name);
-
-                    if let Ok(target_server) = conn
-                        .hget::<_, _, String>(&room_key, target_user_id.to_string())
+                    let target_server: Option<String> = conn
+                        .hget(&room_key, target_user_id.to_string())
                         .await
-                    {
-                        // Create a WebRTC signal message that will be routed to the correct server
-                        let cluster_message = match &message {
-                            ServerMessage::Offer {
-                                from_user_id, sdp, ..
-                            } => Some(ClusterMessage::WebRTCSignal {
-                                room_id: room_name.to_string(),
-                                from_user: *from_user_id,
-                                to_user: target_user_id,
-                                signal_type: "offer".to_string(),
-                                signal_data: sdp.clone(),
-                            }),
-                            ServerMessage::Answer {
-                                from_user_id, sdp, ..
-                            } => Some(ClusterMessage::WebRTCSignal {
-                                room_id: room_name.to_string(),
-                                from_user: *from_user_id,
-                                to_user: target_user_id,
-                                signal_type: "answer".to_string(),
-                                signal_data: sdp.clone(),
-                            }),
-                            ServerMessage::IceCandidate {
-                                from_user_id,
-                                candidate,
-                                ..
-                            } => Some(ClusterMessage::WebRTCSignal {
-                                room_id: room_name.to_string(),
-                                from_user: *from_user_id,
-                                to_user: target_user_id,
-                                signal_type: "ice-candidate".to_string(),
-                                signal_data: candidate.clone(),
-                            }),
-                            _ => None, // Not a WebRTC signal, handle locally
+                        .map_err(|e| DeliveryError::Failed(e.to_string()))?;
+                    let Some(target_server) = target_server else {
+                        return Err(DeliveryError::Undelivered(
+                            Self::missing_recipient(&mut conn, room_name, target_user_id).await,
+                        ));
+                    };
+                    // The user's server drops the registration as their connection closes
+                    let registered: bool = conn
+                        .hexists(
+                            format!("servers:{}:connections", target_server),
+                            target_user_id.to_string(),
+                        )
+                        .await
+                        .unwrap_or(true);
+                    if !registered {
+                        return Err(DeliveryError::Undelivered(DeliveryFailure::RecipientGone));
+                    }
+                    if target_server == self.node_id {
+                        let connections = self.local_connections.read().await;
+                        let Some(participant) = connections.get(&target_user_id) else {
+                            return Err(DeliveryError::Undelivered(DeliveryFailure::RecipientGone));
                         };
+                        return participant.deliver(&message);
+                    }
 
-                        if let Some(cluster_message) = cluster_message {
-                            if let Ok(message_json) = serde_json::to_string(&cluster_message) {
-                                if let Err(e) = conn
-                                    .publish::<_, _, ()>("cluster:messages", message_json)
-                                    .await
-                                {
-                                    warn!("Failed to route message via Redis: {}", e);
-                                    return Err("Failed to route message".to_string());
-                                }
-
-                                debug!(
-                                    "Routed message to user {} on server {}",
-                                    target_user_id, target_server
-                                );
-                                return Ok(());
-                            }
-                        } else {
-                            // Not a WebRTC signal, try to handle locally
+                    // Create a WebRTC signal message that will be routed to the correct server
+                    let signal = |from_user: u32,
+                                  signal_type: &str,
+                                  signal_data: &String,
+                                  sdp_mid: &Option<String>,
+                                  sdp_mline_index: &Option<u32>| {
+                        ClusterMessage::WebRTCSignal {
+                            room_id: room_name.to_string(),
+                            from_user,
+                            to_user: target_user_id,
+                            signal_type: signal_type.to_string(),
+                            signal_data: signal_data.clone(),
+                            sdp_mid: sdp_mid.clone(),
+                            sdp_mline_index: *sdp_mline_index,
+                            target_server: Some(target_server.clone()),
+                            origin_server: Some(self.node_id.clone()),
+                        }
+                    };
+                    let cluster_message = match &message {
+                        ServerMessage::Offer {
+                            from_user_id, sdp, ..
+                        } => signal(*from_user_id, "offer", sdp, &None, &None),
+                        ServerMessage::Answer {
+                            from_user_id, sdp, ..
+                        } => signal(*from_user_id, "answer", sdp, &None, &None),
+                        ServerMessage::IceCandidate {
+                            from_user_id,
+                            candidate,
+                            sdp_mid,
+                            sdp_mline_index,
+                            ..
+                        } => signal(
+                            *from_user_id,
+                            "ice-candidate",
+                            candidate,
+                            sdp_mid,
+                            sdp_mline_index,
+                        ),
+                        // Not a WebRTC signal, try to handle locally
+                        _ => {
                             return self
                                 .local_manager
                                 .send_to_user_in_room(room_name, target_user_id, message)
-                                .await;
+                                .await
                         }
+                    };
+
+                    let message_json = serde_json::to_string(&cluster_message)
+                        .map_err(|e| DeliveryError::Failed(e.to_string()))?;
+                    if let Err(e) = conn
+                        .publish::<_, _, ()>("cluster:messages", message_json)
+                        .await
+                    {
+                        warn!("Failed to route message via Redis: {}", e);
+                        return Err(DeliveryError::Failed("Failed to route message".to_string()));
                     }
 
-                    Err("User not found in room".to_string())
+                    debug!(
+                        "Routed message to user {} on server {}",
+                        target_user_id, target_server
+                    );
+                    Ok(())
                 }
                 Err(e) => {
                     warn!("Failed to connect to Redis for message routing: {}", e);
-                    Err("Redis connection failed".to_string())
+                    Err(DeliveryError::Failed("Redis connection failed".to_string()))
                 }
             }
         } else {
@@ -724,15 +4114,38 @@ impl RoomManagerTrait for ClusterRoomManager {
         }
     }
 
+    async fn room_role(&self, room_name: &str, user_id: u32) -> RoomRole {
+        if self.is_redis_healthy().await {
+            if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
+                return Self::role_in_redis(&mut conn, room_name, user_id).await;
+            }
+        }
+        self.local_manager.room_role(room_name, user_id).await
+    }
+
     async fn remove_user_from_all_rooms(&self, user_id: u32, connection_id: Uuid) {
-        // Remove from local connections
+        // Remove from local connections, unless a rejoin has replaced this one
         {
             let mut connections = self.local_connections.write().await;
-            connections.remove(&user_id);
+            if connections
+                .get(&user_id)
+                .is_some_and(|p| p.connection_id == connection_id)
+            {
+                connections.remove(&user_id);
+            }
         }
 
         if self.is_redis_healthy().await {
+            let same_connection =
+                |waiting: &WaitingParticipant| waiting.participant.connection_id == connection_id;
+            if self.leave_redis_waitlist(user_id, same_connection).await
+                || self.leave_redis_lobby(user_id, same_connection).await
+            {
+                return;
+            }
+
             // In cluster mode, clean up Redis state
+            let mut vacated_room = None;
             if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
                 let server_key = format!("servers:{}:connections", self.node_id);
 
@@ -745,31 +4158,54 @@ impl RoomManagerTrait for ClusterRoomManager {
                         serde_json::from_str::<ConnectionInfo>(&connection_json)
                     {
                         if connection_info.connection_id == connection_id {
+                            let spectator =
+                                Self::role_in_redis(&mut conn, &connection_info.room_id, user_id)
+                                    .await
+                                    .is_spectator();
+
                             // Remove from room
                             let room_key =
                                 format!("rooms:{}:participants", connection_info.room_id);
                             let _: Result<(), _> = conn.hdel(&room_key, user_id.to_string()).await;
+                            let spectators_key =
+                                format!("rooms:{}:spectators", connection_info.room_id);
+                            let _: Result<(), _> =
+                                conn.srem(&spectators_key, user_id.to_string()).await;
+                            let roles_key = format!("rooms:{}:roles", connection_info.room_id);
+                            let _: Result<(), _> = conn.hdel(&roles_key, user_id.to_string()).await;
+                            record_departure(&mut conn, &connection_info.room_id, user_id).await;
+                            vacated_room = Some(connection_info.room_id.clone());
 
                             // Remove from server connections
                             let _: Result<(), _> =
                                 conn.hdel(&server_key, user_id.to_string()).await;
 
-                            // Notify other servers
+                            // Notify other servers, unless nobody was told they joined
                             let leave_message = ClusterMessage::UserLeft {
                                 room_id: connection_info.room_id,
                                 user_id,
                                 target_server: None,
+                                reason: Some(LeaveReason::Disconnected),
                             };
 
                             if let Ok(message_json) = serde_json::to_string(&leave_message) {
-                                let _ = conn
-                                    .publish::<_, _, ()>("cluster:messages", message_json)
-                                    .await;
+                                if !spectator {
+                                    let _ = conn
+                                        .publish::<_, _, ()>("cluster:messages", message_json)
+                                        .await;
+                                }
                             }
                         }
                     }
                 }
             }
+
+            if let Some(room_id) = vacated_room {
+                self.emit_left(&room_id, user_id).await;
+                self.reassign_host_in_redis(&room_id, user_id).await;
+                self.admit_from_redis_waitlist(&room_id).await;
+                self.release_room_in_redis(&room_id).await;
+            }
         } else {
             self.local_manager
                 .remove_user_from_all_rooms(user_id, connection_id)
@@ -785,6 +4221,313 @@ impl RoomManagerTrait for ClusterRoomManager {
         }
     }
 
+    async fn moderate(
+        &self,
+        room_name: &str,
+        actor_id: u32,
+        action: ModeratorAction,
+    ) -> Result<(), ModerationError> {
+        if self.is_redis_healthy().await {
+            self.moderate_in_redis(room_name, actor_id, action).await
+        } else {
+            self.local_manager
+                .moderate(room_name, actor_id, action)
+                .await
+        }
+    }
+
+    async fn room_config(&self, room_name: &str) -> RoomConfig {
+        self.local_manager.room_config(room_name).await
+    }
+
+    async fn create_room(&self, room_name: &str, config: RoomConfig) -> Result<(), String> {
+        self.local_manager.create_room(room_name, config).await?;
+        if !self.is_redis_healthy().await {
+            return Ok(());
+        }
+
+        let config = self.local_manager.room_config(room_name).await;
+        let config_json = serde_json::to_string(&config)
+            .map_err(|e| format!("Failed to serialize room configuration: {}", e))?;
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| format!("Redis error: {}", e))?;
+        let _: () = conn
+            .hset(ROOM_DEFINITIONS_KEY, room_name, config_json)
+            .await
+            .map_err(|e| format!("Redis error: {}", e))?;
+        self.keep_room_in_redis(room_name).await;
+        self.publish(&ClusterMessage::RoomDefined {
+            room_id: room_name.to_string(),
+            config: Some(config),
+        })
+        .await
+    }
+
+    async fn delete_room(&self, room_name: &str) -> Result<(), String> {
+        if !self.is_redis_healthy().await {
+            return self.local_manager.delete_room(room_name).await;
+        }
+
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| format!("Redis error: {}", e))?;
+        let removed: i64 = conn
+            .hdel(ROOM_DEFINITIONS_KEY, room_name)
+            .await
+            .map_err(|e| format!("Redis error: {}", e))?;
+        let defined_here = self.local_manager.delete_room(room_name).await.is_ok();
+        if removed == 0 && !defined_here {
+            return Err(format!(
+                "Room {} is not defined",
+                local_room_name(tenant_of(room_name), room_name)
+            ));
+        }
+
+        self.publish(&ClusterMessage::RoomDefined {
+            room_id: room_name.to_string(),
+            config: None,
+        })
+        .await?;
+        self.release_room_in_redis(room_name).await;
+        Ok(())
+    }
+
+    async fn connect_user(&self, participant: RoomParticipant) {
+        let connections_key = format!(
+            "users:{}:connections",
+            user_key(participant.user.tenant.as_deref(), participant.user.user_id)
+        );
+        let member = format!("{}/{}", self.node_id, participant.connection_id);
+        self.users.add(participant).await;
+
+        if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
+            if let Err(e) = conn.sadd::<_, _, ()>(&connections_key, member).await {
+                warn!("Failed to record connection in Redis: {}", e);
+            }
+        }
+    }
+
+    async fn disconnect_user(&self, connection_id: Uuid) {
+        let Some(participant) = self.users.remove(connection_id).await else {
+            return;
+        };
+        let connections_key = format!(
+            "users:{}:connections",
+            user_key(participant.user.tenant.as_deref(), participant.user.user_id)
+        );
+        let member = format!("{}/{}", self.node_id, connection_id);
+        self.directory.unsubscribe(connection_id);
+        if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
+            let _: Result<(), _> = conn.srem(&connections_key, member).await;
+        }
+    }
+
+    async fn invite(
+        &self,
+        caller: &AuthenticatedUser,
+        callee_id: u32,
+        ring_timeout: Duration,
+    ) -> Result<CallInvitation, CallError> {
+        if !self.is_redis_healthy().await {
+            return self
+                .local_manager
+                .invite(caller, callee_id, ring_timeout)
+                .await;
+        }
+        if callee_id == caller.user_id {
+            return Err(CallError::SelfCall);
+        }
+
+        let failed = |e: redis::RedisError| CallError::Failed(format!("Redis error: {}", e));
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(failed)?;
+        let connections_key = format!(
+            "users:{}:connections",
+            user_key(caller.tenant.as_deref(), callee_id)
+        );
+        let connections: usize = conn.scard(&connections_key).await.map_err(failed)?;
+        if connections == 0 {
+            return Err(CallError::UserOffline);
+        }
+
+        let invitation = CallInvitation::new(caller, callee_id);
+        let invitation_json = serde_json::to_string(&invitation)
+            .map_err(|e| CallError::Failed(format!("Failed to serialize call: {}", e)))?;
+        // Outlives the ring so the expiry task, not Redis, ends the call
+        let _: () = conn
+            .set_ex(
+                format!("calls:{}", invitation.call_id),
+                invitation_json,
+                ring_timeout.as_secs() * 2 + 1,
+            )
+            .await
+            .map_err(failed)?;
+        self.expire_call_in_redis(&invitation, ring_timeout);
+
+        info!(
+            "Cluster: User {} is calling user {} (call {})",
+            caller.user_id, callee_id, invitation.call_id
+        );
+        self.publish(&ClusterMessage::UserEvent {
+            tenant: invitation.tenant.clone(),
+            user_id: callee_id,
+            message: invitation.incoming(),
+        })
+        .await
+        .map_err(CallError::Failed)?;
+        Ok(invitation)
+    }
+
+    async fn respond_to_call(
+        &self,
+        user: &AuthenticatedUser,
+        call_id: Uuid,
+        response: CallResponse,
+    ) -> Result<(), CallError> {
+        if !self.is_redis_healthy().await {
+            return self
+                .local_manager
+                .respond_to_call(user, call_id, response)
+                .await;
+        }
+
+        let failed = |e: redis::RedisError| CallError::Failed(format!("Redis error: {}", e));
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(failed)?;
+        let call_key = format!("calls:{}", call_id);
+        let invitation: Option<String> = conn.get(&call_key).await.map_err(failed)?;
+        let invitation = invitation
+            .and_then(|invitation| serde_json::from_str::<CallInvitation>(&invitation).ok())
+            .ok_or(CallError::NotFound)?;
+        invitation.check_response(user, response)?;
+        let removed: i64 = conn.del(&call_key).await.map_err(failed)?;
+        if removed == 0 {
+            // Answered or timed out on another server in the meantime
+            return Err(CallError::NotFound);
+        }
+
+        info!(
+            "Cluster: Call {} answered by user {}: {:?}",
+            call_id, user.user_id, response
+        );
+        if response == CallResponse::Accept {
+            let invited_key = format!("rooms:{}:invited", invitation.room_name());
+            let _: () = redis::pipe()
+                .sadd(&invited_key, &invitation.user_ids()[..])
+                .ignore()
+                .expire(&invited_key, CALL_ROOM_TTL.as_secs() as i64)
+                .ignore()
+                .query_async(&mut conn)
+                .await
+                .map_err(failed)?;
+        }
+        Self::publish_to_call(&mut conn, &invitation, &invitation.outcome(response)).await;
+        Ok(())
+    }
+
+    async fn room_ownership(&self, room_name: &str) -> RoomOwnership {
+        if self.is_redis_healthy().await {
+            if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
+                return Self::ownership_in_redis(&mut conn, room_name).await;
+            }
+        }
+        self.local_manager.room_ownership(room_name).await
+    }
+
+    async fn update_room_state(
+        &self,
+        room_name: &str,
+        user_id: u32,
+        update: RoomStateUpdate,
+    ) -> Result<u64, RoomStateError> {
+        if self.is_redis_healthy().await {
+            self.update_state_in_redis(room_name, user_id, update).await
+        } else {
+            self.local_manager
+                .update_room_state(room_name, user_id, update)
+                .await
+        }
+    }
+
+    async fn room_state(&self, room_name: &str) -> RoomStateMap {
+        if self.is_redis_healthy().await {
+            if let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await {
+                return Self::state_in_redis(&mut conn, room_name).await;
+            }
+        }
+        self.local_manager.room_state(room_name).await
+    }
+
+    async fn plan_mesh(&self, room_name: &str, user_id: u32) {
+        if self.room_configs.get(room_name).await.mesh != MeshPolicy::ServerPlanned {
+            return;
+        }
+        if self.is_redis_healthy().await {
+            self.plan_mesh_in_redis(room_name, user_id).await;
+        } else {
+            self.local_manager.plan_mesh(room_name, user_id).await;
+        }
+    }
+
+    async fn record_signal(
+        &self,
+        room_name: &str,
+        from_user_id: u32,
+        to_user_id: u32,
+        kind: SignalKind,
+    ) {
+        if self.is_redis_healthy().await {
+            self.record_signal_in_redis(room_name, from_user_id, to_user_id, kind)
+                .await;
+        } else {
+            self.local_manager
+                .record_signal(room_name, from_user_id, to_user_id, kind)
+                .await;
+        }
+    }
+
+    async fn list_rooms(&self, tenant: Option<&str>) -> Vec<RoomSummary> {
+        if !self.is_redis_healthy().await {
+            return self.local_manager.list_rooms(tenant).await;
+        }
+        let Ok(mut conn) = self.redis_client.get_multiplexed_async_connection().await else {
+            return self.local_manager.list_rooms(tenant).await;
+        };
+        let live: Vec<String> = conn.smembers(LIVE_ROOMS_KEY).await.unwrap_or_default();
+
+        let mut summaries = Vec::new();
+        for room_id in live.iter().filter(|room_id| same_tenant(tenant, room_id)) {
+            if let Some(summary) =
+                Self::listed_summary_in_redis(&self.redis_client, &self.room_configs, room_id)
+                    .await
+                    .filter(|summary| summary.participant_count > 0)
+            {
+                summaries.push(summary);
+            }
+        }
+        summaries.sort_by(|a, b| a.room_name.cmp(&b.room_name));
+        summaries
+    }
+
+    async fn subscribe_room_directory(&self, participant: RoomParticipant) {
+        self.directory.subscribe(participant);
+    }
+
+    async fn unsubscribe_room_directory(&self, connection_id: Uuid) {
+        self.directory.unsubscribe(connection_id);
+    }
+
     async fn health_check(&self) -> bool {
         // Health check passes if either Redis is healthy OR local manager is working
         self.is_redis_healthy().await || self.local_manager.health_check().await
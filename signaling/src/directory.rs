@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::messages::{RoomSummary, ServerMessage};
+use crate::room::RoomParticipant;
+use crate::tenant::same_tenant;
+
+/// Connections on this server subscribed to the room directory
+#[derive(Debug, Clone, Default)]
+pub struct RoomDirectory {
+    subscribers: Arc<Mutex<HashMap<Uuid, RoomParticipant>>>,
+}
+
+impl RoomDirectory {
+    pub fn subscribe(&self, participant: RoomParticipant) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .insert(participant.connection_id, participant);
+    }
+
+    pub fn unsubscribe(&self, connection_id: Uuid) -> bool {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .remove(&connection_id)
+            .is_some()
+    }
+
+    /// Send the room's new summary to the subscribers of its tenant
+    pub fn publish(&self, summary: &RoomSummary) {
+        let message = ServerMessage::RoomUpdated {
+            room: summary.clone(),
+        };
+        let Ok(json) = serde_json::to_string(&message) else {
+            return;
+        };
+        let subscribers = self.subscribers.lock().unwrap();
+        for subscriber in subscribers
+            .values()
+            .filter(|subscriber| same_tenant(subscriber.user.tenant.as_deref(), &summary.room_name))
+        {
+            if let Err(e) = subscriber.sender.try_send(Message::Text(json.clone())) {
+                warn!(
+                    "Failed to send directory update to user {}: {}",
+                    subscriber.user.user_id, e
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::messages::IceServer;
+
+/// Default lifetime of generated TURN credentials (one day, as in coturn's examples)
+pub const DEFAULT_TURN_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// STUN/TURN servers handed to clients after authentication
+#[derive(Debug, Clone)]
+pub struct IceConfig {
+    pub stun_urls: Vec<String>,
+    pub turn_urls: Vec<String>,
+    /// Shared secret configured as `static-auth-secret` on the TURN server
+    pub turn_secret: Option<String>,
+    /// How long generated TURN credentials stay valid
+    pub turn_ttl: Duration,
+}
+
+impl Default for IceConfig {
+    fn default() -> Self {
+        Self {
+            stun_urls: Vec::new(),
+            turn_urls: Vec::new(),
+            turn_secret: None,
+            turn_ttl: DEFAULT_TURN_TTL,
+        }
+    }
+}
+
+/// Time-limited TURN credentials following coturn's `use-auth-secret` scheme
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnCredentials {
+    pub username: String,
+    pub credential: String,
+    /// Unix timestamp (seconds) after which the TURN server rejects the credentials
+    pub expires_at: u64,
+}
+
+impl IceConfig {
+    /// Load ICE configuration from the environment.
+    ///
+    /// `ICE_STUN_URLS` and `ICE_TURN_URLS` are comma-separated lists, `TURN_SECRET`
+    /// enables credential generation and `TURN_CREDENTIAL_TTL` is given in seconds.
+    pub fn from_env() -> Self {
+        let stun_urls = parse_url_list(&env::var("ICE_STUN_URLS").unwrap_or_default());
+        let turn_urls = parse_url_list(&env::var("ICE_TURN_URLS").unwrap_or_default());
+        let turn_secret = env::var("TURN_SECRET").ok().filter(|s| !s.is_empty());
+        let turn_ttl = env::var("TURN_CREDENTIAL_TTL")
+            .ok()
+            .and_then(|ttl| ttl.parse::<u64>().ok())
+            .filter(|ttl| *ttl > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TURN_TTL);
+
+        if !turn_urls.is_empty() && turn_secret.is_none() {
+            warn!("ICE_TURN_URLS is set but TURN_SECRET is missing - TURN servers will not be advertised");
+        }
+
+        Self {
+            stun_urls,
+            turn_urls,
+            turn_secret,
+            turn_ttl,
+        }
+    }
+
+    /// Whether there is anything to send to clients
+    pub fn is_empty(&self) -> bool {
+        self.stun_urls.is_empty() && !self.has_turn()
+    }
+
+    /// Whether TURN servers with generated credentials are configured
+    pub fn has_turn(&self) -> bool {
+        !self.turn_urls.is_empty() && self.turn_secret.is_some()
+    }
+
+    /// How often a connected client should receive fresh TURN credentials.
+    ///
+    /// Credentials are re-issued once 80% of their lifetime has passed so that
+    /// clients never hold expired ones. Returns `None` when TURN is not in use.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        if self.has_turn() {
+            Some(self.turn_ttl.mul_f64(0.8).max(Duration::from_secs(1)))
+        } else {
+            None
+        }
+    }
+
+    /// Build the ICE server list for a user, generating TURN credentials as of now
+    pub fn ice_servers_for(&self, user_id: u32) -> Vec<IceServer> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.ice_servers_at(user_id, now)
+    }
+
+    /// Build the ICE server list for a user with credentials issued at `now` (Unix seconds)
+    pub fn ice_servers_at(&self, user_id: u32, now: u64) -> Vec<IceServer> {
+        let mut servers = Vec::new();
+
+        if !self.stun_urls.is_empty() {
+            servers.push(IceServer {
+                urls: self.stun_urls.clone(),
+                username: None,
+                credential: None,
+            });
+        }
+
+        if let (false, Some(secret)) = (self.turn_urls.is_empty(), &self.turn_secret) {
+            let credentials =
+                generate_turn_credentials(secret, &user_id.to_string(), self.turn_ttl, now);
+            servers.push(IceServer {
+                urls: self.turn_urls.clone(),
+                username: Some(credentials.username),
+                credential: Some(credentials.credential),
+            });
+        }
+
+        servers
+    }
+}
+
+/// Generate TURN credentials for the coturn `use-auth-secret` mechanism.
+///
+/// The username is `<expiry>:<user>` and the password is the base64-encoded
+/// HMAC-SHA1 of that username keyed with the shared secret.
+pub fn generate_turn_credentials(
+    secret: &str,
+    user: &str,
+    ttl: Duration,
+    now: u64,
+) -> TurnCredentials {
+    let expires_at = now + ttl.as_secs();
+    let username = format!("{}:{}", expires_at, user);
+
+    let mut mac =
+        HmacSha1::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(username.as_bytes());
+    let credential = BASE64.encode(mac.finalize().into_bytes());
+
+    TurnCredentials {
+        username,
+        credential,
+        expires_at,
+    }
+}
+
+fn parse_url_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .collect()
+}
@@ -1,5 +1,20 @@
+pub mod access;
+pub mod audit;
 pub mod auth;
+pub mod call;
+pub mod candidate;
 pub mod cluster;
+pub mod directory;
+pub mod ice;
+pub mod mesh;
 pub mod messages;
+pub mod moderation;
 pub mod room;
+pub mod room_config;
+pub mod room_name;
+pub mod room_state;
+pub mod schedule;
+pub mod sdp;
 pub mod server;
+pub mod tenant;
+pub mod webhook;
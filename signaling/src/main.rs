@@ -1,13 +1,9 @@
-mod auth;
-mod cluster;
-mod messages;
-mod room;
-mod server;
-
 use anyhow::Result;
 use clap::Parser;
 use std::env;
+use std::sync::Arc;
 use tracing::{info, warn};
+use webrtc_signaling::{access, audit, cluster, room, room_config, server, webhook};
 
 #[derive(Parser)]
 #[command(name = "webrtc-signaling")]
@@ -40,9 +36,36 @@ async fn main() -> Result<()> {
         .parse::<bool>()
         .unwrap_or(false);
 
+    let room_configs =
+        Arc::new(room_config::RoomConfigStore::from_env().map_err(|e| anyhow::anyhow!(e))?);
+    let persistent_rooms = room_configs.persistent_rooms().await;
+
+    let webhook_config = webhook::WebhookConfig::from_env();
+    let webhooks = if webhook_config.is_enabled() {
+        info!(
+            "Webhooks enabled for {} endpoint(s)",
+            webhook_config.urls.len()
+        );
+        Some(webhook::WebhookDispatcher::start(webhook_config).map_err(|e| anyhow::anyhow!(e))?)
+    } else {
+        None
+    };
+
+    let audit_config = audit::AuditConfig::from_env();
+    let audit_log = if audit_config.is_enabled() {
+        if let Some(dir) = &audit_config.dir {
+            info!("Audit log enabled in {}", dir.display());
+        }
+        Some(audit::AuditLog::open(audit_config).map_err(|e| anyhow::anyhow!(e))?)
+    } else {
+        None
+    };
+
     let room_manager = if cluster_mode {
         // Try to initialize cluster mode
-        match initialize_cluster_mode().await {
+        match initialize_cluster_mode(room_configs.clone(), webhooks.clone(), audit_log.clone())
+            .await
+        {
             Ok(manager) => {
                 info!("✅ Cluster mode enabled with Redis coordination");
                 manager
@@ -50,37 +73,86 @@ async fn main() -> Result<()> {
             Err(e) => {
                 warn!("❌ Failed to initialize cluster mode: {}", e);
                 warn!("🔄 Falling back to local mode");
-                room::RoomManager::new()
+                local_room_manager(room_configs, webhooks, audit_log.clone())
             }
         }
     } else {
         info!("📍 Local mode enabled (clustering disabled)");
-        room::RoomManager::new()
+        local_room_manager(room_configs, webhooks, audit_log.clone())
     };
 
+    // Rooms marked persistent in the configuration exist from startup
+    for (room_name, config) in persistent_rooms {
+        room_manager
+            .create_room(&room_name, config)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+    }
+
     println!("Starting WebRTC signaling server on {}:{}", host, port);
     println!("JWT authentication enabled");
 
-    server::start_server_with_room_manager(host, port, jwt_secret, room_manager).await
+    let config = server::ServerConfig {
+        audit: audit_log,
+        ..server::ServerConfig::from_env()
+    };
+    if !config.ice.is_empty() {
+        println!(
+            "ICE servers configured ({} STUN, {} TURN)",
+            config.ice.stun_urls.len(),
+            if config.ice.has_turn() {
+                config.ice.turn_urls.len()
+            } else {
+                0
+            }
+        );
+    }
+
+    server::start_server_with_config(host, port, jwt_secret, room_manager, config).await
+}
+
+fn local_room_manager(
+    room_configs: Arc<room_config::RoomConfigStore>,
+    webhooks: Option<webhook::WebhookDispatcher>,
+    audit_log: Option<audit::AuditLog>,
+) -> room::RoomManager {
+    let mut manager = room::LocalRoomManager::with_room_configs(room_configs)
+        .with_lockout_policy(access::LockoutPolicy::from_env());
+    if let Some(webhooks) = webhooks {
+        manager = manager.with_webhooks(webhooks);
+    }
+    if let Some(audit_log) = audit_log {
+        manager = manager.with_audit_log(audit_log);
+    }
+    room::RoomManager::with_implementation(Box::new(manager))
 }
 
 /// Initialize cluster mode with Redis
 async fn initialize_cluster_mode(
+    room_configs: Arc<room_config::RoomConfigStore>,
+    webhooks: Option<webhook::WebhookDispatcher>,
+    audit_log: Option<audit::AuditLog>,
 ) -> Result<room::RoomManager, Box<dyn std::error::Error + Send + Sync>> {
     let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
 
     let node_id = env::var("NODE_ID").unwrap_or_else(|_| {
         // Generate a unique node ID if not provided
-        format!(
-            "signaling-{}",
-            &uuid::Uuid::new_v4().to_string()[..8]
-        )
+        format!("signaling-{}", &uuid::Uuid::new_v4().to_string()[..8])
     });
 
     info!("Initializing cluster mode with Redis URL: {}", redis_url);
     info!("Node ID: {}", node_id);
 
-    let cluster_manager = cluster::ClusterRoomManager::new(&redis_url, node_id).await?;
+    let mut cluster_manager =
+        cluster::ClusterRoomManager::with_room_configs(&redis_url, node_id, room_configs)
+            .await?
+            .with_lockout_policy(access::LockoutPolicy::from_env());
+    if let Some(webhooks) = webhooks {
+        cluster_manager = cluster_manager.with_webhooks(webhooks);
+    }
+    if let Some(audit_log) = audit_log {
+        cluster_manager = cluster_manager.with_audit_log(audit_log);
+    }
     let room_manager = room::RoomManager::with_implementation(Box::new(cluster_manager));
 
     Ok(room_manager)
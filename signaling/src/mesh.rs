@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::audit::SignalKind;
+use crate::messages::ServerMessage;
+
+/// How long a pair has to complete its offer/answer exchange before it is told to
+/// connect again
+pub const NEGOTIATION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Instructions a pair is sent before the server gives up on it
+pub const MAX_CONNECT_ATTEMPTS: u32 = 3;
+
+/// How often rooms are checked for pairs that stalled
+pub const MESH_SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How far a pair has got in negotiating its connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NegotiationState {
+    /// Both peers were told to connect and the initiator has not offered yet
+    Instructed,
+    Offered,
+    /// The responder answered, which completes the negotiation
+    Answered,
+    /// The pair did not complete after [`MAX_CONNECT_ATTEMPTS`] instructions
+    Failed,
+}
+
+/// Two peers of a room, and which of them sends the offer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerPair {
+    pub initiator: u32,
+    pub responder: u32,
+    pub state: NegotiationState,
+    /// 1 for the first instruction, counting up with every re-issue
+    pub attempt: u32,
+}
+
+impl PeerPair {
+    pub fn new(initiator: u32, responder: u32) -> Self {
+        Self {
+            initiator,
+            responder,
+            state: NegotiationState::Instructed,
+            attempt: 1,
+        }
+    }
+
+    /// Name of the pair of `a` and `b`, whichever of them initiates
+    pub fn key(a: u32, b: u32) -> String {
+        format!("{}:{}", a.min(b), a.max(b))
+    }
+
+    pub fn includes(&self, user_id: u32) -> bool {
+        self.initiator == user_id || self.responder == user_id
+    }
+
+    /// Still waiting for the offer or the answer
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self.state,
+            NegotiationState::Instructed | NegotiationState::Offered
+        )
+    }
+
+    /// Advance the negotiation for a signal relayed from `from` to `to`, returning
+    /// whether the state changed
+    pub fn record(&mut self, from: u32, to: u32, kind: SignalKind) -> bool {
+        let state = match kind {
+            SignalKind::Offer if (from, to) == (self.initiator, self.responder) => {
+                NegotiationState::Offered
+            }
+            SignalKind::Answer if (from, to) == (self.responder, self.initiator) => {
+                NegotiationState::Answered
+            }
+            _ => return false,
+        };
+        if !self.is_pending() || self.state == state {
+            return false;
+        }
+        self.state = state;
+        true
+    }
+
+    /// Start another attempt, or mark the pair failed once it has had
+    /// [`MAX_CONNECT_ATTEMPTS`]. Returns whether the pair should be instructed again.
+    pub fn retry(&mut self) -> bool {
+        if self.attempt >= MAX_CONNECT_ATTEMPTS {
+            self.state = NegotiationState::Failed;
+            return false;
+        }
+        self.attempt += 1;
+        self.state = NegotiationState::Instructed;
+        true
+    }
+
+    /// The `connect-to` instruction for each peer of the pair
+    pub fn instructions(&self, room_name: &str) -> [(u32, ServerMessage); 2] {
+        let instruction = |peer_user_id, initiator| ServerMessage::ConnectTo {
+            room_name: room_name.to_string(),
+            peer_user_id,
+            initiator,
+            attempt: self.attempt,
+        };
+        [
+            (self.initiator, instruction(self.responder, true)),
+            (self.responder, instruction(self.initiator, false)),
+        ]
+    }
+}
+
+/// The pairs the server told to connect in a room, and when each was last told
+#[derive(Debug, Clone, Default)]
+pub struct MeshPlan {
+    pairs: HashMap<String, (PeerPair, Instant)>,
+}
+
+impl MeshPlan {
+    /// Start a new negotiation between the peers, replacing any earlier one
+    pub fn connect(&mut self, initiator: u32, responder: u32, now: Instant) -> PeerPair {
+        let pair = PeerPair::new(initiator, responder);
+        self.pairs
+            .insert(PeerPair::key(initiator, responder), (pair, now));
+        pair
+    }
+
+    pub fn pair(&self, a: u32, b: u32) -> Option<PeerPair> {
+        self.pairs.get(&PeerPair::key(a, b)).map(|(pair, _)| *pair)
+    }
+
+    pub fn pairs(&self) -> impl Iterator<Item = &PeerPair> {
+        self.pairs.values().map(|(pair, _)| pair)
+    }
+
+    /// Forget the pairs of a user who left
+    pub fn remove_user(&mut self, user_id: u32) {
+        self.pairs.retain(|_, (pair, _)| !pair.includes(user_id));
+    }
+
+    /// Advance the negotiation of the pair the signal belongs to
+    pub fn record(&mut self, from: u32, to: u32, kind: SignalKind) -> Option<NegotiationState> {
+        let (pair, _) = self.pairs.get_mut(&PeerPair::key(from, to))?;
+        pair.record(from, to, kind).then_some(pair.state)
+    }
+
+    /// Retry every pair still pending [`NEGOTIATION_TIMEOUT`] after it was last
+    /// instructed. Returns the retried pairs, to be instructed again, along with
+    /// those that just failed.
+    pub fn retry_stalled(&mut self, now: Instant) -> Vec<PeerPair> {
+        let mut stalled = Vec::new();
+        for (pair, instructed_at) in self.pairs.values_mut() {
+            if !pair.is_pending() || now.duration_since(*instructed_at) < NEGOTIATION_TIMEOUT {
+                continue;
+            }
+            pair.retry();
+            *instructed_at = now;
+            stalled.push(*pair);
+        }
+        stalled
+    }
+}
@@ -1,4 +1,8 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::room_state::RoomStateMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
@@ -49,6 +53,167 @@ pub enum ClientMessage {
         #[serde(rename = "targetUserId")]
         target_user_id: Option<u32>,
     },
+
+    /// Remove a participant from the room (moderators only)
+    #[serde(rename = "kick")]
+    Kick {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+        reason: Option<String>,
+    },
+
+    /// Remove a user and keep them out of the room (moderators only)
+    #[serde(rename = "ban")]
+    Ban {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+        /// Ban length; the ban lasts until the server restarts when omitted
+        #[serde(rename = "durationSeconds")]
+        duration_seconds: Option<u64>,
+        reason: Option<String>,
+    },
+
+    /// Ask a participant to mute their microphone or camera (moderators only)
+    #[serde(rename = "request-mute")]
+    RequestMute {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+        /// `audio` or `video`; both when omitted
+        media: Option<String>,
+    },
+
+    /// Hand the host role to another participant (host only)
+    #[serde(rename = "transfer-host")]
+    TransferHost {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+    },
+
+    /// Make a spectator a presenter (host only)
+    #[serde(rename = "promote-spectator")]
+    PromoteSpectator {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+    },
+
+    /// Let a user waiting in the lobby into the room (moderators only)
+    #[serde(rename = "admit")]
+    Admit {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+    },
+
+    /// Refuse the join of a user waiting in the lobby (moderators only)
+    #[serde(rename = "deny")]
+    Deny {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+        reason: Option<String>,
+    },
+
+    /// Open breakout rooms and move participants into them (host only)
+    #[serde(rename = "create-breakouts")]
+    CreateBreakouts {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        breakouts: Vec<BreakoutAssignment>,
+    },
+
+    /// Return everyone in the breakout rooms to the parent room (host only)
+    #[serde(rename = "close-breakouts")]
+    CloseBreakouts {
+        #[serde(rename = "roomName")]
+        room_name: String,
+    },
+
+    /// Lock or unlock the room against new joins (moderators only)
+    #[serde(rename = "lock-room")]
+    LockRoom {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        locked: bool,
+    },
+
+    /// Set a key of the room's shared state, if it is still at `expectedVersion`
+    #[serde(rename = "set-room-state")]
+    SetRoomState {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        key: String,
+        value: Value,
+        #[serde(
+            rename = "expectedVersion",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        expected_version: Option<u64>,
+    },
+
+    /// Remove a key from the room's shared state, if it is still at `expectedVersion`
+    #[serde(rename = "delete-room-state")]
+    DeleteRoomState {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        key: String,
+        #[serde(
+            rename = "expectedVersion",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        expected_version: Option<u64>,
+    },
+
+    /// Call another online user, ringing each of their connections
+    #[serde(rename = "ring")]
+    Ring {
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+    },
+
+    /// Answer an incoming call; the server opens a private room for it
+    #[serde(rename = "accept-call")]
+    AcceptCall {
+        #[serde(rename = "callId")]
+        call_id: Uuid,
+    },
+
+    #[serde(rename = "decline-call")]
+    DeclineCall {
+        #[serde(rename = "callId")]
+        call_id: Uuid,
+    },
+
+    /// Hang up a call that has not been answered yet
+    #[serde(rename = "cancel-call")]
+    CancelCall {
+        #[serde(rename = "callId")]
+        call_id: Uuid,
+    },
+
+    /// Ask for the rooms of the user's tenant that have someone in them
+    #[serde(rename = "list-rooms")]
+    ListRooms,
+
+    /// Receive the room list now and a `room-updated` event whenever it changes
+    #[serde(rename = "subscribe-room-directory")]
+    SubscribeRoomDirectory,
+
+    #[serde(rename = "unsubscribe-room-directory")]
+    UnsubscribeRoomDirectory,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +226,116 @@ pub enum ServerMessage {
         #[serde(rename = "userId")]
         user_id: u32,
         participants: Vec<Participant>,
+        /// Joined as a spectator, either by role or because the room was full
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        spectator: bool,
+        /// First joiner, or the user whose token claimed the room
+        #[serde(
+            rename = "ownerUserId",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        owner_user_id: Option<u32>,
+        #[serde(
+            rename = "hostUserId",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        host_user_id: Option<u32>,
+        /// Shared key-value state of the room
+        #[serde(default, skip_serializing_if = "RoomStateMap::is_empty")]
+        state: RoomStateMap,
+    },
+
+    /// A key of the room's shared state was set, or deleted when `value` is absent
+    #[serde(rename = "room-state-changed")]
+    RoomStateChanged {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        key: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<Value>,
+        version: u64,
+        #[serde(rename = "byUserId")]
+        by_user_id: u32,
+    },
+
+    /// The host role moved to another participant, or became vacant
+    #[serde(rename = "host-changed")]
+    HostChanged {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "hostUserId")]
+        host_user_id: Option<u32>,
+        #[serde(rename = "previousHostUserId")]
+        previous_host_user_id: Option<u32>,
+    },
+
+    /// The room is at capacity and the join was refused
+    #[serde(rename = "room-full")]
+    RoomFull {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        capacity: usize,
+    },
+
+    /// The room is at capacity and the user is queued for the next free slot
+    #[serde(rename = "waitlisted")]
+    Waitlisted {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        /// 1-based place in the queue
+        position: usize,
+    },
+
+    /// The room holds joiners in a lobby and the user is waiting to be admitted
+    #[serde(rename = "in-lobby")]
+    InLobby {
+        #[serde(rename = "roomName")]
+        room_name: String,
+    },
+
+    /// Sent to moderators when a user in the lobby asks to join
+    #[serde(rename = "knock")]
+    Knock {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        user: Participant,
+    },
+
+    /// Sent to moderators when a knock was answered, expired or withdrawn
+    #[serde(rename = "knock-resolved")]
+    KnockResolved {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "userId")]
+        user_id: u32,
+        admitted: bool,
+    },
+
+    /// The user was moved to another room by the server; replaces the usual
+    /// `room-left` and `room-joined` pair
+    #[serde(rename = "moved-to-room")]
+    MovedToRoom {
+        #[serde(rename = "fromRoomName")]
+        from_room_name: String,
+        #[serde(rename = "roomName")]
+        room_name: String,
+        participants: Vec<Participant>,
+        #[serde(
+            rename = "ownerUserId",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        owner_user_id: Option<u32>,
+        #[serde(
+            rename = "hostUserId",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        host_user_id: Option<u32>,
+        #[serde(default, skip_serializing_if = "RoomStateMap::is_empty")]
+        state: RoomStateMap,
     },
 
     #[serde(rename = "room-left")]
@@ -69,6 +344,8 @@ pub enum ServerMessage {
         room_name: String,
         #[serde(rename = "userId")]
         user_id: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<LeaveReason>,
     },
 
     #[serde(rename = "user-joined")]
@@ -84,6 +361,8 @@ pub enum ServerMessage {
         room_name: String,
         #[serde(rename = "userId")]
         user_id: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<LeaveReason>,
     },
 
     #[serde(rename = "offer")]
@@ -117,6 +396,152 @@ pub enum ServerMessage {
         sdp_mline_index: Option<u32>,
     },
 
+    /// Sent to a participant removed by a moderator
+    #[serde(rename = "kicked")]
+    Kicked {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "byUserId")]
+        by_user_id: u32,
+        reason: Option<String>,
+    },
+
+    /// Sent to a user banned by a moderator
+    #[serde(rename = "banned")]
+    Banned {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "byUserId")]
+        by_user_id: u32,
+        reason: Option<String>,
+        #[serde(rename = "durationSeconds")]
+        duration_seconds: Option<u64>,
+    },
+
+    #[serde(rename = "mute-requested")]
+    MuteRequested {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "byUserId")]
+        by_user_id: u32,
+        media: Option<String>,
+    },
+
+    /// Sent to a spectator the host made a presenter; the room has been told they joined
+    #[serde(rename = "promoted")]
+    Promoted {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "byUserId")]
+        by_user_id: u32,
+    },
+
+    #[serde(rename = "room-locked")]
+    RoomLocked {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        locked: bool,
+        #[serde(rename = "byUserId")]
+        by_user_id: u32,
+    },
+
+    /// Sent to the caller once the callee's connections are ringing
+    #[serde(rename = "ringing")]
+    Ringing {
+        #[serde(rename = "callId")]
+        call_id: Uuid,
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+    },
+
+    /// Sent to every connection of the user being called
+    #[serde(rename = "incoming-call")]
+    IncomingCall {
+        #[serde(rename = "callId")]
+        call_id: Uuid,
+        from: Participant,
+    },
+
+    /// Sent to both users' connections; they meet in the private room `roomName`
+    #[serde(rename = "call-accepted")]
+    CallAccepted {
+        #[serde(rename = "callId")]
+        call_id: Uuid,
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "byUserId")]
+        by_user_id: u32,
+    },
+
+    /// Sent to both users' connections when a call ends without being accepted
+    #[serde(rename = "call-ended")]
+    CallEnded {
+        #[serde(rename = "callId")]
+        call_id: Uuid,
+        reason: CallEndReason,
+    },
+
+    /// A participant rejoined from a new connection, which replaces their old one
+    #[serde(rename = "participant-replaced")]
+    ParticipantReplaced {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "userId")]
+        user_id: u32,
+    },
+
+    /// The room closes in `closes_in_seconds`
+    #[serde(rename = "room-closing")]
+    RoomClosing {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "closesInSeconds")]
+        closes_in_seconds: u64,
+        reason: RoomCloseReason,
+    },
+
+    /// The room was closed and everyone in it removed
+    #[serde(rename = "room-closed")]
+    RoomClosed {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        reason: RoomCloseReason,
+    },
+
+    /// Connect to `peer_user_id`, sending the offer when `initiator` is set and
+    /// waiting for it otherwise. Sent again, with a higher `attempt`, when the pair
+    /// has not completed its negotiation in time.
+    #[serde(rename = "connect-to")]
+    ConnectTo {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "peerUserId")]
+        peer_user_id: u32,
+        initiator: bool,
+        attempt: u32,
+    },
+
+    /// A targeted offer, answer or ICE candidate did not reach its recipient
+    #[serde(rename = "delivery-failed")]
+    DeliveryFailed {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "targetUserId")]
+        target_user_id: u32,
+        /// Type of the undelivered message, e.g. `offer`
+        #[serde(rename = "messageType")]
+        message_type: String,
+        reason: DeliveryFailure,
+    },
+
+    /// Live rooms visible to the user, in answer to `list-rooms` or a subscription
+    #[serde(rename = "room-list")]
+    RoomList { rooms: Vec<RoomSummary> },
+
+    /// Someone joined or left a listed room; a `participantCount` of 0 means it closed
+    #[serde(rename = "room-updated")]
+    RoomUpdated { room: RoomSummary },
+
     #[serde(rename = "error")]
     Error { message: String, code: Option<u32> },
 
@@ -126,6 +551,74 @@ pub enum ServerMessage {
         user_id: u32,
         username: String,
     },
+
+    #[serde(rename = "ice-servers")]
+    IceServers {
+        #[serde(rename = "iceServers")]
+        ice_servers: Vec<IceServer>,
+        /// Seconds until any TURN credentials in the list expire
+        ttl: Option<u64>,
+    },
+}
+
+/// Why a user left a room
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LeaveReason {
+    /// The user asked to leave
+    Left,
+    /// The user's connection closed without them leaving first
+    Disconnected,
+    Kicked,
+    Banned,
+    /// Moved to a breakout room or back to its parent
+    Moved,
+    /// The server holding the user's connection shut down or stopped responding
+    ServerShutdown,
+}
+
+/// Why a live room was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RoomCloseReason {
+    /// The room reached its `closes_at` time
+    ScheduledEnd,
+    /// The session reached its maximum duration
+    MaxDuration,
+}
+
+/// Why a targeted message was not delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeliveryFailure {
+    /// The recipient is not in the room and has not been recently
+    UnknownRecipient,
+    /// The recipient left the room recently, or is still listed in it but their
+    /// connection has closed
+    RecipientGone,
+    /// The recipient's connection has too many messages waiting to be sent
+    QueueFull,
+}
+
+/// Why a call ended before it was accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CallEndReason {
+    Declined,
+    /// The caller hung up
+    Cancelled,
+    /// Nobody answered in time
+    Timeout,
+}
+
+/// Breakout room to open and the participants to move into it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreakoutAssignment {
+    /// Name under the parent room; the full room name is `{parent}/{name}`
+    pub name: String,
+    #[serde(default)]
+    pub user_ids: Vec<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -136,6 +629,62 @@ pub struct Participant {
     pub username: String,
 }
 
+/// Entry of the room directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomSummary {
+    #[serde(rename = "roomName")]
+    pub room_name: String,
+    /// Everyone in the room, spectators included
+    pub participant_count: usize,
+    pub spectator_count: usize,
+    /// Participants who send media
+    pub participants: Vec<Participant>,
+}
+
+/// Entry of an `RTCConfiguration.iceServers` list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IceServer {
+    pub urls: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+}
+
+impl ClientMessage {
+    /// Room the message is about, if any
+    pub fn room_name_mut(&mut self) -> Option<&mut String> {
+        match self {
+            ClientMessage::Auth { .. }
+            | ClientMessage::Ring { .. }
+            | ClientMessage::AcceptCall { .. }
+            | ClientMessage::DeclineCall { .. }
+            | ClientMessage::CancelCall { .. }
+            | ClientMessage::ListRooms
+            | ClientMessage::SubscribeRoomDirectory
+            | ClientMessage::UnsubscribeRoomDirectory => None,
+            ClientMessage::JoinRoom { room_name, .. }
+            | ClientMessage::LeaveRoom { room_name }
+            | ClientMessage::Offer { room_name, .. }
+            | ClientMessage::Answer { room_name, .. }
+            | ClientMessage::IceCandidate { room_name, .. }
+            | ClientMessage::Kick { room_name, .. }
+            | ClientMessage::Ban { room_name, .. }
+            | ClientMessage::RequestMute { room_name, .. }
+            | ClientMessage::TransferHost { room_name, .. }
+            | ClientMessage::PromoteSpectator { room_name, .. }
+            | ClientMessage::Admit { room_name, .. }
+            | ClientMessage::Deny { room_name, .. }
+            | ClientMessage::CreateBreakouts { room_name, .. }
+            | ClientMessage::CloseBreakouts { room_name }
+            | ClientMessage::LockRoom { room_name, .. }
+            | ClientMessage::SetRoomState { room_name, .. }
+            | ClientMessage::DeleteRoomState { room_name, .. } => Some(room_name),
+        }
+    }
+}
+
 impl ServerMessage {
     pub fn error(message: impl Into<String>) -> Self {
         Self::Error {
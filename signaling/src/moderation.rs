@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::messages::BreakoutAssignment;
+use crate::room::JoinError;
+
+/// A participant's standing in a room. Roles are ordered, so a role may act on
+/// anyone ranked below it.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum RoomRole {
+    /// Receives media from the presenters but is not announced to the room and
+    /// signals only with presenters
+    Spectator,
+    /// Also called a presenter where the room has spectators
+    #[default]
+    Participant,
+    Moderator,
+    /// Runs the room; there is at most one host at a time
+    Host,
+}
+
+impl RoomRole {
+    pub fn can_moderate(&self) -> bool {
+        *self >= RoomRole::Moderator
+    }
+
+    pub fn is_spectator(&self) -> bool {
+        *self == RoomRole::Spectator
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoomRole::Spectator => "spectator",
+            RoomRole::Participant => "participant",
+            RoomRole::Moderator => "moderator",
+            RoomRole::Host => "host",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "spectator" => Some(RoomRole::Spectator),
+            "participant" => Some(RoomRole::Participant),
+            "moderator" => Some(RoomRole::Moderator),
+            "host" => Some(RoomRole::Host),
+            _ => None,
+        }
+    }
+}
+
+/// An action a moderator takes against a room or one of its participants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModeratorAction {
+    /// Remove the target; they may join again
+    Kick { target: u32, reason: Option<String> },
+    /// Remove the target and refuse their joins for `duration`, or indefinitely
+    Ban {
+        target: u32,
+        duration: Option<Duration>,
+        reason: Option<String>,
+    },
+    /// Ask the target to mute `media` (`audio` or `video`), or everything
+    RequestMute { target: u32, media: Option<String> },
+    /// Refuse new joins from anyone but moderators
+    Lock { locked: bool },
+    /// Hand the host role to another participant (host only)
+    TransferHost { target: u32 },
+    /// Make a spectator a presenter, announcing them to the room (host only)
+    Promote { target: u32 },
+    /// Let a user waiting in the lobby into the room
+    Admit { target: u32 },
+    /// Refuse the join of a user waiting in the lobby
+    Deny { target: u32, reason: Option<String> },
+    /// Open breakout rooms and move the assigned participants into them (host only)
+    OpenBreakouts { breakouts: Vec<BreakoutAssignment> },
+    /// Bring everyone back from the breakout rooms and close them (host only)
+    CloseBreakouts,
+}
+
+impl ModeratorAction {
+    /// The user the action is aimed at, if any
+    pub fn target(&self) -> Option<u32> {
+        match self {
+            ModeratorAction::Kick { target, .. }
+            | ModeratorAction::Ban { target, .. }
+            | ModeratorAction::RequestMute { target, .. }
+            | ModeratorAction::TransferHost { target }
+            | ModeratorAction::Promote { target }
+            | ModeratorAction::Admit { target }
+            | ModeratorAction::Deny { target, .. } => Some(*target),
+            ModeratorAction::Lock { .. }
+            | ModeratorAction::OpenBreakouts { .. }
+            | ModeratorAction::CloseBreakouts => None,
+        }
+    }
+}
+
+/// Check that a user with `actor_role` may take `action`, given the roles of its target
+pub fn authorize(
+    action: &ModeratorAction,
+    actor_role: RoomRole,
+    role_of: impl Fn(u32) -> RoomRole,
+) -> Result<(), ModerationError> {
+    let host_only = matches!(
+        action,
+        ModeratorAction::TransferHost { .. }
+            | ModeratorAction::Promote { .. }
+            | ModeratorAction::OpenBreakouts { .. }
+            | ModeratorAction::CloseBreakouts
+    );
+    match action {
+        _ if host_only && actor_role != RoomRole::Host => Err(ModerationError::NotHost),
+        _ if host_only => Ok(()),
+        _ if !actor_role.can_moderate() => Err(ModerationError::NotModerator),
+        _ => match action.target() {
+            Some(target) if role_of(target) >= actor_role => Err(ModerationError::TargetProtected),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Why a moderator action was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModerationError {
+    /// The acting user is not in the room
+    NotInRoom,
+    NotModerator,
+    NotHost,
+    TargetNotFound,
+    /// The target's role is not below the actor's
+    TargetProtected,
+    /// The action itself is malformed
+    Invalid(String),
+    Failed(String),
+}
+
+impl ModerationError {
+    /// Error code sent to the client alongside the message
+    pub fn code(&self) -> u32 {
+        match self {
+            ModerationError::NotInRoom => 403,
+            ModerationError::NotModerator => 403,
+            ModerationError::NotHost => 403,
+            ModerationError::TargetNotFound => 404,
+            ModerationError::TargetProtected => 403,
+            ModerationError::Invalid(_) => 400,
+            ModerationError::Failed(_) => 500,
+        }
+    }
+}
+
+impl fmt::Display for ModerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModerationError::NotInRoom => write!(f, "You are not in this room"),
+            ModerationError::NotModerator => write!(f, "Only moderators can do that"),
+            ModerationError::NotHost => write!(f, "Only the host can do that"),
+            ModerationError::TargetNotFound => write!(f, "User not in room"),
+            ModerationError::TargetProtected => {
+                write!(f, "Cannot act on a user with an equal or higher role")
+            }
+            ModerationError::Invalid(message) | ModerationError::Failed(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModerationError {}
+
+/// Room bans held by a single node; `None` expiry means the ban never lapses
+#[derive(Debug, Default)]
+pub struct BanList {
+    bans: Mutex<HashMap<(String, u32), Option<Instant>>>,
+}
+
+impl BanList {
+    pub fn ban(&self, room_name: &str, user_id: u32, duration: Option<Duration>) {
+        info!(
+            "User {} banned from room {} for {:?}",
+            user_id, room_name, duration
+        );
+        let expires = duration.map(|duration| Instant::now() + duration);
+        self.bans
+            .lock()
+            .unwrap()
+            .insert((room_name.to_string(), user_id), expires);
+    }
+
+    /// Refuse the join if `user_id` is banned from the room
+    pub fn check(&self, room_name: &str, user_id: u32) -> Result<(), JoinError> {
+        let now = Instant::now();
+        let mut bans = self.bans.lock().unwrap();
+        let key = (room_name.to_string(), user_id);
+        match bans.get(&key) {
+            Some(None) => Err(JoinError::Banned { remaining: None }),
+            Some(Some(expires)) if *expires > now => Err(JoinError::Banned {
+                remaining: Some(*expires - now),
+            }),
+            Some(Some(_)) => {
+                bans.remove(&key);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Check breakout names and that each assigned user is in the parent room once
+pub fn validate_breakouts(
+    breakouts: &[BreakoutAssignment],
+    in_parent: impl Fn(u32) -> bool,
+) -> Result<(), ModerationError> {
+    let mut names = HashSet::new();
+    let mut assigned = HashSet::new();
+    for assignment in breakouts {
+        if assignment.name.is_empty() || assignment.name.contains('/') {
+            return Err(ModerationError::Invalid(format!(
+                "Invalid breakout room name: {:?}",
+                assignment.name
+            )));
+        }
+        if !names.insert(assignment.name.as_str()) {
+            return Err(ModerationError::Invalid(format!(
+                "Duplicate breakout room: {}",
+                assignment.name
+            )));
+        }
+        for user_id in &assignment.user_ids {
+            if !in_parent(*user_id) {
+                return Err(ModerationError::TargetNotFound);
+            }
+            if !assigned.insert(*user_id) {
+                return Err(ModerationError::Invalid(format!(
+                    "User {} is assigned to more than one breakout room",
+                    user_id
+                )));
+            }
+        }
+    }
+    Ok(())
+}
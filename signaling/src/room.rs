@@ -1,24 +1,128 @@
-use std::collections::HashMap;
+use chrono::Utc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::{mpsc, RwLock};
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 use tokio_tungstenite::tungstenite::Message;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+use crate::access::{LockoutPolicy, RoomAccessGuard};
+use crate::audit::{AuditEvent, AuditLog, SignalKind};
 use crate::auth::AuthenticatedUser;
-use crate::messages::{Participant, ServerMessage};
+use crate::call::{CallError, CallInvitation, CallResponse, UserConnections, CALL_ROOM_TTL};
+use crate::directory::RoomDirectory;
+use crate::mesh::{MeshPlan, NegotiationState, PeerPair, MESH_SWEEP_INTERVAL};
+use crate::messages::{
+    BreakoutAssignment, CallEndReason, DeliveryFailure, LeaveReason, Participant, RoomCloseReason,
+    RoomSummary, ServerMessage,
+};
+use crate::moderation::{
+    authorize, validate_breakouts, BanList, ModerationError, ModeratorAction, RoomRole,
+};
+use crate::room_config::{
+    HostTransferPolicy, MeshPolicy, OverflowPolicy, RejoinPolicy, RoomConfig, RoomConfigStore,
+    RoomVisibility,
+};
+use crate::room_state::{RoomState, RoomStateError, RoomStateMap, RoomStateUpdate};
+use crate::schedule::{check_window, RoomDeadline, CLOSING_WARNINGS};
+use crate::tenant::{local_room_name, same_tenant, tenant_of};
+use crate::webhook::{WebhookDispatcher, WebhookEvent};
+
+/// Close code sent to a connection whose place in a room was taken by the same
+/// user rejoining from a new connection
+pub const CLOSE_CODE_REPLACED: u16 = 4001;
+
+/// Departed users a room remembers, so signals still on their way to them are
+/// reported as gone rather than unknown
+pub const RECENTLY_DEPARTED: usize = 32;
+
+/// Messages a connection can have waiting to be written before sends to it fail
+/// with [`DeliveryFailure::QueueFull`]
+pub const OUTBOUND_QUEUE_CAPACITY: usize = 256;
+
+/// Channel for the messages written to a connection, bounded by
+/// [`OUTBOUND_QUEUE_CAPACITY`]
+pub fn outbound_channel() -> (mpsc::Sender<Message>, mpsc::Receiver<Message>) {
+    mpsc::channel(OUTBOUND_QUEUE_CAPACITY)
+}
 
 #[derive(Debug, Clone)]
 pub struct RoomParticipant {
     pub user: AuthenticatedUser,
     pub connection_id: Uuid,
-    pub sender: mpsc::UnboundedSender<Message>,
+    pub sender: mpsc::Sender<Message>,
+}
+
+impl RoomParticipant {
+    /// Queue a message on the participant's connection
+    pub fn deliver(&self, message: &ServerMessage) -> Result<(), DeliveryError> {
+        let json_message = serde_json::to_string(message)
+            .map_err(|e| DeliveryError::Failed(format!("Failed to serialize message: {}", e)))?;
+        self.sender
+            .try_send(Message::Text(json_message))
+            .map_err(|e| {
+                DeliveryError::Undelivered(match e {
+                    TrySendError::Full(_) => DeliveryFailure::QueueFull,
+                    TrySendError::Closed(_) => DeliveryFailure::RecipientGone,
+                })
+            })
+    }
 }
 
 #[derive(Debug)]
 pub struct Room {
     pub name: String,
     pub participants: HashMap<u32, RoomParticipant>, // user_id -> participant
+    /// Participants who do not count towards capacity: spectators by role and
+    /// those admitted over capacity
+    pub spectators: HashSet<u32>,
+    /// Users waiting for a free slot, in arrival order
+    pub waitlist: VecDeque<RoomParticipant>,
+    /// Users waiting for a moderator to admit them, in arrival order
+    pub lobby: VecDeque<RoomParticipant>,
+    /// Roles other than [`RoomRole::Participant`], for participants and waiting users
+    pub roles: HashMap<u32, RoomRole>,
+    /// Session limits from the tokens of users waiting in the lobby
+    pub knock_durations: HashMap<u32, Duration>,
+    /// Locked rooms refuse joins from anyone but moderators
+    pub locked: bool,
+    /// First joiner, or the user whose token claimed the room
+    pub owner: Option<u32>,
+    pub host: Option<u32>,
+    /// When each participant joined, for picking the next host
+    pub joined_at: HashMap<u32, Instant>,
+    /// Room this breakout room was opened from
+    pub parent: Option<String>,
+    /// Full names of the breakout rooms opened from this room
+    pub breakouts: Vec<String>,
+    /// When the room last became empty, while it waits out its TTL
+    pub emptied_at: Option<Instant>,
+    /// Receives the room's membership changes
+    pub webhooks: Option<WebhookDispatcher>,
+    /// Shared key-value state, delivered to everyone who joins
+    pub state: RoomState,
+    /// Records the room's membership changes
+    pub audit: Option<AuditLog>,
+    /// Users allowed into a private room; anyone may join when empty
+    pub invited: HashSet<u32>,
+    /// Receives the room's summary whenever it changes
+    pub directory: Option<RoomDirectory>,
+    /// Taken from the room's configuration as users join
+    pub visibility: RoomVisibility,
+    /// When the live room closes, set as it becomes live
+    pub deadline: Option<RoomDeadline>,
+    /// Taken from the room's configuration as users join
+    pub mesh_policy: MeshPolicy,
+    /// Pairs told to connect, under [`MeshPolicy::ServerPlanned`]
+    pub mesh: MeshPlan,
+    /// The last [`RECENTLY_DEPARTED`] users to leave, oldest first
+    pub departed: VecDeque<u32>,
 }
 
 impl Room {
@@ -26,6 +130,36 @@ impl Room {
         Self {
             name,
             participants: HashMap::new(),
+            spectators: HashSet::new(),
+            waitlist: VecDeque::new(),
+            lobby: VecDeque::new(),
+            roles: HashMap::new(),
+            knock_durations: HashMap::new(),
+            locked: false,
+            owner: None,
+            host: None,
+            joined_at: HashMap::new(),
+            parent: None,
+            breakouts: Vec::new(),
+            emptied_at: None,
+            webhooks: None,
+            state: RoomState::default(),
+            audit: None,
+            invited: HashSet::new(),
+            directory: None,
+            visibility: RoomVisibility::default(),
+            deadline: None,
+            mesh_policy: MeshPolicy::default(),
+            mesh: MeshPlan::default(),
+            departed: VecDeque::new(),
+        }
+    }
+
+    /// A breakout room linked to `parent`
+    pub fn breakout(name: String, parent: &str) -> Self {
+        Self {
+            parent: Some(parent.to_string()),
+            ..Self::new(name)
         }
     }
 
@@ -40,25 +174,99 @@ impl Room {
             "User {} ({}) joined room {}",
             user_id, participant.user.username, self.name
         );
+        let username = participant.user.username.clone();
         self.participants.insert(user_id, participant);
+        self.departed.retain(|departed| *departed != user_id);
+        self.joined_at.insert(user_id, Instant::now());
+        if let Some(audit) = &self.audit {
+            audit.record(
+                &self.name,
+                AuditEvent::Joined {
+                    user_id,
+                    username: username.clone(),
+                },
+            );
+        }
+        self.emit(WebhookEvent::UserJoined {
+            room_name: self.name.clone(),
+            user_id,
+            username,
+            participant_count: self.participants.len(),
+            spectator_count: self.spectators.len(),
+        });
+        self.update_directory();
         true
     }
 
     pub fn remove_participant(&mut self, user_id: u32) -> Option<RoomParticipant> {
+        self.spectators.remove(&user_id);
+        self.roles.remove(&user_id);
+        self.joined_at.remove(&user_id);
+        self.mesh.remove_user(user_id);
         if let Some(participant) = self.participants.remove(&user_id) {
             info!(
                 "User {} ({}) left room {}",
                 user_id, participant.user.username, self.name
             );
+            self.departed.push_back(user_id);
+            if self.departed.len() > RECENTLY_DEPARTED {
+                self.departed.pop_front();
+            }
+            if let Some(audit) = &self.audit {
+                audit.record(&self.name, AuditEvent::Left { user_id });
+            }
+            self.emit(WebhookEvent::UserLeft {
+                room_name: self.name.clone(),
+                user_id,
+                participant_count: self.participants.len(),
+                spectator_count: self.spectators.len(),
+            });
+            if self.participants.is_empty() {
+                self.emit(WebhookEvent::RoomEmptied {
+                    room_name: self.name.clone(),
+                });
+            }
+            self.update_directory();
             Some(participant)
         } else {
             None
         }
     }
 
+    fn emit(&self, event: WebhookEvent) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.emit(event);
+        }
+    }
+
+    /// Listed rooms are public and not limited to invited users
+    pub fn is_listed(&self) -> bool {
+        self.visibility == RoomVisibility::Public && self.invited.is_empty()
+    }
+
+    /// Directory entry for the room
+    pub fn summary(&self) -> RoomSummary {
+        RoomSummary {
+            room_name: self.name.clone(),
+            participant_count: self.participants.len(),
+            spectator_count: self.spectators.len(),
+            participants: self.get_participants_list(),
+        }
+    }
+
+    fn update_directory(&self) {
+        if let Some(directory) = &self.directory {
+            if self.is_listed() {
+                directory.publish(&self.summary());
+            }
+        }
+    }
+
+    /// Participants announced to the room; spectators by role are left out
     pub fn get_participants_list(&self) -> Vec<Participant> {
         self.participants
             .values()
+            .filter(|p| !self.role_of(p.user.user_id).is_spectator())
             .map(|p| Participant {
                 user_id: p.user.user_id,
                 username: p.user.username.clone(),
@@ -77,7 +285,27 @@ impl Room {
 
         for (user_id, participant) in &self.participants {
             if *user_id != sender_id {
-                if let Err(e) = participant.sender.send(json_message.clone()) {
+                if let Err(e) = participant.sender.try_send(json_message.clone()) {
+                    warn!("Failed to send message to user {}: {}", user_id, e);
+                }
+            }
+        }
+    }
+
+    /// Relay an offer or candidate to every presenter but the sender. Spectators
+    /// only receive signaling addressed to them.
+    pub fn broadcast_signal(&self, sender_id: u32, message: ServerMessage) {
+        let json_message = match serde_json::to_string(&message) {
+            Ok(json) => Message::Text(json),
+            Err(e) => {
+                warn!("Failed to serialize message: {}", e);
+                return;
+            }
+        };
+
+        for (user_id, participant) in &self.participants {
+            if *user_id != sender_id && !self.role_of(*user_id).is_spectator() {
+                if let Err(e) = participant.sender.try_send(json_message.clone()) {
                     warn!("Failed to send message to user {}: {}", user_id, e);
                 }
             }
@@ -94,49 +322,905 @@ impl Room {
         };
 
         for (user_id, participant) in &self.participants {
-            if let Err(e) = participant.sender.send(json_message.clone()) {
+            if let Err(e) = participant.sender.try_send(json_message.clone()) {
                 warn!("Failed to send message to user {}: {}", user_id, e);
             }
         }
     }
 
     pub fn send_to_user(&self, user_id: u32, message: ServerMessage) {
-        if let Some(participant) = self.participants.get(&user_id) {
-            let json_message = match serde_json::to_string(&message) {
-                Ok(json) => Message::Text(json),
-                Err(e) => {
-                    warn!("Failed to serialize message: {}", e);
-                    return;
-                }
+        if !self.participants.contains_key(&user_id) {
+            return;
+        }
+        if let Err(e) = self.deliver_to_user(user_id, message) {
+            warn!("Failed to send message to user {}: {}", user_id, e);
+        }
+    }
+
+    /// Like [`Room::send_to_user`], but reports why the message was not delivered
+    pub fn deliver_to_user(
+        &self,
+        user_id: u32,
+        message: ServerMessage,
+    ) -> Result<(), DeliveryError> {
+        let Some(participant) = self.participants.get(&user_id) else {
+            let failure = if self.departed.contains(&user_id) {
+                DeliveryFailure::RecipientGone
+            } else {
+                DeliveryFailure::UnknownRecipient
             };
+            return Err(DeliveryError::Undelivered(failure));
+        };
+        participant.deliver(&message)
+    }
 
-            if let Err(e) = participant.sender.send(json_message) {
-                warn!("Failed to send message to user {}: {}", user_id, e);
-            }
+    pub fn role_of(&self, user_id: u32) -> RoomRole {
+        if self.host == Some(user_id) {
+            return RoomRole::Host;
+        }
+        self.roles.get(&user_id).copied().unwrap_or_default()
+    }
+
+    pub fn ownership(&self) -> RoomOwnership {
+        RoomOwnership {
+            owner: self.owner,
+            host: self.host,
+        }
+    }
+
+    /// Make a newly joined participant host if they claim the room, or if the role
+    /// is vacant and the policy lets them take it. Returns the `host-changed` event
+    /// for the other participants when the role changed hands.
+    pub fn settle_host(
+        &mut self,
+        user_id: u32,
+        claims_host: bool,
+        policy: HostTransferPolicy,
+    ) -> Option<ServerMessage> {
+        if claims_host {
+            self.owner = Some(user_id);
+        }
+        let owner = *self.owner.get_or_insert(user_id);
+        let takes_vacancy = self.host.is_none()
+            && (owner == user_id || policy == HostTransferPolicy::LongestPresent);
+        if !(claims_host || takes_vacancy) || self.host == Some(user_id) {
+            return None;
+        }
+
+        let previous = self.host.replace(user_id);
+        info!("User {} is now host of room {}", user_id, self.name);
+        Some(ServerMessage::HostChanged {
+            room_name: self.name.clone(),
+            host_user_id: Some(user_id),
+            previous_host_user_id: previous,
+        })
+    }
+
+    /// Hand the host role to `user_id` and tell the room
+    pub fn set_host(&mut self, user_id: Option<u32>) {
+        let previous = std::mem::replace(&mut self.host, user_id);
+        if previous == user_id {
+            return;
         }
+        info!("Host of room {} changed to {:?}", self.name, user_id);
+        self.broadcast_to_all(ServerMessage::HostChanged {
+            room_name: self.name.clone(),
+            host_user_id: user_id,
+            previous_host_user_id: previous,
+        });
+    }
+
+    /// Replace a host who is no longer in the room according to `policy`
+    pub fn reassign_host(&mut self, policy: HostTransferPolicy) {
+        let Some(host) = self.host else {
+            return;
+        };
+        if self.has_participant(host) {
+            return;
+        }
+
+        let successor = match policy {
+            HostTransferPolicy::LongestPresent => self.longest_present(),
+            HostTransferPolicy::Vacant => None,
+        };
+        self.set_host(successor);
+    }
+
+    /// The participant who joined first, preferring those admitted within capacity.
+    /// Spectators by role never become host.
+    fn longest_present(&self) -> Option<u32> {
+        self.joined_at
+            .iter()
+            .filter(|(user_id, _)| !self.role_of(**user_id).is_spectator())
+            .min_by_key(|(user_id, joined_at)| {
+                (self.spectators.contains(user_id), **joined_at, **user_id)
+            })
+            .map(|(user_id, _)| *user_id)
     }
 
     pub fn has_participant(&self, user_id: u32) -> bool {
         self.participants.contains_key(&user_id)
     }
 
+    /// Remove a participant and tell the rest of the room, unless they were a
+    /// spectator the room was never told about
+    pub fn leave(&mut self, user_id: u32, reason: LeaveReason) -> Option<RoomParticipant> {
+        let announced = !self.role_of(user_id).is_spectator();
+        let participant = self.remove_participant(user_id)?;
+        if announced {
+            self.broadcast_to_all(ServerMessage::UserLeft {
+                room_name: self.name.clone(),
+                user_id,
+                reason: Some(reason),
+            });
+        }
+        Some(participant)
+    }
+
+    /// Remove everyone from the room, including waiting users, telling them why the
+    /// room closed
+    pub fn close(&mut self, reason: RoomCloseReason) {
+        let closed = ServerMessage::RoomClosed {
+            room_name: self.name.clone(),
+            reason,
+        };
+        info!("Closing room {}: {:?}", self.name, reason);
+        for participant in self.lobby.drain(..).chain(self.waitlist.drain(..)) {
+            self.roles.remove(&participant.user.user_id);
+            self.knock_durations.remove(&participant.user.user_id);
+            send_to_participant(&participant, closed.clone());
+        }
+        let user_ids: Vec<u32> = self.participants.keys().copied().collect();
+        for user_id in user_ids {
+            if let Some(participant) = self.remove_participant(user_id) {
+                send_to_participant(&participant, closed.clone());
+            }
+        }
+        self.host = None;
+        self.deadline = None;
+    }
+
+    /// Make a spectator a presenter: they are announced to the room, count towards
+    /// its capacity and may signal with everyone
+    pub fn promote(
+        &mut self,
+        user_id: u32,
+        by_user_id: u32,
+        config: &RoomConfig,
+    ) -> Result<(), ModerationError> {
+        let Some(participant) = self.participants.get(&user_id) else {
+            return Err(ModerationError::TargetNotFound);
+        };
+        if !self.role_of(user_id).is_spectator() {
+            return Err(ModerationError::Invalid(format!(
+                "User {} is not a spectator",
+                user_id
+            )));
+        }
+        let user = Participant {
+            user_id,
+            username: participant.user.username.clone(),
+        };
+        self.roles.remove(&user_id);
+        self.spectators.remove(&user_id);
+        info!(
+            "User {} promoted spectator {} in room {}",
+            by_user_id, user_id, self.name
+        );
+
+        self.send_to_user(
+            user_id,
+            ServerMessage::Promoted {
+                room_name: self.name.clone(),
+                by_user_id,
+            },
+        );
+        self.broadcast_to_others(
+            user_id,
+            ServerMessage::UserJoined {
+                room_name: self.name.clone(),
+                user,
+            },
+        );
+        if let Some(host_changed) = self.settle_host(user_id, false, config.host_transfer) {
+            self.broadcast_to_all(host_changed);
+        }
+        self.update_directory();
+        self.connect_peers(user_id);
+        Ok(())
+    }
+
+    /// Pair a participant who just got into the room with everyone already in it and
+    /// tell both peers of each pair to connect, the newcomer sending the offer. Only
+    /// rooms under [`MeshPolicy::ServerPlanned`] are planned, and spectators are not
+    /// paired with each other.
+    pub fn connect_peers(&mut self, user_id: u32) {
+        if self.mesh_policy != MeshPolicy::ServerPlanned || !self.has_participant(user_id) {
+            return;
+        }
+        let spectator = self.spectators.contains(&user_id);
+        let mut peers: Vec<u32> = self
+            .participants
+            .keys()
+            .copied()
+            .filter(|peer| *peer != user_id && !(spectator && self.spectators.contains(peer)))
+            .collect();
+        peers.sort_unstable();
+
+        self.mesh.remove_user(user_id);
+        let now = tokio::time::Instant::now();
+        for peer in peers {
+            let pair = self.mesh.connect(user_id, peer, now);
+            self.instruct(&pair);
+        }
+    }
+
+    /// Advance the negotiation of the pair a relayed offer or answer belongs to
+    pub fn record_signal(&mut self, from_user_id: u32, to_user_id: u32, kind: SignalKind) {
+        if let Some(state) = self.mesh.record(from_user_id, to_user_id, kind) {
+            debug!(
+                "Pair {} in room {} is now {:?}",
+                PeerPair::key(from_user_id, to_user_id),
+                self.name,
+                state
+            );
+        }
+    }
+
+    /// Tell the peers of pairs that stalled to connect again, giving up on those
+    /// out of attempts
+    pub fn retry_stalled_pairs(&mut self, now: tokio::time::Instant) {
+        for pair in self.mesh.retry_stalled(now) {
+            if pair.state == NegotiationState::Failed {
+                warn!(
+                    "Users {} and {} in room {} did not connect after {} attempts",
+                    pair.initiator, pair.responder, self.name, pair.attempt
+                );
+            } else {
+                debug!(
+                    "Re-issuing connect-to for users {} and {} in room {}, attempt {}",
+                    pair.initiator, pair.responder, self.name, pair.attempt
+                );
+                self.instruct(&pair);
+            }
+        }
+    }
+
+    fn instruct(&self, pair: &PeerPair) {
+        for (user_id, message) in pair.instructions(&self.name) {
+            self.send_to_user(user_id, message);
+        }
+    }
+
+    /// No participants, nobody waiting in the lobby and no open breakout rooms
     pub fn is_empty(&self) -> bool {
-        self.participants.is_empty()
+        self.participants.is_empty() && self.lobby.is_empty() && self.breakouts.is_empty()
+    }
+
+    /// Participants counted against the room's capacity
+    pub fn active_count(&self) -> usize {
+        self.participants.len() - self.spectators.len()
+    }
+
+    pub fn is_full(&self, max_participants: Option<usize>) -> bool {
+        max_participants.is_some_and(|max| self.active_count() >= max)
+    }
+
+    /// 1-based position of `user_id` in the waitlist
+    pub fn waitlist_position(&self, user_id: u32) -> Option<usize> {
+        self.waitlist
+            .iter()
+            .position(|p| p.user.user_id == user_id)
+            .map(|index| index + 1)
+    }
+
+    pub fn remove_from_waitlist(&mut self, user_id: u32) -> Option<RoomParticipant> {
+        let index = self.waitlist_position(user_id)? - 1;
+        let participant = self.waitlist.remove(index);
+        self.roles.remove(&user_id);
+        self.notify_waitlist();
+        participant
+    }
+
+    /// Move waiting users into the room while there is capacity for them
+    pub fn admit_from_waitlist(&mut self, config: &RoomConfig) {
+        let mut admitted = false;
+        while !self.is_full(config.max_participants) {
+            let Some(participant) = self.waitlist.pop_front() else {
+                break;
+            };
+            let user_id = participant.user.user_id;
+            let existing_participants = self.get_participants_list();
+            if !self.add_participant(participant.clone()) {
+                continue;
+            }
+            admitted = true;
+
+            let host_changed = self.settle_host(user_id, false, config.host_transfer);
+            self.send_to_user(
+                user_id,
+                ServerMessage::RoomJoined {
+                    room_name: self.name.clone(),
+                    user_id,
+                    participants: existing_participants,
+                    spectator: false,
+                    owner_user_id: self.owner,
+                    host_user_id: self.host,
+                    state: self.state.snapshot(),
+                },
+            );
+            self.broadcast_to_others(
+                user_id,
+                ServerMessage::UserJoined {
+                    room_name: self.name.clone(),
+                    user: Participant {
+                        user_id,
+                        username: participant.user.username.clone(),
+                    },
+                },
+            );
+            if let Some(host_changed) = host_changed {
+                self.broadcast_to_others(user_id, host_changed);
+            }
+            self.connect_peers(user_id);
+        }
+        if admitted {
+            self.notify_waitlist();
+        }
+    }
+
+    /// Add a participant whose lock and lobby checks have passed, applying the
+    /// capacity and overflow policy. Tells the room about the newcomer.
+    pub fn enter(
+        &mut self,
+        participant: RoomParticipant,
+        role: RoomRole,
+        config: &RoomConfig,
+    ) -> Result<JoinOutcome, JoinError> {
+        let user_id = participant.user.user_id;
+        if self.has_participant(user_id) {
+            return Err(JoinError::AlreadyInRoom);
+        }
+        // The host role itself lives in `Room::host` so it can move between users
+        let claims_host = role == RoomRole::Host;
+        let role = role.min(RoomRole::Moderator);
+        if role != RoomRole::Participant {
+            self.roles.insert(user_id, role);
+        }
+
+        // Spectators by role never take a slot
+        let mut spectator = role.is_spectator();
+        if !spectator && self.is_full(config.max_participants) {
+            let capacity = config.max_participants.unwrap_or_default();
+            match config.overflow {
+                OverflowPolicy::Reject => {
+                    self.roles.remove(&user_id);
+                    info!("Room {} is full, refusing user {}", self.name, user_id);
+                    return Err(JoinError::RoomFull { capacity });
+                }
+                OverflowPolicy::Waitlist => {
+                    self.waitlist.push_back(participant);
+                    let position = self.waitlist.len();
+                    info!(
+                        "Room {} is full, user {} waitlisted at position {}",
+                        self.name, user_id, position
+                    );
+                    return Ok(JoinOutcome::Waitlisted { position });
+                }
+                OverflowPolicy::Spectator => spectator = true,
+            }
+        }
+
+        let existing_participants = self.get_participants_list();
+
+        if spectator {
+            self.spectators.insert(user_id);
+        }
+        self.add_participant(participant.clone());
+
+        // Spectators by role join unannounced and never take the host role
+        if !role.is_spectator() {
+            let user_joined_msg = ServerMessage::UserJoined {
+                room_name: self.name.clone(),
+                user: Participant {
+                    user_id: participant.user.user_id,
+                    username: participant.user.username.clone(),
+                },
+            };
+            self.broadcast_to_others(participant.user.user_id, user_joined_msg);
+            if let Some(host_changed) = self.settle_host(user_id, claims_host, config.host_transfer)
+            {
+                self.broadcast_to_others(user_id, host_changed);
+            }
+        }
+        if self.role_of(user_id).can_moderate() {
+            self.send_knocks_to(user_id);
+        }
+
+        if spectator {
+            Ok(JoinOutcome::Spectating(existing_participants))
+        } else {
+            Ok(JoinOutcome::Joined(existing_participants))
+        }
+    }
+
+    /// Whether `participant` is rejoining from a new connection while their old
+    /// one is still in the room
+    pub fn is_rejoin(&self, participant: &RoomParticipant) -> bool {
+        self.participants
+            .get(&participant.user.user_id)
+            .is_some_and(|current| current.connection_id != participant.connection_id)
+    }
+
+    /// Hand a participant's place to their new connection and close the old one.
+    /// Their role, host status and join time are kept, and the room is told once
+    /// rather than seeing them leave and join again.
+    pub fn replace_participant(&mut self, participant: RoomParticipant) -> JoinOutcome {
+        let user_id = participant.user.user_id;
+        let existing_participants: Vec<Participant> = self
+            .get_participants_list()
+            .into_iter()
+            .filter(|p| p.user_id != user_id)
+            .collect();
+        if let Some(stale) = self.participants.insert(user_id, participant) {
+            info!(
+                "User {} rejoined room {}, replacing connection {}",
+                user_id, self.name, stale.connection_id
+            );
+            close_replaced(&stale.sender);
+        }
+
+        let role = self.role_of(user_id);
+        if !role.is_spectator() {
+            self.broadcast_to_others(
+                user_id,
+                ServerMessage::ParticipantReplaced {
+                    room_name: self.name.clone(),
+                    user_id,
+                },
+            );
+        }
+        if role.can_moderate() {
+            self.send_knocks_to(user_id);
+        }
+
+        if self.spectators.contains(&user_id) {
+            JoinOutcome::Spectating(existing_participants)
+        } else {
+            JoinOutcome::Joined(existing_participants)
+        }
+    }
+
+    /// Whether a join must wait in the lobby. Moderators and the room's owner skip it.
+    pub fn holds_in_lobby(&self, user_id: u32, role: RoomRole, config: &RoomConfig) -> bool {
+        config.lobby && !role.can_moderate() && self.owner != Some(user_id)
+    }
+
+    pub fn is_knocking(&self, user_id: u32) -> bool {
+        self.lobby.iter().any(|p| p.user.user_id == user_id)
+    }
+
+    /// Put a user in the lobby and tell the moderators. Their role and their token's
+    /// session limit are kept until the knock is answered.
+    pub fn knock(
+        &mut self,
+        participant: RoomParticipant,
+        role: RoomRole,
+        max_duration: Option<Duration>,
+    ) {
+        if role != RoomRole::Participant {
+            self.roles.insert(participant.user.user_id, role);
+        }
+        if let Some(max_duration) = max_duration {
+            self.knock_durations
+                .insert(participant.user.user_id, max_duration);
+        }
+        info!(
+            "User {} is waiting in the lobby of room {}",
+            participant.user.user_id, self.name
+        );
+        self.notify_moderators(ServerMessage::Knock {
+            room_name: self.name.clone(),
+            user: Participant {
+                user_id: participant.user.user_id,
+                username: participant.user.username.clone(),
+            },
+        });
+        self.lobby.push_back(participant);
+    }
+
+    /// Take a user out of the lobby and tell the moderators what became of them
+    pub fn resolve_knock(&mut self, user_id: u32, admitted: bool) -> Option<RoomParticipant> {
+        let index = self.lobby.iter().position(|p| p.user.user_id == user_id)?;
+        let participant = self.lobby.remove(index)?;
+        self.roles.remove(&user_id);
+        self.knock_durations.remove(&user_id);
+        self.notify_moderators(ServerMessage::KnockResolved {
+            room_name: self.name.clone(),
+            user_id,
+            admitted,
+        });
+        Some(participant)
+    }
+
+    fn notify_moderators(&self, message: ServerMessage) {
+        for user_id in self.participants.keys() {
+            if self.role_of(*user_id).can_moderate() {
+                self.send_to_user(*user_id, message.clone());
+            }
+        }
+    }
+
+    /// Replay the knocks still waiting to a moderator who just arrived
+    fn send_knocks_to(&self, user_id: u32) {
+        for participant in &self.lobby {
+            self.send_to_user(
+                user_id,
+                ServerMessage::Knock {
+                    room_name: self.name.clone(),
+                    user: Participant {
+                        user_id: participant.user.user_id,
+                        username: participant.user.username.clone(),
+                    },
+                },
+            );
+        }
+    }
+
+    /// Apply a participant's change to the shared state and tell the whole room
+    pub fn update_state(
+        &mut self,
+        user_id: u32,
+        update: RoomStateUpdate,
+    ) -> Result<u64, RoomStateError> {
+        if !self.has_participant(user_id) {
+            return Err(RoomStateError::NotInRoom);
+        }
+        let key = update.key.clone();
+        let value = update.value.clone();
+        let version = self.state.apply(update)?;
+
+        self.broadcast_to_all(ServerMessage::RoomStateChanged {
+            room_name: self.name.clone(),
+            key,
+            value,
+            version,
+            by_user_id: user_id,
+        });
+        Ok(version)
+    }
+
+    /// Take a participant out of the room so they can be moved to another one
+    fn take_for_move(
+        &mut self,
+        user_id: u32,
+        config: &RoomConfig,
+    ) -> Option<(RoomParticipant, RoomRole)> {
+        // The host role stays with this room
+        let role = self.role_of(user_id).min(RoomRole::Moderator);
+        let participant = self.leave(user_id, LeaveReason::Moved)?;
+        self.reassign_host(config.host_transfer);
+        self.admit_from_waitlist(config);
+        Some((participant, role))
+    }
+
+    /// Add a participant moved here from `from_room`, bypassing the join checks.
+    /// The participant gets `moved-to-room` in place of the leave and join replies.
+    fn receive_moved(
+        &mut self,
+        participant: RoomParticipant,
+        role: RoomRole,
+        from_room: &str,
+        config: &RoomConfig,
+    ) {
+        let user_id = participant.user.user_id;
+        if self.has_participant(user_id) {
+            return;
+        }
+        self.visibility = config.visibility;
+        self.mesh_policy = config.mesh;
+        let existing_participants = self.get_participants_list();
+        if role != RoomRole::Participant {
+            self.roles.insert(user_id, role);
+        }
+        if role.is_spectator() {
+            self.spectators.insert(user_id);
+        }
+        self.add_participant(participant.clone());
+
+        if !role.is_spectator() {
+            self.broadcast_to_others(
+                user_id,
+                ServerMessage::UserJoined {
+                    room_name: self.name.clone(),
+                    user: Participant {
+                        user_id,
+                        username: participant.user.username.clone(),
+                    },
+                },
+            );
+            if let Some(host_changed) = self.settle_host(user_id, false, config.host_transfer) {
+                self.broadcast_to_others(user_id, host_changed);
+            }
+        }
+        self.send_to_user(
+            user_id,
+            ServerMessage::MovedToRoom {
+                from_room_name: from_room.to_string(),
+                room_name: self.name.clone(),
+                participants: existing_participants,
+                owner_user_id: self.owner,
+                host_user_id: self.host,
+                state: self.state.snapshot(),
+            },
+        );
+        if self.role_of(user_id).can_moderate() {
+            self.send_knocks_to(user_id);
+        }
+        self.connect_peers(user_id);
+    }
+
+    /// Tell everyone still waiting their current position
+    fn notify_waitlist(&self) {
+        for (index, participant) in self.waitlist.iter().enumerate() {
+            let message = ServerMessage::Waitlisted {
+                room_name: self.name.clone(),
+                position: index + 1,
+            };
+            if let Ok(json) = serde_json::to_string(&message) {
+                let _ = participant.sender.try_send(Message::Text(json));
+            }
+        }
+    }
+}
+
+/// Who owns and who currently hosts a room
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoomOwnership {
+    pub owner: Option<u32>,
+    pub host: Option<u32>,
+}
+
+/// Credentials and connection details presented when joining a room
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JoinRequest {
+    pub password: Option<String>,
+    pub client_ip: Option<IpAddr>,
+    /// Role granted by the user's token
+    pub role: RoomRole,
+    /// Longest session the user's token allows in a room they open
+    pub max_duration: Option<Duration>,
+}
+
+/// How a join request was satisfied
+#[derive(Debug, Clone)]
+pub enum JoinOutcome {
+    /// Joined as a regular participant; carries the participants already present
+    Joined(Vec<Participant>),
+    /// The room was full and the user was admitted as a spectator
+    Spectating(Vec<Participant>),
+    /// The room was full and the user was queued at `position` (1-based)
+    Waitlisted { position: usize },
+    /// The user is waiting in the room's lobby for a moderator to admit them
+    InLobby,
+}
+
+impl JoinOutcome {
+    /// Participants already in the room, or `None` when the user is waiting
+    pub fn participants(&self) -> Option<&[Participant]> {
+        match self {
+            JoinOutcome::Joined(participants) | JoinOutcome::Spectating(participants) => {
+                Some(participants)
+            }
+            JoinOutcome::Waitlisted { .. } | JoinOutcome::InLobby => None,
+        }
+    }
+
+    /// Event telling the user how their join went
+    pub fn to_server_message(
+        &self,
+        room_name: &str,
+        user_id: u32,
+        ownership: RoomOwnership,
+        state: RoomStateMap,
+    ) -> ServerMessage {
+        match self {
+            JoinOutcome::Waitlisted { position } => ServerMessage::Waitlisted {
+                room_name: room_name.to_string(),
+                position: *position,
+            },
+            JoinOutcome::InLobby => ServerMessage::InLobby {
+                room_name: room_name.to_string(),
+            },
+            JoinOutcome::Joined(participants) | JoinOutcome::Spectating(participants) => {
+                ServerMessage::RoomJoined {
+                    room_name: room_name.to_string(),
+                    user_id,
+                    participants: participants.clone(),
+                    spectator: matches!(self, JoinOutcome::Spectating(_)),
+                    owner_user_id: ownership.owner,
+                    host_user_id: ownership.host,
+                    state,
+                }
+            }
+        }
+    }
+}
+
+/// Why a join was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinError {
+    AlreadyInRoom,
+    /// The room is at capacity and does not overflow
+    RoomFull {
+        capacity: usize,
+    },
+    /// The room is protected and no password was given
+    PasswordRequired,
+    InvalidPassword,
+    /// Too many failed attempts from this user or address
+    LockedOut {
+        retry_after: Duration,
+    },
+    /// The room is locked by a moderator
+    RoomLocked,
+    /// A moderator banned the user; `None` while the ban is indefinite
+    Banned {
+        remaining: Option<Duration>,
+    },
+    /// A moderator turned the user away from the lobby
+    LobbyDenied {
+        reason: Option<String>,
+    },
+    /// Nobody answered the user's knock in time
+    LobbyTimeout,
+    /// The room belongs to a tenant other than the user's
+    WrongTenant,
+    /// The room is private to the users of a call
+    NotInvited,
+    /// The room's scheduled window has not started yet
+    RoomNotOpen {
+        opens_in: Duration,
+    },
+    /// The room's scheduled window has ended
+    RoomClosed,
+}
+
+impl JoinError {
+    /// Error code sent to the client alongside the message
+    pub fn code(&self) -> u32 {
+        match self {
+            JoinError::AlreadyInRoom => 409,
+            JoinError::RoomFull { .. } => 403,
+            JoinError::PasswordRequired => 401,
+            JoinError::InvalidPassword => 403,
+            JoinError::LockedOut { .. } => 429,
+            JoinError::RoomLocked => 423,
+            JoinError::Banned { .. } => 403,
+            JoinError::LobbyDenied { .. } => 403,
+            JoinError::LobbyTimeout => 408,
+            JoinError::WrongTenant => 403,
+            JoinError::NotInvited => 403,
+            JoinError::RoomNotOpen { .. } => 425,
+            JoinError::RoomClosed => 410,
+        }
+    }
+
+    /// Event telling the user their join was refused
+    pub fn to_server_message(&self, room_name: &str) -> ServerMessage {
+        match self {
+            JoinError::RoomFull { capacity } => ServerMessage::RoomFull {
+                room_name: room_name.to_string(),
+                capacity: *capacity,
+            },
+            e => ServerMessage::error_with_code(format!("Failed to join room: {}", e), e.code()),
+        }
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinError::AlreadyInRoom => write!(f, "User already in room"),
+            JoinError::RoomFull { capacity } => {
+                write!(f, "Room is full ({} participants)", capacity)
+            }
+            JoinError::PasswordRequired => write!(f, "Room requires a password"),
+            JoinError::InvalidPassword => write!(f, "Invalid room password"),
+            JoinError::LockedOut { retry_after } => write!(
+                f,
+                "Too many failed attempts, try again in {} seconds",
+                retry_after.as_secs().max(1)
+            ),
+            JoinError::RoomLocked => write!(f, "Room is locked"),
+            JoinError::Banned { remaining: None } => write!(f, "You are banned from this room"),
+            JoinError::Banned {
+                remaining: Some(remaining),
+            } => write!(
+                f,
+                "You are banned from this room for another {} seconds",
+                remaining.as_secs().max(1)
+            ),
+            JoinError::LobbyDenied { reason: None } => write!(f, "A moderator denied the join"),
+            JoinError::LobbyDenied {
+                reason: Some(reason),
+            } => write!(f, "A moderator denied the join: {}", reason),
+            JoinError::LobbyTimeout => write!(f, "Nobody admitted you from the lobby in time"),
+            JoinError::WrongTenant => write!(f, "The room belongs to another tenant"),
+            JoinError::NotInvited => write!(f, "The room is private"),
+            JoinError::RoomNotOpen { opens_in } => {
+                write!(f, "The room opens in {} seconds", opens_in.as_secs().max(1))
+            }
+            JoinError::RoomClosed => write!(f, "The room has closed"),
+        }
     }
 }
 
+impl std::error::Error for JoinError {}
+
+/// Why a targeted send failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryError {
+    /// The recipient could not be reached; the sender is told with `delivery-failed`
+    Undelivered(DeliveryFailure),
+    /// The server could not route the message
+    Failed(String),
+}
+
+impl fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeliveryError::Undelivered(DeliveryFailure::UnknownRecipient) => {
+                write!(f, "User not found in room")
+            }
+            DeliveryError::Undelivered(DeliveryFailure::RecipientGone) => {
+                write!(f, "User has left the room")
+            }
+            DeliveryError::Undelivered(DeliveryFailure::QueueFull) => {
+                write!(f, "User has too many messages waiting")
+            }
+            DeliveryError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DeliveryError {}
+
 // Legacy type alias for backward compatibility
 pub type Rooms = Arc<RwLock<HashMap<String, Room>>>;
 
 // New trait-based system for room management
 #[async_trait::async_trait]
 pub trait RoomManagerTrait: Send + Sync {
+    /// Join without credentials; password-protected rooms refuse this
     async fn join_room(
         &self,
         room_name: String,
         participant: RoomParticipant,
-    ) -> Result<Vec<Participant>, String>;
+    ) -> Result<Vec<Participant>, String> {
+        match self
+            .join_room_with(room_name, participant, JoinRequest::default())
+            .await
+        {
+            Ok(JoinOutcome::Joined(participants)) | Ok(JoinOutcome::Spectating(participants)) => {
+                Ok(participants)
+            }
+            Ok(JoinOutcome::Waitlisted { position }) => {
+                Err(format!("Room is full, waitlisted at position {}", position))
+            }
+            Ok(JoinOutcome::InLobby) => Err("Waiting in the lobby to be admitted".to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+    /// Join after checking the room's access policy and capacity
+    async fn join_room_with(
+        &self,
+        room_name: String,
+        participant: RoomParticipant,
+        request: JoinRequest,
+    ) -> Result<JoinOutcome, JoinError>;
     async fn leave_room(&self, room_name: &str, user_id: u32) -> Result<(), String>;
+    /// Relay an offer or candidate to the room's presenters, other than the sender
     async fn broadcast_to_room(
         &self,
         room_name: &str,
@@ -148,90 +1232,620 @@ pub trait RoomManagerTrait: Send + Sync {
         room_name: &str,
         target_user_id: u32,
         message: ServerMessage,
-    ) -> Result<(), String>;
+    ) -> Result<(), DeliveryError>;
     async fn user_in_room(&self, room_name: &str, user_id: u32) -> bool;
+    /// The user's role in the room; participants are presenters unless they spectate
+    async fn room_role(&self, room_name: &str, user_id: u32) -> RoomRole;
     async fn remove_user_from_all_rooms(&self, user_id: u32, connection_id: Uuid);
     async fn get_room_participants(&self, room_name: &str) -> Vec<Participant>;
+    /// Apply a moderator action taken by `actor_id`
+    async fn moderate(
+        &self,
+        room_name: &str,
+        actor_id: u32,
+        action: ModeratorAction,
+    ) -> Result<(), ModerationError>;
+    async fn room_config(&self, room_name: &str) -> RoomConfig;
+    /// Define a room that exists while empty, keeping its lock, bans and owner
+    async fn create_room(&self, room_name: &str, config: RoomConfig) -> Result<(), String>;
+    /// Drop a room's definition; it is removed once nobody is left in it
+    async fn delete_room(&self, room_name: &str) -> Result<(), String>;
+    async fn room_ownership(&self, room_name: &str) -> RoomOwnership;
+    /// Change the room's shared state on behalf of `user_id`, telling the room
+    async fn update_room_state(
+        &self,
+        room_name: &str,
+        user_id: u32,
+        update: RoomStateUpdate,
+    ) -> Result<u64, RoomStateError>;
+    async fn room_state(&self, room_name: &str) -> RoomStateMap;
+    /// Tell a user who just got into the room, and each peer they are paired with,
+    /// whom to connect to; only rooms under [`MeshPolicy::ServerPlanned`] are planned
+    async fn plan_mesh(&self, room_name: &str, user_id: u32);
+    /// Track the negotiation of a planned pair through an offer or answer relayed
+    /// from `from_user_id` to `to_user_id`
+    async fn record_signal(
+        &self,
+        room_name: &str,
+        from_user_id: u32,
+        to_user_id: u32,
+        kind: SignalKind,
+    );
+    /// Make a connection reachable by events addressed to its user, such as calls
+    async fn connect_user(&self, participant: RoomParticipant);
+    async fn disconnect_user(&self, connection_id: Uuid);
+    /// Ring every connection of `callee_id` until the call is answered or
+    /// `ring_timeout` passes
+    async fn invite(
+        &self,
+        caller: &AuthenticatedUser,
+        callee_id: u32,
+        ring_timeout: Duration,
+    ) -> Result<CallInvitation, CallError>;
+    /// Answer or cancel a ringing call, opening its private room once accepted
+    async fn respond_to_call(
+        &self,
+        user: &AuthenticatedUser,
+        call_id: Uuid,
+        response: CallResponse,
+    ) -> Result<(), CallError>;
+    /// Listed rooms of `tenant` that have someone in them, by name
+    async fn list_rooms(&self, tenant: Option<&str>) -> Vec<RoomSummary>;
+    /// Send `room-updated` to the connection whenever a listed room of its tenant changes
+    async fn subscribe_room_directory(&self, participant: RoomParticipant);
+    async fn unsubscribe_room_directory(&self, connection_id: Uuid);
     async fn health_check(&self) -> bool;
 
-    // For testing purposes - get access to internal room state
-    fn get_rooms_for_testing(&self) -> Option<Rooms> {
-        None // Default implementation returns None
+    // For testing purposes - get access to internal room state
+    fn get_rooms_for_testing(&self) -> Option<Rooms> {
+        None // Default implementation returns None
+    }
+}
+
+// Local implementation (existing behavior)
+pub struct LocalRoomManager {
+    rooms: Rooms,
+    room_configs: Arc<RoomConfigStore>,
+    access: RoomAccessGuard,
+    bans: BanList,
+    webhooks: Option<WebhookDispatcher>,
+    audit: Option<AuditLog>,
+    users: UserConnections,
+    /// Ringing calls by id
+    calls: Arc<RwLock<HashMap<Uuid, CallInvitation>>>,
+    directory: RoomDirectory,
+    /// Set once the task re-issuing stalled pairs is running
+    mesh_sweeper: AtomicBool,
+}
+
+impl Default for LocalRoomManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalRoomManager {
+    pub fn new() -> Self {
+        Self::with_room_configs(Arc::new(RoomConfigStore::default()))
+    }
+
+    pub fn with_room_configs(room_configs: Arc<RoomConfigStore>) -> Self {
+        Self {
+            rooms: Arc::new(RwLock::new(HashMap::new())),
+            room_configs,
+            access: RoomAccessGuard::default(),
+            bans: BanList::default(),
+            webhooks: None,
+            audit: None,
+            users: UserConnections::default(),
+            calls: Arc::new(RwLock::new(HashMap::new())),
+            directory: RoomDirectory::default(),
+            mesh_sweeper: AtomicBool::new(false),
+        }
+    }
+
+    pub fn with_lockout_policy(mut self, policy: LockoutPolicy) -> Self {
+        self.access = RoomAccessGuard::new(policy);
+        self
+    }
+
+    pub fn get_rooms(&self) -> Rooms {
+        self.rooms.clone()
+    }
+
+    /// Send room lifecycle events to `webhooks`
+    pub fn with_webhooks(mut self, webhooks: WebhookDispatcher) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Record who joins and leaves each room in `audit`
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Hook a room that is about to be added up to the webhooks, audit log and
+    /// room directory
+    fn new_room(&self, mut room: Room) -> Room {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.emit(WebhookEvent::RoomCreated {
+                room_name: room.name.clone(),
+            });
+            room.webhooks = Some(webhooks.clone());
+        }
+        room.audit = self.audit.clone();
+        room.directory = Some(self.directory.clone());
+        room
+    }
+
+    /// Once a room's pairs are planned by the server, start checking every
+    /// [`MESH_SWEEP_INTERVAL`] for pairs that stalled. The task ends with the manager.
+    fn start_mesh_sweeper(&self, config: &RoomConfig) {
+        if config.mesh != MeshPolicy::ServerPlanned
+            || self.mesh_sweeper.swap(true, Ordering::Relaxed)
+        {
+            return;
+        }
+        let rooms = Arc::downgrade(&self.rooms);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(MESH_SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(rooms) = rooms.upgrade() else {
+                    return;
+                };
+                let now = tokio::time::Instant::now();
+                for room in rooms.write().await.values_mut() {
+                    room.retry_stalled_pairs(now);
+                }
+            }
+        });
+    }
+
+    fn lifecycle(&self) -> RoomLifecycle {
+        RoomLifecycle {
+            rooms: Arc::clone(&self.rooms),
+            room_configs: Arc::clone(&self.room_configs),
+        }
+    }
+
+    pub fn room_configs(&self) -> Arc<RoomConfigStore> {
+        self.room_configs.clone()
+    }
+
+    /// Authenticated connections on this server
+    pub fn users(&self) -> UserConnections {
+        self.users.clone()
+    }
+
+    /// Connections on this server subscribed to the room directory
+    pub fn directory(&self) -> RoomDirectory {
+        self.directory.clone()
+    }
+
+    pub fn lockout_policy(&self) -> LockoutPolicy {
+        self.access.policy()
+    }
+
+    /// Check the room's password, applying lockout after repeated failures
+    pub async fn check_access(
+        &self,
+        room_name: &str,
+        user_id: u32,
+        request: &JoinRequest,
+    ) -> Result<(), JoinError> {
+        let config = self.room_configs.get(room_name).await;
+        self.access
+            .check(
+                room_name,
+                &config,
+                user_id,
+                request.password.as_deref(),
+                request.client_ip,
+            )
+            .await
+    }
+
+    /// The user's role in the room: the token's grant, raised to moderator for users
+    /// the room configuration lists
+    pub async fn effective_role(
+        &self,
+        room_name: &str,
+        user_id: u32,
+        request: &JoinRequest,
+    ) -> RoomRole {
+        let config = self.room_configs.get(room_name).await;
+        if config.moderators.contains(&user_id) {
+            request.role.max(RoomRole::Moderator)
+        } else {
+            request.role
+        }
+    }
+
+    /// Refuse users from a tenant other than the room's
+    pub fn check_tenant(
+        &self,
+        room_name: &str,
+        participant: &RoomParticipant,
+    ) -> Result<(), JoinError> {
+        if same_tenant(participant.user.tenant.as_deref(), room_name) {
+            Ok(())
+        } else {
+            Err(JoinError::WrongTenant)
+        }
+    }
+
+    /// Refuse joins outside the room's scheduled window
+    pub async fn check_schedule(&self, room_name: &str) -> Result<(), JoinError> {
+        check_window(&self.room_configs.get(room_name).await, Utc::now())
+    }
+
+    /// Check the bans issued on this node
+    pub fn check_ban(&self, room_name: &str, user_id: u32) -> Result<(), JoinError> {
+        self.bans.check(room_name, user_id)
+    }
+
+    /// Add a participant whose access has already been checked, applying the room's
+    /// lock, capacity and overflow policy
+    pub(crate) async fn add_participant(
+        &self,
+        room_name: String,
+        participant: RoomParticipant,
+        role: RoomRole,
+        max_duration: Option<Duration>,
+    ) -> Result<JoinOutcome, JoinError> {
+        let config = self.room_configs.get(&room_name).await;
+        let user_id = participant.user.user_id;
+
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .entry(room_name.clone())
+            .or_insert_with(|| self.new_room(Room::new(room_name.clone())));
+        room.visibility = config.visibility;
+        room.mesh_policy = config.mesh;
+        self.start_mesh_sweeper(&config);
+
+        if config.rejoin == RejoinPolicy::Replace && room.is_rejoin(&participant) {
+            return Ok(room.replace_participant(participant));
+        }
+        if room.has_participant(user_id)
+            || room.waitlist_position(user_id).is_some()
+            || room.is_knocking(user_id)
+        {
+            warn!("User {} already in room {}", user_id, room_name);
+            return Err(JoinError::AlreadyInRoom);
+        }
+
+        if !room.invited.is_empty() && !room.invited.contains(&user_id) {
+            info!("Room {} is private, refusing user {}", room_name, user_id);
+            return Err(JoinError::NotInvited);
+        }
+
+        if room.locked && !role.can_moderate() {
+            info!("Room {} is locked, refusing user {}", room_name, user_id);
+            return Err(JoinError::RoomLocked);
+        }
+
+        if room.holds_in_lobby(user_id, role, &config) {
+            self.expire_knock(&room_name, &participant, config.lobby_timeout());
+            room.knock(participant, role, max_duration);
+            return Ok(JoinOutcome::InLobby);
+        }
+
+        let result = room.enter(participant, role, &config);
+        if result.is_err() {
+            self.lifecycle().release(&mut rooms, &room_name).await;
+        } else if room.deadline.is_none() {
+            // The join made the room live, which starts its session
+            room.deadline = RoomDeadline::for_session(&config, max_duration, Utc::now());
+            if let Some(deadline) = room.deadline {
+                self.lifecycle().schedule_close(room_name, deadline);
+            }
+        }
+        result
+    }
+
+    /// Refuse the join if the knock is still unanswered after `timeout`
+    fn expire_knock(&self, room_name: &str, participant: &RoomParticipant, timeout: Duration) {
+        let rooms = Arc::clone(&self.rooms);
+        let lifecycle = self.lifecycle();
+        let room_name = room_name.to_string();
+        let user_id = participant.user.user_id;
+        let connection_id = participant.connection_id;
+
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let mut rooms = rooms.write().await;
+            let Some(room) = rooms.get_mut(&room_name) else {
+                return;
+            };
+            let still_waiting = room
+                .lobby
+                .iter()
+                .any(|p| p.user.user_id == user_id && p.connection_id == connection_id);
+            if !still_waiting {
+                return;
+            }
+
+            info!("Knock by user {} on room {} timed out", user_id, room_name);
+            if let Some(participant) = room.resolve_knock(user_id, false) {
+                send_to_participant(
+                    &participant,
+                    JoinError::LobbyTimeout.to_server_message(&room_name),
+                );
+            }
+            lifecycle.release(&mut rooms, &room_name).await;
+        });
+    }
+
+    /// Open the private room of an accepted call; it is dropped if nobody joins it
+    /// within [`CALL_ROOM_TTL`]
+    pub(crate) async fn open_call_room(&self, invitation: &CallInvitation) {
+        let room_name = invitation.room_name();
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .entry(room_name.clone())
+            .or_insert_with(|| self.new_room(Room::new(room_name.clone())));
+        room.invited.extend(invitation.user_ids());
+        if room.is_empty() {
+            let emptied_at = Instant::now();
+            room.emptied_at = Some(emptied_at);
+            self.lifecycle()
+                .expire(room_name, CALL_ROOM_TTL, emptied_at);
+        }
+    }
+
+    /// End the call if it is still ringing after `timeout`
+    fn expire_call(&self, invitation: &CallInvitation, timeout: Duration) {
+        let calls = Arc::clone(&self.calls);
+        let users = self.users.clone();
+        let call_id = invitation.call_id;
+
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let Some(invitation) = calls.write().await.remove(&call_id) else {
+                return;
+            };
+
+            info!(
+                "Call {} from user {} timed out",
+                call_id, invitation.caller.user_id
+            );
+            let ended = invitation.ended(CallEndReason::Timeout);
+            for user_id in invitation.user_ids() {
+                users
+                    .send(invitation.tenant.as_deref(), user_id, &ended)
+                    .await;
+            }
+        });
+    }
+
+    /// Open breakout rooms under `parent_name` and move the assigned participants
+    /// into them. Every assignment is checked before anyone is moved.
+    async fn open_breakouts(
+        &self,
+        rooms: &mut HashMap<String, Room>,
+        parent_name: &str,
+        breakouts: Vec<BreakoutAssignment>,
+    ) -> Result<(), ModerationError> {
+        let Some(mut parent) = rooms.remove(parent_name) else {
+            return Err(ModerationError::NotInRoom);
+        };
+        if let Err(e) = validate_breakouts(&breakouts, |user_id| parent.has_participant(user_id)) {
+            rooms.insert(parent_name.to_string(), parent);
+            return Err(e);
+        }
+
+        let parent_config = self.room_configs.get(parent_name).await;
+        for assignment in breakouts {
+            let name = breakout_room_name(parent_name, &assignment.name);
+            let config = self.room_configs.get(&name).await;
+            self.start_mesh_sweeper(&config);
+            let mut breakout = rooms
+                .remove(&name)
+                .unwrap_or_else(|| self.new_room(Room::breakout(name.clone(), parent_name)));
+            if !parent.breakouts.contains(&name) {
+                info!("Opened breakout room {}", name);
+                parent.breakouts.push(name.clone());
+            }
+
+            for user_id in assignment.user_ids {
+                if let Some((participant, role)) = parent.take_for_move(user_id, &parent_config) {
+                    breakout.receive_moved(participant, role, parent_name, &config);
+                }
+            }
+            rooms.insert(name, breakout);
+        }
+
+        rooms.insert(parent_name.to_string(), parent);
+        Ok(())
+    }
+
+    /// Move everyone in the breakout rooms of `parent_name` back to it and close them
+    async fn close_breakouts(&self, rooms: &mut HashMap<String, Room>, parent_name: &str) {
+        let Some(mut parent) = rooms.remove(parent_name) else {
+            return;
+        };
+        let config = self.room_configs.get(parent_name).await;
+
+        for name in std::mem::take(&mut parent.breakouts) {
+            let Some(mut breakout) = rooms.remove(&name) else {
+                continue;
+            };
+            let breakout_config = self.room_configs.get(&name).await;
+            let mut user_ids: Vec<u32> = breakout.participants.keys().copied().collect();
+            user_ids.sort_unstable();
+            for user_id in user_ids {
+                if let Some((participant, role)) = breakout.take_for_move(user_id, &breakout_config)
+                {
+                    parent.receive_moved(participant, role, &name, &config);
+                }
+            }
+            info!("Closed breakout room {}", name);
+        }
+
+        rooms.insert(parent_name.to_string(), parent);
     }
 }
 
-// Local implementation (existing behavior)
-pub struct LocalRoomManager {
-    rooms: Rooms,
+/// Full name of the breakout room `name` opened from `parent`
+pub fn breakout_room_name(parent: &str, name: &str) -> String {
+    format!("{}/{}", parent, name)
 }
 
-impl Default for LocalRoomManager {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Decides what happens to rooms that have become empty
+#[derive(Clone)]
+struct RoomLifecycle {
+    rooms: Rooms,
+    room_configs: Arc<RoomConfigStore>,
 }
 
-impl LocalRoomManager {
-    pub fn new() -> Self {
-        Self {
-            rooms: Arc::new(RwLock::new(HashMap::new())),
+impl RoomLifecycle {
+    /// Drop a room once it is empty, unless it is persistent or has an empty-room
+    /// TTL to wait out. Removing a breakout room detaches it from its parent, which
+    /// is then checked in turn.
+    async fn release(&self, rooms: &mut HashMap<String, Room>, room_name: &str) {
+        let mut room_name = room_name.to_string();
+        loop {
+            let config = self.room_configs.get(&room_name).await;
+            let Some(room) = rooms.get_mut(&room_name) else {
+                return;
+            };
+            if !room.is_empty() {
+                return;
+            }
+            // The session ends with the last participant
+            room.deadline = None;
+            if config.persistent {
+                return;
+            }
+            if let Some(ttl) = config.empty_ttl() {
+                let emptied_at = Instant::now();
+                room.emptied_at = Some(emptied_at);
+                self.expire(room_name, ttl, emptied_at);
+                return;
+            }
+
+            match remove_room(rooms, &room_name) {
+                Some(parent_name) => room_name = parent_name,
+                None => return,
+            }
         }
     }
 
-    pub fn get_rooms(&self) -> Rooms {
-        self.rooms.clone()
+    /// Warn the room's participants as `deadline` approaches and close the room when
+    /// it passes, unless the session ends first
+    fn schedule_close(&self, room_name: String, deadline: RoomDeadline) {
+        let lifecycle = self.clone();
+        tokio::spawn(async move {
+            let current = |rooms: &HashMap<String, Room>| {
+                rooms
+                    .get(&room_name)
+                    .is_some_and(|room| room.deadline == Some(deadline))
+            };
+            let closes_at = deadline.instant();
+            for warning in CLOSING_WARNINGS {
+                let Some(warn_at) = closes_at.checked_sub(warning) else {
+                    continue;
+                };
+                if warn_at <= tokio::time::Instant::now() {
+                    continue;
+                }
+                tokio::time::sleep_until(warn_at).await;
+                let rooms = lifecycle.rooms.read().await;
+                if !current(&rooms) {
+                    return;
+                }
+                rooms[&room_name].broadcast_to_all(ServerMessage::RoomClosing {
+                    room_name: room_name.clone(),
+                    closes_in_seconds: warning.as_secs(),
+                    reason: deadline.reason,
+                });
+            }
+
+            tokio::time::sleep_until(closes_at).await;
+            let rooms = Arc::clone(&lifecycle.rooms);
+            let mut rooms = rooms.write().await;
+            if !current(&rooms) {
+                return;
+            }
+            if let Some(room) = rooms.get_mut(&room_name) {
+                room.close(deadline.reason);
+            }
+            lifecycle.release(&mut rooms, &room_name).await;
+        });
+    }
+
+    /// Remove the room after `ttl` if nobody has used it since it became empty
+    fn expire(&self, room_name: String, ttl: Duration, emptied_at: Instant) {
+        let lifecycle = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(ttl).await;
+            let rooms = Arc::clone(&lifecycle.rooms);
+            let mut rooms = rooms.write().await;
+            let expired = rooms
+                .get(&room_name)
+                .is_some_and(|room| room.is_empty() && room.emptied_at == Some(emptied_at));
+            if !expired {
+                return;
+            }
+
+            debug!("Empty room {} reached its TTL", room_name);
+            if let Some(parent_name) = remove_room(&mut rooms, &room_name) {
+                lifecycle.release(&mut rooms, &parent_name).await;
+            }
+        });
+    }
+}
+
+/// Remove a room and detach it from its parent, returning the parent's name
+fn remove_room(rooms: &mut HashMap<String, Room>, room_name: &str) -> Option<String> {
+    let room = rooms.remove(room_name)?;
+    debug!("Removed empty room: {}", room_name);
+
+    let parent_name = room.parent?;
+    if let Some(parent) = rooms.get_mut(&parent_name) {
+        parent.breakouts.retain(|name| name != room_name);
     }
+    Some(parent_name)
 }
 
 #[async_trait::async_trait]
 impl RoomManagerTrait for LocalRoomManager {
-    async fn join_room(
+    async fn join_room_with(
         &self,
         room_name: String,
         participant: RoomParticipant,
-    ) -> Result<Vec<Participant>, String> {
-        let mut rooms = self.rooms.write().await;
-        let room = rooms
-            .entry(room_name.clone())
-            .or_insert_with(|| Room::new(room_name.clone()));
-
-        let existing_participants = room.get_participants_list();
-
-        if room.add_participant(participant.clone()) {
-            // Notify other participants about the new user
-            let user_joined_msg = ServerMessage::UserJoined {
-                room_name: room_name.clone(),
-                user: Participant {
-                    user_id: participant.user.user_id,
-                    username: participant.user.username.clone(),
-                },
-            };
-            room.broadcast_to_others(participant.user.user_id, user_joined_msg);
-
-            Ok(existing_participants)
-        } else {
-            Err("User already in room".to_string())
-        }
+        request: JoinRequest,
+    ) -> Result<JoinOutcome, JoinError> {
+        let user_id = participant.user.user_id;
+        self.check_tenant(&room_name, &participant)?;
+        self.check_access(&room_name, user_id, &request).await?;
+        self.check_ban(&room_name, user_id)?;
+        self.check_schedule(&room_name).await?;
+        let role = self.effective_role(&room_name, user_id, &request).await;
+        self.add_participant(room_name, participant, role, request.max_duration)
+            .await
     }
 
     async fn leave_room(&self, room_name: &str, user_id: u32) -> Result<(), String> {
+        let config = self.room_configs.get(room_name).await;
         let mut rooms = self.rooms.write().await;
 
         if let Some(room) = rooms.get_mut(room_name) {
-            if let Some(_participant) = room.remove_participant(user_id) {
-                // Notify other participants about the user leaving
-                let user_left_msg = ServerMessage::UserLeft {
-                    room_name: room_name.to_string(),
-                    user_id,
-                };
-                room.broadcast_to_all(user_left_msg);
-
-                // Remove empty rooms
-                if room.is_empty() {
-                    rooms.remove(room_name);
-                    debug!("Removed empty room: {}", room_name);
-                }
+            if room.remove_from_waitlist(user_id).is_some() {
+                debug!("User {} left the waitlist of room {}", user_id, room_name);
+                Ok(())
+            } else if room.resolve_knock(user_id, false).is_some() {
+                debug!("User {} left the lobby of room {}", user_id, room_name);
+                self.lifecycle().release(&mut rooms, room_name).await;
+                Ok(())
+            } else if room.leave(user_id, LeaveReason::Left).is_some() {
+                room.reassign_host(config.host_transfer);
+                room.admit_from_waitlist(&config);
 
+                self.lifecycle().release(&mut rooms, room_name).await;
                 Ok(())
             } else {
                 Err("User not in room".to_string())
@@ -250,7 +1864,7 @@ impl RoomManagerTrait for LocalRoomManager {
         let rooms = self.rooms.read().await;
 
         if let Some(room) = rooms.get(room_name) {
-            room.broadcast_to_others(sender_id, message);
+            room.broadcast_signal(sender_id, message);
             Ok(())
         } else {
             Err("Room not found".to_string())
@@ -262,14 +1876,14 @@ impl RoomManagerTrait for LocalRoomManager {
         room_name: &str,
         target_user_id: u32,
         message: ServerMessage,
-    ) -> Result<(), String> {
+    ) -> Result<(), DeliveryError> {
         let rooms = self.rooms.read().await;
 
-        if let Some(room) = rooms.get(room_name) {
-            room.send_to_user(target_user_id, message);
-            Ok(())
-        } else {
-            Err("Room not found".to_string())
+        match rooms.get(room_name) {
+            Some(room) => room.deliver_to_user(target_user_id, message),
+            None => Err(DeliveryError::Undelivered(
+                DeliveryFailure::UnknownRecipient,
+            )),
         }
     }
 
@@ -281,21 +1895,47 @@ impl RoomManagerTrait for LocalRoomManager {
             .unwrap_or(false)
     }
 
+    async fn room_role(&self, room_name: &str, user_id: u32) -> RoomRole {
+        let rooms = self.rooms.read().await;
+        rooms
+            .get(room_name)
+            .map(|room| room.role_of(user_id))
+            .unwrap_or_default()
+    }
+
     async fn remove_user_from_all_rooms(&self, user_id: u32, connection_id: Uuid) {
         let mut rooms = self.rooms.write().await;
         let mut rooms_to_remove = Vec::new();
 
         for (room_name, room) in rooms.iter_mut() {
+            let waiting = room
+                .waitlist
+                .iter()
+                .any(|p| p.user.user_id == user_id && p.connection_id == connection_id);
+            if waiting {
+                room.remove_from_waitlist(user_id);
+                continue;
+            }
+
+            let knocking = room
+                .lobby
+                .iter()
+                .any(|p| p.user.user_id == user_id && p.connection_id == connection_id);
+            if knocking {
+                room.resolve_knock(user_id, false);
+                if room.is_empty() {
+                    rooms_to_remove.push(room_name.clone());
+                }
+                continue;
+            }
+
             if let Some(participant) = room.participants.get(&user_id) {
                 if participant.connection_id == connection_id {
-                    room.remove_participant(user_id);
+                    room.leave(user_id, LeaveReason::Disconnected);
 
-                    // Notify other participants
-                    let user_left_msg = ServerMessage::UserLeft {
-                        room_name: room_name.clone(),
-                        user_id,
-                    };
-                    room.broadcast_to_all(user_left_msg);
+                    let config = self.room_configs.get(room_name).await;
+                    room.reassign_host(config.host_transfer);
+                    room.admit_from_waitlist(&config);
 
                     if room.is_empty() {
                         rooms_to_remove.push(room_name.clone());
@@ -304,10 +1944,8 @@ impl RoomManagerTrait for LocalRoomManager {
             }
         }
 
-        // Remove empty rooms
         for room_name in rooms_to_remove {
-            rooms.remove(&room_name);
-            debug!("Removed empty room: {}", room_name);
+            self.lifecycle().release(&mut rooms, &room_name).await;
         }
     }
 
@@ -319,6 +1957,343 @@ impl RoomManagerTrait for LocalRoomManager {
             .unwrap_or_default()
     }
 
+    async fn moderate(
+        &self,
+        room_name: &str,
+        actor_id: u32,
+        action: ModeratorAction,
+    ) -> Result<(), ModerationError> {
+        let config = self.room_configs.get(room_name).await;
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_name)
+            .filter(|room| room.has_participant(actor_id))
+            .ok_or(ModerationError::NotInRoom)?;
+
+        let actor_role = room.role_of(actor_id);
+        authorize(&action, actor_role, |target| room.role_of(target))?;
+
+        match action {
+            ModeratorAction::Kick { target, reason } => {
+                let removed = room
+                    .leave(target, LeaveReason::Kicked)
+                    .ok_or(ModerationError::TargetNotFound)?;
+                info!(
+                    "User {} kicked {} from room {}",
+                    actor_id, target, room_name
+                );
+                send_to_participant(
+                    &removed,
+                    ServerMessage::Kicked {
+                        room_name: room_name.to_string(),
+                        by_user_id: actor_id,
+                        reason,
+                    },
+                );
+                room.reassign_host(config.host_transfer);
+                room.admit_from_waitlist(&config);
+            }
+            ModeratorAction::Ban {
+                target,
+                duration,
+                reason,
+            } => {
+                // Bans also apply to users who are not currently in the room
+                self.bans.ban(room_name, target, duration);
+                let banned = ServerMessage::Banned {
+                    room_name: room_name.to_string(),
+                    by_user_id: actor_id,
+                    reason,
+                    duration_seconds: duration.map(|duration| duration.as_secs()),
+                };
+                if let Some(waiting) = room.remove_from_waitlist(target) {
+                    send_to_participant(&waiting, banned);
+                } else if let Some(knocking) = room.resolve_knock(target, false) {
+                    send_to_participant(&knocking, banned);
+                } else if let Some(removed) = room.leave(target, LeaveReason::Banned) {
+                    send_to_participant(&removed, banned);
+                    room.reassign_host(config.host_transfer);
+                    room.admit_from_waitlist(&config);
+                }
+            }
+            ModeratorAction::RequestMute { target, media } => {
+                if !room.has_participant(target) {
+                    return Err(ModerationError::TargetNotFound);
+                }
+                room.send_to_user(
+                    target,
+                    ServerMessage::MuteRequested {
+                        room_name: room_name.to_string(),
+                        by_user_id: actor_id,
+                        media,
+                    },
+                );
+            }
+            ModeratorAction::Lock { locked } => {
+                info!("User {} set room {} locked={}", actor_id, room_name, locked);
+                room.locked = locked;
+                room.broadcast_to_all(ServerMessage::RoomLocked {
+                    room_name: room_name.to_string(),
+                    locked,
+                    by_user_id: actor_id,
+                });
+            }
+            ModeratorAction::TransferHost { target } => {
+                if !room.has_participant(target) {
+                    return Err(ModerationError::TargetNotFound);
+                }
+                room.set_host(Some(target));
+            }
+            ModeratorAction::Promote { target } => {
+                room.promote(target, actor_id, &config)?;
+            }
+            ModeratorAction::Admit { target } => {
+                let role = room.role_of(target);
+                let max_duration = room.knock_durations.get(&target).copied();
+                if let Err(e) = check_window(&config, Utc::now()) {
+                    // Outside the room's window the knock is refused instead
+                    let knocking = room
+                        .resolve_knock(target, false)
+                        .ok_or(ModerationError::TargetNotFound)?;
+                    send_to_participant(&knocking, e.to_server_message(room_name));
+                    self.lifecycle().release(&mut rooms, room_name).await;
+                    return Ok(());
+                }
+                let knocking = room
+                    .resolve_knock(target, true)
+                    .ok_or(ModerationError::TargetNotFound)?;
+                info!(
+                    "User {} admitted {} to room {}",
+                    actor_id, target, room_name
+                );
+                let sender = knocking.clone();
+                let message = match room.enter(knocking, role, &config) {
+                    Ok(outcome) => {
+                        if room.deadline.is_none() {
+                            // The admission made the room live, which starts its session
+                            room.deadline =
+                                RoomDeadline::for_session(&config, max_duration, Utc::now());
+                            if let Some(deadline) = room.deadline {
+                                self.lifecycle()
+                                    .schedule_close(room_name.to_string(), deadline);
+                            }
+                        }
+                        outcome.to_server_message(
+                            room_name,
+                            target,
+                            room.ownership(),
+                            room.state.snapshot(),
+                        )
+                    }
+                    Err(e) => e.to_server_message(room_name),
+                };
+                send_to_participant(&sender, message);
+                room.connect_peers(target);
+            }
+            ModeratorAction::OpenBreakouts { breakouts } => {
+                self.open_breakouts(&mut rooms, room_name, breakouts)
+                    .await?;
+            }
+            ModeratorAction::CloseBreakouts => {
+                self.close_breakouts(&mut rooms, room_name).await;
+            }
+            ModeratorAction::Deny { target, reason } => {
+                let knocking = room
+                    .resolve_knock(target, false)
+                    .ok_or(ModerationError::TargetNotFound)?;
+                info!(
+                    "User {} denied {} entry to room {}",
+                    actor_id, target, room_name
+                );
+                send_to_participant(
+                    &knocking,
+                    JoinError::LobbyDenied { reason }.to_server_message(room_name),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn room_config(&self, room_name: &str) -> RoomConfig {
+        self.room_configs.get(room_name).await
+    }
+
+    async fn create_room(&self, room_name: &str, config: RoomConfig) -> Result<(), String> {
+        if room_name.is_empty() {
+            return Err("Room name must not be empty".to_string());
+        }
+        let config = RoomConfig {
+            persistent: true,
+            ..config
+        };
+        self.room_configs.set(room_name, config).await;
+
+        let mut rooms = self.rooms.write().await;
+        rooms
+            .entry(room_name.to_string())
+            .or_insert_with(|| self.new_room(Room::new(room_name.to_string())))
+            .emptied_at = None;
+        info!("Created persistent room {}", room_name);
+        Ok(())
+    }
+
+    async fn delete_room(&self, room_name: &str) -> Result<(), String> {
+        if self.room_configs.remove(room_name).await.is_none() {
+            return Err(format!(
+                "Room {} is not defined",
+                local_room_name(tenant_of(room_name), room_name)
+            ));
+        }
+
+        let mut rooms = self.rooms.write().await;
+        self.lifecycle().release(&mut rooms, room_name).await;
+        info!("Deleted room definition {}", room_name);
+        Ok(())
+    }
+
+    async fn update_room_state(
+        &self,
+        room_name: &str,
+        user_id: u32,
+        update: RoomStateUpdate,
+    ) -> Result<u64, RoomStateError> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms.get_mut(room_name).ok_or(RoomStateError::NotInRoom)?;
+        room.update_state(user_id, update)
+    }
+
+    async fn room_state(&self, room_name: &str) -> RoomStateMap {
+        let rooms = self.rooms.read().await;
+        rooms
+            .get(room_name)
+            .map(|room| room.state.snapshot())
+            .unwrap_or_default()
+    }
+
+    async fn plan_mesh(&self, room_name: &str, user_id: u32) {
+        let mut rooms = self.rooms.write().await;
+        if let Some(room) = rooms.get_mut(room_name) {
+            room.connect_peers(user_id);
+        }
+    }
+
+    async fn record_signal(
+        &self,
+        room_name: &str,
+        from_user_id: u32,
+        to_user_id: u32,
+        kind: SignalKind,
+    ) {
+        let mut rooms = self.rooms.write().await;
+        if let Some(room) = rooms.get_mut(room_name) {
+            room.record_signal(from_user_id, to_user_id, kind);
+        }
+    }
+
+    async fn connect_user(&self, participant: RoomParticipant) {
+        self.users.add(participant).await;
+    }
+
+    async fn disconnect_user(&self, connection_id: Uuid) {
+        self.users.remove(connection_id).await;
+        self.directory.unsubscribe(connection_id);
+    }
+
+    async fn invite(
+        &self,
+        caller: &AuthenticatedUser,
+        callee_id: u32,
+        ring_timeout: Duration,
+    ) -> Result<CallInvitation, CallError> {
+        if callee_id == caller.user_id {
+            return Err(CallError::SelfCall);
+        }
+        let tenant = caller.tenant.as_deref();
+        if !self.users.is_online(tenant, callee_id).await {
+            return Err(CallError::UserOffline);
+        }
+
+        let invitation = CallInvitation::new(caller, callee_id);
+        self.calls
+            .write()
+            .await
+            .insert(invitation.call_id, invitation.clone());
+        self.expire_call(&invitation, ring_timeout);
+        info!(
+            "User {} is calling user {} (call {})",
+            caller.user_id, callee_id, invitation.call_id
+        );
+        self.users
+            .send(tenant, callee_id, &invitation.incoming())
+            .await;
+        Ok(invitation)
+    }
+
+    async fn respond_to_call(
+        &self,
+        user: &AuthenticatedUser,
+        call_id: Uuid,
+        response: CallResponse,
+    ) -> Result<(), CallError> {
+        let invitation = self
+            .calls
+            .read()
+            .await
+            .get(&call_id)
+            .cloned()
+            .ok_or(CallError::NotFound)?;
+        invitation.check_response(user, response)?;
+        if self.calls.write().await.remove(&call_id).is_none() {
+            // Answered or timed out in the meantime
+            return Err(CallError::NotFound);
+        }
+
+        info!(
+            "Call {} answered by user {}: {:?}",
+            call_id, user.user_id, response
+        );
+        if response == CallResponse::Accept {
+            self.open_call_room(&invitation).await;
+        }
+        let outcome = invitation.outcome(response);
+        for user_id in invitation.user_ids() {
+            self.users
+                .send(invitation.tenant.as_deref(), user_id, &outcome)
+                .await;
+        }
+        Ok(())
+    }
+
+    async fn list_rooms(&self, tenant: Option<&str>) -> Vec<RoomSummary> {
+        let rooms = self.rooms.read().await;
+        let mut summaries: Vec<RoomSummary> = rooms
+            .values()
+            .filter(|room| {
+                same_tenant(tenant, &room.name) && room.is_listed() && !room.participants.is_empty()
+            })
+            .map(Room::summary)
+            .collect();
+        summaries.sort_by(|a, b| a.room_name.cmp(&b.room_name));
+        summaries
+    }
+
+    async fn subscribe_room_directory(&self, participant: RoomParticipant) {
+        self.directory.subscribe(participant);
+    }
+
+    async fn unsubscribe_room_directory(&self, connection_id: Uuid) {
+        self.directory.unsubscribe(connection_id);
+    }
+
+    async fn room_ownership(&self, room_name: &str) -> RoomOwnership {
+        let rooms = self.rooms.read().await;
+        rooms
+            .get(room_name)
+            .map(|room| room.ownership())
+            .unwrap_or_default()
+    }
+
     async fn health_check(&self) -> bool {
         true // Local implementation is always healthy
     }
@@ -328,6 +2303,26 @@ impl RoomManagerTrait for LocalRoomManager {
     }
 }
 
+/// Close a connection replaced by a rejoin with [`CLOSE_CODE_REPLACED`]
+pub(crate) fn close_replaced(sender: &mpsc::Sender<Message>) {
+    let _ = sender.try_send(Message::Close(Some(CloseFrame {
+        code: CLOSE_CODE_REPLACED.into(),
+        reason: "Replaced by a new connection".into(),
+    })));
+}
+
+/// Send to a participant who is no longer in the room's participant list
+fn send_to_participant(participant: &RoomParticipant, message: ServerMessage) {
+    if let Ok(json) = serde_json::to_string(&message) {
+        if let Err(e) = participant.sender.try_send(Message::Text(json)) {
+            warn!(
+                "Failed to send message to user {}: {}",
+                participant.user.user_id, e
+            );
+        }
+    }
+}
+
 // Legacy RoomManager for backward compatibility
 pub struct RoomManager {
     pub inner: Box<dyn RoomManagerTrait>,
@@ -372,6 +2367,17 @@ impl RoomManager {
         self.inner.join_room(room_name, participant).await
     }
 
+    pub async fn join_room_with(
+        &self,
+        room_name: String,
+        participant: RoomParticipant,
+        request: JoinRequest,
+    ) -> Result<JoinOutcome, JoinError> {
+        self.inner
+            .join_room_with(room_name, participant, request)
+            .await
+    }
+
     pub async fn leave_room(&self, room_name: &str, user_id: u32) -> Result<(), String> {
         self.inner.leave_room(room_name, user_id).await
     }
@@ -392,7 +2398,7 @@ impl RoomManager {
         room_name: &str,
         target_user_id: u32,
         message: ServerMessage,
-    ) -> Result<(), String> {
+    ) -> Result<(), DeliveryError> {
         self.inner
             .send_to_user_in_room(room_name, target_user_id, message)
             .await
@@ -402,6 +2408,10 @@ impl RoomManager {
         self.inner.user_in_room(room_name, user_id).await
     }
 
+    pub async fn room_role(&self, room_name: &str, user_id: u32) -> RoomRole {
+        self.inner.room_role(room_name, user_id).await
+    }
+
     pub async fn remove_user_from_all_rooms(&self, user_id: u32, connection_id: Uuid) {
         self.inner
             .remove_user_from_all_rooms(user_id, connection_id)
@@ -412,6 +2422,100 @@ impl RoomManager {
         self.inner.get_room_participants(room_name).await
     }
 
+    pub async fn moderate(
+        &self,
+        room_name: &str,
+        actor_id: u32,
+        action: ModeratorAction,
+    ) -> Result<(), ModerationError> {
+        self.inner.moderate(room_name, actor_id, action).await
+    }
+
+    pub async fn room_config(&self, room_name: &str) -> RoomConfig {
+        self.inner.room_config(room_name).await
+    }
+
+    pub async fn create_room(&self, room_name: &str, config: RoomConfig) -> Result<(), String> {
+        self.inner.create_room(room_name, config).await
+    }
+
+    pub async fn delete_room(&self, room_name: &str) -> Result<(), String> {
+        self.inner.delete_room(room_name).await
+    }
+
+    pub async fn room_ownership(&self, room_name: &str) -> RoomOwnership {
+        self.inner.room_ownership(room_name).await
+    }
+
+    pub async fn update_room_state(
+        &self,
+        room_name: &str,
+        user_id: u32,
+        update: RoomStateUpdate,
+    ) -> Result<u64, RoomStateError> {
+        self.inner
+            .update_room_state(room_name, user_id, update)
+            .await
+    }
+
+    pub async fn room_state(&self, room_name: &str) -> RoomStateMap {
+        self.inner.room_state(room_name).await
+    }
+
+    pub async fn plan_mesh(&self, room_name: &str, user_id: u32) {
+        self.inner.plan_mesh(room_name, user_id).await
+    }
+
+    pub async fn record_signal(
+        &self,
+        room_name: &str,
+        from_user_id: u32,
+        to_user_id: u32,
+        kind: SignalKind,
+    ) {
+        self.inner
+            .record_signal(room_name, from_user_id, to_user_id, kind)
+            .await
+    }
+
+    pub async fn connect_user(&self, participant: RoomParticipant) {
+        self.inner.connect_user(participant).await
+    }
+
+    pub async fn disconnect_user(&self, connection_id: Uuid) {
+        self.inner.disconnect_user(connection_id).await
+    }
+
+    pub async fn invite(
+        &self,
+        caller: &AuthenticatedUser,
+        callee_id: u32,
+        ring_timeout: Duration,
+    ) -> Result<CallInvitation, CallError> {
+        self.inner.invite(caller, callee_id, ring_timeout).await
+    }
+
+    pub async fn respond_to_call(
+        &self,
+        user: &AuthenticatedUser,
+        call_id: Uuid,
+        response: CallResponse,
+    ) -> Result<(), CallError> {
+        self.inner.respond_to_call(user, call_id, response).await
+    }
+
+    pub async fn list_rooms(&self, tenant: Option<&str>) -> Vec<RoomSummary> {
+        self.inner.list_rooms(tenant).await
+    }
+
+    pub async fn subscribe_room_directory(&self, participant: RoomParticipant) {
+        self.inner.subscribe_room_directory(participant).await
+    }
+
+    pub async fn unsubscribe_room_directory(&self, connection_id: Uuid) {
+        self.inner.unsubscribe_room_directory(connection_id).await
+    }
+
     pub async fn health_check(&self) -> bool {
         self.inner.health_check().await
     }
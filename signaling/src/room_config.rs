@@ -0,0 +1,223 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use crate::candidate::CandidatePolicy;
+use crate::sdp::SdpPolicy;
+use crate::tenant::tenant_of;
+
+/// What happens to a join once a room has reached `max_participants`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowPolicy {
+    /// Refuse the join with a `room-full` error
+    #[default]
+    Reject,
+    /// Queue the user and admit them when a participant leaves
+    Waitlist,
+    /// Admit the user as a spectator who does not count towards capacity
+    Spectator,
+}
+
+/// Who becomes host when the host leaves the room
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostTransferPolicy {
+    /// Promote the participant who has been in the room longest
+    #[default]
+    LongestPresent,
+    /// Leave the role empty until the room's owner rejoins
+    Vacant,
+}
+
+/// Whether a room is shown in the room directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RoomVisibility {
+    #[default]
+    Public,
+    /// Joinable by name but left out of the directory
+    Unlisted,
+}
+
+/// What happens when a user joins a room they are already in from another connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RejoinPolicy {
+    /// Refuse the join while the old connection is in the room
+    #[default]
+    Reject,
+    /// Hand the user's place to the new connection and close the old one
+    Replace,
+}
+
+/// Who decides which peer of a pair sends the offer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MeshPolicy {
+    /// Clients work it out between themselves
+    #[default]
+    ClientDriven,
+    /// The server pairs up the peers, tells each pair who offers with `connect-to`
+    /// and instructs pairs again when their negotiation stalls
+    ServerPlanned,
+}
+
+/// How long a knock waits for an answer when `lobby_timeout_secs` is unset
+pub const DEFAULT_LOBBY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Settings that apply to a single room
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RoomConfig {
+    pub sdp_policy: SdpPolicy,
+    pub candidate_policy: CandidatePolicy,
+    /// Argon2 PHC hash of the room password; see [`crate::access::hash_password`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password_hash: Option<String>,
+    /// Participants allowed at once; unlimited when unset
+    pub max_participants: Option<usize>,
+    pub overflow: OverflowPolicy,
+    /// Users who moderate the room regardless of their token's grants
+    pub moderators: Vec<u32>,
+    pub host_transfer: HostTransferPolicy,
+    /// Hold joiners in a lobby until a moderator or the host admits them
+    pub lobby: bool,
+    /// Seconds a lobby entry waits before the join is refused
+    pub lobby_timeout_secs: Option<u64>,
+    /// Keep the room, with its lock, bans and owner, while nobody is in it
+    pub persistent: bool,
+    /// Seconds an empty room is kept before it is removed; removed at once when unset
+    pub empty_ttl_secs: Option<u64>,
+    pub visibility: RoomVisibility,
+    pub rejoin: RejoinPolicy,
+    /// Joins are refused before this time
+    pub opens_at: Option<DateTime<Utc>>,
+    /// Joins are refused from this time on, and a live room is closed
+    pub closes_at: Option<DateTime<Utc>>,
+    /// Seconds a session may last, counted from when the room becomes live
+    pub max_duration_secs: Option<u64>,
+    pub mesh: MeshPolicy,
+}
+
+impl RoomConfig {
+    pub fn lobby_timeout(&self) -> Duration {
+        self.lobby_timeout_secs
+            .map_or(DEFAULT_LOBBY_TIMEOUT, Duration::from_secs)
+    }
+
+    pub fn empty_ttl(&self) -> Option<Duration> {
+        self.empty_ttl_secs.map(Duration::from_secs)
+    }
+
+    pub fn max_duration(&self) -> Option<Duration> {
+        self.max_duration_secs.map(Duration::from_secs)
+    }
+}
+
+/// On-disk format of the room configuration file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RoomConfigFile {
+    default: RoomConfig,
+    rooms: HashMap<String, RoomConfig>,
+    tenants: HashMap<String, RoomConfig>,
+}
+
+/// Per-room configuration with a fallback for rooms that are not listed, first to
+/// their tenant's default and then to the global one
+#[derive(Debug, Default)]
+pub struct RoomConfigStore {
+    default: RoomConfig,
+    rooms: RwLock<HashMap<String, RoomConfig>>,
+    tenants: HashMap<String, RoomConfig>,
+}
+
+impl RoomConfigStore {
+    pub fn new(default: RoomConfig) -> Self {
+        Self {
+            default,
+            rooms: RwLock::new(HashMap::new()),
+            tenants: HashMap::new(),
+        }
+    }
+
+    /// Apply `config` to the rooms of `tenant` that are not listed individually
+    pub fn with_tenant_default(mut self, tenant: impl Into<String>, config: RoomConfig) -> Self {
+        self.tenants.insert(tenant.into(), config);
+        self
+    }
+
+    /// Parse a JSON document of the form
+    /// `{"default": {...}, "tenants": {"id": {...}}, "rooms": {"name": {...}}}`,
+    /// where tenant rooms are listed as `tenant::room`
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let file: RoomConfigFile =
+            serde_json::from_str(json).map_err(|e| format!("Invalid room configuration: {}", e))?;
+
+        Ok(Self {
+            default: file.default,
+            rooms: RwLock::new(file.rooms),
+            tenants: file.tenants,
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+
+    /// Load the file named by `ROOM_CONFIG_PATH`, or use defaults when it is unset
+    pub fn from_env() -> Result<Self, String> {
+        match env::var("ROOM_CONFIG_PATH") {
+            Ok(path) if !path.is_empty() => Self::from_file(path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Configuration for `room_name`, falling back to its tenant's default and then
+    /// to the global default
+    pub async fn get(&self, room_name: &str) -> RoomConfig {
+        if let Some(config) = self.rooms.read().await.get(room_name) {
+            return config.clone();
+        }
+        tenant_of(room_name)
+            .and_then(|tenant| self.tenants.get(tenant))
+            .unwrap_or(&self.default)
+            .clone()
+    }
+
+    /// Replace the configuration of a single room
+    pub async fn set(&self, room_name: impl Into<String>, config: RoomConfig) {
+        self.rooms.write().await.insert(room_name.into(), config);
+    }
+
+    /// Drop the configuration of a single room so it falls back to the default
+    pub async fn remove(&self, room_name: &str) -> Option<RoomConfig> {
+        self.rooms.write().await.remove(room_name)
+    }
+
+    /// Rooms configured to exist whether or not anybody is in them
+    pub async fn persistent_rooms(&self) -> Vec<(String, RoomConfig)> {
+        let mut rooms: Vec<_> = self
+            .rooms
+            .read()
+            .await
+            .iter()
+            .filter(|(_, config)| config.persistent)
+            .map(|(name, config)| (name.clone(), config.clone()))
+            .collect();
+        rooms.sort_by(|a, b| a.0.cmp(&b.0));
+        rooms
+    }
+
+    pub fn default_config(&self) -> &RoomConfig {
+        &self.default
+    }
+}
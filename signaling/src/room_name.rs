@@ -0,0 +1,203 @@
+use icu_normalizer::ComposingNormalizerBorrowed;
+use std::env;
+use std::fmt;
+
+pub const DEFAULT_MAX_ROOM_NAME_LENGTH: usize = 128;
+
+/// Characters a room name may contain besides the punctuation `-`, `_`, `.` and
+/// `/` (which separates a breakout from its parent room, and is refused in the names
+/// clients join; see [`RoomNamePolicy::validate_join`])
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoomNameCharset {
+    /// ASCII letters and digits
+    #[default]
+    Ascii,
+    /// Letters and digits of any script
+    Unicode,
+}
+
+impl RoomNameCharset {
+    fn allows(self, c: char) -> bool {
+        let alphanumeric = match self {
+            RoomNameCharset::Ascii => c.is_ascii_alphanumeric(),
+            RoomNameCharset::Unicode => c.is_alphanumeric(),
+        };
+        alphanumeric || matches!(c, '-' | '_' | '.' | '/')
+    }
+}
+
+/// Rules every room name from a client must pass before it is used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomNamePolicy {
+    /// Longest name accepted, in bytes after normalization
+    pub max_length: usize,
+    pub charset: RoomNameCharset,
+    /// Names starting with any of these are kept for the server's own use
+    pub reserved_prefixes: Vec<String>,
+    /// Compose names to Unicode NFC so equivalent spellings name the same room
+    pub normalize_nfc: bool,
+    /// Lowercase names so rooms are matched case-insensitively
+    pub case_fold: bool,
+}
+
+impl Default for RoomNamePolicy {
+    fn default() -> Self {
+        Self {
+            max_length: DEFAULT_MAX_ROOM_NAME_LENGTH,
+            charset: RoomNameCharset::default(),
+            reserved_prefixes: Vec::new(),
+            normalize_nfc: false,
+            case_fold: false,
+        }
+    }
+}
+
+impl RoomNamePolicy {
+    /// Load the policy from `ROOM_NAME_MAX_LENGTH`, `ROOM_NAME_CHARSET` (`ascii` or
+    /// `unicode`), the comma-separated `ROOM_NAME_RESERVED_PREFIXES`, `ROOM_NAME_NFC`
+    /// and `ROOM_NAME_CASE_FOLD`
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+            env::var(name).ok().and_then(|value| value.parse().ok())
+        }
+
+        let defaults = Self::default();
+        let charset = match env::var("ROOM_NAME_CHARSET").as_deref() {
+            Ok("unicode") => RoomNameCharset::Unicode,
+            Ok("ascii") => RoomNameCharset::Ascii,
+            _ => defaults.charset,
+        };
+        let reserved_prefixes = env::var("ROOM_NAME_RESERVED_PREFIXES")
+            .map(|prefixes| {
+                prefixes
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|prefix| !prefix.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or(defaults.reserved_prefixes);
+
+        Self {
+            max_length: parse("ROOM_NAME_MAX_LENGTH")
+                .filter(|length| *length > 0)
+                .unwrap_or(defaults.max_length),
+            charset,
+            reserved_prefixes,
+            normalize_nfc: parse("ROOM_NAME_NFC").unwrap_or(defaults.normalize_nfc),
+            case_fold: parse("ROOM_NAME_CASE_FOLD").unwrap_or(defaults.case_fold),
+        }
+    }
+
+    /// Normalize `name` as configured and check it, returning the name to use
+    pub fn apply(&self, name: &str) -> Result<String, RoomNameError> {
+        // Refuse oversized input before spending time normalizing it
+        if name.len() > self.max_length.saturating_mul(4) {
+            return Err(RoomNameError::TooLong {
+                max: self.max_length,
+            });
+        }
+
+        let mut name = if self.normalize_nfc {
+            ComposingNormalizerBorrowed::new_nfc()
+                .normalize(name)
+                .into_owned()
+        } else {
+            name.to_string()
+        };
+        if self.case_fold {
+            name = name.to_lowercase();
+        }
+
+        self.validate(&name)?;
+        Ok(name)
+    }
+
+    /// Check an already normalized name
+    pub fn validate(&self, name: &str) -> Result<(), RoomNameError> {
+        if name.is_empty() {
+            return Err(RoomNameError::Empty);
+        }
+        if name.len() > self.max_length {
+            return Err(RoomNameError::TooLong {
+                max: self.max_length,
+            });
+        }
+        if let Some(c) = name.chars().find(|c| !self.charset.allows(*c)) {
+            return Err(RoomNameError::InvalidCharacter(c));
+        }
+        if name.starts_with('/') || name.ends_with('/') || name.contains("//") {
+            return Err(RoomNameError::EmptySegment);
+        }
+        if let Some(prefix) = self
+            .reserved_prefixes
+            .iter()
+            .find(|prefix| name.starts_with(prefix.as_str()))
+        {
+            return Err(RoomNameError::ReservedPrefix(prefix.clone()));
+        }
+        Ok(())
+    }
+
+    /// Check the name of a room a client asks to join. Breakout rooms take their
+    /// members from the moderator's assignments, so they cannot be joined by name.
+    pub fn validate_join(&self, name: &str) -> Result<(), RoomNameError> {
+        if name.contains('/') {
+            return Err(RoomNameError::BreakoutRoom);
+        }
+        Ok(())
+    }
+}
+
+/// Reasons a room name is refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoomNameError {
+    Empty,
+    TooLong {
+        max: usize,
+    },
+    InvalidCharacter(char),
+    /// A `/` at either end or doubled, leaving part of the name empty
+    EmptySegment,
+    ReservedPrefix(String),
+    /// The name of a breakout room, which only its assigned members enter
+    BreakoutRoom,
+}
+
+impl RoomNameError {
+    /// Error code sent to the client alongside the message
+    pub fn code(&self) -> u32 {
+        match self {
+            RoomNameError::BreakoutRoom => 403,
+            _ => 400,
+        }
+    }
+}
+
+impl fmt::Display for RoomNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomNameError::Empty => write!(f, "Room name is empty"),
+            RoomNameError::TooLong { max } => {
+                write!(f, "Room name is longer than {} bytes", max)
+            }
+            RoomNameError::InvalidCharacter(c) => {
+                write!(f, "Room name contains the invalid character {:?}", c)
+            }
+            RoomNameError::EmptySegment => {
+                write!(f, "Room name has an empty part between slashes")
+            }
+            RoomNameError::ReservedPrefix(prefix) => {
+                write!(f, "Room names starting with {:?} are reserved", prefix)
+            }
+            RoomNameError::BreakoutRoom => {
+                write!(
+                    f,
+                    "Breakout rooms are entered through a moderator's assignment"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RoomNameError {}
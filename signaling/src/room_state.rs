@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Longest key accepted in a room's shared state
+pub const MAX_KEY_LENGTH: usize = 128;
+/// Largest value accepted, measured as serialized JSON
+pub const MAX_VALUE_BYTES: usize = 16 * 1024;
+/// Keys a single room may hold
+pub const MAX_KEYS: usize = 256;
+
+/// A value in a room's shared state with the version that last wrote it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomStateEntry {
+    pub value: Value,
+    pub version: u64,
+}
+
+/// Full shared state of a room, as delivered to joiners
+pub type RoomStateMap = BTreeMap<String, RoomStateEntry>;
+
+/// Change to one key of a room's shared state
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomStateUpdate {
+    pub key: String,
+    /// New value, or `None` to delete the key
+    pub value: Option<Value>,
+    /// Version the client last saw for the key, 0 for a key it expects to be
+    /// absent; the update applies unconditionally when unset
+    pub expected_version: Option<u64>,
+}
+
+impl RoomStateUpdate {
+    /// Check the key and value against the size limits
+    pub fn validate(&self) -> Result<(), RoomStateError> {
+        if self.key.is_empty() || self.key.len() > MAX_KEY_LENGTH {
+            return Err(RoomStateError::Invalid(format!(
+                "Keys must be 1 to {} bytes long",
+                MAX_KEY_LENGTH
+            )));
+        }
+        if let Some(value) = &self.value {
+            if value.to_string().len() > MAX_VALUE_BYTES {
+                return Err(RoomStateError::Invalid(format!(
+                    "Values must be at most {} bytes of JSON",
+                    MAX_VALUE_BYTES
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a shared state update was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoomStateError {
+    NotInRoom,
+    /// The key changed since the version the client expected
+    VersionConflict {
+        current: u64,
+    },
+    /// Deleting a key that is not set
+    KeyNotFound,
+    /// The room already holds `MAX_KEYS` keys
+    TooManyKeys,
+    Invalid(String),
+    Failed(String),
+}
+
+impl RoomStateError {
+    /// HTTP-style status code sent to the client
+    pub fn code(&self) -> u32 {
+        match self {
+            RoomStateError::NotInRoom => 403,
+            RoomStateError::VersionConflict { .. } => 409,
+            RoomStateError::KeyNotFound => 404,
+            RoomStateError::TooManyKeys | RoomStateError::Invalid(_) => 400,
+            RoomStateError::Failed(_) => 500,
+        }
+    }
+}
+
+impl fmt::Display for RoomStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomStateError::NotInRoom => write!(f, "You are not in this room"),
+            RoomStateError::VersionConflict { current } => {
+                write!(f, "The key has changed, it is now at version {}", current)
+            }
+            RoomStateError::KeyNotFound => write!(f, "The key is not set"),
+            RoomStateError::TooManyKeys => {
+                write!(f, "Rooms hold at most {} state keys", MAX_KEYS)
+            }
+            RoomStateError::Invalid(message) | RoomStateError::Failed(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RoomStateError {}
+
+/// Shared key-value state of a room. Versions come from one counter per room, so
+/// a key that is deleted and set again never reuses a version.
+#[derive(Debug, Default)]
+pub struct RoomState {
+    entries: RoomStateMap,
+    version: u64,
+}
+
+impl RoomState {
+    pub fn snapshot(&self) -> RoomStateMap {
+        self.entries.clone()
+    }
+
+    /// Version of `key`, 0 when it is not set
+    pub fn version_of(&self, key: &str) -> u64 {
+        self.entries.get(key).map_or(0, |entry| entry.version)
+    }
+
+    /// Apply an update, returning the version it was stored at
+    pub fn apply(&mut self, update: RoomStateUpdate) -> Result<u64, RoomStateError> {
+        update.validate()?;
+        let current = self.version_of(&update.key);
+        if let Some(expected) = update.expected_version {
+            if expected != current {
+                return Err(RoomStateError::VersionConflict { current });
+            }
+        }
+
+        match update.value {
+            Some(value) => {
+                if current == 0 && self.entries.len() >= MAX_KEYS {
+                    return Err(RoomStateError::TooManyKeys);
+                }
+                self.version += 1;
+                self.entries.insert(
+                    update.key,
+                    RoomStateEntry {
+                        value,
+                        version: self.version,
+                    },
+                );
+            }
+            None => {
+                if self.entries.remove(&update.key).is_none() {
+                    return Err(RoomStateError::KeyNotFound);
+                }
+                self.version += 1;
+            }
+        }
+        Ok(self.version)
+    }
+}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::messages::RoomCloseReason;
+use crate::room::JoinError;
+use crate::room_config::RoomConfig;
+
+/// How long before a room closes its participants are warned
+pub const CLOSING_WARNINGS: [Duration; 2] = [Duration::from_secs(300), Duration::from_secs(60)];
+
+/// When a live room closes and why
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomDeadline {
+    pub at: DateTime<Utc>,
+    pub reason: RoomCloseReason,
+}
+
+impl RoomDeadline {
+    /// Deadline of a session starting at `now`: the room's `closes_at` or the end of
+    /// the shorter of its maximum duration and `max_duration`, whichever comes first
+    pub fn for_session(
+        config: &RoomConfig,
+        max_duration: Option<Duration>,
+        now: DateTime<Utc>,
+    ) -> Option<Self> {
+        let max_duration = match (config.max_duration(), max_duration) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let session_end = max_duration
+            .and_then(|duration| chrono::Duration::from_std(duration).ok())
+            .map(|duration| Self {
+                at: now + duration,
+                reason: RoomCloseReason::MaxDuration,
+            });
+        let scheduled_end = config.closes_at.map(|at| Self {
+            at,
+            reason: RoomCloseReason::ScheduledEnd,
+        });
+        match (scheduled_end, session_end) {
+            (Some(a), Some(b)) => Some(if b.at < a.at { b } else { a }),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Time left at `now`, zero once the deadline has passed
+    pub fn remaining(&self, now: DateTime<Utc>) -> Duration {
+        (self.at - now).to_std().unwrap_or_default()
+    }
+
+    /// The deadline on the runtime's clock, for timers
+    pub fn instant(&self) -> tokio::time::Instant {
+        tokio::time::Instant::now() + self.remaining(Utc::now())
+    }
+}
+
+/// Refuse joins outside the room's `opens_at`/`closes_at` window
+pub fn check_window(config: &RoomConfig, now: DateTime<Utc>) -> Result<(), JoinError> {
+    if let Some(opens_at) = config.opens_at.filter(|opens_at| now < *opens_at) {
+        return Err(JoinError::RoomNotOpen {
+            opens_in: (opens_at - now).to_std().unwrap_or_default(),
+        });
+    }
+    if config.closes_at.is_some_and(|closes_at| now >= closes_at) {
+        return Err(JoinError::RoomClosed);
+    }
+    Ok(())
+}
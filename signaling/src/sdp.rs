@@ -0,0 +1,677 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+
+use crate::candidate::{CandidateFilterStats, CandidatePolicy};
+
+/// Default maximum SDP payload size in bytes
+pub const DEFAULT_MAX_SDP_SIZE: usize = 64 * 1024;
+/// Default maximum number of `m=` sections per SDP
+pub const DEFAULT_MAX_MEDIA_SECTIONS: usize = 32;
+/// Default maximum length of a single SDP line in bytes
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 4096;
+
+/// Limits applied to every SDP before it is relayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SdpLimits {
+    pub max_size: usize,
+    pub max_media_sections: usize,
+    pub max_line_length: usize,
+}
+
+impl Default for SdpLimits {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_SDP_SIZE,
+            max_media_sections: DEFAULT_MAX_MEDIA_SECTIONS,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+        }
+    }
+}
+
+impl SdpLimits {
+    /// Load limits from `SDP_MAX_SIZE`, `SDP_MAX_MEDIA_SECTIONS` and `SDP_MAX_LINE_LENGTH`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_size: env_usize("SDP_MAX_SIZE").unwrap_or(defaults.max_size),
+            max_media_sections: env_usize("SDP_MAX_MEDIA_SECTIONS")
+                .unwrap_or(defaults.max_media_sections),
+            max_line_length: env_usize("SDP_MAX_LINE_LENGTH").unwrap_or(defaults.max_line_length),
+        }
+    }
+}
+
+fn env_usize(name: &str) -> Option<usize> {
+    env::var(name).ok().and_then(|value| value.parse().ok())
+}
+
+/// Reasons an SDP payload is rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdpError {
+    Empty,
+    TooLarge {
+        size: usize,
+        max: usize,
+    },
+    LineTooLong {
+        line: usize,
+        max: usize,
+    },
+    TooManyMediaSections {
+        count: usize,
+        max: usize,
+    },
+    /// A line that is not of the form `<type>=<value>`
+    MalformedLine {
+        line: usize,
+    },
+    /// The first line is not `v=0`
+    InvalidVersion,
+    /// A mandatory session-level line (`o=`, `s=`, `t=`) is absent
+    MissingField(char),
+    InvalidMediaLine {
+        line: usize,
+    },
+    InvalidAttribute {
+        line: usize,
+        attribute: String,
+    },
+    /// The room's codec policy leaves a media section without any codec
+    NoAllowedCodecs {
+        media: String,
+    },
+}
+
+impl fmt::Display for SdpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdpError::Empty => write!(f, "SDP is empty"),
+            SdpError::TooLarge { size, max } => {
+                write!(f, "SDP is {} bytes, maximum is {}", size, max)
+            }
+            SdpError::LineTooLong { line, max } => {
+                write!(f, "line {} exceeds {} bytes", line, max)
+            }
+            SdpError::TooManyMediaSections { count, max } => {
+                write!(f, "SDP has {} media sections, maximum is {}", count, max)
+            }
+            SdpError::MalformedLine { line } => write!(f, "line {} is malformed", line),
+            SdpError::InvalidVersion => write!(f, "SDP must start with v=0"),
+            SdpError::MissingField(field) => write!(f, "missing mandatory {}= line", field),
+            SdpError::InvalidMediaLine { line } => {
+                write!(f, "line {} is not a valid m= line", line)
+            }
+            SdpError::InvalidAttribute { line, attribute } => {
+                write!(f, "line {} has an invalid {} attribute", line, attribute)
+            }
+            SdpError::NoAllowedCodecs { media } => {
+                write!(f, "no allowed codecs in {} section", media)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SdpError {}
+
+/// Server-side rewrite rules applied to offers and answers relayed in a room
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SdpPolicy {
+    /// Bandwidth cap for audio sections, written as `b=AS` (kbps) and `b=TIAS` (bps)
+    pub max_audio_bitrate_kbps: Option<u32>,
+    /// Bandwidth cap for video sections, written as `b=AS` (kbps) and `b=TIAS` (bps)
+    pub max_video_bitrate_kbps: Option<u32>,
+    /// Codec names moved to the front of each `m=` line, in this order
+    pub preferred_codecs: Vec<String>,
+    /// When non-empty, codecs not in this list are stripped
+    pub allowed_codecs: Vec<String>,
+}
+
+impl SdpPolicy {
+    /// Whether the policy leaves SDP untouched
+    pub fn is_empty(&self) -> bool {
+        self.max_audio_bitrate_kbps.is_none()
+            && self.max_video_bitrate_kbps.is_none()
+            && self.preferred_codecs.is_empty()
+            && self.allowed_codecs.is_empty()
+    }
+
+    fn max_bitrate_kbps(&self, kind: &str) -> Option<u32> {
+        match kind {
+            "audio" => self.max_audio_bitrate_kbps,
+            "video" => self.max_video_bitrate_kbps,
+            _ => None,
+        }
+    }
+
+    fn allows(&self, codec: &str) -> bool {
+        self.allowed_codecs.is_empty()
+            || self
+                .allowed_codecs
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(codec))
+    }
+
+    fn preference(&self, codec: &str) -> usize {
+        self.preferred_codecs
+            .iter()
+            .position(|preferred| preferred.eq_ignore_ascii_case(codec))
+            .unwrap_or(self.preferred_codecs.len())
+    }
+}
+
+/// Payload formats that protect or repair other codecs rather than carry media themselves
+const AUXILIARY_CODECS: [&str; 4] = ["rtx", "red", "ulpfec", "flexfec-03"];
+
+/// Media direction attribute (RFC 4566 section 6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    SendRecv,
+    SendOnly,
+    RecvOnly,
+    Inactive,
+}
+
+impl Direction {
+    fn from_attribute(attribute: &str) -> Option<Self> {
+        match attribute {
+            "sendrecv" => Some(Direction::SendRecv),
+            "sendonly" => Some(Direction::SendOnly),
+            "recvonly" => Some(Direction::RecvOnly),
+            "inactive" => Some(Direction::Inactive),
+            _ => None,
+        }
+    }
+}
+
+/// Codec negotiated for a payload type via `a=rtpmap` (or a static payload type)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Codec {
+    pub payload_type: u8,
+    pub name: String,
+    pub clock_rate: u32,
+    pub channels: Option<u16>,
+    pub fmtp: Option<String>,
+}
+
+/// A single `m=` section and the lines that belong to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaDescription {
+    pub kind: String,
+    pub port: u16,
+    pub protocol: String,
+    pub formats: Vec<String>,
+    pub direction: Direction,
+    pub mid: Option<String>,
+    pub codecs: Vec<Codec>,
+    lines: Vec<String>,
+}
+
+impl MediaDescription {
+    /// Raw SDP lines of this section, starting with the `m=` line
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    fn apply_policy(&mut self, policy: &SdpPolicy) -> Result<(), SdpError> {
+        // Port 0 marks a rejected or stopped section; leave it alone
+        if self.port == 0 {
+            return Ok(());
+        }
+
+        if self.protocol.contains("RTP") {
+            self.filter_codecs(policy)?;
+            self.reorder_codecs(policy);
+        }
+        if let Some(kbps) = policy.max_bitrate_kbps(&self.kind) {
+            self.cap_bandwidth(kbps);
+        }
+        Ok(())
+    }
+
+    fn filter_codecs(&mut self, policy: &SdpPolicy) -> Result<(), SdpError> {
+        if policy.allowed_codecs.is_empty() {
+            return Ok(());
+        }
+
+        let is_auxiliary =
+            |codec: &Codec| AUXILIARY_CODECS.contains(&codec.name.to_ascii_lowercase().as_str());
+        let kept: Vec<u8> = self
+            .codecs
+            .iter()
+            .filter(|codec| !is_auxiliary(codec) && policy.allows(&codec.name))
+            .map(|codec| codec.payload_type)
+            .collect();
+        if kept.is_empty() {
+            return Err(SdpError::NoAllowedCodecs {
+                media: self.kind.clone(),
+            });
+        }
+
+        let removed: Vec<u8> = self
+            .codecs
+            .iter()
+            .filter(|codec| {
+                if kept.contains(&codec.payload_type) {
+                    return false;
+                }
+                if codec.name.eq_ignore_ascii_case("rtx") {
+                    // Retransmission streams survive only alongside the codec they repair
+                    return !rtx_target(codec).is_some_and(|apt| kept.contains(&apt));
+                }
+                !is_auxiliary(codec)
+            })
+            .map(|codec| codec.payload_type)
+            .collect();
+
+        self.formats
+            .retain(|format| !format.parse::<u8>().is_ok_and(|pt| removed.contains(&pt)));
+        self.codecs
+            .retain(|codec| !removed.contains(&codec.payload_type));
+        self.lines
+            .retain(|line| payload_attribute(line).is_none_or(|pt| !removed.contains(&pt)));
+        self.rewrite_media_line();
+        Ok(())
+    }
+
+    fn reorder_codecs(&mut self, policy: &SdpPolicy) {
+        if policy.preferred_codecs.is_empty() {
+            return;
+        }
+
+        let codecs = &self.codecs;
+        let rank = |format: &String| {
+            format
+                .parse::<u8>()
+                .ok()
+                .and_then(|pt| codecs.iter().find(|codec| codec.payload_type == pt))
+                .map_or(policy.preferred_codecs.len(), |codec| {
+                    policy.preference(&codec.name)
+                })
+        };
+        // Stable sort keeps the offerer's order among codecs with equal preference
+        let mut formats = self.formats.clone();
+        formats.sort_by_key(rank);
+        self.formats = formats;
+        self.codecs
+            .sort_by_key(|codec| policy.preference(&codec.name));
+        self.rewrite_media_line();
+    }
+
+    fn cap_bandwidth(&mut self, max_kbps: u32) {
+        let existing_kbps = self
+            .lines
+            .iter()
+            .filter_map(|line| line.strip_prefix("b=AS:"))
+            .filter_map(|kbps| kbps.parse::<u32>().ok())
+            .min();
+        let kbps = existing_kbps.map_or(max_kbps, |existing| existing.min(max_kbps));
+
+        self.lines
+            .retain(|line| !line.starts_with("b=AS:") && !line.starts_with("b=TIAS:"));
+
+        // b= lines follow the optional i= and c= lines of a media section (RFC 4566)
+        let position = 1 + self.lines[1..]
+            .iter()
+            .take_while(|line| line.starts_with("i=") || line.starts_with("c="))
+            .count();
+        self.lines.insert(position, format!("b=AS:{}", kbps));
+        self.lines
+            .insert(position + 1, format!("b=TIAS:{}", u64::from(kbps) * 1000));
+    }
+
+    fn rewrite_media_line(&mut self) {
+        let mut parts = self.lines[0].splitn(4, ' ');
+        let kind = parts.next().unwrap_or_default();
+        let port = parts.next().unwrap_or_default();
+        let protocol = parts.next().unwrap_or_default();
+        self.lines[0] = format!("{} {} {} {}", kind, port, protocol, self.formats.join(" "));
+    }
+}
+
+/// Parsed and validated session description
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionDescription {
+    pub session_name: String,
+    /// Session-level direction, inherited by media sections without their own
+    pub direction: Option<Direction>,
+    pub media: Vec<MediaDescription>,
+    session_lines: Vec<String>,
+}
+
+impl SessionDescription {
+    /// Parse an SDP blob, enforcing `limits`
+    pub fn parse(sdp: &str, limits: &SdpLimits) -> Result<Self, SdpError> {
+        if sdp.len() > limits.max_size {
+            return Err(SdpError::TooLarge {
+                size: sdp.len(),
+                max: limits.max_size,
+            });
+        }
+
+        let raw_lines: Vec<&str> = sdp
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .filter(|line| !line.is_empty())
+            .collect();
+        if raw_lines.is_empty() {
+            return Err(SdpError::Empty);
+        }
+
+        let media_count = raw_lines.iter().filter(|l| l.starts_with("m=")).count();
+        if media_count > limits.max_media_sections {
+            return Err(SdpError::TooManyMediaSections {
+                count: media_count,
+                max: limits.max_media_sections,
+            });
+        }
+
+        if raw_lines[0] != "v=0" {
+            return Err(SdpError::InvalidVersion);
+        }
+
+        let mut session_lines = Vec::new();
+        let mut session_name = None;
+        let mut has_origin = false;
+        let mut has_timing = false;
+        let mut direction = None;
+        let mut media: Vec<MediaDescription> = Vec::new();
+
+        for (index, line) in raw_lines.iter().enumerate() {
+            let line_number = index + 1;
+            if line.len() > limits.max_line_length {
+                return Err(SdpError::LineTooLong {
+                    line: line_number,
+                    max: limits.max_line_length,
+                });
+            }
+            let (kind, value) =
+                split_line(line).ok_or(SdpError::MalformedLine { line: line_number })?;
+
+            if kind == 'm' {
+                media.push(parse_media_line(value, line_number)?);
+            }
+
+            match media.last_mut() {
+                Some(section) => {
+                    if kind == 'a' {
+                        parse_media_attribute(section, value, line_number)?;
+                    }
+                    section.lines.push(line.to_string());
+                }
+                None => {
+                    match kind {
+                        'o' => has_origin = true,
+                        's' => session_name = Some(value.to_string()),
+                        't' => has_timing = true,
+                        'a' => {
+                            if let Some(d) = Direction::from_attribute(value) {
+                                direction = Some(d);
+                            }
+                        }
+                        _ => {}
+                    }
+                    session_lines.push(line.to_string());
+                }
+            }
+        }
+
+        if !has_origin {
+            return Err(SdpError::MissingField('o'));
+        }
+        let session_name = session_name.ok_or(SdpError::MissingField('s'))?;
+        if !has_timing {
+            return Err(SdpError::MissingField('t'));
+        }
+
+        for section in &mut media {
+            if !section.lines.iter().any(|l| is_direction_line(l)) {
+                section.direction = direction.unwrap_or(Direction::SendRecv);
+            }
+            section.codecs = collect_codecs(section);
+        }
+
+        Ok(Self {
+            session_name,
+            direction,
+            media,
+            session_lines,
+        })
+    }
+
+    /// Raw session-level lines (everything before the first `m=`)
+    pub fn session_lines(&self) -> &[String] {
+        &self.session_lines
+    }
+
+    /// Rewrite or drop the `a=candidate` lines of every media section.
+    ///
+    /// `filter` receives the attribute value (`candidate:...`) and returns the value
+    /// to keep, or `None` to remove the line.
+    pub fn retain_candidates<F>(&mut self, mut filter: F)
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        for section in &mut self.media {
+            section.lines = std::mem::take(&mut section.lines)
+                .into_iter()
+                .filter_map(|line| match line.strip_prefix("a=") {
+                    Some(value) if value.starts_with("candidate:") => {
+                        filter(value).map(|value| format!("a={}", value))
+                    }
+                    _ => Some(line),
+                })
+                .collect();
+        }
+    }
+
+    /// Rewrite bandwidth and codec lines of every media section according to `policy`
+    pub fn apply_policy(&mut self, policy: &SdpPolicy) -> Result<(), SdpError> {
+        for section in &mut self.media {
+            section.apply_policy(policy)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SessionDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.session_lines {
+            write!(f, "{}\r\n", line)?;
+        }
+        for section in &self.media {
+            for line in &section.lines {
+                write!(f, "{}\r\n", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Validate an offer or answer and rewrite it with a room's SDP and candidate
+/// policies, recording dropped candidates in `stats`. When both policies are empty
+/// the SDP is relayed byte for byte.
+pub fn prepare_for_relay(
+    sdp: &str,
+    limits: &SdpLimits,
+    policy: &SdpPolicy,
+    candidates: &CandidatePolicy,
+    stats: &CandidateFilterStats,
+) -> Result<String, SdpError> {
+    let mut description = SessionDescription::parse(sdp, limits)?;
+    if policy.is_empty() && candidates.is_empty() {
+        return Ok(sdp.to_string());
+    }
+
+    description.apply_policy(policy)?;
+    candidates.filter_description(&mut description, stats);
+    Ok(description.to_string())
+}
+
+/// Payload type of an `a=rtpmap`, `a=fmtp` or `a=rtcp-fb` line
+fn payload_attribute(line: &str) -> Option<u8> {
+    let value = line
+        .strip_prefix("a=rtpmap:")
+        .or_else(|| line.strip_prefix("a=fmtp:"))
+        .or_else(|| line.strip_prefix("a=rtcp-fb:"))?;
+    value.split(' ').next()?.parse().ok()
+}
+
+/// Payload type repaired by an RTX codec (`apt=` in its fmtp)
+fn rtx_target(codec: &Codec) -> Option<u8> {
+    codec
+        .fmtp
+        .as_deref()?
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("apt="))?
+        .parse()
+        .ok()
+}
+
+fn split_line(line: &str) -> Option<(char, &str)> {
+    let mut chars = line.chars();
+    let kind = chars.next()?;
+    if !kind.is_ascii_lowercase() || chars.next()? != '=' {
+        return None;
+    }
+    if line.chars().any(|c| c.is_control() && c != '\t') {
+        return None;
+    }
+    Some((kind, &line[2..]))
+}
+
+fn is_direction_line(line: &str) -> bool {
+    line.strip_prefix("a=")
+        .and_then(Direction::from_attribute)
+        .is_some()
+}
+
+fn parse_media_line(value: &str, line_number: usize) -> Result<MediaDescription, SdpError> {
+    let invalid = || SdpError::InvalidMediaLine { line: line_number };
+    let mut parts = value.split(' ');
+
+    let kind = parts.next().filter(|k| !k.is_empty()).ok_or_else(invalid)?;
+    let port = parts
+        .next()
+        .and_then(|p| p.split('/').next())
+        .and_then(|p| p.parse::<u16>().ok())
+        .ok_or_else(invalid)?;
+    let protocol = parts.next().filter(|p| !p.is_empty()).ok_or_else(invalid)?;
+    let formats: Vec<String> = parts
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+        .collect();
+    if formats.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(MediaDescription {
+        kind: kind.to_string(),
+        port,
+        protocol: protocol.to_string(),
+        formats,
+        direction: Direction::SendRecv,
+        mid: None,
+        codecs: Vec::new(),
+        lines: Vec::new(),
+    })
+}
+
+fn parse_media_attribute(
+    section: &mut MediaDescription,
+    value: &str,
+    line_number: usize,
+) -> Result<(), SdpError> {
+    let invalid = |attribute: &str| SdpError::InvalidAttribute {
+        line: line_number,
+        attribute: attribute.to_string(),
+    };
+
+    if let Some(direction) = Direction::from_attribute(value) {
+        section.direction = direction;
+    } else if let Some(mid) = value.strip_prefix("mid:") {
+        section.mid = Some(mid.to_string());
+    } else if let Some(rtpmap) = value.strip_prefix("rtpmap:") {
+        parse_rtpmap(rtpmap).ok_or_else(|| invalid("rtpmap"))?;
+    } else if let Some(fmtp) = value.strip_prefix("fmtp:") {
+        let (payload_type, _) = fmtp.split_once(' ').ok_or_else(|| invalid("fmtp"))?;
+        payload_type.parse::<u8>().map_err(|_| invalid("fmtp"))?;
+    }
+
+    Ok(())
+}
+
+/// Parse the value of `a=rtpmap:<pt> <name>/<clock>[/<channels>]`
+fn parse_rtpmap(value: &str) -> Option<Codec> {
+    let (payload_type, encoding) = value.split_once(' ')?;
+    let mut encoding = encoding.split('/');
+    let name = encoding.next().filter(|n| !n.is_empty())?;
+    let clock_rate = encoding.next()?.parse().ok()?;
+    let channels = match encoding.next() {
+        Some(channels) => Some(channels.parse().ok()?),
+        None => None,
+    };
+
+    Some(Codec {
+        payload_type: payload_type.parse().ok()?,
+        name: name.to_string(),
+        clock_rate,
+        channels,
+        fmtp: None,
+    })
+}
+
+/// Well-known static RTP payload types (RFC 3551) that may appear without `a=rtpmap`
+fn static_codec(payload_type: u8) -> Option<Codec> {
+    let (name, clock_rate) = match payload_type {
+        0 => ("PCMU", 8000),
+        8 => ("PCMA", 8000),
+        9 => ("G722", 8000),
+        _ => return None,
+    };
+    Some(Codec {
+        payload_type,
+        name: name.to_string(),
+        clock_rate,
+        channels: None,
+        fmtp: None,
+    })
+}
+
+/// Codecs of a media section in `m=` line preference order
+fn collect_codecs(section: &MediaDescription) -> Vec<Codec> {
+    let mut rtpmaps = Vec::new();
+    let mut fmtps = Vec::new();
+    for line in &section.lines {
+        if let Some(rtpmap) = line.strip_prefix("a=rtpmap:") {
+            rtpmaps.extend(parse_rtpmap(rtpmap));
+        } else if let Some((payload_type, params)) = line
+            .strip_prefix("a=fmtp:")
+            .and_then(|fmtp| fmtp.split_once(' '))
+        {
+            if let Ok(payload_type) = payload_type.parse::<u8>() {
+                fmtps.push((payload_type, params.to_string()));
+            }
+        }
+    }
+
+    section
+        .formats
+        .iter()
+        .filter_map(|format| format.parse::<u8>().ok())
+        .filter_map(|payload_type| {
+            let mut codec = rtpmaps
+                .iter()
+                .find(|codec| codec.payload_type == payload_type)
+                .cloned()
+                .or_else(|| static_codec(payload_type))?;
+            codec.fmtp = fmtps
+                .iter()
+                .find(|(pt, _)| *pt == payload_type)
+                .map(|(_, params)| params.clone());
+            Some(codec)
+        })
+        .collect()
+}
@@ -1,16 +1,120 @@
 use futures_util::{SinkExt, StreamExt};
-use std::sync::Arc;
+use std::env;
+use std::net::IpAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
-use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio_tungstenite::accept_async_with_config;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, WebSocketConfig};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
 use uuid::Uuid;
 // Removed url dependency
 use anyhow::Result;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use crate::auth::JwtValidator;
-use crate::messages::{ClientMessage, ServerMessage};
-use crate::room::{RoomManager, RoomParticipant};
+use crate::audit::{AuditLog, SignalKind};
+use crate::auth::{AuthenticatedUser, JwtValidator, TokenGrants};
+use crate::call::{CallConfig, CallResponse};
+use crate::candidate::TenantCandidateStats;
+use crate::ice::IceConfig;
+use crate::messages::{ClientMessage, LeaveReason, ServerMessage};
+use crate::moderation::ModeratorAction;
+use crate::room::{
+    breakout_room_name, outbound_channel, DeliveryError, JoinRequest, RoomManager, RoomParticipant,
+};
+use crate::room_config::RoomConfig;
+use crate::room_name::{RoomNameError, RoomNamePolicy};
+use crate::room_state::RoomStateUpdate;
+use crate::sdp::{prepare_for_relay, SdpError, SdpLimits};
+use crate::tenant::{local_room_name, localize_message, qualify_room_name};
+
+/// Size limits for incoming WebSocket traffic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WebSocketLimits {
+    /// Largest message handed to the JSON parser; larger ones are answered with an error
+    pub max_message_size: usize,
+    /// Largest frame or reassembled message the WebSocket layer buffers at all.
+    /// Exceeding it closes the connection with code 1009.
+    pub max_frame_size: usize,
+    /// Outgoing messages the WebSocket layer may queue before applying backpressure
+    pub max_send_queue: Option<usize>,
+    /// Oversized messages tolerated before the connection is closed with code 1009
+    pub max_oversized_messages: u32,
+}
+
+impl Default for WebSocketLimits {
+    fn default() -> Self {
+        Self {
+            max_message_size: 128 * 1024,
+            max_frame_size: 1024 * 1024,
+            max_send_queue: Some(1024),
+            max_oversized_messages: 3,
+        }
+    }
+}
+
+impl WebSocketLimits {
+    /// Load limits from `WS_MAX_MESSAGE_SIZE`, `WS_MAX_FRAME_SIZE`, `WS_MAX_SEND_QUEUE`
+    /// and `WS_MAX_OVERSIZED_MESSAGES`
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+            env::var(name).ok().and_then(|value| value.parse().ok())
+        }
+
+        let defaults = Self::default();
+        Self {
+            max_message_size: parse("WS_MAX_MESSAGE_SIZE").unwrap_or(defaults.max_message_size),
+            max_frame_size: parse("WS_MAX_FRAME_SIZE").unwrap_or(defaults.max_frame_size),
+            max_send_queue: parse("WS_MAX_SEND_QUEUE").or(defaults.max_send_queue),
+            max_oversized_messages: parse("WS_MAX_OVERSIZED_MESSAGES")
+                .unwrap_or(defaults.max_oversized_messages),
+        }
+    }
+
+    pub fn websocket_config(&self) -> WebSocketConfig {
+        // The hard limit must leave room for messages up to the soft limit
+        let hard_limit = self.max_frame_size.max(self.max_message_size);
+        WebSocketConfig {
+            max_send_queue: self.max_send_queue,
+            max_message_size: Some(hard_limit),
+            max_frame_size: Some(hard_limit),
+            accept_unmasked_frames: false,
+        }
+    }
+}
+
+/// Runtime configuration for the signaling server
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfig {
+    pub ice: IceConfig,
+    pub websocket: WebSocketLimits,
+    pub sdp_limits: SdpLimits,
+    /// Counters shared by every connection for candidates dropped by room policies,
+    /// kept per tenant
+    pub candidate_stats: Arc<TenantCandidateStats>,
+    /// Records the offers, answers and candidates relayed in each room
+    pub audit: Option<AuditLog>,
+    /// Rules for the room names clients send
+    pub room_names: RoomNamePolicy,
+    pub calls: CallConfig,
+}
+
+impl ServerConfig {
+    /// Load the server configuration from environment variables
+    pub fn from_env() -> Self {
+        Self {
+            ice: IceConfig::from_env(),
+            websocket: WebSocketLimits::from_env(),
+            sdp_limits: SdpLimits::from_env(),
+            candidate_stats: Arc::default(),
+            audit: None,
+            room_names: RoomNamePolicy::from_env(),
+            calls: CallConfig::from_env(),
+        }
+    }
+}
 
 pub async fn start_server(host: String, port: u16, jwt_secret: String) -> Result<()> {
     let room_manager = RoomManager::new();
@@ -22,6 +126,23 @@ pub async fn start_server_with_room_manager(
     port: u16,
     jwt_secret: String,
     room_manager: RoomManager,
+) -> Result<()> {
+    start_server_with_config(
+        host,
+        port,
+        jwt_secret,
+        room_manager,
+        ServerConfig::default(),
+    )
+    .await
+}
+
+pub async fn start_server_with_config(
+    host: String,
+    port: u16,
+    jwt_secret: String,
+    room_manager: RoomManager,
+    config: ServerConfig,
 ) -> Result<()> {
     let addr = format!("{}:{}", host, port);
     let listener = TcpListener::bind(&addr).await?;
@@ -30,15 +151,17 @@ pub async fn start_server_with_room_manager(
 
     let jwt_validator = Arc::new(JwtValidator::new(&jwt_secret));
     let room_manager = Arc::new(room_manager);
+    let config = Arc::new(config);
 
     while let Ok((stream, peer_addr)) = listener.accept().await {
         info!("New connection from: {}", peer_addr);
 
         let jwt_validator = jwt_validator.clone();
         let room_manager = room_manager.clone();
+        let config = config.clone();
 
         tokio::spawn(async move {
-            if let Err(e) = handle_connection(stream, jwt_validator, room_manager).await {
+            if let Err(e) = handle_connection(stream, jwt_validator, room_manager, config).await {
                 error!("Connection error: {}", e);
             }
         });
@@ -51,18 +174,30 @@ async fn handle_connection(
     stream: TcpStream,
     jwt_validator: Arc<JwtValidator>,
     room_manager: Arc<RoomManager>,
+    config: Arc<ServerConfig>,
 ) -> Result<()> {
     let connection_id = Uuid::new_v4();
+    let client_ip = stream.peer_addr().ok().map(|addr| addr.ip());
 
-    let ws_stream = accept_async(stream).await?;
+    let ws_stream =
+        accept_async_with_config(stream, Some(config.websocket.websocket_config())).await?;
     debug!("WebSocket connection established: {}", connection_id);
 
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
-    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    let (tx, mut rx) = outbound_channel();
+    // Set once the user authenticates, if their token names a tenant
+    let tenant = Arc::new(OnceLock::<String>::new());
 
     // Handle outgoing messages
+    let outgoing_tenant = Arc::clone(&tenant);
     let outgoing_task = tokio::spawn(async move {
-        while let Some(message) = rx.recv().await {
+        while let Some(mut message) = rx.recv().await {
+            // Rooms are stored under tenant-qualified names the client never sees
+            if let (Some(tenant), Message::Text(text)) = (outgoing_tenant.get(), &message) {
+                if let Some(localized) = localize_message(tenant, text) {
+                    message = Message::Text(localized);
+                }
+            }
             if let Err(e) = ws_sender.send(message).await {
                 error!("Failed to send WebSocket message: {}", e);
                 break;
@@ -71,8 +206,8 @@ async fn handle_connection(
     });
 
     // Wait for authentication message (first message should be auth)
-    let user = match authenticate_connection(&mut ws_receiver, &jwt_validator).await {
-        Ok(user) => user,
+    let (user, grants) = match authenticate_connection(&mut ws_receiver, &jwt_validator).await {
+        Ok(authenticated) => authenticated,
         Err(e) => {
             error!("Authentication failed: {}", e);
             let error_msg = ServerMessage::error(format!("Authentication failed: {}", e));
@@ -82,6 +217,9 @@ async fn handle_connection(
     };
 
     println!("DEBUG: Authenticated user: {}", user.username);
+    if let Some(user_tenant) = &user.tenant {
+        let _ = tenant.set(user_tenant.clone());
+    }
 
     info!(
         "User {} ({}) authenticated successfully",
@@ -95,14 +233,78 @@ async fn handle_connection(
     };
     let _ = send_message(&tx, auth_msg);
 
+    // Calls and other events addressed to the user reach every connection they have open
+    room_manager
+        .connect_user(RoomParticipant {
+            user: user.clone(),
+            connection_id,
+            sender: tx.clone(),
+        })
+        .await;
+
+    // Hand out ICE servers and keep TURN credentials fresh for the connection's lifetime
+    let ice_refresh_task = if config.ice.is_empty() {
+        None
+    } else {
+        let _ = send_message(&tx, ice_servers_message(&config.ice, user.user_id));
+        config.ice.refresh_interval().map(|refresh_interval| {
+            let ice_config = config.ice.clone();
+            let tx = tx.clone();
+            let user_id = user.user_id;
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(refresh_interval);
+                interval.tick().await; // First tick completes immediately
+                loop {
+                    interval.tick().await;
+                    debug!("Refreshing TURN credentials for user {}", user_id);
+                    if send_message(&tx, ice_servers_message(&ice_config, user_id)).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+    };
+
     // Handle incoming messages
     let user_id = user.user_id;
+    let context = ConnectionContext {
+        user: user.clone(),
+        grants,
+        connection_id,
+        client_ip,
+        tx: tx.clone(),
+    };
     let incoming_task = tokio::spawn(async move {
+        let limits = config.websocket;
+        let mut oversized_messages = 0;
+
         while let Some(msg_result) = ws_receiver.next().await {
             match msg_result {
+                Ok(Message::Text(text)) if text.len() > limits.max_message_size => {
+                    oversized_messages += 1;
+                    warn!(
+                        "User {} sent an oversized message ({} bytes, {} so far)",
+                        user.user_id,
+                        text.len(),
+                        oversized_messages
+                    );
+                    if oversized_messages >= limits.max_oversized_messages {
+                        close_message_too_big(&tx, "Too many oversized messages");
+                        break;
+                    }
+                    let error_msg = ServerMessage::error_with_code(
+                        format!(
+                            "Message too large: {} bytes (maximum {})",
+                            text.len(),
+                            limits.max_message_size
+                        ),
+                        413,
+                    );
+                    let _ = send_message(&tx, error_msg);
+                }
                 Ok(Message::Text(text)) => {
                     if let Err(e) =
-                        handle_client_message(&text, &user, connection_id, &room_manager, &tx).await
+                        handle_client_message(&text, &context, &room_manager, &config).await
                     {
                         error!("Error handling message: {}", e);
                         let error_msg =
@@ -117,6 +319,15 @@ async fn handle_connection(
                 Ok(_) => {
                     // Ignore other message types
                 }
+                Err(WsError::Capacity(e)) => {
+                    // The WebSocket layer cannot resynchronise after refusing a frame
+                    warn!("User {} exceeded the frame size limit: {}", user.user_id, e);
+                    let error_msg =
+                        ServerMessage::error_with_code(format!("Message too large: {}", e), 413);
+                    let _ = send_message(&tx, error_msg);
+                    close_message_too_big(&tx, "Message too large");
+                    break;
+                }
                 Err(e) => {
                     error!("WebSocket error: {}", e);
                     break;
@@ -125,6 +336,7 @@ async fn handle_connection(
         }
 
         // Clean up user from all rooms when connection closes
+        room_manager.disconnect_user(connection_id).await;
         room_manager
             .remove_user_from_all_rooms(user.user_id, connection_id)
             .await;
@@ -141,15 +353,45 @@ async fn handle_connection(
         }
     }
 
+    if let Some(task) = ice_refresh_task {
+        task.abort();
+    }
+
     Ok(())
 }
 
+/// Everything known about an authenticated connection
+struct ConnectionContext {
+    user: AuthenticatedUser,
+    grants: TokenGrants,
+    connection_id: Uuid,
+    client_ip: Option<IpAddr>,
+    tx: mpsc::Sender<Message>,
+}
+
+/// Close the connection with status 1009 (message too big)
+fn close_message_too_big(tx: &mpsc::Sender<Message>, reason: &str) {
+    let _ = tx.try_send(Message::Close(Some(CloseFrame {
+        code: CloseCode::Size,
+        reason: reason.to_string().into(),
+    })));
+}
+
+fn ice_servers_message(ice_config: &IceConfig, user_id: u32) -> ServerMessage {
+    ServerMessage::IceServers {
+        ice_servers: ice_config.ice_servers_for(user_id),
+        ttl: ice_config
+            .has_turn()
+            .then_some(ice_config.turn_ttl.as_secs()),
+    }
+}
+
 async fn authenticate_connection(
     ws_receiver: &mut futures_util::stream::SplitStream<
         tokio_tungstenite::WebSocketStream<TcpStream>,
     >,
     jwt_validator: &JwtValidator,
-) -> Result<crate::auth::AuthenticatedUser, String> {
+) -> Result<(AuthenticatedUser, TokenGrants), String> {
     debug!("Waiting for authentication message...");
     println!("DEBUG: authenticate_connection called");
 
@@ -174,7 +416,7 @@ async fn authenticate_connection(
                             ClientMessage::Auth { token } => {
                                 debug!("Extracted token from Auth message: {}", token);
                                 println!("DEBUG: Extracted token from Auth message: {}", token);
-                                return jwt_validator.validate_token(&token);
+                                return jwt_validator.validate_token_with_grants(&token);
                             }
                             _ => {
                                 debug!("Parsed as non-Auth message type");
@@ -197,7 +439,7 @@ async fn authenticate_connection(
                     debug!("Successfully parsed as generic JSON: {:?}", auth_msg);
                     if let Some(token) = auth_msg.get("token").and_then(|t| t.as_str()) {
                         debug!("Extracted token from generic auth message: {}", token);
-                        jwt_validator.validate_token(token)
+                        jwt_validator.validate_token_with_grants(token)
                     } else {
                         debug!("No 'token' field found in JSON");
                         Err("No 'token' field found in authentication message".to_string())
@@ -218,15 +460,38 @@ async fn authenticate_connection(
 
 async fn handle_client_message(
     text: &str,
-    user: &crate::auth::AuthenticatedUser,
-    connection_id: Uuid,
+    context: &ConnectionContext,
     room_manager: &RoomManager,
-    tx: &mpsc::UnboundedSender<Message>,
+    config: &ServerConfig,
 ) -> Result<(), String> {
-    debug!("Received message from user {}: {}", user.user_id, text);
-
-    let client_message: ClientMessage =
+    let user = &context.user;
+    let tx = &context.tx;
+    let mut client_message: ClientMessage =
         serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+    debug!(
+        "Received message from user {}: {}",
+        user.user_id,
+        loggable(&client_message, text)
+    );
+
+    if let Err(e) = normalize_room_names(&mut client_message, &config.room_names) {
+        debug!("Refused room name from user {}: {}", user.user_id, e);
+        let error_msg =
+            ServerMessage::error_with_code(format!("Invalid room name: {}", e), e.code());
+        send_message(tx, error_msg)?;
+        return Ok(());
+    }
+
+    // Every room the client names lives in its tenant's namespace
+    if let Some(room_name) = client_message.room_name_mut() {
+        match qualify_room_name(user.tenant.as_deref(), room_name) {
+            Ok(qualified) => *room_name = qualified,
+            Err(e) => {
+                send_message(tx, ServerMessage::error_with_code(e, 400))?;
+                return Ok(());
+            }
+        }
+    }
 
     match client_message {
         ClientMessage::Auth { .. } => {
@@ -236,27 +501,37 @@ async fn handle_client_message(
 
         ClientMessage::JoinRoom {
             room_name,
-            password: _,
+            password,
         } => {
             let participant = RoomParticipant {
                 user: user.clone(),
-                connection_id,
+                connection_id: context.connection_id,
                 sender: tx.clone(),
             };
+            let request = JoinRequest {
+                password,
+                client_ip: context.client_ip,
+                role: context
+                    .grants
+                    .role_for(local_room_name(user.tenant.as_deref(), &room_name)),
+                max_duration: context.grants.max_duration(),
+            };
 
-            match room_manager.join_room(room_name.clone(), participant).await {
-                Ok(existing_participants) => {
-                    let join_msg = ServerMessage::RoomJoined {
-                        room_name,
-                        user_id: user.user_id,
-                        participants: existing_participants,
-                    };
+            match room_manager
+                .join_room_with(room_name.clone(), participant, request)
+                .await
+            {
+                Ok(outcome) => {
+                    let ownership = room_manager.room_ownership(&room_name).await;
+                    let state = room_manager.room_state(&room_name).await;
+                    let join_msg =
+                        outcome.to_server_message(&room_name, user.user_id, ownership, state);
                     send_message(tx, join_msg)?;
+                    if outcome.participants().is_some() {
+                        room_manager.plan_mesh(&room_name, user.user_id).await;
+                    }
                 }
-                Err(e) => {
-                    let error_msg = ServerMessage::error(format!("Failed to join room: {}", e));
-                    send_message(tx, error_msg)?;
-                }
+                Err(e) => send_message(tx, e.to_server_message(&room_name))?,
             }
         }
 
@@ -266,6 +541,7 @@ async fn handle_client_message(
                     let leave_msg = ServerMessage::RoomLeft {
                         room_name,
                         user_id: user.user_id,
+                        reason: Some(LeaveReason::Left),
                     };
                     send_message(tx, leave_msg)?;
                 }
@@ -286,7 +562,30 @@ async fn handle_client_message(
                 send_message(tx, error_msg)?;
                 return Ok(());
             }
+            if !may_signal(room_manager, &room_name, user.user_id, target_user_id).await {
+                send_message(tx, spectator_signal_error())?;
+                return Ok(());
+            }
 
+            let room_config = room_manager.room_config(&room_name).await;
+            let sdp = match apply_room_policies(&room_name, &sdp, &room_config, config) {
+                Ok(sdp) => sdp,
+                Err(e) => {
+                    warn!("Rejected SDP from user {}: {}", user.user_id, e);
+                    send_message(tx, sdp_error_message(&e))?;
+                    return Ok(());
+                }
+            };
+
+            if let Some(audit) = &config.audit {
+                audit.record_signal(
+                    &room_name,
+                    SignalKind::Offer,
+                    user.user_id,
+                    target_user_id,
+                    &sdp,
+                );
+            }
             let offer_msg = ServerMessage::Offer {
                 room_name: room_name.clone(),
                 from_user_id: user.user_id,
@@ -294,10 +593,16 @@ async fn handle_client_message(
             };
 
             if let Some(target_id) = target_user_id {
-                room_manager
-                    .send_to_user_in_room(&room_name, target_id, offer_msg)
-                    .await
-                    .map_err(|e| format!("Failed to send offer: {}", e))?;
+                send_signal(
+                    room_manager,
+                    &room_name,
+                    user.user_id,
+                    target_id,
+                    offer_msg,
+                    SignalKind::Offer,
+                    tx,
+                )
+                .await?;
             } else {
                 room_manager
                     .broadcast_to_room(&room_name, user.user_id, offer_msg)
@@ -316,17 +621,46 @@ async fn handle_client_message(
                 send_message(tx, error_msg)?;
                 return Ok(());
             }
+            if !may_signal(room_manager, &room_name, user.user_id, Some(target_user_id)).await {
+                send_message(tx, spectator_signal_error())?;
+                return Ok(());
+            }
 
+            let room_config = room_manager.room_config(&room_name).await;
+            let sdp = match apply_room_policies(&room_name, &sdp, &room_config, config) {
+                Ok(sdp) => sdp,
+                Err(e) => {
+                    warn!("Rejected SDP from user {}: {}", user.user_id, e);
+                    send_message(tx, sdp_error_message(&e))?;
+                    return Ok(());
+                }
+            };
+
+            if let Some(audit) = &config.audit {
+                audit.record_signal(
+                    &room_name,
+                    SignalKind::Answer,
+                    user.user_id,
+                    Some(target_user_id),
+                    &sdp,
+                );
+            }
             let answer_msg = ServerMessage::Answer {
                 room_name: room_name.clone(),
                 from_user_id: user.user_id,
                 sdp,
             };
 
-            room_manager
-                .send_to_user_in_room(&room_name, target_user_id, answer_msg)
-                .await
-                .map_err(|e| format!("Failed to send answer: {}", e))?;
+            send_signal(
+                room_manager,
+                &room_name,
+                user.user_id,
+                target_user_id,
+                answer_msg,
+                SignalKind::Answer,
+                tx,
+            )
+            .await?;
         }
 
         ClientMessage::IceCandidate {
@@ -341,7 +675,39 @@ async fn handle_client_message(
                 send_message(tx, error_msg)?;
                 return Ok(());
             }
+            if !may_signal(room_manager, &room_name, user.user_id, target_user_id).await {
+                send_message(tx, spectator_signal_error())?;
+                return Ok(());
+            }
+
+            let room_config = room_manager.room_config(&room_name).await;
+            let filtered = room_config.candidate_policy.apply(&candidate);
+            if !room_config.candidate_policy.is_empty() {
+                config
+                    .candidate_stats
+                    .for_room(&room_name)
+                    .record(&filtered);
+            }
+            let candidate = match filtered {
+                Ok(candidate) => candidate,
+                Err(reason) => {
+                    debug!(
+                        "Dropped ICE candidate from user {} in room {}: {:?}",
+                        user.user_id, room_name, reason
+                    );
+                    return Ok(());
+                }
+            };
 
+            if let Some(audit) = &config.audit {
+                audit.record_signal(
+                    &room_name,
+                    SignalKind::IceCandidate,
+                    user.user_id,
+                    target_user_id,
+                    &candidate,
+                );
+            }
             let ice_msg = ServerMessage::IceCandidate {
                 room_name: room_name.clone(),
                 from_user_id: user.user_id,
@@ -351,10 +717,16 @@ async fn handle_client_message(
             };
 
             if let Some(target_id) = target_user_id {
-                room_manager
-                    .send_to_user_in_room(&room_name, target_id, ice_msg)
-                    .await
-                    .map_err(|e| format!("Failed to send ICE candidate: {}", e))?;
+                send_signal(
+                    room_manager,
+                    &room_name,
+                    user.user_id,
+                    target_id,
+                    ice_msg,
+                    SignalKind::IceCandidate,
+                    tx,
+                )
+                .await?;
             } else {
                 room_manager
                     .broadcast_to_room(&room_name, user.user_id, ice_msg)
@@ -362,16 +734,386 @@ async fn handle_client_message(
                     .map_err(|e| format!("Failed to broadcast ICE candidate: {}", e))?;
             }
         }
+
+        ClientMessage::Kick {
+            room_name,
+            target_user_id,
+            reason,
+        } => {
+            let action = ModeratorAction::Kick {
+                target: target_user_id,
+                reason,
+            };
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::Ban {
+            room_name,
+            target_user_id,
+            duration_seconds,
+            reason,
+        } => {
+            let action = ModeratorAction::Ban {
+                target: target_user_id,
+                duration: duration_seconds.map(Duration::from_secs),
+                reason,
+            };
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::RequestMute {
+            room_name,
+            target_user_id,
+            media,
+        } => {
+            let action = ModeratorAction::RequestMute {
+                target: target_user_id,
+                media,
+            };
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::TransferHost {
+            room_name,
+            target_user_id,
+        } => {
+            let action = ModeratorAction::TransferHost {
+                target: target_user_id,
+            };
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::PromoteSpectator {
+            room_name,
+            target_user_id,
+        } => {
+            let action = ModeratorAction::Promote {
+                target: target_user_id,
+            };
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::Admit {
+            room_name,
+            target_user_id,
+        } => {
+            let action = ModeratorAction::Admit {
+                target: target_user_id,
+            };
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::Deny {
+            room_name,
+            target_user_id,
+            reason,
+        } => {
+            let action = ModeratorAction::Deny {
+                target: target_user_id,
+                reason,
+            };
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::CreateBreakouts {
+            room_name,
+            breakouts,
+        } => {
+            let action = ModeratorAction::OpenBreakouts { breakouts };
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::CloseBreakouts { room_name } => {
+            let action = ModeratorAction::CloseBreakouts;
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::SetRoomState {
+            room_name,
+            key,
+            value,
+            expected_version,
+        } => {
+            let update = RoomStateUpdate {
+                key,
+                value: Some(value),
+                expected_version,
+            };
+            update_room_state(room_manager, &room_name, user.user_id, update, tx).await?;
+        }
+
+        ClientMessage::DeleteRoomState {
+            room_name,
+            key,
+            expected_version,
+        } => {
+            let update = RoomStateUpdate {
+                key,
+                value: None,
+                expected_version,
+            };
+            update_room_state(room_manager, &room_name, user.user_id, update, tx).await?;
+        }
+
+        ClientMessage::LockRoom { room_name, locked } => {
+            let action = ModeratorAction::Lock { locked };
+            moderate(room_manager, &room_name, user.user_id, action, tx).await?;
+        }
+
+        ClientMessage::Ring { target_user_id } => {
+            match room_manager
+                .invite(user, target_user_id, config.calls.ring_timeout)
+                .await
+            {
+                Ok(invitation) => {
+                    let ringing_msg = ServerMessage::Ringing {
+                        call_id: invitation.call_id,
+                        target_user_id,
+                    };
+                    send_message(tx, ringing_msg)?;
+                }
+                Err(e) => {
+                    debug!("Refused call from user {}: {}", user.user_id, e);
+                    let error_msg =
+                        ServerMessage::error_with_code(format!("Call failed: {}", e), e.code());
+                    send_message(tx, error_msg)?;
+                }
+            }
+        }
+
+        ClientMessage::AcceptCall { call_id } => {
+            respond_to_call(room_manager, user, call_id, CallResponse::Accept, tx).await?;
+        }
+
+        ClientMessage::DeclineCall { call_id } => {
+            respond_to_call(room_manager, user, call_id, CallResponse::Decline, tx).await?;
+        }
+
+        ClientMessage::CancelCall { call_id } => {
+            respond_to_call(room_manager, user, call_id, CallResponse::Cancel, tx).await?;
+        }
+
+        ClientMessage::ListRooms => {
+            let rooms = room_manager.list_rooms(user.tenant.as_deref()).await;
+            send_message(tx, ServerMessage::RoomList { rooms })?;
+        }
+
+        ClientMessage::SubscribeRoomDirectory => {
+            // Subscribe before taking the snapshot so that no change falls between them
+            room_manager
+                .subscribe_room_directory(RoomParticipant {
+                    user: user.clone(),
+                    connection_id: context.connection_id,
+                    sender: tx.clone(),
+                })
+                .await;
+            let rooms = room_manager.list_rooms(user.tenant.as_deref()).await;
+            send_message(tx, ServerMessage::RoomList { rooms })?;
+        }
+
+        ClientMessage::UnsubscribeRoomDirectory => {
+            room_manager
+                .unsubscribe_room_directory(context.connection_id)
+                .await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Answer or cancel a call, telling the user if it no longer rings
+async fn respond_to_call(
+    room_manager: &RoomManager,
+    user: &AuthenticatedUser,
+    call_id: Uuid,
+    response: CallResponse,
+    tx: &mpsc::Sender<Message>,
+) -> Result<(), String> {
+    if let Err(e) = room_manager.respond_to_call(user, call_id, response).await {
+        debug!("Refused call response by user {}: {}", user.user_id, e);
+        let error_msg = ServerMessage::error_with_code(format!("Call failed: {}", e), e.code());
+        send_message(tx, error_msg)?;
+    }
+    Ok(())
+}
+
+/// Apply a moderator action, reporting refusals to the moderator
+async fn moderate(
+    room_manager: &RoomManager,
+    room_name: &str,
+    actor_id: u32,
+    action: ModeratorAction,
+    tx: &mpsc::Sender<Message>,
+) -> Result<(), String> {
+    if let Err(e) = room_manager.moderate(room_name, actor_id, action).await {
+        warn!("Refused moderator action by user {}: {}", actor_id, e);
+        let error_msg =
+            ServerMessage::error_with_code(format!("Moderation failed: {}", e), e.code());
+        send_message(tx, error_msg)?;
+    }
+    Ok(())
+}
+
+/// Whether the sender may signal `target_user_id`, or broadcast when there is no
+/// target. Spectators only signal with presenters; broadcasts never reach them.
+async fn may_signal(
+    room_manager: &RoomManager,
+    room_name: &str,
+    user_id: u32,
+    target_user_id: Option<u32>,
+) -> bool {
+    let Some(target_user_id) = target_user_id else {
+        return true;
+    };
+    !(room_manager
+        .room_role(room_name, user_id)
+        .await
+        .is_spectator()
+        && room_manager
+            .room_role(room_name, target_user_id)
+            .await
+            .is_spectator())
+}
+
+/// Send a signal to one peer, telling the sender with `delivery-failed` if the
+/// peer cannot be reached. Offers and answers that get through advance the pair's
+/// negotiation when the server plans the room's mesh.
+async fn send_signal(
+    room_manager: &RoomManager,
+    room_name: &str,
+    from_user_id: u32,
+    target_user_id: u32,
+    message: ServerMessage,
+    kind: SignalKind,
+    tx: &mpsc::Sender<Message>,
+) -> Result<(), String> {
+    let message_type = kind.as_str();
+    match room_manager
+        .send_to_user_in_room(room_name, target_user_id, message)
+        .await
+    {
+        Ok(()) => {
+            if kind != SignalKind::IceCandidate {
+                room_manager
+                    .record_signal(room_name, from_user_id, target_user_id, kind)
+                    .await;
+            }
+            Ok(())
+        }
+        Err(DeliveryError::Undelivered(reason)) => {
+            debug!(
+                "Could not deliver {} to user {} in room {}: {:?}",
+                message_type, target_user_id, room_name, reason
+            );
+            let failed_msg = ServerMessage::DeliveryFailed {
+                room_name: room_name.to_string(),
+                target_user_id,
+                message_type: message_type.to_string(),
+                reason,
+            };
+            send_message(tx, failed_msg)
+        }
+        Err(e) => Err(format!("Failed to send {}: {}", message_type, e)),
     }
+}
+
+fn spectator_signal_error() -> ServerMessage {
+    ServerMessage::error_with_code("Spectators can only signal presenters", 403)
+}
+
+/// The message as it may be logged, with any room password blanked out
+fn loggable(message: &ClientMessage, text: &str) -> String {
+    let ClientMessage::JoinRoom {
+        room_name,
+        password: Some(_),
+    } = message
+    else {
+        return text.to_string();
+    };
+    let redacted = ClientMessage::JoinRoom {
+        room_name: room_name.clone(),
+        password: Some("<redacted>".to_string()),
+    };
+    serde_json::to_string(&redacted).unwrap_or_default()
+}
+
+/// Normalize and check the room names in a client message, including the names of
+/// breakout rooms it would create
+fn normalize_room_names(
+    message: &mut ClientMessage,
+    policy: &RoomNamePolicy,
+) -> Result<(), RoomNameError> {
+    let Some(room_name) = message.room_name_mut() else {
+        return Ok(());
+    };
+    *room_name = policy.apply(room_name)?;
 
+    if let ClientMessage::JoinRoom { room_name, .. } = message {
+        policy.validate_join(room_name)?;
+    }
+    if let ClientMessage::CreateBreakouts {
+        room_name,
+        breakouts,
+    } = message
+    {
+        for assignment in breakouts {
+            assignment.name = policy.apply(&assignment.name)?;
+            policy.validate(&breakout_room_name(room_name, &assignment.name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Apply a shared state update, telling the sender if it was refused
+async fn update_room_state(
+    room_manager: &RoomManager,
+    room_name: &str,
+    user_id: u32,
+    update: RoomStateUpdate,
+    tx: &mpsc::Sender<Message>,
+) -> Result<(), String> {
+    if let Err(e) = room_manager
+        .update_room_state(room_name, user_id, update)
+        .await
+    {
+        debug!("Refused room state update by user {}: {}", user_id, e);
+        let error_msg =
+            ServerMessage::error_with_code(format!("Room state update failed: {}", e), e.code());
+        send_message(tx, error_msg)?;
+    }
     Ok(())
 }
 
-fn send_message(tx: &mpsc::UnboundedSender<Message>, msg: ServerMessage) -> Result<(), String> {
+/// Validate an SDP and apply the room's SDP and candidate policies to it
+fn apply_room_policies(
+    room_name: &str,
+    sdp: &str,
+    room_config: &RoomConfig,
+    config: &ServerConfig,
+) -> Result<String, SdpError> {
+    prepare_for_relay(
+        sdp,
+        &config.sdp_limits,
+        &room_config.sdp_policy,
+        &room_config.candidate_policy,
+        &config.candidate_stats.for_room(room_name),
+    )
+}
+
+fn sdp_error_message(error: &SdpError) -> ServerMessage {
+    let code = match error {
+        SdpError::TooLarge { .. } => 413,
+        SdpError::NoAllowedCodecs { .. } => 415,
+        _ => 400,
+    };
+    ServerMessage::error_with_code(format!("Invalid SDP: {}", error), code)
+}
+
+fn send_message(tx: &mpsc::Sender<Message>, msg: ServerMessage) -> Result<(), String> {
     let json =
         serde_json::to_string(&msg).map_err(|e| format!("Failed to serialize message: {}", e))?;
 
-    tx.send(Message::Text(json))
+    tx.try_send(Message::Text(json))
         .map_err(|e| format!("Failed to send message: {}", e))?;
 
     Ok(())
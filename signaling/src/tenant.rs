@@ -0,0 +1,93 @@
+use serde_json::{Map, Value};
+
+/// Separates the tenant from the room in a qualified room name, as in `acme::standup`
+pub const TENANT_SEPARATOR: &str = "::";
+/// Longest tenant id accepted from a token
+pub const MAX_TENANT_ID_LENGTH: usize = 64;
+
+/// Message fields holding a room name, rewritten on the way to a tenant's clients
+const ROOM_NAME_FIELDS: [&str; 2] = ["roomName", "fromRoomName"];
+
+/// Check a tenant id from a token: 1 to 64 ASCII letters, digits, `-` or `_`
+pub fn validate_tenant_id(tenant: &str) -> Result<(), String> {
+    let valid_chars = tenant
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+    if tenant.is_empty() || tenant.len() > MAX_TENANT_ID_LENGTH || !valid_chars {
+        return Err(format!(
+            "Invalid tenant id {:?}: use 1 to {} letters, digits, '-' or '_'",
+            tenant, MAX_TENANT_ID_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+/// Name under which a client's room is stored, prefixed with the tenant if there
+/// is one. Names containing the separator are refused so no client can address
+/// another tenant's rooms.
+pub fn qualify_room_name(tenant: Option<&str>, room_name: &str) -> Result<String, String> {
+    if room_name.contains(TENANT_SEPARATOR) {
+        return Err(format!("Room names may not contain {:?}", TENANT_SEPARATOR));
+    }
+    Ok(match tenant {
+        Some(tenant) => format!("{}{}{}", tenant, TENANT_SEPARATOR, room_name),
+        None => room_name.to_string(),
+    })
+}
+
+/// Tenant owning a stored room name, `None` for rooms outside any tenant
+pub fn tenant_of(room_name: &str) -> Option<&str> {
+    room_name
+        .split_once(TENANT_SEPARATOR)
+        .map(|(tenant, _)| tenant)
+}
+
+/// Whether a user of `user_tenant` may join or hear from `room_name`
+pub fn same_tenant(user_tenant: Option<&str>, room_name: &str) -> bool {
+    user_tenant == tenant_of(room_name)
+}
+
+/// Room name as the tenant's clients know it
+pub fn local_room_name<'a>(tenant: Option<&str>, room_name: &'a str) -> &'a str {
+    tenant
+        .and_then(|tenant| room_name.strip_prefix(tenant))
+        .and_then(|rest| rest.strip_prefix(TENANT_SEPARATOR))
+        .unwrap_or(room_name)
+}
+
+/// Strip the tenant prefix from the room names in a serialized server message,
+/// including any named in an error's text, returning `None` when nothing needed
+/// changing
+pub fn localize_message(tenant: &str, text: &str) -> Option<String> {
+    let prefix = format!("{}{}", tenant, TENANT_SEPARATOR);
+    if !text.contains(&prefix) {
+        return None;
+    }
+    let mut message: Value = serde_json::from_str(text).ok()?;
+    let fields = message.as_object_mut()?;
+    localize_fields(&prefix, fields);
+    if let Some(Value::String(error)) = fields.get_mut("message") {
+        *error = error.replace(&prefix, "");
+    }
+    serde_json::to_string(&message).ok()
+}
+
+/// Strip `prefix` from the room names of a message object and of the room
+/// summaries it carries
+fn localize_fields(prefix: &str, fields: &mut Map<String, Value>) {
+    for field in ROOM_NAME_FIELDS {
+        if let Some(Value::String(room_name)) = fields.get_mut(field) {
+            if let Some(local) = room_name.strip_prefix(prefix) {
+                *room_name = local.to_string();
+            }
+        }
+    }
+    if let Some(Value::Object(room)) = fields.get_mut("room") {
+        localize_fields(prefix, room);
+    }
+    if let Some(Value::Array(rooms)) = fields.get_mut("rooms") {
+        for room in rooms.iter_mut().filter_map(Value::as_object_mut) {
+            localize_fields(prefix, room);
+        }
+    }
+}
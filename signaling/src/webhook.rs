@@ -0,0 +1,630 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Notify};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Deliveries kept on disk when `WEBHOOK_QUEUE_CAPACITY` is unset
+pub const DEFAULT_QUEUE_CAPACITY: usize = 10_000;
+
+/// Room lifecycle event sent to the webhook endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum WebhookEvent {
+    #[serde(rename = "room-created")]
+    RoomCreated {
+        #[serde(rename = "roomName")]
+        room_name: String,
+    },
+    /// The last participant left; the room itself may persist
+    #[serde(rename = "room-emptied")]
+    RoomEmptied {
+        #[serde(rename = "roomName")]
+        room_name: String,
+    },
+    #[serde(rename = "user-joined")]
+    UserJoined {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "userId")]
+        user_id: u32,
+        username: String,
+        /// Everyone in the room, spectators included
+        #[serde(rename = "participantCount")]
+        participant_count: usize,
+        #[serde(rename = "spectatorCount", default)]
+        spectator_count: usize,
+    },
+    #[serde(rename = "user-left")]
+    UserLeft {
+        #[serde(rename = "roomName")]
+        room_name: String,
+        #[serde(rename = "userId")]
+        user_id: u32,
+        /// Everyone in the room, spectators included
+        #[serde(rename = "participantCount")]
+        participant_count: usize,
+        #[serde(rename = "spectatorCount", default)]
+        spectator_count: usize,
+    },
+}
+
+/// Body of a webhook request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: WebhookEvent,
+}
+
+/// Where and how webhook events are delivered
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// `http://` endpoints that receive every event
+    pub urls: Vec<String>,
+    /// Key for the `X-Webhook-Signature` HMAC; requests are unsigned without it
+    pub secret: Option<String>,
+    /// Attempts per delivery before it is dropped
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled after each failure
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Limit on a single request, including connecting
+    pub timeout: Duration,
+    /// File holding undelivered events across restarts; kept in memory when unset
+    pub queue_path: Option<PathBuf>,
+    /// Deliveries kept before the oldest is dropped
+    pub queue_capacity: usize,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            secret: None,
+            max_attempts: 8,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+            timeout: Duration::from_secs(10),
+            queue_path: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Load webhook configuration from the environment.
+    ///
+    /// `WEBHOOK_URLS` is a comma-separated list, `WEBHOOK_SECRET` signs requests,
+    /// `WEBHOOK_MAX_ATTEMPTS` bounds retries and `WEBHOOK_QUEUE_PATH` and
+    /// `WEBHOOK_QUEUE_CAPACITY` configure the on-disk queue.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let urls = env::var("WEBHOOK_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        Self {
+            urls,
+            secret: env::var("WEBHOOK_SECRET").ok().filter(|s| !s.is_empty()),
+            max_attempts: env::var("WEBHOOK_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|attempts| attempts.parse().ok())
+                .filter(|attempts| *attempts > 0)
+                .unwrap_or(defaults.max_attempts),
+            queue_path: env::var("WEBHOOK_QUEUE_PATH")
+                .ok()
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+            queue_capacity: env::var("WEBHOOK_QUEUE_CAPACITY")
+                .ok()
+                .and_then(|capacity| capacity.parse().ok())
+                .filter(|capacity| *capacity > 0)
+                .unwrap_or(defaults.queue_capacity),
+            ..defaults
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.urls.is_empty()
+    }
+
+    /// Wait before the next attempt after `attempts` failures
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Hex HMAC-SHA256 of `{timestamp}.{body}`, as sent in `X-Webhook-Signature`
+/// after the `sha256=` prefix
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    let mut signature = String::with_capacity(64);
+    for byte in mac.finalize().into_bytes() {
+        let _ = write!(signature, "{:02x}", byte);
+    }
+    signature
+}
+
+/// One event on its way to one endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Delivery {
+    id: Uuid,
+    url: String,
+    body: String,
+    attempts: u32,
+    /// Not kept on disk: reloaded deliveries are retried straight away
+    #[serde(skip)]
+    retry_at: Option<Instant>,
+}
+
+/// Journal entries appended before the queue file is rewritten from the queue
+const COMPACT_AFTER: usize = 1_000;
+
+/// A change to the queue, appended to the queue file as one JSON line. Replaying
+/// the file in order rebuilds the queue; replaying an entry twice is harmless.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum JournalEntry {
+    Queued(Delivery),
+    Retried {
+        id: Uuid,
+        attempts: u32,
+    },
+    /// Delivered, given up on or dropped to stay within capacity
+    Finished {
+        id: Uuid,
+    },
+}
+
+/// Pending deliveries, journaled to a JSON Lines file when a path is configured
+#[derive(Debug)]
+struct WebhookQueue {
+    capacity: usize,
+    pending: VecDeque<Delivery>,
+    /// Feeds the journal writer, which owns the file
+    journal: Option<mpsc::UnboundedSender<JournalEntry>>,
+}
+
+impl WebhookQueue {
+    fn open(path: Option<&Path>, capacity: usize) -> Result<Self, String> {
+        let mut queue = Self {
+            capacity,
+            pending: VecDeque::new(),
+            journal: None,
+        };
+        if let Some(path) = path.filter(|path| path.exists()) {
+            let contents = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+                // Files written before the journal hold one delivery per line
+                let entry = serde_json::from_str::<JournalEntry>(line).or_else(|e| {
+                    serde_json::from_str::<Delivery>(line)
+                        .map(JournalEntry::Queued)
+                        .map_err(|_| e)
+                });
+                match entry {
+                    Ok(entry) => queue.replay(entry),
+                    Err(e) => warn!("Skipping unreadable webhook journal entry: {}", e),
+                }
+            }
+            if !queue.pending.is_empty() {
+                info!("Resuming {} queued webhook deliveries", queue.pending.len());
+            }
+        }
+        queue.trim();
+        Ok(queue)
+    }
+
+    fn replay(&mut self, entry: JournalEntry) {
+        match entry {
+            JournalEntry::Queued(delivery) => {
+                if !self.pending.iter().any(|d| d.id == delivery.id) {
+                    self.pending.push_back(delivery);
+                }
+            }
+            JournalEntry::Retried { id, attempts } => {
+                if let Some(delivery) = self.pending.iter_mut().find(|d| d.id == id) {
+                    delivery.attempts = attempts;
+                }
+            }
+            JournalEntry::Finished { id } => self.pending.retain(|d| d.id != id),
+        }
+    }
+
+    fn push(&mut self, delivery: Delivery) {
+        self.record(JournalEntry::Queued(delivery.clone()));
+        self.pending.push_back(delivery);
+        self.trim();
+    }
+
+    /// Drop the oldest deliveries beyond capacity
+    fn trim(&mut self) {
+        while self.pending.len() > self.capacity {
+            if let Some(dropped) = self.pending.pop_front() {
+                warn!(
+                    "Webhook queue is full, dropping delivery {} to {}",
+                    dropped.id, dropped.url
+                );
+                self.record(JournalEntry::Finished { id: dropped.id });
+            }
+        }
+    }
+
+    /// The next delivery due, or when the earliest one will be
+    fn next_due(&self, now: Instant) -> Option<Result<Delivery, Instant>> {
+        let mut earliest = None;
+        for delivery in &self.pending {
+            match delivery.retry_at {
+                Some(at) if at > now => {
+                    earliest = Some(earliest.map_or(at, |earliest: Instant| earliest.min(at)));
+                }
+                _ => return Some(Ok(delivery.clone())),
+            }
+        }
+        earliest.map(Err)
+    }
+
+    fn remove(&mut self, id: Uuid) {
+        self.pending.retain(|delivery| delivery.id != id);
+        self.record(JournalEntry::Finished { id });
+    }
+
+    fn reschedule(&mut self, id: Uuid, attempts: u32, retry_at: Instant) {
+        if let Some(delivery) = self.pending.iter_mut().find(|d| d.id == id) {
+            delivery.attempts = attempts;
+            delivery.retry_at = Some(retry_at);
+        }
+        self.record(JournalEntry::Retried { id, attempts });
+    }
+
+    /// Hand the change to the journal writer; the file is never touched here
+    fn record(&self, entry: JournalEntry) {
+        if let Some(journal) = &self.journal {
+            let _ = journal.send(entry);
+        }
+    }
+}
+
+/// Rewrite the queue file with only the pending deliveries, replacing it in one
+/// step so a crash cannot truncate it
+fn write_compacted(path: &Path, pending: &[Delivery]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    for delivery in pending {
+        let line = serde_json::to_string(&JournalEntry::Queued(delivery.clone()))
+            .map_err(std::io::Error::other)?;
+        writeln!(file, "{}", line)?;
+    }
+    file.sync_all()?;
+    fs::rename(tmp_path, path)
+}
+
+fn open_journal(path: &Path) -> std::io::Result<fs::File> {
+    fs::OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Append the queue's changes to its file in batches, off the async threads, and
+/// rewrite the file from the queue once enough entries have piled up
+async fn write_journal(
+    inner: Arc<DispatcherInner>,
+    path: PathBuf,
+    mut file: fs::File,
+    mut entries: mpsc::UnboundedReceiver<JournalEntry>,
+) {
+    let mut appended = 0;
+    while let Some(entry) = entries.recv().await {
+        let mut batch = vec![entry];
+        while let Ok(entry) = entries.try_recv() {
+            batch.push(entry);
+        }
+        appended += batch.len();
+
+        let written = tokio::task::spawn_blocking(move || {
+            let result = append_entries(&mut file, &batch);
+            (file, result)
+        })
+        .await;
+        let Ok((returned, result)) = written else {
+            warn!("Webhook journal writer stopped");
+            return;
+        };
+        file = returned;
+        if let Err(e) = result {
+            warn!(
+                "Failed to append to webhook queue {}: {}",
+                path.display(),
+                e
+            );
+        }
+
+        let pending: Vec<Delivery> = {
+            let queue = inner.queue.lock().unwrap();
+            if appended < COMPACT_AFTER.max(queue.pending.len() * 2) {
+                continue;
+            }
+            queue.pending.iter().cloned().collect()
+        };
+        // Entries still in the channel are appended after the rewrite and replay
+        // harmlessly over it
+        let compact_path = path.clone();
+        let compacted = tokio::task::spawn_blocking(move || {
+            write_compacted(&compact_path, &pending)?;
+            open_journal(&compact_path)
+        })
+        .await;
+        match compacted {
+            Ok(Ok(reopened)) => {
+                file = reopened;
+                appended = 0;
+            }
+            Ok(Err(e)) => warn!("Failed to compact webhook queue {}: {}", path.display(), e),
+            Err(_) => {
+                warn!("Webhook journal writer stopped");
+                return;
+            }
+        }
+    }
+}
+
+fn append_entries(file: &mut fs::File, entries: &[JournalEntry]) -> std::io::Result<()> {
+    let mut lines = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    file.write_all(lines.as_bytes())?;
+    file.sync_data()
+}
+
+struct DispatcherInner {
+    config: WebhookConfig,
+    queue: Mutex<WebhookQueue>,
+    notify: Notify,
+}
+
+/// Queues webhook events and delivers them in the background with retries.
+/// Cheap to clone; every clone feeds the same queue.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    inner: Arc<DispatcherInner>,
+}
+
+impl std::fmt::Debug for WebhookDispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookDispatcher")
+            .field("urls", &self.inner.config.urls)
+            .finish()
+    }
+}
+
+impl WebhookDispatcher {
+    /// Load any queued deliveries and start delivering in the background
+    pub fn start(config: WebhookConfig) -> Result<Self, String> {
+        for url in &config.urls {
+            if !url.starts_with("http://") {
+                return Err(format!(
+                    "Unsupported webhook URL {}: only http:// endpoints are supported",
+                    url
+                ));
+            }
+        }
+        let mut queue = WebhookQueue::open(config.queue_path.as_deref(), config.queue_capacity)?;
+
+        // Start the journal from the replayed queue
+        let journal = match &config.queue_path {
+            Some(path) => {
+                let pending: Vec<Delivery> = queue.pending.iter().cloned().collect();
+                let file = write_compacted(path, &pending)
+                    .and_then(|()| open_journal(path))
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                let (tx, rx) = mpsc::unbounded_channel();
+                queue.journal = Some(tx);
+                Some((path.clone(), file, rx))
+            }
+            None => None,
+        };
+
+        let dispatcher = Self {
+            inner: Arc::new(DispatcherInner {
+                config,
+                queue: Mutex::new(queue),
+                notify: Notify::new(),
+            }),
+        };
+        if let Some((path, file, rx)) = journal {
+            tokio::spawn(write_journal(Arc::clone(&dispatcher.inner), path, file, rx));
+        }
+        tokio::spawn(Self::run(Arc::clone(&dispatcher.inner)));
+        Ok(dispatcher)
+    }
+
+    /// Queue `event` for every configured endpoint
+    pub fn emit(&self, event: WebhookEvent) {
+        let payload = WebhookPayload {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            event,
+        };
+        let body = match serde_json::to_string(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize webhook event: {}", e);
+                return;
+            }
+        };
+
+        let mut queue = self.inner.queue.lock().unwrap();
+        for url in &self.inner.config.urls {
+            queue.push(Delivery {
+                id: Uuid::new_v4(),
+                url: url.clone(),
+                body: body.clone(),
+                attempts: 0,
+                retry_at: None,
+            });
+        }
+        drop(queue);
+        self.inner.notify.notify_one();
+    }
+
+    /// Deliveries not yet acknowledged by their endpoint
+    pub fn pending(&self) -> usize {
+        self.inner.queue.lock().unwrap().pending.len()
+    }
+
+    async fn run(inner: Arc<DispatcherInner>) {
+        loop {
+            let next = inner.queue.lock().unwrap().next_due(Instant::now());
+            let delivery = match next {
+                Some(Ok(delivery)) => delivery,
+                Some(Err(retry_at)) => {
+                    tokio::select! {
+                        _ = inner.notify.notified() => {}
+                        _ = tokio::time::sleep_until(retry_at.into()) => {}
+                    }
+                    continue;
+                }
+                None => {
+                    inner.notify.notified().await;
+                    continue;
+                }
+            };
+
+            let result = deliver(&inner.config, &delivery).await;
+            let mut queue = inner.queue.lock().unwrap();
+            match result {
+                Ok(()) => {
+                    debug!("Delivered webhook {} to {}", delivery.id, delivery.url);
+                    queue.remove(delivery.id);
+                }
+                Err(e) => {
+                    let attempts = delivery.attempts + 1;
+                    if attempts >= inner.config.max_attempts {
+                        warn!(
+                            "Giving up on webhook {} to {} after {} attempts: {}",
+                            delivery.id, delivery.url, attempts, e
+                        );
+                        queue.remove(delivery.id);
+                    } else {
+                        let backoff = inner.config.backoff(attempts);
+                        debug!(
+                            "Webhook {} to {} failed ({}), retrying in {:?}",
+                            delivery.id, delivery.url, e, backoff
+                        );
+                        queue.reschedule(delivery.id, attempts, Instant::now() + backoff);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// POST the delivery once, succeeding on any 2xx response
+async fn deliver(config: &WebhookConfig, delivery: &Delivery) -> Result<(), String> {
+    let timestamp = Utc::now().timestamp();
+    let mut headers = vec![
+        ("X-Webhook-Id", delivery.id.to_string()),
+        ("X-Webhook-Timestamp", timestamp.to_string()),
+    ];
+    if let Some(secret) = &config.secret {
+        let signature = sign(secret, timestamp, &delivery.body);
+        headers.push(("X-Webhook-Signature", format!("sha256={}", signature)));
+    }
+
+    let status = tokio::time::timeout(
+        config.timeout,
+        post(&delivery.url, &headers, &delivery.body),
+    )
+    .await
+    .map_err(|_| "request timed out".to_string())??;
+    if (200..300).contains(&status) {
+        Ok(())
+    } else {
+        Err(format!("endpoint answered {}", status))
+    }
+}
+
+/// Minimal HTTP/1.1 POST of a JSON body, returning the response status
+async fn post(url: &str, headers: &[(&str, String)], body: &str) -> Result<u16, String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("Unsupported webhook URL {}", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let address = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+
+    let mut stream = TcpStream::connect(&address)
+        .await
+        .map_err(|e| format!("failed to connect to {}: {}", address, e))?;
+    let mut request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        path,
+        authority,
+        body.len()
+    );
+    for (name, value) in headers {
+        let _ = write!(request, "{}: {}\r\n", name, value);
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| format!("failed to send request: {}", e))?;
+
+    // Only the status line matters
+    let mut response = Vec::new();
+    let mut buf = [0u8; 512];
+    while !response.windows(2).any(|window| window == b"\r\n") {
+        let read = stream
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("failed to read response: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..read]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| "malformed response".to_string())
+}
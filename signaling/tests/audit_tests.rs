@@ -0,0 +1,259 @@
+mod common;
+
+use std::fs;
+use std::time::Duration;
+use tokio_tungstenite::connect_async;
+
+use common::{
+    create_test_participant, create_test_token, find_available_port, next_message, send, Client,
+    TEST_OFFER_SDP,
+};
+use webrtc_signaling::audit::{
+    log_file_name, AuditConfig, AuditEvent, AuditLog, AuditRecord, SignalKind,
+};
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::room::{LocalRoomManager, RoomManager, RoomManagerTrait};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+fn read_records(path: &std::path::Path) -> Vec<AuditRecord> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn left(user_id: u32) -> AuditEvent {
+    AuditEvent::Left { user_id }
+}
+
+#[test]
+fn test_record_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let audit = AuditLog::open(AuditConfig {
+        dir: Some(dir.path().to_path_buf()),
+        ..AuditConfig::default()
+    })
+    .unwrap();
+
+    audit.record_signal("standup", SignalKind::Offer, 1, Some(2), "v=0");
+    audit.record_signal("standup", SignalKind::IceCandidate, 1, None, "candidate:1");
+    audit.flush();
+
+    let contents = fs::read_to_string(dir.path().join("standup.jsonl")).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains(r#""roomName":"standup""#));
+    assert!(lines[0].contains(r#""type":"signal""#));
+    assert!(lines[0].contains(r#""kind":"offer""#));
+    assert!(lines[0].contains(r#""toUserId":2"#));
+    assert!(lines[0].contains(r#""size":3"#));
+    // Bodies stay out of the log unless enabled
+    assert!(!lines[0].contains("body"));
+    assert!(lines[1].contains(r#""kind":"ice-candidate""#));
+    assert!(!lines[1].contains("toUserId"));
+}
+
+#[test]
+fn test_includes_bodies_when_enabled() {
+    let dir = tempfile::tempdir().unwrap();
+    let audit = AuditLog::open(AuditConfig {
+        dir: Some(dir.path().to_path_buf()),
+        include_sdp: true,
+        ..AuditConfig::default()
+    })
+    .unwrap();
+
+    audit.record_signal("standup", SignalKind::Answer, 2, Some(1), "v=0");
+    audit.flush();
+    let records = read_records(&dir.path().join("standup.jsonl"));
+    assert_eq!(
+        records[0].event,
+        AuditEvent::Signal {
+            kind: SignalKind::Answer,
+            from_user_id: 2,
+            to_user_id: Some(1),
+            size: 3,
+            body: Some("v=0".to_string()),
+        }
+    );
+}
+
+#[test]
+fn test_rotates_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let audit = AuditLog::open(AuditConfig {
+        dir: Some(dir.path().to_path_buf()),
+        max_file_bytes: 200,
+        max_files: 3,
+        ..AuditConfig::default()
+    })
+    .unwrap();
+
+    for user_id in 0..20 {
+        audit.record("room/a", left(user_id));
+    }
+    audit.flush();
+
+    let current = dir.path().join(log_file_name("room/a"));
+    assert_eq!(current.file_name().unwrap(), "room_a.jsonl");
+    let mut files: Vec<String> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        vec!["room_a.jsonl", "room_a.jsonl.1", "room_a.jsonl.2"]
+    );
+
+    for name in &files {
+        let path = dir.path().join(name);
+        assert!(fs::metadata(&path).unwrap().len() <= 200);
+    }
+    // The newest events are in the current file, and the oldest were dropped
+    let newest = read_records(&current);
+    assert_eq!(newest.last().unwrap().event, left(19));
+    let oldest = read_records(&dir.path().join("room_a.jsonl.2"));
+    assert_ne!(oldest[0].event, left(0));
+}
+
+#[test]
+fn test_timeline_is_bounded() {
+    let audit = AuditLog::open(AuditConfig {
+        timeline_capacity: 3,
+        ..AuditConfig::default()
+    })
+    .unwrap();
+
+    for user_id in 1..=5 {
+        audit.record("standup", left(user_id));
+    }
+    audit.record("other", left(9));
+
+    let events: Vec<AuditEvent> = audit
+        .timeline("standup", 10)
+        .into_iter()
+        .map(|record| record.event)
+        .collect();
+    assert_eq!(events, vec![left(3), left(4), left(5)]);
+    assert_eq!(audit.timeline("standup", 1)[0].event, left(5));
+    assert!(audit.timeline("missing", 10).is_empty());
+}
+
+#[tokio::test]
+async fn test_room_manager_records_membership() {
+    let audit = AuditLog::open(AuditConfig::default()).unwrap();
+    let manager = LocalRoomManager::new().with_audit_log(audit.clone());
+
+    let (alice, _alice_rx) = create_test_participant(1, "alice");
+    manager.join_room("room".to_string(), alice).await.unwrap();
+    manager.leave_room("room", 1).await.unwrap();
+
+    let events: Vec<AuditEvent> = audit
+        .timeline("room", 10)
+        .into_iter()
+        .map(|record| record.event)
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            AuditEvent::Joined {
+                user_id: 1,
+                username: "alice".to_string(),
+            },
+            left(1),
+        ]
+    );
+}
+
+async fn connect_and_join(port: u16, token: String) -> Client {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::RoomJoined { .. }
+    ));
+    ws_stream
+}
+
+#[tokio::test]
+async fn test_server_records_signaling() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+    let dir = tempfile::tempdir().unwrap();
+    let audit = AuditLog::open(AuditConfig {
+        dir: Some(dir.path().to_path_buf()),
+        ..AuditConfig::default()
+    })
+    .unwrap();
+
+    let room_manager = RoomManager::with_implementation(Box::new(
+        LocalRoomManager::new().with_audit_log(audit.clone()),
+    ));
+    let config = ServerConfig {
+        audit: Some(audit.clone()),
+        ..ServerConfig::default()
+    };
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            room_manager,
+            config,
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut alice = connect_and_join(port, create_test_token(jwt_secret, 1, "alice")).await;
+    let mut bob = connect_and_join(port, create_test_token(jwt_secret, 2, "bob")).await;
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::UserJoined { .. }
+    ));
+
+    send(
+        &mut alice,
+        ClientMessage::Offer {
+            room_name: "room".to_string(),
+            sdp: TEST_OFFER_SDP.to_string(),
+            target_user_id: Some(2),
+        },
+    )
+    .await;
+    let relayed_size = match next_message(&mut bob).await {
+        ServerMessage::Offer { sdp, .. } => sdp.len(),
+        other => panic!("Expected offer, got {:?}", other),
+    };
+
+    let timeline = audit.timeline("room", 10);
+    assert_eq!(timeline.len(), 3);
+    assert_eq!(
+        timeline[2].event,
+        AuditEvent::Signal {
+            kind: SignalKind::Offer,
+            from_user_id: 1,
+            to_user_id: Some(2),
+            size: relayed_size,
+            body: None,
+        }
+    );
+    audit.flush();
+    assert_eq!(read_records(&dir.path().join("room.jsonl")), timeline);
+}
@@ -183,6 +183,7 @@ fn test_authenticated_user_creation() {
     let user = AuthenticatedUser {
         user_id: 42,
         username: "test_user".to_string(),
+        tenant: None,
     };
 
     assert_eq!(user.user_id, 42);
@@ -0,0 +1,338 @@
+mod common;
+
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{
+    create_test_participant, create_test_token, find_available_port, next_message, received, send,
+    Client,
+};
+use webrtc_signaling::messages::{BreakoutAssignment, ClientMessage, LeaveReason, ServerMessage};
+use webrtc_signaling::moderation::{ModerationError, ModeratorAction};
+use webrtc_signaling::room::{LocalRoomManager, RoomManager, RoomManagerTrait, RoomOwnership};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+const HOST: u32 = 1;
+
+async fn join(manager: &LocalRoomManager, user_id: u32, username: &str) -> mpsc::Receiver<Message> {
+    let (participant, rx) = create_test_participant(user_id, username);
+    manager
+        .join_room("room".to_string(), participant)
+        .await
+        .unwrap();
+    rx
+}
+
+fn assignment(name: &str, user_ids: &[u32]) -> BreakoutAssignment {
+    BreakoutAssignment {
+        name: name.to_string(),
+        user_ids: user_ids.to_vec(),
+    }
+}
+
+async fn open(
+    manager: &LocalRoomManager,
+    actor_id: u32,
+    breakouts: Vec<BreakoutAssignment>,
+) -> Result<(), ModerationError> {
+    manager
+        .moderate(
+            "room",
+            actor_id,
+            ModeratorAction::OpenBreakouts { breakouts },
+        )
+        .await
+}
+
+async fn participant_ids(manager: &LocalRoomManager, room_name: &str) -> Vec<u32> {
+    let mut user_ids: Vec<u32> = manager
+        .get_room_participants(room_name)
+        .await
+        .iter()
+        .map(|p| p.user_id)
+        .collect();
+    user_ids.sort_unstable();
+    user_ids
+}
+
+#[tokio::test]
+async fn test_open_breakouts_moves_participants() {
+    let manager = LocalRoomManager::new();
+    let mut host_rx = join(&manager, HOST, "host").await;
+    let mut alice_rx = join(&manager, 2, "alice").await;
+    let mut bob_rx = join(&manager, 3, "bob").await;
+    let mut carol_rx = join(&manager, 4, "carol").await;
+    for rx in [&mut host_rx, &mut alice_rx, &mut bob_rx, &mut carol_rx] {
+        received(rx);
+    }
+
+    open(
+        &manager,
+        HOST,
+        vec![assignment("a", &[2, 3]), assignment("b", &[4])],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(participant_ids(&manager, "room").await, vec![HOST]);
+    assert_eq!(participant_ids(&manager, "room/a").await, vec![2, 3]);
+    assert_eq!(participant_ids(&manager, "room/b").await, vec![4]);
+
+    // The moved user hears about the new room only, the parent sees them leave
+    let alice_messages = received(&mut alice_rx);
+    match alice_messages.as_slice() {
+        [ServerMessage::MovedToRoom {
+            from_room_name,
+            room_name,
+            participants,
+            ..
+        }, ServerMessage::UserJoined { user, .. }] => {
+            assert_eq!(from_room_name, "room");
+            assert_eq!(room_name, "room/a");
+            assert!(participants.is_empty());
+            assert_eq!(user.user_id, 3);
+        }
+        other => panic!("Unexpected messages for alice: {:?}", other),
+    }
+    let left: Vec<_> = received(&mut host_rx)
+        .into_iter()
+        .filter_map(|message| match message {
+            ServerMessage::UserLeft {
+                user_id, reason, ..
+            } => Some((user_id, reason)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        left,
+        vec![
+            (2, Some(LeaveReason::Moved)),
+            (3, Some(LeaveReason::Moved)),
+            (4, Some(LeaveReason::Moved)),
+        ]
+    );
+
+    // The first user moved into a breakout hosts it, the parent keeps its host
+    assert_eq!(manager.room_ownership("room/a").await.host, Some(2));
+    assert_eq!(manager.room_ownership("room").await.host, Some(HOST));
+}
+
+#[tokio::test]
+async fn test_close_breakouts_returns_everyone() {
+    let manager = LocalRoomManager::new();
+    let mut host_rx = join(&manager, HOST, "host").await;
+    let mut alice_rx = join(&manager, 2, "alice").await;
+    let _bob_rx = join(&manager, 3, "bob").await;
+    open(
+        &manager,
+        HOST,
+        vec![assignment("a", &[2]), assignment("b", &[3])],
+    )
+    .await
+    .unwrap();
+    received(&mut host_rx);
+    received(&mut alice_rx);
+
+    manager
+        .moderate("room", HOST, ModeratorAction::CloseBreakouts)
+        .await
+        .unwrap();
+
+    assert_eq!(participant_ids(&manager, "room").await, vec![HOST, 2, 3]);
+    assert!(participant_ids(&manager, "room/a").await.is_empty());
+    assert!(!manager.user_in_room("room/b", 3).await);
+
+    match received(&mut alice_rx).first() {
+        Some(ServerMessage::MovedToRoom {
+            from_room_name,
+            room_name,
+            participants,
+            host_user_id,
+            ..
+        }) => {
+            assert_eq!(from_room_name, "room/a");
+            assert_eq!(room_name, "room");
+            assert_eq!(participants.len(), 1);
+            assert_eq!(*host_user_id, Some(HOST));
+        }
+        other => panic!("Expected moved-to-room, got {:?}", other),
+    }
+    let joined = received(&mut host_rx)
+        .into_iter()
+        .filter(|message| matches!(message, ServerMessage::UserJoined { .. }))
+        .count();
+    assert_eq!(joined, 2);
+}
+
+#[tokio::test]
+async fn test_breakouts_require_host() {
+    let manager = LocalRoomManager::new();
+    let _host_rx = join(&manager, HOST, "host").await;
+    let _alice_rx = join(&manager, 2, "alice").await;
+
+    assert_eq!(
+        open(&manager, 2, vec![assignment("a", &[2])])
+            .await
+            .unwrap_err(),
+        ModerationError::NotHost
+    );
+    assert_eq!(
+        manager
+            .moderate("room", 2, ModeratorAction::CloseBreakouts)
+            .await
+            .unwrap_err(),
+        ModerationError::NotHost
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_breakouts_move_nobody() {
+    let manager = LocalRoomManager::new();
+    let _host_rx = join(&manager, HOST, "host").await;
+    let _alice_rx = join(&manager, 2, "alice").await;
+
+    assert_eq!(
+        open(&manager, HOST, vec![assignment("a", &[2, 9])])
+            .await
+            .unwrap_err(),
+        ModerationError::TargetNotFound
+    );
+    assert!(matches!(
+        open(
+            &manager,
+            HOST,
+            vec![assignment("a", &[2]), assignment("a", &[])]
+        )
+        .await,
+        Err(ModerationError::Invalid(_))
+    ));
+    assert!(matches!(
+        open(&manager, HOST, vec![assignment("a/b", &[2])]).await,
+        Err(ModerationError::Invalid(_))
+    ));
+    assert!(matches!(
+        open(
+            &manager,
+            HOST,
+            vec![assignment("a", &[2]), assignment("b", &[2])]
+        )
+        .await,
+        Err(ModerationError::Invalid(_))
+    ));
+
+    assert_eq!(participant_ids(&manager, "room").await, vec![HOST, 2]);
+}
+
+#[tokio::test]
+async fn test_parent_outlives_empty_breakouts() {
+    let manager = LocalRoomManager::new();
+    let _host_rx = join(&manager, HOST, "host").await;
+    let _alice_rx = join(&manager, 2, "alice").await;
+    open(&manager, HOST, vec![assignment("a", &[2])])
+        .await
+        .unwrap();
+
+    // The parent stays open while a breakout is still in use
+    manager.leave_room("room", HOST).await.unwrap();
+    assert_eq!(
+        manager.room_ownership("room").await,
+        RoomOwnership {
+            owner: Some(HOST),
+            host: None,
+        }
+    );
+
+    manager.leave_room("room/a", 2).await.unwrap();
+    assert_eq!(
+        manager.room_ownership("room").await,
+        RoomOwnership::default()
+    );
+}
+
+async fn connect_and_join(port: u16, token: String) -> Client {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::RoomJoined { .. }
+    ));
+    ws_stream
+}
+
+#[tokio::test]
+async fn test_breakouts_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut host = connect_and_join(port, create_test_token(jwt_secret, HOST, "host")).await;
+    let mut alice = connect_and_join(port, create_test_token(jwt_secret, 2, "alice")).await;
+    assert!(matches!(
+        next_message(&mut host).await,
+        ServerMessage::UserJoined { .. }
+    ));
+
+    send(
+        &mut host,
+        ClientMessage::CreateBreakouts {
+            room_name: "room".to_string(),
+            breakouts: vec![assignment("a", &[2])],
+        },
+    )
+    .await;
+    match next_message(&mut alice).await {
+        ServerMessage::MovedToRoom { room_name, .. } => assert_eq!(room_name, "room/a"),
+        other => panic!("Expected moved-to-room, got {:?}", other),
+    }
+    match next_message(&mut host).await {
+        ServerMessage::UserLeft {
+            user_id, reason, ..
+        } => {
+            assert_eq!(user_id, 2);
+            assert_eq!(reason, Some(LeaveReason::Moved));
+        }
+        other => panic!("Expected user-left, got {:?}", other),
+    }
+
+    send(
+        &mut host,
+        ClientMessage::CloseBreakouts {
+            room_name: "room".to_string(),
+        },
+    )
+    .await;
+    match next_message(&mut alice).await {
+        ServerMessage::MovedToRoom { room_name, .. } => assert_eq!(room_name, "room"),
+        other => panic!("Expected moved-to-room, got {:?}", other),
+    }
+    assert!(matches!(
+        next_message(&mut host).await,
+        ServerMessage::UserJoined { .. }
+    ));
+}
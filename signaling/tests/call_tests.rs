@@ -0,0 +1,304 @@
+mod common;
+
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+use common::{
+    connect_client, create_tenant_participant, find_available_port, next_message, received, send,
+};
+use webrtc_signaling::call::{CallError, CallResponse};
+use webrtc_signaling::messages::{CallEndReason, ClientMessage, ServerMessage};
+use webrtc_signaling::room::{
+    JoinError, JoinOutcome, LocalRoomManager, RoomManager, RoomManagerTrait, RoomParticipant,
+};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+const RING_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Open a connection for the user, returning it and what it receives
+async fn connect(
+    manager: &LocalRoomManager,
+    user_id: u32,
+    tenant: Option<&str>,
+) -> (RoomParticipant, mpsc::Receiver<Message>) {
+    let (participant, rx) = create_tenant_participant(user_id, tenant);
+    manager.connect_user(participant.clone()).await;
+    (participant, rx)
+}
+
+#[tokio::test]
+async fn test_ring_reaches_every_connection_of_the_callee() {
+    let manager = LocalRoomManager::new();
+    let (caller, mut caller_rx) = connect(&manager, 1, None).await;
+    let (_, mut phone_rx) = connect(&manager, 2, None).await;
+    let (_, mut laptop_rx) = connect(&manager, 2, None).await;
+
+    let invitation = manager.invite(&caller.user, 2, RING_TIMEOUT).await.unwrap();
+    for rx in [&mut phone_rx, &mut laptop_rx] {
+        assert!(matches!(
+            received(rx).as_slice(),
+            [ServerMessage::IncomingCall { call_id, from }]
+                if *call_id == invitation.call_id && from.user_id == 1
+        ));
+    }
+    assert!(received(&mut caller_rx).is_empty());
+}
+
+#[tokio::test]
+async fn test_calls_to_unavailable_users_are_refused() {
+    let manager = LocalRoomManager::new();
+    let (caller, _caller_rx) = connect(&manager, 1, Some("acme")).await;
+    let (other_tenant, _other_rx) = connect(&manager, 2, Some("globex")).await;
+
+    assert_eq!(
+        manager
+            .invite(&caller.user, 1, RING_TIMEOUT)
+            .await
+            .unwrap_err(),
+        CallError::SelfCall
+    );
+    // The same user id in another tenant is someone else
+    assert_eq!(
+        manager
+            .invite(&caller.user, 2, RING_TIMEOUT)
+            .await
+            .unwrap_err(),
+        CallError::UserOffline
+    );
+
+    manager.disconnect_user(other_tenant.connection_id).await;
+    let (_, _callee_rx) = connect(&manager, 2, Some("acme")).await;
+    assert!(manager.invite(&caller.user, 2, RING_TIMEOUT).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_accepted_call_opens_a_private_room() {
+    let manager = LocalRoomManager::new();
+    let (caller, mut caller_rx) = connect(&manager, 1, None).await;
+    let (callee, mut phone_rx) = connect(&manager, 2, None).await;
+    let (_, mut laptop_rx) = connect(&manager, 2, None).await;
+    let (intruder, _intruder_rx) = connect(&manager, 3, None).await;
+
+    let invitation = manager.invite(&caller.user, 2, RING_TIMEOUT).await.unwrap();
+    let call_id = invitation.call_id;
+    // Only the callee may answer
+    assert_eq!(
+        manager
+            .respond_to_call(&intruder.user, call_id, CallResponse::Accept)
+            .await,
+        Err(CallError::NotFound)
+    );
+    assert_eq!(
+        manager
+            .respond_to_call(&caller.user, call_id, CallResponse::Accept)
+            .await,
+        Err(CallError::NotFound)
+    );
+
+    manager
+        .respond_to_call(&callee.user, call_id, CallResponse::Accept)
+        .await
+        .unwrap();
+    let room_name = invitation.room_name();
+    for rx in [&mut caller_rx, &mut laptop_rx] {
+        assert!(matches!(
+            received(rx).as_slice(),
+            [.., ServerMessage::CallAccepted { room_name: name, by_user_id: 2, .. }]
+                if *name == room_name
+        ));
+    }
+    assert!(matches!(
+        received(&mut phone_rx).last(),
+        Some(ServerMessage::CallAccepted { .. })
+    ));
+
+    // The call is over once answered
+    assert_eq!(
+        manager
+            .respond_to_call(&callee.user, call_id, CallResponse::Decline)
+            .await,
+        Err(CallError::NotFound)
+    );
+
+    assert_eq!(
+        manager
+            .join_room_with(room_name.clone(), intruder, Default::default())
+            .await
+            .unwrap_err(),
+        JoinError::NotInvited
+    );
+    let outcome = manager
+        .join_room_with(room_name.clone(), caller, Default::default())
+        .await
+        .unwrap();
+    assert!(matches!(outcome, JoinOutcome::Joined(participants) if participants.is_empty()));
+    let outcome = manager
+        .join_room_with(room_name, callee, Default::default())
+        .await
+        .unwrap();
+    assert!(matches!(outcome, JoinOutcome::Joined(participants) if participants.len() == 1));
+}
+
+#[tokio::test]
+async fn test_declined_and_cancelled_calls_end_for_both_users() {
+    let manager = LocalRoomManager::new();
+    let (caller, mut caller_rx) = connect(&manager, 1, None).await;
+    let (callee, mut callee_rx) = connect(&manager, 2, None).await;
+
+    let declined = manager.invite(&caller.user, 2, RING_TIMEOUT).await.unwrap();
+    manager
+        .respond_to_call(&callee.user, declined.call_id, CallResponse::Decline)
+        .await
+        .unwrap();
+    let cancelled = manager.invite(&caller.user, 2, RING_TIMEOUT).await.unwrap();
+    // Only the caller may cancel
+    assert_eq!(
+        manager
+            .respond_to_call(&callee.user, cancelled.call_id, CallResponse::Cancel)
+            .await,
+        Err(CallError::NotFound)
+    );
+    manager
+        .respond_to_call(&caller.user, cancelled.call_id, CallResponse::Cancel)
+        .await
+        .unwrap();
+
+    let ended = |messages: Vec<ServerMessage>| -> Vec<(Uuid, CallEndReason)> {
+        messages
+            .into_iter()
+            .filter_map(|message| match message {
+                ServerMessage::CallEnded { call_id, reason } => Some((call_id, reason)),
+                _ => None,
+            })
+            .collect()
+    };
+    let expected = vec![
+        (declined.call_id, CallEndReason::Declined),
+        (cancelled.call_id, CallEndReason::Cancelled),
+    ];
+    assert_eq!(ended(received(&mut caller_rx)), expected);
+    assert_eq!(ended(received(&mut callee_rx)), expected);
+    assert!(manager
+        .get_rooms()
+        .read()
+        .await
+        .get(&declined.room_name())
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_unanswered_call_times_out() {
+    let manager = LocalRoomManager::new();
+    let (caller, mut caller_rx) = connect(&manager, 1, None).await;
+    let (callee, mut callee_rx) = connect(&manager, 2, None).await;
+
+    let invitation = manager
+        .invite(&caller.user, 2, Duration::from_millis(50))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(150)).await;
+
+    for rx in [&mut caller_rx, &mut callee_rx] {
+        assert!(matches!(
+            received(rx).last(),
+            Some(ServerMessage::CallEnded { call_id, reason: CallEndReason::Timeout })
+                if *call_id == invitation.call_id
+        ));
+    }
+    assert_eq!(
+        manager
+            .respond_to_call(&callee.user, invitation.call_id, CallResponse::Accept)
+            .await,
+        Err(CallError::NotFound)
+    );
+}
+
+fn create_test_token(secret: &str, user_id: u32, username: &str) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+
+    let claims = serde_json::json!({
+        "sub": user_id,
+        "username": username,
+        "iat": now,
+        "exp": now + 3600,
+        "tenant": "acme",
+    });
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_call_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut alice = connect_client(port, create_test_token(jwt_secret, 1, "alice")).await;
+    let mut bob = connect_client(port, create_test_token(jwt_secret, 2, "bob")).await;
+
+    send(&mut alice, ClientMessage::Ring { target_user_id: 3 }).await;
+    match next_message(&mut alice).await {
+        ServerMessage::Error { code, .. } => assert_eq!(code, Some(404)),
+        other => panic!("Expected error, got {:?}", other),
+    }
+
+    send(&mut alice, ClientMessage::Ring { target_user_id: 2 }).await;
+    let ServerMessage::Ringing { call_id, .. } = next_message(&mut alice).await else {
+        panic!("Expected ringing");
+    };
+    assert!(matches!(
+        next_message(&mut bob).await,
+        ServerMessage::IncomingCall { call_id: id, from } if id == call_id && from.username == "alice"
+    ));
+
+    send(&mut bob, ClientMessage::AcceptCall { call_id }).await;
+    let expected_room = format!("call-{}", call_id);
+    for client in [&mut alice, &mut bob] {
+        // The tenant prefix never reaches the clients
+        match next_message(client).await {
+            ServerMessage::CallAccepted { room_name, .. } => assert_eq!(room_name, expected_room),
+            other => panic!("Expected call-accepted, got {:?}", other),
+        }
+    }
+
+    for client in [&mut alice, &mut bob] {
+        send(
+            client,
+            ClientMessage::JoinRoom {
+                room_name: expected_room.clone(),
+                password: None,
+            },
+        )
+        .await;
+        assert!(matches!(
+            next_message(client).await,
+            ServerMessage::RoomJoined { .. }
+        ));
+    }
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::UserJoined { user, .. } if user.user_id == 2
+    ));
+}
@@ -0,0 +1,206 @@
+use webrtc_signaling::candidate::{
+    Candidate, CandidateFilterStats, CandidatePolicy, CandidateType, Cidr, FilterReason,
+    TenantCandidateStats,
+};
+use webrtc_signaling::room_config::RoomConfigStore;
+use webrtc_signaling::sdp::{SdpLimits, SessionDescription};
+
+const HOST_IP: &str = "candidate:1 1 udp 2122260223 192.168.1.20 54321 typ host generation 0";
+const HOST_MDNS: &str =
+    "candidate:2 1 udp 2122260223 4f1c2a9e-7d6b-4a11-9c3e-0f6a1b2c3d4e.local 54321 typ host";
+const SRFLX: &str =
+    "candidate:3 1 udp 1686052607 203.0.113.7 61000 typ srflx raddr 192.168.1.20 rport 54321";
+const RELAY: &str =
+    "candidate:4 1 udp 41885439 198.51.100.9 3478 typ relay raddr 203.0.113.7 rport 61000";
+
+fn relay_only() -> CandidatePolicy {
+    CandidatePolicy {
+        relay_only: true,
+        ..CandidatePolicy::default()
+    }
+}
+
+#[test]
+fn test_parse_candidate() {
+    let candidate = Candidate::parse(SRFLX).unwrap();
+    assert_eq!(candidate.foundation, "3");
+    assert_eq!(candidate.component, 1);
+    assert_eq!(candidate.transport, "udp");
+    assert_eq!(candidate.address, "203.0.113.7");
+    assert_eq!(candidate.port, 61000);
+    assert_eq!(candidate.candidate_type, CandidateType::ServerReflexive);
+
+    assert!(Candidate::parse(&format!("a={}", HOST_MDNS))
+        .unwrap()
+        .is_mdns());
+    assert!(Candidate::parse("candidate:garbage").is_none());
+}
+
+#[test]
+fn test_empty_policy_passes_everything() {
+    let policy = CandidatePolicy::default();
+    assert_eq!(policy.apply(HOST_IP), Ok(HOST_IP.to_string()));
+    assert_eq!(
+        policy.apply("not a candidate"),
+        Ok("not a candidate".to_string())
+    );
+}
+
+#[test]
+fn test_relay_only_policy() {
+    let policy = relay_only();
+
+    assert_eq!(policy.apply(HOST_IP), Err(FilterReason::NotRelay));
+    assert_eq!(policy.apply(SRFLX), Err(FilterReason::NotRelay));
+    assert_eq!(
+        policy.apply(RELAY),
+        Ok(
+            "candidate:4 1 udp 41885439 198.51.100.9 3478 typ relay raddr 0.0.0.0 rport 0"
+                .to_string()
+        )
+    );
+    // End-of-candidates marker is always forwarded
+    assert_eq!(policy.apply(""), Ok(String::new()));
+    assert_eq!(
+        policy.apply("candidate:bogus"),
+        Err(FilterReason::Malformed)
+    );
+}
+
+#[test]
+fn test_drop_host_candidates_keeps_mdns() {
+    let policy = CandidatePolicy {
+        drop_host_candidates: true,
+        ..CandidatePolicy::default()
+    };
+
+    assert_eq!(policy.apply(HOST_IP), Err(FilterReason::HostAddress));
+    assert_eq!(policy.apply(HOST_MDNS), Ok(HOST_MDNS.to_string()));
+    // The reflexive candidate survives but no longer reveals the local address
+    let srflx = policy.apply(SRFLX).unwrap();
+    assert!(srflx.ends_with("raddr 0.0.0.0 rport 0"));
+    assert!(!srflx.contains("192.168.1.20"));
+}
+
+#[test]
+fn test_cidr_allow_and_deny_lists() {
+    let policy = CandidatePolicy {
+        allow_cidrs: vec!["198.51.100.0/24".parse().unwrap()],
+        ..CandidatePolicy::default()
+    };
+    assert!(policy.apply(RELAY).is_ok());
+    assert_eq!(policy.apply(SRFLX), Err(FilterReason::AddressBlocked));
+    assert_eq!(policy.apply(HOST_MDNS), Err(FilterReason::AddressBlocked));
+
+    let policy = CandidatePolicy {
+        deny_cidrs: vec![
+            "192.168.0.0/16".parse().unwrap(),
+            "fd00::/8".parse().unwrap(),
+        ],
+        ..CandidatePolicy::default()
+    };
+    assert_eq!(policy.apply(HOST_IP), Err(FilterReason::AddressBlocked));
+    assert!(policy.apply(SRFLX).is_ok());
+    assert_eq!(
+        policy.apply("candidate:5 1 udp 2122260223 fd12:3456::1 5000 typ host"),
+        Err(FilterReason::AddressBlocked)
+    );
+}
+
+#[test]
+fn test_cidr_parsing() {
+    let cidr: Cidr = "10.0.0.0/8".parse().unwrap();
+    assert!(cidr.contains(&"10.20.30.40".parse().unwrap()));
+    assert!(!cidr.contains(&"11.0.0.1".parse().unwrap()));
+    assert!(!cidr.contains(&"::1".parse().unwrap()));
+
+    let any: Cidr = "0.0.0.0/0".parse().unwrap();
+    assert!(any.contains(&"8.8.8.8".parse().unwrap()));
+
+    let single: Cidr = "203.0.113.7".parse().unwrap();
+    assert_eq!(single.to_string(), "203.0.113.7/32");
+
+    assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+    assert!("example.com/8".parse::<Cidr>().is_err());
+}
+
+#[test]
+fn test_filter_stats() {
+    let policy = relay_only();
+    let stats = CandidateFilterStats::default();
+
+    for candidate in [HOST_IP, SRFLX, RELAY, "candidate:bogus"] {
+        stats.record(&policy.apply(candidate));
+    }
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.inspected, 4);
+    assert_eq!(snapshot.not_relay, 2);
+    assert_eq!(snapshot.malformed, 1);
+    assert_eq!(snapshot.dropped(), 3);
+}
+
+#[test]
+fn test_filter_stats_are_kept_per_tenant() {
+    let policy = relay_only();
+    let stats = TenantCandidateStats::default();
+
+    stats.for_room("acme::standup").record(&policy.apply(HOST_IP));
+    stats.for_room("acme::retro").record(&policy.apply(RELAY));
+    stats.for_room("standup").record(&policy.apply(SRFLX));
+
+    let acme = stats.snapshot(Some("acme"));
+    assert_eq!(acme.inspected, 2);
+    assert_eq!(acme.not_relay, 1);
+    assert_eq!(stats.snapshot(None).inspected, 1);
+    assert_eq!(stats.snapshot(Some("globex")).inspected, 0);
+}
+
+#[test]
+fn test_filter_candidates_embedded_in_sdp() {
+    let offer = format!(
+        "v=0\r\no=- 1 2 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\n\
+         m=audio 54321 UDP/TLS/RTP/SAVPF 111\r\nc=IN IP4 203.0.113.7\r\n\
+         a=rtpmap:111 opus/48000/2\r\na={}\r\na={}\r\na={}\r\na=end-of-candidates\r\n",
+        HOST_IP, SRFLX, RELAY
+    );
+    let mut description = SessionDescription::parse(&offer, &SdpLimits::default()).unwrap();
+    let stats = CandidateFilterStats::default();
+
+    relay_only().filter_description(&mut description, &stats);
+
+    let filtered = description.to_string();
+    assert!(!filtered.contains("typ host"));
+    assert!(!filtered.contains("typ srflx"));
+    assert!(filtered.contains("a=candidate:4 1 udp 41885439 198.51.100.9 3478 typ relay"));
+    assert!(filtered.contains("a=end-of-candidates\r\n"));
+    assert_eq!(stats.snapshot().not_relay, 2);
+}
+
+#[tokio::test]
+async fn test_candidate_policy_from_room_config() {
+    let store = RoomConfigStore::from_json(
+        r#"{
+            "rooms": {
+                "private": {
+                    "candidatePolicy": {
+                        "dropHostCandidates": true,
+                        "denyCidrs": ["10.0.0.0/8", "fc00::/7"]
+                    }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let policy = store.get("private").await.candidate_policy;
+    assert!(policy.drop_host_candidates);
+    assert!(!policy.relay_only);
+    assert_eq!(policy.deny_cidrs.len(), 2);
+    assert!(store.get("public").await.candidate_policy.is_empty());
+
+    let invalid = RoomConfigStore::from_json(
+        r#"{ "default": { "candidatePolicy": { "allowCidrs": ["10.0.0.0/99"] } } }"#,
+    );
+    assert!(invalid.is_err());
+}
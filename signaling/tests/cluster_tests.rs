@@ -1,26 +1,29 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::RwLock;
 use tokio::time::timeout;
 use tokio_tungstenite::tungstenite::Message;
 use uuid::Uuid;
 
 use webrtc_signaling::auth::AuthenticatedUser;
 use webrtc_signaling::cluster::{ClusterMessage, ConnectionInfo};
-use webrtc_signaling::messages::{Participant, ServerMessage};
-use webrtc_signaling::room::{LocalRoomManager, RoomManagerTrait, RoomParticipant};
+use webrtc_signaling::messages::{DeliveryFailure, Participant, ServerMessage};
+use webrtc_signaling::room::{
+    outbound_channel, LocalRoomManager, RoomManagerTrait, RoomParticipant,
+};
 
 // Test utilities
 fn create_test_user(user_id: u32, username: &str) -> AuthenticatedUser {
     AuthenticatedUser {
         user_id,
         username: username.to_string(),
+        tenant: None,
     }
 }
 
 fn create_test_participant(user_id: u32, username: &str) -> RoomParticipant {
-    let (tx, _rx) = mpsc::unbounded_channel::<Message>();
+    let (tx, _rx) = outbound_channel();
     RoomParticipant {
         user: create_test_user(user_id, username),
         connection_id: Uuid::new_v4(),
@@ -30,6 +33,7 @@ fn create_test_participant(user_id: u32, username: &str) -> RoomParticipant {
 
 // Mock Redis for testing
 struct MockRedisClient {
+    #[allow(dead_code)]
     data: Arc<RwLock<HashMap<String, String>>>,
     hash_data: Arc<RwLock<HashMap<String, HashMap<String, String>>>>,
     pub_sub_messages: Arc<RwLock<Vec<(String, String)>>>,
@@ -149,6 +153,10 @@ fn test_webrtc_signal_message_serialization() {
         to_user: 1002,
         signal_type: "offer".to_string(),
         signal_data: "v=0\r\no=alice...".to_string(),
+        sdp_mid: None,
+        sdp_mline_index: None,
+        target_server: Some("server-2".to_string()),
+        origin_server: Some("server-1".to_string()),
     };
 
     let json = serde_json::to_string(&webrtc_signal).unwrap();
@@ -161,12 +169,44 @@ fn test_webrtc_signal_message_serialization() {
             to_user,
             signal_type,
             signal_data,
+            target_server,
+            origin_server,
+            ..
         } => {
             assert_eq!(room_id, "room123");
             assert_eq!(from_user, 1001);
             assert_eq!(to_user, 1002);
             assert_eq!(signal_type, "offer");
             assert_eq!(signal_data, "v=0\r\no=alice...");
+            assert_eq!(target_server.as_deref(), Some("server-2"));
+            assert_eq!(origin_server.as_deref(), Some("server-1"));
+        }
+        _ => panic!("Wrong message type deserialized"),
+    }
+}
+
+#[test]
+fn test_signal_undelivered_message_serialization() {
+    let undelivered = ClusterMessage::SignalUndelivered {
+        room_id: "room123".to_string(),
+        from_user: 1001,
+        to_user: 1002,
+        signal_type: "offer".to_string(),
+        reason: DeliveryFailure::RecipientGone,
+        target_server: "server-1".to_string(),
+    };
+
+    let json = serde_json::to_string(&undelivered).unwrap();
+    match serde_json::from_str(&json).unwrap() {
+        ClusterMessage::SignalUndelivered {
+            to_user,
+            reason,
+            target_server,
+            ..
+        } => {
+            assert_eq!(to_user, 1002);
+            assert_eq!(reason, DeliveryFailure::RecipientGone);
+            assert_eq!(target_server, "server-1");
         }
         _ => panic!("Wrong message type deserialized"),
     }
@@ -294,6 +334,10 @@ async fn test_cluster_message_routing_simulation() {
         to_user: 1002,
         signal_type: "offer".to_string(),
         signal_data: "v=0\r\no=alice...".to_string(),
+        sdp_mid: None,
+        sdp_mline_index: None,
+        target_server: None,
+        origin_server: None,
     };
 
     // Simulate message routing via Redis pub/sub
@@ -380,6 +424,7 @@ async fn test_cluster_user_join_leave_simulation() {
         room_id: "room123".to_string(),
         user_id: 1001,
         target_server: None,
+        reason: None,
     };
     let leave_json = serde_json::to_string(&leave_message).unwrap();
     mock_redis
@@ -424,6 +469,7 @@ async fn test_cluster_failure_recovery_simulation() {
             room_id: "room123".to_string(),
             user_id,
             target_server: None,
+            reason: None,
         };
         let leave_json = serde_json::to_string(&leave_message).unwrap();
         mock_redis
@@ -539,7 +585,7 @@ async fn test_message_broadcast_simulation() {
     // Create multiple test participants with message receivers
     let mut receivers = Vec::new();
     for i in 1..=3 {
-        let (tx, rx) = mpsc::unbounded_channel::<Message>();
+        let (tx, rx) = outbound_channel();
         let participant = RoomParticipant {
             user: create_test_user(i, &format!("user{}", i)),
             connection_id: Uuid::new_v4(),
@@ -563,8 +609,8 @@ async fn test_message_broadcast_simulation() {
         let websocket_message = Message::Text(json_message);
         let connections = local_connections.read().await;
 
-        for (user_id, participant) in connections.iter() {
-            let _ = participant.sender.send(websocket_message.clone());
+        for participant in connections.values() {
+            let _ = participant.sender.try_send(websocket_message.clone());
         }
     }
 
@@ -615,10 +661,9 @@ fn test_cluster_heartbeat_message() {
 }
 
 // Integration test helpers
-fn create_mock_cluster_environment() -> (
-    MockRedisClient,
-    Vec<Arc<RwLock<HashMap<u32, RoomParticipant>>>>,
-) {
+type ServerConnections = Arc<RwLock<HashMap<u32, RoomParticipant>>>;
+
+fn create_mock_cluster_environment() -> (MockRedisClient, Vec<ServerConnections>) {
     let mock_redis = MockRedisClient::new();
     let mut server_connections = Vec::new();
 
@@ -672,6 +717,10 @@ async fn test_full_cluster_simulation() {
         to_user: 1002,
         signal_type: "offer".to_string(),
         signal_data: "sdp_offer_data".to_string(),
+        sdp_mid: None,
+        sdp_mline_index: None,
+        target_server: None,
+        origin_server: None,
     };
 
     let signal_json = serde_json::to_string(&webrtc_signal).unwrap();
@@ -711,6 +760,7 @@ async fn test_full_cluster_simulation() {
         room_id: "room123".to_string(),
         user_id: 1003,
         target_server: None,
+        reason: None,
     };
     let leave_json = serde_json::to_string(&leave_message).unwrap();
     mock_redis.clear_published_messages().await;
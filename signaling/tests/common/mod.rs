@@ -0,0 +1,117 @@
+//! Helpers shared by the integration tests. Each test crate uses only some of them.
+#![allow(dead_code)]
+
+use futures_util::{SinkExt, StreamExt};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+use webrtc_signaling::auth::AuthenticatedUser;
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::room::{outbound_channel, RoomParticipant};
+
+pub type Client = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+pub const TEST_OFFER_SDP: &str = "v=0\r\no=- 4611731400430051336 2 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\nc=IN IP4 0.0.0.0\r\na=mid:0\r\na=sendrecv\r\na=rtpmap:111 opus/48000/2\r\n";
+
+pub fn create_test_participant(
+    user_id: u32,
+    username: &str,
+) -> (RoomParticipant, mpsc::Receiver<Message>) {
+    let (tx, rx) = outbound_channel();
+    let participant = RoomParticipant {
+        user: AuthenticatedUser {
+            user_id,
+            username: username.to_string(),
+            tenant: None,
+        },
+        connection_id: Uuid::new_v4(),
+        sender: tx,
+    };
+    (participant, rx)
+}
+
+/// A participant named `user{id}` belonging to `tenant`
+pub fn create_tenant_participant(
+    user_id: u32,
+    tenant: Option<&str>,
+) -> (RoomParticipant, mpsc::Receiver<Message>) {
+    let (tx, rx) = outbound_channel();
+    let participant = RoomParticipant {
+        user: AuthenticatedUser {
+            user_id,
+            username: format!("user{}", user_id),
+            tenant: tenant.map(str::to_string),
+        },
+        connection_id: Uuid::new_v4(),
+        sender: tx,
+    };
+    (participant, rx)
+}
+
+/// Drain the messages sent to a participant so far
+pub fn received(rx: &mut mpsc::Receiver<Message>) -> Vec<ServerMessage> {
+    let mut messages = Vec::new();
+    while let Ok(Message::Text(text)) = rx.try_recv() {
+        messages.push(serde_json::from_str(&text).unwrap());
+    }
+    messages
+}
+
+pub fn create_test_token(secret: &str, user_id: u32, username: &str) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+
+    let claims = serde_json::json!({
+        "sub": user_id,
+        "username": username,
+        "iat": now,
+        "exp": now + 3600,
+    });
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap()
+}
+
+pub async fn find_available_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+    port
+}
+
+pub async fn send(ws_stream: &mut Client, msg: ClientMessage) {
+    let text = serde_json::to_string(&msg).unwrap();
+    ws_stream.send(Message::Text(text)).await.unwrap();
+}
+
+pub async fn next_message(ws_stream: &mut Client) -> ServerMessage {
+    let msg = tokio::time::timeout(Duration::from_secs(2), ws_stream.next())
+        .await
+        .expect("Timed out waiting for message")
+        .unwrap()
+        .unwrap();
+    serde_json::from_str(msg.to_text().unwrap()).unwrap()
+}
+
+/// Connect to the server on `port` and authenticate with `token`
+pub async fn connect_client(port: u16, token: String) -> Client {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    ws_stream
+}
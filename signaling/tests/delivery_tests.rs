@@ -0,0 +1,185 @@
+mod common;
+
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use common::{
+    connect_client, create_test_participant, create_test_token, find_available_port, next_message,
+    send, TEST_OFFER_SDP,
+};
+use webrtc_signaling::messages::{ClientMessage, DeliveryFailure, ServerMessage};
+use webrtc_signaling::room::{
+    DeliveryError, LocalRoomManager, RoomManager, RoomManagerTrait, RoomParticipant,
+};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+fn offer(from_user_id: u32) -> ServerMessage {
+    ServerMessage::Offer {
+        room_name: "room".to_string(),
+        from_user_id,
+        sdp: TEST_OFFER_SDP.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_targeted_sends_report_why_they_failed() {
+    let manager = LocalRoomManager::new();
+    let (alice, _alice_rx) = create_test_participant(1, "alice");
+    let (bob, bob_rx) = create_test_participant(2, "bob");
+    for participant in [alice, bob] {
+        manager
+            .join_room_with("room".to_string(), participant, Default::default())
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        manager.send_to_user_in_room("room", 2, offer(1)).await,
+        Ok(())
+    );
+    assert_eq!(
+        manager.send_to_user_in_room("room", 3, offer(1)).await,
+        Err(DeliveryError::Undelivered(
+            DeliveryFailure::UnknownRecipient
+        ))
+    );
+    assert_eq!(
+        manager.send_to_user_in_room("elsewhere", 2, offer(1)).await,
+        Err(DeliveryError::Undelivered(
+            DeliveryFailure::UnknownRecipient
+        ))
+    );
+
+    // Bob's connection is gone before the server noticed and removed him
+    drop(bob_rx);
+    assert_eq!(
+        manager.send_to_user_in_room("room", 2, offer(1)).await,
+        Err(DeliveryError::Undelivered(DeliveryFailure::RecipientGone))
+    );
+}
+
+#[tokio::test]
+async fn test_signals_to_a_backed_up_connection_report_a_full_queue() {
+    let manager = LocalRoomManager::new();
+    let (alice, _alice_rx) = create_test_participant(1, "alice");
+    // Bob's connection stops draining once one message is waiting
+    let (tx, mut bob_rx) = mpsc::channel::<Message>(1);
+    let bob = RoomParticipant {
+        sender: tx,
+        ..create_test_participant(2, "bob").0
+    };
+    for participant in [alice, bob] {
+        manager
+            .join_room_with("room".to_string(), participant, Default::default())
+            .await
+            .unwrap();
+    }
+    while bob_rx.try_recv().is_ok() {}
+
+    assert_eq!(
+        manager.send_to_user_in_room("room", 2, offer(1)).await,
+        Ok(())
+    );
+    assert_eq!(
+        manager.send_to_user_in_room("room", 2, offer(1)).await,
+        Err(DeliveryError::Undelivered(DeliveryFailure::QueueFull))
+    );
+
+    // Once the connection catches up, signals get through again
+    bob_rx.try_recv().unwrap();
+    assert_eq!(
+        manager.send_to_user_in_room("room", 2, offer(1)).await,
+        Ok(())
+    );
+}
+
+#[tokio::test]
+async fn test_signals_to_departed_users_report_them_gone() {
+    let manager = LocalRoomManager::new();
+    let (alice, _alice_rx) = create_test_participant(1, "alice");
+    let (bob, _bob_rx) = create_test_participant(2, "bob");
+    for participant in [alice, bob] {
+        manager
+            .join_room_with("room".to_string(), participant, Default::default())
+            .await
+            .unwrap();
+    }
+
+    manager.leave_room("room", 2).await.unwrap();
+    assert_eq!(
+        manager.send_to_user_in_room("room", 2, offer(1)).await,
+        Err(DeliveryError::Undelivered(DeliveryFailure::RecipientGone))
+    );
+    assert_eq!(
+        manager.send_to_user_in_room("room", 3, offer(1)).await,
+        Err(DeliveryError::Undelivered(
+            DeliveryFailure::UnknownRecipient
+        ))
+    );
+
+    // Rejoining makes Bob reachable again
+    let (bob, _bob_rx) = create_test_participant(2, "bob");
+    manager.join_room("room".to_string(), bob).await.unwrap();
+    assert_eq!(
+        manager.send_to_user_in_room("room", 2, offer(1)).await,
+        Ok(())
+    );
+}
+
+#[tokio::test]
+async fn test_delivery_failed_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut alice = connect_client(port, create_test_token(jwt_secret, 1, "alice")).await;
+    send(
+        &mut alice,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::RoomJoined { .. }
+    ));
+
+    // The peer left before the offer arrived
+    send(
+        &mut alice,
+        ClientMessage::Offer {
+            room_name: "room".to_string(),
+            target_user_id: Some(2),
+            sdp: TEST_OFFER_SDP.to_string(),
+        },
+    )
+    .await;
+    match next_message(&mut alice).await {
+        ServerMessage::DeliveryFailed {
+            room_name,
+            target_user_id,
+            message_type,
+            reason,
+        } => {
+            assert_eq!(room_name, "room");
+            assert_eq!(target_user_id, 2);
+            assert_eq!(message_type, "offer");
+            assert_eq!(reason, DeliveryFailure::UnknownRecipient);
+        }
+        other => panic!("Expected delivery-failed, got {:?}", other),
+    }
+}
@@ -0,0 +1,282 @@
+mod common;
+
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use common::{
+    connect_client, create_tenant_participant, find_available_port, next_message, received, send,
+};
+use webrtc_signaling::call::CallResponse;
+use webrtc_signaling::messages::{ClientMessage, RoomSummary, ServerMessage};
+use webrtc_signaling::moderation::{ModeratorAction, RoomRole};
+use webrtc_signaling::room::{JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait};
+use webrtc_signaling::room_config::{RoomConfig, RoomVisibility};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+async fn join(
+    manager: &LocalRoomManager,
+    room_name: &str,
+    user_id: u32,
+    tenant: Option<&str>,
+    role: RoomRole,
+) -> mpsc::Receiver<Message> {
+    let (participant, rx) = create_tenant_participant(user_id, tenant);
+    let request = JoinRequest {
+        role,
+        ..JoinRequest::default()
+    };
+    manager
+        .join_room_with(room_name.to_string(), participant, request)
+        .await
+        .unwrap();
+    rx
+}
+
+fn updates(rx: &mut mpsc::Receiver<Message>) -> Vec<RoomSummary> {
+    received(rx)
+        .into_iter()
+        .filter_map(|message| match message {
+            ServerMessage::RoomUpdated { room } => Some(room),
+            _ => None,
+        })
+        .collect()
+}
+
+fn names(rooms: &[RoomSummary]) -> Vec<&str> {
+    rooms.iter().map(|room| room.room_name.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_list_rooms_shows_live_listed_rooms_of_the_tenant() {
+    let manager = LocalRoomManager::new();
+    let unlisted = RoomConfig {
+        visibility: RoomVisibility::Unlisted,
+        ..RoomConfig::default()
+    };
+    manager.room_configs().set("acme::hidden", unlisted).await;
+
+    let _standup = join(
+        &manager,
+        "acme::standup",
+        1,
+        Some("acme"),
+        RoomRole::Participant,
+    )
+    .await;
+    let _viewer = join(
+        &manager,
+        "acme::standup",
+        2,
+        Some("acme"),
+        RoomRole::Spectator,
+    )
+    .await;
+    let _hidden = join(
+        &manager,
+        "acme::hidden",
+        3,
+        Some("acme"),
+        RoomRole::Participant,
+    )
+    .await;
+    let _other = join(
+        &manager,
+        "globex::standup",
+        1,
+        Some("globex"),
+        RoomRole::Participant,
+    )
+    .await;
+    let _open = join(&manager, "lobby", 4, None, RoomRole::Participant).await;
+
+    // Rooms of an accepted call are private
+    let (caller, _caller_rx) = create_tenant_participant(5, Some("acme"));
+    let (callee, _callee_rx) = create_tenant_participant(6, Some("acme"));
+    manager.connect_user(callee.clone()).await;
+    let invitation = manager
+        .invite(&caller.user, 6, Duration::from_secs(30))
+        .await
+        .unwrap();
+    manager
+        .respond_to_call(&callee.user, invitation.call_id, CallResponse::Accept)
+        .await
+        .unwrap();
+    manager
+        .join_room_with(invitation.room_name(), caller, JoinRequest::default())
+        .await
+        .unwrap();
+
+    let rooms = manager.list_rooms(Some("acme")).await;
+    assert_eq!(names(&rooms), ["acme::standup"]);
+    assert_eq!(rooms[0].participant_count, 2);
+    assert_eq!(rooms[0].spectator_count, 1);
+    // Spectators are counted but not named
+    assert_eq!(rooms[0].participants.len(), 1);
+    assert_eq!(rooms[0].participants[0].user_id, 1);
+
+    assert_eq!(
+        names(&manager.list_rooms(Some("globex")).await),
+        ["globex::standup"]
+    );
+    assert_eq!(names(&manager.list_rooms(None).await), ["lobby"]);
+
+    // Empty rooms are not live, even when kept around
+    manager.leave_room("lobby", 4).await.unwrap();
+    assert!(manager.list_rooms(None).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_subscribers_receive_room_updates() {
+    let manager = LocalRoomManager::new();
+    let unlisted = RoomConfig {
+        visibility: RoomVisibility::Unlisted,
+        ..RoomConfig::default()
+    };
+    manager.room_configs().set("acme::hidden", unlisted).await;
+    let (subscriber, mut subscriber_rx) = create_tenant_participant(10, Some("acme"));
+    let (outsider, mut outsider_rx) = create_tenant_participant(11, Some("globex"));
+    manager.subscribe_room_directory(subscriber.clone()).await;
+    manager.subscribe_room_directory(outsider).await;
+
+    let _host = join(
+        &manager,
+        "acme::standup",
+        1,
+        Some("acme"),
+        RoomRole::Participant,
+    )
+    .await;
+    let _viewer = join(
+        &manager,
+        "acme::standup",
+        2,
+        Some("acme"),
+        RoomRole::Spectator,
+    )
+    .await;
+    let _hidden = join(
+        &manager,
+        "acme::hidden",
+        3,
+        Some("acme"),
+        RoomRole::Participant,
+    )
+    .await;
+    manager
+        .moderate("acme::standup", 1, ModeratorAction::Promote { target: 2 })
+        .await
+        .unwrap();
+    manager.leave_room("acme::standup", 2).await.unwrap();
+    manager.leave_room("acme::standup", 1).await.unwrap();
+
+    let counts: Vec<(usize, usize, usize)> = updates(&mut subscriber_rx)
+        .iter()
+        .inspect(|room| assert_eq!(room.room_name, "acme::standup"))
+        .map(|room| {
+            (
+                room.participant_count,
+                room.spectator_count,
+                room.participants.len(),
+            )
+        })
+        .collect();
+    // Joined, spectator joined, promoted, left, closed
+    assert_eq!(
+        counts,
+        [(1, 0, 1), (2, 1, 1), (2, 0, 2), (1, 0, 1), (0, 0, 0)]
+    );
+    assert!(updates(&mut outsider_rx).is_empty());
+
+    manager
+        .unsubscribe_room_directory(subscriber.connection_id)
+        .await;
+    let _late = join(
+        &manager,
+        "acme::standup",
+        4,
+        Some("acme"),
+        RoomRole::Participant,
+    )
+    .await;
+    assert!(updates(&mut subscriber_rx).is_empty());
+}
+
+fn create_test_token(secret: &str, user_id: u32, username: &str) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+
+    let claims = serde_json::json!({
+        "sub": user_id,
+        "username": username,
+        "iat": now,
+        "exp": now + 3600,
+        "tenant": "acme",
+    });
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_room_directory_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut watcher = connect_client(port, create_test_token(jwt_secret, 1, "watcher")).await;
+    let mut alice = connect_client(port, create_test_token(jwt_secret, 2, "alice")).await;
+
+    send(&mut watcher, ClientMessage::SubscribeRoomDirectory).await;
+    assert!(matches!(
+        next_message(&mut watcher).await,
+        ServerMessage::RoomList { rooms } if rooms.is_empty()
+    ));
+
+    send(
+        &mut alice,
+        ClientMessage::JoinRoom {
+            room_name: "standup".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::RoomJoined { .. }
+    ));
+
+    // The tenant prefix never reaches the clients
+    match next_message(&mut watcher).await {
+        ServerMessage::RoomUpdated { room } => {
+            assert_eq!(room.room_name, "standup");
+            assert_eq!(room.participant_count, 1);
+            assert_eq!(room.participants[0].username, "alice");
+        }
+        other => panic!("Expected room-updated, got {:?}", other),
+    }
+
+    send(&mut alice, ClientMessage::ListRooms).await;
+    match next_message(&mut alice).await {
+        ServerMessage::RoomList { rooms } => assert_eq!(names(&rooms), ["standup"]),
+        other => panic!("Expected room-list, got {:?}", other),
+    }
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use futures_util::{SinkExt, StreamExt};
+use std::time::Duration;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{create_test_token, find_available_port};
+use webrtc_signaling::ice::{generate_turn_credentials, IceConfig};
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::room::RoomManager;
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+fn test_ice_config() -> IceConfig {
+    IceConfig {
+        stun_urls: vec!["stun:stun.example.com:3478".to_string()],
+        turn_urls: vec![
+            "turn:turn.example.com:3478?transport=udp".to_string(),
+            "turns:turn.example.com:5349".to_string(),
+        ],
+        turn_secret: Some("turn-secret".to_string()),
+        turn_ttl: Duration::from_secs(86400),
+    }
+}
+
+#[test]
+fn test_turn_credentials_match_coturn_scheme() {
+    let credentials =
+        generate_turn_credentials("turn-secret", "42", Duration::from_secs(86400), 1700000000);
+
+    assert_eq!(credentials.username, "1700086400:42");
+    assert_eq!(credentials.credential, "ymdn3291qbQ5jTQplFkZwTIkGX8=");
+    assert_eq!(credentials.expires_at, 1700086400);
+}
+
+#[test]
+fn test_ice_servers_include_stun_and_turn() {
+    let servers = test_ice_config().ice_servers_at(42, 1700000000);
+
+    assert_eq!(servers.len(), 2);
+    assert_eq!(servers[0].urls, vec!["stun:stun.example.com:3478"]);
+    assert_eq!(servers[0].username, None);
+    assert_eq!(servers[1].urls.len(), 2);
+    assert_eq!(servers[1].username.as_deref(), Some("1700086400:42"));
+    assert_eq!(
+        servers[1].credential.as_deref(),
+        Some("ymdn3291qbQ5jTQplFkZwTIkGX8=")
+    );
+}
+
+#[test]
+fn test_turn_requires_secret() {
+    let config = IceConfig {
+        turn_secret: None,
+        ..test_ice_config()
+    };
+
+    assert!(!config.has_turn());
+    assert_eq!(config.refresh_interval(), None);
+    let servers = config.ice_servers_at(42, 1700000000);
+    assert_eq!(servers.len(), 1);
+    assert!(servers[0].username.is_none());
+}
+
+#[test]
+fn test_refresh_interval_precedes_expiry() {
+    let config = IceConfig {
+        turn_ttl: Duration::from_secs(600),
+        ..test_ice_config()
+    };
+
+    assert_eq!(config.refresh_interval(), Some(Duration::from_secs(480)));
+    assert!(IceConfig::default().is_empty());
+}
+
+#[test]
+fn test_ice_servers_message_serialization() {
+    let msg = ServerMessage::IceServers {
+        ice_servers: test_ice_config().ice_servers_at(42, 1700000000),
+        ttl: Some(86400),
+    };
+
+    let json = serde_json::to_string(&msg).unwrap();
+    assert!(json.contains("\"type\":\"ice-servers\""));
+    assert!(json.contains("\"iceServers\":["));
+    assert!(json.contains("\"credential\":\"ymdn3291qbQ5jTQplFkZwTIkGX8=\""));
+    // STUN entries carry no credentials
+    assert!(json.contains("{\"urls\":[\"stun:stun.example.com:3478\"]}"));
+}
+
+#[tokio::test]
+async fn test_ice_servers_sent_after_authentication() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+    let token = create_test_token(jwt_secret, 123, "testuser");
+
+    let config = ServerConfig {
+        ice: test_ice_config(),
+        ..ServerConfig::default()
+    };
+    let server_handle = tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            config,
+        )
+        .await
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let ws_url = format!("ws://127.0.0.1:{}", port);
+    let (ws_stream, _) = connect_async(&ws_url).await.expect("Failed to connect");
+    let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+
+    let auth_msg = ClientMessage::Auth { token };
+    ws_sender
+        .send(Message::Text(serde_json::to_string(&auth_msg).unwrap()))
+        .await
+        .unwrap();
+
+    let _auth_response = ws_receiver.next().await;
+
+    if let Some(Ok(Message::Text(response))) = ws_receiver.next().await {
+        let server_msg: ServerMessage = serde_json::from_str(&response).unwrap();
+        match server_msg {
+            ServerMessage::IceServers { ice_servers, ttl } => {
+                assert_eq!(ice_servers.len(), 2);
+                assert_eq!(ttl, Some(86400));
+                let username = ice_servers[1].username.as_deref().unwrap();
+                assert!(username.ends_with(":123"));
+            }
+            _ => panic!("Expected ice-servers message, got: {:?}", server_msg),
+        }
+    } else {
+        panic!("No ice-servers message received");
+    }
+
+    server_handle.abort();
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{
+    connect_client, create_test_participant, create_test_token, find_available_port, next_message,
+    received, send, Client,
+};
+use webrtc_signaling::messages::{ClientMessage, LeaveReason, ServerMessage};
+use webrtc_signaling::room::{LocalRoomManager, RoomManager, RoomManagerTrait};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+#[tokio::test]
+async fn test_peers_learn_why_a_user_left() {
+    let manager = LocalRoomManager::new();
+    let (alice, _alice_rx) = create_test_participant(1, "alice");
+    let (bob, _bob_rx) = create_test_participant(2, "bob");
+    let (carol, mut carol_rx) = create_test_participant(3, "carol");
+    let bob_connection = bob.connection_id;
+    for participant in [alice, bob, carol] {
+        manager
+            .join_room_with("room".to_string(), participant, Default::default())
+            .await
+            .unwrap();
+    }
+    received(&mut carol_rx);
+
+    manager.leave_room("room", 1).await.unwrap();
+    manager.remove_user_from_all_rooms(2, bob_connection).await;
+
+    let reasons: Vec<(u32, Option<LeaveReason>)> = received(&mut carol_rx)
+        .into_iter()
+        .filter_map(|message| match message {
+            ServerMessage::UserLeft {
+                user_id, reason, ..
+            } => Some((user_id, reason)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        reasons,
+        [
+            (1, Some(LeaveReason::Left)),
+            (2, Some(LeaveReason::Disconnected))
+        ]
+    );
+}
+
+async fn join_room(client: &mut Client) -> ServerMessage {
+    send(
+        client,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    next_message(client).await
+}
+
+#[tokio::test]
+async fn test_leave_reasons_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut alice = connect_client(port, create_test_token(jwt_secret, 1, "alice")).await;
+    let mut bob = connect_client(port, create_test_token(jwt_secret, 2, "bob")).await;
+    let mut carol = connect_client(port, create_test_token(jwt_secret, 3, "carol")).await;
+    join_room(&mut alice).await;
+    join_room(&mut bob).await;
+    join_room(&mut carol).await;
+    for _ in 0..2 {
+        assert!(matches!(
+            next_message(&mut alice).await,
+            ServerMessage::UserJoined { .. }
+        ));
+    }
+    assert!(matches!(
+        next_message(&mut bob).await,
+        ServerMessage::UserJoined { .. }
+    ));
+
+    send(
+        &mut bob,
+        ClientMessage::LeaveRoom {
+            room_name: "room".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut bob).await,
+        ServerMessage::RoomLeft {
+            reason: Some(LeaveReason::Left),
+            ..
+        }
+    ));
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::UserLeft {
+            user_id: 2,
+            reason: Some(LeaveReason::Left),
+            ..
+        }
+    ));
+
+    // Closing the socket without leaving counts as a disconnect
+    carol.close(None).await.unwrap();
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::UserLeft {
+            user_id: 3,
+            reason: Some(LeaveReason::Disconnected),
+            ..
+        }
+    ));
+}
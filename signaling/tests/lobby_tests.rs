@@ -0,0 +1,451 @@
+mod common;
+
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{create_test_participant, find_available_port, next_message, received, send, Client};
+use webrtc_signaling::messages::{ClientMessage, RoomCloseReason, ServerMessage};
+use webrtc_signaling::moderation::{ModerationError, ModeratorAction, RoomRole};
+use webrtc_signaling::room::{
+    JoinError, JoinOutcome, JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait,
+};
+use webrtc_signaling::room_config::{OverflowPolicy, RoomConfig, RoomConfigStore};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+const HOST: u32 = 1;
+
+fn lobby_config() -> RoomConfig {
+    RoomConfig {
+        lobby: true,
+        ..RoomConfig::default()
+    }
+}
+
+fn lobby_manager(config: RoomConfig) -> LocalRoomManager {
+    LocalRoomManager::with_room_configs(Arc::new(RoomConfigStore::new(config)))
+}
+
+async fn join_as(
+    manager: &LocalRoomManager,
+    user_id: u32,
+    username: &str,
+    role: RoomRole,
+) -> (Result<JoinOutcome, JoinError>, mpsc::Receiver<Message>) {
+    let (participant, rx) = create_test_participant(user_id, username);
+    let request = JoinRequest {
+        role,
+        ..JoinRequest::default()
+    };
+    let result = manager
+        .join_room_with("room".to_string(), participant, request)
+        .await;
+    (result, rx)
+}
+
+/// Join as the room's host, who skips the lobby
+async fn join_host(manager: &LocalRoomManager) -> mpsc::Receiver<Message> {
+    let (result, rx) = join_as(manager, HOST, "host", RoomRole::Host).await;
+    assert!(matches!(result, Ok(JoinOutcome::Joined(_))));
+    rx
+}
+
+async fn knock(
+    manager: &LocalRoomManager,
+    user_id: u32,
+    username: &str,
+) -> mpsc::Receiver<Message> {
+    let (result, rx) = join_as(manager, user_id, username, RoomRole::Participant).await;
+    assert!(matches!(result, Ok(JoinOutcome::InLobby)));
+    rx
+}
+
+#[tokio::test]
+async fn test_knock_notifies_host_and_withholds_room_traffic() {
+    let manager = lobby_manager(lobby_config());
+    let mut host_rx = join_host(&manager).await;
+    let mut guest_rx = knock(&manager, 2, "guest").await;
+
+    match received(&mut host_rx).as_slice() {
+        [ServerMessage::Knock { room_name, user }] => {
+            assert_eq!(room_name, "room");
+            assert_eq!(user.user_id, 2);
+        }
+        other => panic!("Expected knock, got {:?}", other),
+    }
+    assert!(!manager.user_in_room("room", 2).await);
+
+    let offer = ServerMessage::Offer {
+        room_name: "room".to_string(),
+        from_user_id: HOST,
+        sdp: "v=0".to_string(),
+    };
+    manager
+        .broadcast_to_room("room", HOST, offer)
+        .await
+        .unwrap();
+    assert!(received(&mut guest_rx).is_empty());
+
+    // Knocking twice is refused
+    let (result, _) = join_as(&manager, 2, "guest", RoomRole::Participant).await;
+    assert_eq!(result.unwrap_err(), JoinError::AlreadyInRoom);
+}
+
+#[tokio::test]
+async fn test_admit_completes_join() {
+    let manager = lobby_manager(lobby_config());
+    let mut host_rx = join_host(&manager).await;
+    let mut guest_rx = knock(&manager, 2, "guest").await;
+    received(&mut host_rx);
+
+    manager
+        .moderate("room", HOST, ModeratorAction::Admit { target: 2 })
+        .await
+        .unwrap();
+
+    match received(&mut guest_rx).as_slice() {
+        [ServerMessage::RoomJoined {
+            participants,
+            host_user_id,
+            ..
+        }] => {
+            assert_eq!(participants.len(), 1);
+            assert_eq!(*host_user_id, Some(HOST));
+        }
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+    match received(&mut host_rx).as_slice() {
+        [ServerMessage::KnockResolved {
+            user_id,
+            admitted: true,
+            ..
+        }, ServerMessage::UserJoined { user, .. }] => {
+            assert_eq!(*user_id, 2);
+            assert_eq!(user.user_id, 2);
+        }
+        other => panic!("Expected knock-resolved and user-joined, got {:?}", other),
+    }
+    assert!(manager.user_in_room("room", 2).await);
+
+    // The knock has been answered
+    assert_eq!(
+        manager
+            .moderate("room", HOST, ModeratorAction::Admit { target: 2 })
+            .await
+            .unwrap_err(),
+        ModerationError::TargetNotFound
+    );
+}
+
+#[tokio::test]
+async fn test_deny_rejects_join() {
+    let manager = lobby_manager(lobby_config());
+    let mut host_rx = join_host(&manager).await;
+    let (_, _member_rx) = join_as(&manager, 3, "member", RoomRole::Moderator).await;
+    let mut guest_rx = knock(&manager, 2, "guest").await;
+    received(&mut host_rx);
+
+    // Any moderator may answer, not only the host
+    manager
+        .moderate(
+            "room",
+            3,
+            ModeratorAction::Deny {
+                target: 2,
+                reason: Some("wrong call".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+
+    match received(&mut guest_rx).as_slice() {
+        [ServerMessage::Error { message, code }] => {
+            assert_eq!(*code, Some(403));
+            assert!(message.contains("wrong call"));
+        }
+        other => panic!("Expected error, got {:?}", other),
+    }
+    assert!(matches!(
+        received(&mut host_rx).as_slice(),
+        [ServerMessage::KnockResolved {
+            admitted: false,
+            ..
+        }]
+    ));
+    assert!(!manager.user_in_room("room", 2).await);
+}
+
+#[tokio::test]
+async fn test_participants_cannot_answer_knocks() {
+    let manager = lobby_manager(lobby_config());
+    let _host_rx = join_host(&manager).await;
+    let mut guest_rx = knock(&manager, 2, "guest").await;
+    let _other_rx = knock(&manager, 3, "other").await;
+    manager
+        .moderate("room", HOST, ModeratorAction::Admit { target: 2 })
+        .await
+        .unwrap();
+    received(&mut guest_rx);
+
+    assert_eq!(
+        manager
+            .moderate("room", 2, ModeratorAction::Admit { target: 3 })
+            .await
+            .unwrap_err(),
+        ModerationError::NotModerator
+    );
+}
+
+#[tokio::test]
+async fn test_knock_times_out() {
+    let manager = lobby_manager(RoomConfig {
+        lobby_timeout_secs: Some(1),
+        ..lobby_config()
+    });
+    let mut host_rx = join_host(&manager).await;
+    let mut guest_rx = knock(&manager, 2, "guest").await;
+    received(&mut host_rx);
+
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+
+    match received(&mut guest_rx).as_slice() {
+        [ServerMessage::Error { code, .. }] => assert_eq!(*code, Some(408)),
+        other => panic!("Expected timeout error, got {:?}", other),
+    }
+    assert!(matches!(
+        received(&mut host_rx).as_slice(),
+        [ServerMessage::KnockResolved {
+            admitted: false,
+            ..
+        }]
+    ));
+    assert_eq!(
+        manager
+            .moderate("room", HOST, ModeratorAction::Admit { target: 2 })
+            .await
+            .unwrap_err(),
+        ModerationError::TargetNotFound
+    );
+}
+
+#[tokio::test]
+async fn test_lobby_survives_until_a_host_arrives() {
+    let manager = lobby_manager(RoomConfig {
+        max_participants: Some(1),
+        overflow: OverflowPolicy::Reject,
+        ..lobby_config()
+    });
+    let mut guest_rx = knock(&manager, 2, "guest").await;
+
+    // A host arriving later hears about the waiting knock
+    let mut host_rx = join_host(&manager).await;
+    assert!(matches!(
+        received(&mut host_rx).as_slice(),
+        [ServerMessage::Knock { .. }]
+    ));
+
+    // Admitting into a full room reports it to the guest
+    manager
+        .moderate("room", HOST, ModeratorAction::Admit { target: 2 })
+        .await
+        .unwrap();
+    assert!(matches!(
+        received(&mut guest_rx).as_slice(),
+        [ServerMessage::RoomFull { capacity: 1, .. }]
+    ));
+
+    // Leaving the lobby withdraws the knock
+    let _again_rx = knock(&manager, 3, "again").await;
+    received(&mut host_rx);
+    manager.leave_room("room", 3).await.unwrap();
+    assert!(matches!(
+        received(&mut host_rx).as_slice(),
+        [ServerMessage::KnockResolved {
+            user_id: 3,
+            admitted: false,
+            ..
+        }]
+    ));
+}
+
+#[tokio::test]
+async fn test_admit_is_refused_once_the_room_has_closed() {
+    let configs = Arc::new(RoomConfigStore::new(lobby_config()));
+    let manager = LocalRoomManager::with_room_configs(Arc::clone(&configs));
+    let mut host_rx = join_host(&manager).await;
+    let mut guest_rx = knock(&manager, 2, "guest").await;
+    received(&mut host_rx);
+
+    // The room's window ends while the guest is still waiting
+    configs
+        .set(
+            "room",
+            RoomConfig {
+                closes_at: Some(chrono::Utc::now() - chrono::Duration::minutes(1)),
+                ..lobby_config()
+            },
+        )
+        .await;
+    manager
+        .moderate("room", HOST, ModeratorAction::Admit { target: 2 })
+        .await
+        .unwrap();
+
+    match received(&mut guest_rx).as_slice() {
+        [ServerMessage::Error { code, .. }] => assert_eq!(*code, Some(410)),
+        other => panic!("Expected room-closed error, got {:?}", other),
+    }
+    assert!(matches!(
+        received(&mut host_rx).as_slice(),
+        [ServerMessage::KnockResolved {
+            user_id: 2,
+            admitted: false,
+            ..
+        }]
+    ));
+    assert!(!manager.user_in_room("room", 2).await);
+
+    // Nothing of the refused knock keeps the room alive
+    manager.leave_room("room", HOST).await.unwrap();
+    assert!(manager.get_rooms().read().await.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_admission_applies_the_knockers_session_limit() {
+    let manager = lobby_manager(lobby_config());
+    let mut host_rx = join_host(&manager).await;
+    let (guest, mut guest_rx) = create_test_participant(2, "guest");
+    let request = JoinRequest {
+        max_duration: Some(Duration::from_secs(60)),
+        ..JoinRequest::default()
+    };
+    let result = manager
+        .join_room_with("room".to_string(), guest, request)
+        .await;
+    assert!(matches!(result, Ok(JoinOutcome::InLobby)));
+
+    manager
+        .moderate("room", HOST, ModeratorAction::Admit { target: 2 })
+        .await
+        .unwrap();
+    received(&mut host_rx);
+    received(&mut guest_rx);
+
+    // The admission opened the session, so the guest's token limits it
+    tokio::time::sleep(Duration::from_secs(61)).await;
+    assert!(received(&mut guest_rx).iter().any(|message| matches!(
+        message,
+        ServerMessage::RoomClosed {
+            reason: RoomCloseReason::MaxDuration,
+            ..
+        }
+    )));
+    assert!(!manager.user_in_room("room", 2).await);
+}
+
+fn create_test_token(secret: &str, user_id: u32, username: &str, host: bool) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+
+    let mut claims = serde_json::json!({
+        "sub": user_id,
+        "username": username,
+        "iat": now,
+        "exp": now + 3600,
+    });
+    if host {
+        claims["room_roles"] = serde_json::json!({ "room": "host" });
+    }
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap()
+}
+
+async fn connect_and_join(port: u16, token: String) -> (Client, ServerMessage) {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    let joined = next_message(&mut ws_stream).await;
+    (ws_stream, joined)
+}
+
+#[tokio::test]
+async fn test_lobby_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+    let room_manager = RoomManager::with_implementation(Box::new(lobby_manager(lobby_config())));
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            room_manager,
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (mut host, joined) =
+        connect_and_join(port, create_test_token(jwt_secret, 1, "host", true)).await;
+    assert!(matches!(joined, ServerMessage::RoomJoined { .. }));
+    let (mut guest, waiting) =
+        connect_and_join(port, create_test_token(jwt_secret, 2, "guest", false)).await;
+    assert!(matches!(waiting, ServerMessage::InLobby { .. }));
+    assert!(matches!(
+        next_message(&mut host).await,
+        ServerMessage::Knock { .. }
+    ));
+
+    // Lobby users cannot signal the room
+    send(
+        &mut guest,
+        ClientMessage::Offer {
+            room_name: "room".to_string(),
+            sdp: "v=0".to_string(),
+            target_user_id: Some(1),
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut guest).await,
+        ServerMessage::Error { .. }
+    ));
+
+    send(
+        &mut host,
+        ClientMessage::Admit {
+            room_name: "room".to_string(),
+            target_user_id: 2,
+        },
+    )
+    .await;
+    match next_message(&mut guest).await {
+        ServerMessage::RoomJoined { participants, .. } => {
+            assert_eq!(participants[0].user_id, 1);
+        }
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+}
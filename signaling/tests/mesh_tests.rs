@@ -0,0 +1,263 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use common::{
+    connect_client, create_test_participant, create_test_token, find_available_port, next_message,
+    send, Client, TEST_OFFER_SDP,
+};
+use webrtc_signaling::audit::SignalKind;
+use webrtc_signaling::mesh::{NegotiationState, PeerPair, MAX_CONNECT_ATTEMPTS};
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::moderation::RoomRole;
+use webrtc_signaling::room::{JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait};
+use webrtc_signaling::room_config::{MeshPolicy, RoomConfig, RoomConfigStore};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+fn planned_configs() -> Arc<RoomConfigStore> {
+    Arc::new(RoomConfigStore::new(RoomConfig {
+        mesh: MeshPolicy::ServerPlanned,
+        ..RoomConfig::default()
+    }))
+}
+
+/// Join the way the server does, planning the mesh once the join went through
+async fn join(manager: &LocalRoomManager, user_id: u32, role: RoomRole) -> mpsc::Receiver<Message> {
+    let (participant, rx) = create_test_participant(user_id, &format!("user{}", user_id));
+    let request = JoinRequest {
+        role,
+        ..JoinRequest::default()
+    };
+    manager
+        .join_room_with("room".to_string(), participant, request)
+        .await
+        .unwrap();
+    manager.plan_mesh("room", user_id).await;
+    rx
+}
+
+/// The `connect-to` instructions received, as (peer, initiator, attempt)
+fn connect_to(rx: &mut mpsc::Receiver<Message>) -> Vec<(u32, bool, u32)> {
+    let mut instructions = Vec::new();
+    while let Ok(Message::Text(text)) = rx.try_recv() {
+        if let ServerMessage::ConnectTo {
+            peer_user_id,
+            initiator,
+            attempt,
+            ..
+        } = serde_json::from_str(&text).unwrap()
+        {
+            instructions.push((peer_user_id, initiator, attempt));
+        }
+    }
+    instructions
+}
+
+#[test]
+fn test_pair_follows_offer_and_answer() {
+    let mut pair = PeerPair::new(2, 1);
+    assert_eq!(PeerPair::key(2, 1), "1:2");
+    assert_eq!(pair.state, NegotiationState::Instructed);
+
+    // Only the initiator's offer and the responder's answer count
+    assert!(!pair.record(1, 2, SignalKind::Offer));
+    assert!(!pair.record(2, 1, SignalKind::Answer));
+    assert!(pair.record(2, 1, SignalKind::Offer));
+    assert_eq!(pair.state, NegotiationState::Offered);
+    assert!(pair.record(1, 2, SignalKind::Answer));
+    assert_eq!(pair.state, NegotiationState::Answered);
+    assert!(!pair.is_pending());
+
+    let mut pair = PeerPair::new(2, 1);
+    for attempt in 2..=MAX_CONNECT_ATTEMPTS {
+        assert!(pair.retry());
+        assert_eq!(pair.attempt, attempt);
+        assert_eq!(pair.state, NegotiationState::Instructed);
+    }
+    assert!(!pair.retry());
+    assert_eq!(pair.state, NegotiationState::Failed);
+}
+
+#[tokio::test]
+async fn test_newcomer_is_told_to_offer_to_everyone() {
+    let manager = LocalRoomManager::with_room_configs(planned_configs());
+    let mut alice_rx = join(&manager, 1, RoomRole::Participant).await;
+    let mut bob_rx = join(&manager, 2, RoomRole::Participant).await;
+    assert_eq!(connect_to(&mut alice_rx), vec![(2, false, 1)]);
+    assert_eq!(connect_to(&mut bob_rx), vec![(1, true, 1)]);
+
+    let mut carol_rx = join(&manager, 3, RoomRole::Spectator).await;
+    assert_eq!(connect_to(&mut carol_rx), vec![(1, true, 1), (2, true, 1)]);
+    assert_eq!(connect_to(&mut alice_rx), vec![(3, false, 1)]);
+    assert_eq!(connect_to(&mut bob_rx), vec![(3, false, 1)]);
+
+    // Spectators are only paired with presenters
+    let mut dave_rx = join(&manager, 4, RoomRole::Spectator).await;
+    assert_eq!(connect_to(&mut dave_rx), vec![(1, true, 1), (2, true, 1)]);
+    assert!(connect_to(&mut carol_rx).is_empty());
+
+    // Leaving drops the user's pairs
+    manager.leave_room("room", 4).await.unwrap();
+    let rooms = manager.get_rooms();
+    let rooms = rooms.read().await;
+    assert_eq!(rooms["room"].mesh.pairs().count(), 3);
+    assert!(rooms["room"].mesh.pair(1, 4).is_none());
+}
+
+#[tokio::test]
+async fn test_client_driven_rooms_are_not_planned() {
+    let manager = LocalRoomManager::new();
+    let mut alice_rx = join(&manager, 1, RoomRole::Participant).await;
+    let mut bob_rx = join(&manager, 2, RoomRole::Participant).await;
+    assert!(connect_to(&mut alice_rx).is_empty());
+    assert!(connect_to(&mut bob_rx).is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_stalled_pairs_are_instructed_again_until_they_fail() {
+    let manager = LocalRoomManager::with_room_configs(planned_configs());
+    let mut alice_rx = join(&manager, 1, RoomRole::Participant).await;
+    let mut bob_rx = join(&manager, 2, RoomRole::Participant).await;
+    let mut carol_rx = join(&manager, 3, RoomRole::Participant).await;
+    connect_to(&mut alice_rx);
+    connect_to(&mut bob_rx);
+    connect_to(&mut carol_rx);
+
+    // Carol and alice complete their negotiation; bob never answers carol
+    manager.record_signal("room", 3, 1, SignalKind::Offer).await;
+    manager
+        .record_signal("room", 1, 3, SignalKind::Answer)
+        .await;
+    manager.record_signal("room", 3, 2, SignalKind::Offer).await;
+
+    tokio::time::sleep(Duration::from_secs(16)).await;
+    // Alice and bob never offered at all
+    assert_eq!(connect_to(&mut alice_rx), vec![(2, false, 2)]);
+    assert_eq!(connect_to(&mut carol_rx), vec![(2, true, 2)]);
+    let mut bob = connect_to(&mut bob_rx);
+    bob.sort_unstable();
+    assert_eq!(bob, vec![(1, true, 2), (3, false, 2)]);
+
+    tokio::time::sleep(Duration::from_secs(15)).await;
+    assert_eq!(connect_to(&mut carol_rx), vec![(2, true, 3)]);
+    assert_eq!(connect_to(&mut alice_rx), vec![(2, false, 3)]);
+
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    assert!(connect_to(&mut carol_rx).is_empty());
+    assert!(connect_to(&mut alice_rx).is_empty());
+
+    let rooms = manager.get_rooms();
+    let rooms = rooms.read().await;
+    let mesh = &rooms["room"].mesh;
+    assert_eq!(mesh.pair(1, 3).unwrap().state, NegotiationState::Answered);
+    assert_eq!(mesh.pair(2, 3).unwrap().state, NegotiationState::Failed);
+    assert_eq!(mesh.pair(1, 2).unwrap().state, NegotiationState::Failed);
+}
+
+async fn join_room(ws_stream: &mut Client) {
+    send(
+        ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(ws_stream).await,
+        ServerMessage::RoomJoined { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_connect_to_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+    let manager = LocalRoomManager::with_room_configs(planned_configs());
+    let rooms = manager.get_rooms();
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::with_implementation(Box::new(manager)),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut alice = connect_client(port, create_test_token(jwt_secret, 1, "alice")).await;
+    join_room(&mut alice).await;
+    let mut bob = connect_client(port, create_test_token(jwt_secret, 2, "bob")).await;
+    join_room(&mut bob).await;
+
+    match next_message(&mut bob).await {
+        ServerMessage::ConnectTo {
+            room_name,
+            peer_user_id,
+            initiator,
+            attempt,
+        } => {
+            assert_eq!(room_name, "room");
+            assert_eq!(peer_user_id, 1);
+            assert!(initiator);
+            assert_eq!(attempt, 1);
+        }
+        other => panic!("Expected connect-to, got {:?}", other),
+    }
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::UserJoined { .. }
+    ));
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::ConnectTo {
+            peer_user_id: 2,
+            initiator: false,
+            ..
+        }
+    ));
+
+    send(
+        &mut bob,
+        ClientMessage::Offer {
+            room_name: "room".to_string(),
+            target_user_id: Some(1),
+            sdp: TEST_OFFER_SDP.to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::Offer {
+            from_user_id: 2,
+            ..
+        }
+    ));
+    send(
+        &mut alice,
+        ClientMessage::Answer {
+            room_name: "room".to_string(),
+            target_user_id: 2,
+            sdp: TEST_OFFER_SDP.to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut bob).await,
+        ServerMessage::Answer {
+            from_user_id: 1,
+            ..
+        }
+    ));
+
+    let rooms = rooms.read().await;
+    let pair = rooms["room"].mesh.pair(1, 2).unwrap();
+    assert_eq!(pair.initiator, 2);
+    assert_eq!(pair.state, NegotiationState::Answered);
+}
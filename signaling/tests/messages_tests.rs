@@ -166,6 +166,10 @@ fn test_server_message_room_joined_serialization() {
         room_name: "test_room".to_string(),
         user_id: 123,
         participants,
+        spectator: false,
+        owner_user_id: None,
+        host_user_id: None,
+        state: Default::default(),
     };
 
     let json = serde_json::to_string(&msg).unwrap();
@@ -272,3 +276,169 @@ fn test_invalid_message_deserialization() {
     let result: Result<ClientMessage, _> = serde_json::from_str(malformed_json);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_moderation_message_formats() {
+    let ban_json = r#"{"type":"ban","roomName":"myroom","targetUserId":7,"durationSeconds":60,"reason":"spam"}"#;
+    match serde_json::from_str::<ClientMessage>(ban_json).unwrap() {
+        ClientMessage::Ban {
+            target_user_id,
+            duration_seconds,
+            ..
+        } => {
+            assert_eq!(target_user_id, 7);
+            assert_eq!(duration_seconds, Some(60));
+        }
+        other => panic!("Failed to parse ban message: {:?}", other),
+    }
+
+    let left = ServerMessage::UserLeft {
+        room_name: "myroom".to_string(),
+        user_id: 7,
+        reason: Some(LeaveReason::Kicked),
+    };
+    let json = serde_json::to_string(&left).unwrap();
+    assert!(json.contains(r#""reason":"kicked""#));
+
+    let left = ServerMessage::UserLeft {
+        room_name: "myroom".to_string(),
+        user_id: 7,
+        reason: Some(LeaveReason::ServerShutdown),
+    };
+    let json = serde_json::to_string(&left).unwrap();
+    assert!(json.contains(r#""reason":"server-shutdown""#));
+
+    // Events without a reason keep the original shape
+    let left = ServerMessage::UserLeft {
+        room_name: "myroom".to_string(),
+        user_id: 7,
+        reason: None,
+    };
+    assert!(!serde_json::to_string(&left).unwrap().contains("reason"));
+}
+
+#[test]
+fn test_host_message_formats() {
+    let transfer_json = r#"{"type":"transfer-host","roomName":"myroom","targetUserId":7}"#;
+    match serde_json::from_str::<ClientMessage>(transfer_json).unwrap() {
+        ClientMessage::TransferHost {
+            room_name,
+            target_user_id,
+        } => {
+            assert_eq!(room_name, "myroom");
+            assert_eq!(target_user_id, 7);
+        }
+        other => panic!("Failed to parse transfer-host message: {:?}", other),
+    }
+
+    let changed = ServerMessage::HostChanged {
+        room_name: "myroom".to_string(),
+        host_user_id: None,
+        previous_host_user_id: Some(7),
+    };
+    let json = serde_json::to_string(&changed).unwrap();
+    assert!(json.contains(r#""type":"host-changed""#));
+    assert!(json.contains(r#""previousHostUserId":7"#));
+}
+
+#[test]
+fn test_lobby_message_formats() {
+    let deny_json = r#"{"type":"deny","roomName":"myroom","targetUserId":7,"reason":"full"}"#;
+    match serde_json::from_str::<ClientMessage>(deny_json).unwrap() {
+        ClientMessage::Deny {
+            target_user_id,
+            reason,
+            ..
+        } => {
+            assert_eq!(target_user_id, 7);
+            assert_eq!(reason.as_deref(), Some("full"));
+        }
+        other => panic!("Failed to parse deny message: {:?}", other),
+    }
+
+    let knock = ServerMessage::Knock {
+        room_name: "myroom".to_string(),
+        user: Participant {
+            user_id: 7,
+            username: "guest".to_string(),
+        },
+    };
+    let json = serde_json::to_string(&knock).unwrap();
+    assert!(json.contains(r#""type":"knock""#));
+    assert!(json.contains(r#""userId":7"#));
+}
+
+#[test]
+fn test_breakout_message_formats() {
+    let create_json = r#"{"type":"create-breakouts","roomName":"myroom","breakouts":[{"name":"a","userIds":[2,3]},{"name":"b"}]}"#;
+    match serde_json::from_str::<ClientMessage>(create_json).unwrap() {
+        ClientMessage::CreateBreakouts { breakouts, .. } => {
+            assert_eq!(breakouts.len(), 2);
+            assert_eq!(breakouts[0].user_ids, vec![2, 3]);
+            assert!(breakouts[1].user_ids.is_empty());
+        }
+        other => panic!("Failed to parse create-breakouts message: {:?}", other),
+    }
+
+    let moved = ServerMessage::MovedToRoom {
+        from_room_name: "myroom".to_string(),
+        room_name: "myroom/a".to_string(),
+        participants: vec![],
+        owner_user_id: None,
+        host_user_id: Some(2),
+        state: Default::default(),
+    };
+    let json = serde_json::to_string(&moved).unwrap();
+    assert!(json.contains(r#""type":"moved-to-room""#));
+    assert!(json.contains(r#""fromRoomName":"myroom""#));
+    assert!(json.contains(r#""hostUserId":2"#));
+    assert!(!json.contains("ownerUserId"));
+
+    let left = ServerMessage::UserLeft {
+        room_name: "myroom".to_string(),
+        user_id: 2,
+        reason: Some(LeaveReason::Moved),
+    };
+    assert!(serde_json::to_string(&left)
+        .unwrap()
+        .contains(r#""reason":"moved""#));
+}
+
+#[test]
+fn test_room_state_message_formats() {
+    let set_json = r#"{"type":"set-room-state","roomName":"myroom","key":"slide","value":{"page":3},"expectedVersion":0}"#;
+    match serde_json::from_str::<ClientMessage>(set_json).unwrap() {
+        ClientMessage::SetRoomState {
+            key,
+            value,
+            expected_version,
+            ..
+        } => {
+            assert_eq!(key, "slide");
+            assert_eq!(value["page"], 3);
+            assert_eq!(expected_version, Some(0));
+        }
+        other => panic!("Failed to parse set-room-state message: {:?}", other),
+    }
+
+    let delete_json = r#"{"type":"delete-room-state","roomName":"myroom","key":"slide"}"#;
+    assert!(matches!(
+        serde_json::from_str::<ClientMessage>(delete_json).unwrap(),
+        ClientMessage::DeleteRoomState {
+            expected_version: None,
+            ..
+        }
+    ));
+
+    let deleted = ServerMessage::RoomStateChanged {
+        room_name: "myroom".to_string(),
+        key: "slide".to_string(),
+        value: None,
+        version: 4,
+        by_user_id: 2,
+    };
+    let json = serde_json::to_string(&deleted).unwrap();
+    assert!(json.contains(r#""type":"room-state-changed""#));
+    assert!(json.contains(r#""byUserId":2"#));
+    assert!(!json.contains("value"));
+}
@@ -0,0 +1,472 @@
+mod common;
+
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{create_test_participant, find_available_port, next_message, received, send, Client};
+use webrtc_signaling::auth::{JwtValidator, TokenGrants};
+use webrtc_signaling::messages::{ClientMessage, LeaveReason, ServerMessage};
+use webrtc_signaling::moderation::{ModerationError, ModeratorAction, RoomRole};
+use webrtc_signaling::room::{
+    JoinError, JoinOutcome, JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait,
+};
+use webrtc_signaling::room_config::{OverflowPolicy, RoomConfig, RoomConfigStore};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+const MODERATOR: u32 = 1;
+
+/// A manager whose room configuration makes user 1 a moderator everywhere
+fn moderated_manager() -> LocalRoomManager {
+    let store = RoomConfigStore::new(RoomConfig {
+        moderators: vec![MODERATOR],
+        ..RoomConfig::default()
+    });
+    LocalRoomManager::with_room_configs(Arc::new(store))
+}
+
+async fn join(
+    manager: &LocalRoomManager,
+    user_id: u32,
+    username: &str,
+) -> (Result<JoinOutcome, JoinError>, mpsc::Receiver<Message>) {
+    let (participant, rx) = create_test_participant(user_id, username);
+    let result = manager
+        .join_room_with("room".to_string(), participant, JoinRequest::default())
+        .await;
+    (result, rx)
+}
+
+#[tokio::test]
+async fn test_kick_notifies_target_and_room() {
+    let manager = moderated_manager();
+    let (_, mut moderator_rx) = join(&manager, MODERATOR, "mod").await;
+    let (_, mut alice_rx) = join(&manager, 2, "alice").await;
+    let (_, mut bob_rx) = join(&manager, 3, "bob").await;
+    received(&mut moderator_rx);
+    received(&mut alice_rx);
+
+    manager
+        .moderate(
+            "room",
+            MODERATOR,
+            ModeratorAction::Kick {
+                target: 3,
+                reason: Some("spam".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+
+    assert!(!manager.user_in_room("room", 3).await);
+    match received(&mut bob_rx).as_slice() {
+        [ServerMessage::Kicked {
+            room_name,
+            by_user_id,
+            reason,
+        }] => {
+            assert_eq!(room_name, "room");
+            assert_eq!(*by_user_id, MODERATOR);
+            assert_eq!(reason.as_deref(), Some("spam"));
+        }
+        other => panic!("Expected kicked event, got {:?}", other),
+    }
+    for rx in [&mut moderator_rx, &mut alice_rx] {
+        match received(rx).as_slice() {
+            [ServerMessage::UserLeft {
+                user_id, reason, ..
+            }] => {
+                assert_eq!(*user_id, 3);
+                assert_eq!(*reason, Some(LeaveReason::Kicked));
+            }
+            other => panic!("Expected user-left, got {:?}", other),
+        }
+    }
+
+    // A kicked user may come back
+    let (result, _bob_rx) = join(&manager, 3, "bob").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_moderation_requires_role() {
+    let manager = moderated_manager();
+    let (_, _moderator_rx) = join(&manager, MODERATOR, "mod").await;
+    let (_, _alice_rx) = join(&manager, 2, "alice").await;
+    let (_, _bob_rx) = join(&manager, 3, "bob").await;
+
+    let kick = |target| ModeratorAction::Kick {
+        target,
+        reason: None,
+    };
+
+    assert_eq!(
+        manager.moderate("room", 2, kick(3)).await.unwrap_err(),
+        ModerationError::NotModerator
+    );
+    assert_eq!(
+        manager.moderate("room", 9, kick(3)).await.unwrap_err(),
+        ModerationError::NotInRoom
+    );
+    assert_eq!(
+        manager
+            .moderate("room", MODERATOR, kick(9))
+            .await
+            .unwrap_err(),
+        ModerationError::TargetNotFound
+    );
+    assert_eq!(
+        manager
+            .moderate("room", MODERATOR, kick(MODERATOR))
+            .await
+            .unwrap_err(),
+        ModerationError::TargetProtected
+    );
+
+    // Token grants make a moderator as well; moderators cannot remove each other
+    let (participant, _carol_rx) = create_test_participant(4, "carol");
+    let request = JoinRequest {
+        role: RoomRole::Moderator,
+        ..JoinRequest::default()
+    };
+    manager
+        .join_room_with("room".to_string(), participant, request)
+        .await
+        .unwrap();
+    assert_eq!(
+        manager
+            .moderate("room", 4, kick(MODERATOR))
+            .await
+            .unwrap_err(),
+        ModerationError::TargetProtected
+    );
+    assert!(manager.moderate("room", 4, kick(3)).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_ban_refuses_rejoin_until_expiry() {
+    let manager = moderated_manager();
+    let (_, _moderator_rx) = join(&manager, MODERATOR, "mod").await;
+    let (_, mut alice_rx) = join(&manager, 2, "alice").await;
+
+    manager
+        .moderate(
+            "room",
+            MODERATOR,
+            ModeratorAction::Ban {
+                target: 2,
+                duration: Some(Duration::from_millis(300)),
+                reason: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        received(&mut alice_rx).as_slice(),
+        [ServerMessage::Banned { .. }]
+    ));
+    assert!(!manager.user_in_room("room", 2).await);
+
+    let (result, _) = join(&manager, 2, "alice").await;
+    match result {
+        Err(JoinError::Banned {
+            remaining: Some(remaining),
+        }) => assert!(remaining <= Duration::from_millis(300)),
+        other => panic!("Expected ban, got {:?}", other),
+    }
+
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    let (result, _alice_rx) = join(&manager, 2, "alice").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_ban_applies_to_absent_and_waiting_users() {
+    let store = RoomConfigStore::new(RoomConfig {
+        moderators: vec![MODERATOR],
+        max_participants: Some(1),
+        overflow: OverflowPolicy::Waitlist,
+        ..RoomConfig::default()
+    });
+    let manager = LocalRoomManager::with_room_configs(Arc::new(store));
+    let (_, _moderator_rx) = join(&manager, MODERATOR, "mod").await;
+    let (result, mut waiting_rx) = join(&manager, 2, "alice").await;
+    assert!(matches!(
+        result,
+        Ok(JoinOutcome::Waitlisted { position: 1 })
+    ));
+
+    for target in [2, 3] {
+        manager
+            .moderate(
+                "room",
+                MODERATOR,
+                ModeratorAction::Ban {
+                    target,
+                    duration: None,
+                    reason: None,
+                },
+            )
+            .await
+            .unwrap();
+    }
+
+    assert!(matches!(
+        received(&mut waiting_rx).as_slice(),
+        [ServerMessage::Banned {
+            duration_seconds: None,
+            ..
+        }]
+    ));
+    for user_id in [2, 3] {
+        let (result, _) = join(&manager, user_id, "banned").await;
+        assert_eq!(result.unwrap_err(), JoinError::Banned { remaining: None });
+    }
+}
+
+#[tokio::test]
+async fn test_locked_room_admits_only_moderators() {
+    let manager = moderated_manager();
+    let (_, mut moderator_rx) = join(&manager, MODERATOR, "mod").await;
+
+    manager
+        .moderate("room", MODERATOR, ModeratorAction::Lock { locked: true })
+        .await
+        .unwrap();
+    assert!(matches!(
+        received(&mut moderator_rx).as_slice(),
+        [ServerMessage::RoomLocked { locked: true, .. }]
+    ));
+
+    let (result, _) = join(&manager, 2, "alice").await;
+    assert_eq!(result.unwrap_err(), JoinError::RoomLocked);
+    assert_eq!(JoinError::RoomLocked.code(), 423);
+
+    let (participant, _carol_rx) = create_test_participant(4, "carol");
+    let request = JoinRequest {
+        role: RoomRole::Moderator,
+        ..JoinRequest::default()
+    };
+    assert!(manager
+        .join_room_with("room".to_string(), participant, request)
+        .await
+        .is_ok());
+
+    manager
+        .moderate("room", MODERATOR, ModeratorAction::Lock { locked: false })
+        .await
+        .unwrap();
+    let (result, _alice_rx) = join(&manager, 2, "alice").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_request_mute_reaches_only_target() {
+    let manager = moderated_manager();
+    let (_, mut moderator_rx) = join(&manager, MODERATOR, "mod").await;
+    let (_, mut alice_rx) = join(&manager, 2, "alice").await;
+    received(&mut moderator_rx);
+
+    manager
+        .moderate(
+            "room",
+            MODERATOR,
+            ModeratorAction::RequestMute {
+                target: 2,
+                media: Some("audio".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+
+    match received(&mut alice_rx).as_slice() {
+        [ServerMessage::MuteRequested {
+            by_user_id, media, ..
+        }] => {
+            assert_eq!(*by_user_id, MODERATOR);
+            assert_eq!(media.as_deref(), Some("audio"));
+        }
+        other => panic!("Expected mute-requested, got {:?}", other),
+    }
+    assert!(received(&mut moderator_rx).is_empty());
+    assert!(manager.user_in_room("room", 2).await);
+}
+
+#[test]
+fn test_token_grants_room_roles() {
+    let grants = TokenGrants {
+        room_roles: HashMap::from([
+            ("standup".to_string(), RoomRole::Moderator),
+            ("*".to_string(), RoomRole::Participant),
+        ]),
+        ..TokenGrants::default()
+    };
+    assert_eq!(grants.role_for("standup"), RoomRole::Moderator);
+    assert_eq!(grants.role_for("other"), RoomRole::Participant);
+
+    let wildcard = TokenGrants {
+        room_roles: HashMap::from([("*".to_string(), RoomRole::Moderator)]),
+        ..TokenGrants::default()
+    };
+    assert_eq!(wildcard.role_for("anything"), RoomRole::Moderator);
+    assert_eq!(
+        TokenGrants::default().role_for("room"),
+        RoomRole::Participant
+    );
+}
+
+fn create_test_token(secret: &str, user_id: u32, username: &str, moderator: bool) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+
+    let mut claims = serde_json::json!({
+        "sub": user_id,
+        "username": username,
+        "iat": now,
+        "exp": now + 3600,
+    });
+    if moderator {
+        claims["room_roles"] = serde_json::json!({ "room": "moderator" });
+    }
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_validate_token_with_grants() {
+    let validator = JwtValidator::new("secret");
+
+    let (user, grants) = validator
+        .validate_token_with_grants(&create_test_token("secret", 7, "mod", true))
+        .unwrap();
+    assert_eq!(user.user_id, 7);
+    assert_eq!(grants.role_for("room"), RoomRole::Moderator);
+
+    let (_, grants) = validator
+        .validate_token_with_grants(&create_test_token("secret", 8, "alice", false))
+        .unwrap();
+    assert_eq!(grants, TokenGrants::default());
+}
+
+async fn connect_and_join(port: u16, token: String) -> Client {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::RoomJoined { .. }
+    ));
+    ws_stream
+}
+
+#[tokio::test]
+async fn test_moderation_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut moderator = connect_and_join(port, create_test_token(jwt_secret, 1, "mod", true)).await;
+    let mut alice = connect_and_join(port, create_test_token(jwt_secret, 2, "alice", false)).await;
+    assert!(matches!(
+        next_message(&mut moderator).await,
+        ServerMessage::UserJoined { .. }
+    ));
+
+    // Participants cannot moderate
+    send(
+        &mut alice,
+        ClientMessage::LockRoom {
+            room_name: "room".to_string(),
+            locked: true,
+        },
+    )
+    .await;
+    match next_message(&mut alice).await {
+        ServerMessage::Error { code, .. } => assert_eq!(code, Some(403)),
+        other => panic!("Expected error, got {:?}", other),
+    }
+
+    send(
+        &mut moderator,
+        ClientMessage::Ban {
+            room_name: "room".to_string(),
+            target_user_id: 2,
+            duration_seconds: Some(600),
+            reason: Some("abuse".to_string()),
+        },
+    )
+    .await;
+    match next_message(&mut alice).await {
+        ServerMessage::Banned {
+            by_user_id,
+            duration_seconds,
+            reason,
+            ..
+        } => {
+            assert_eq!(by_user_id, 1);
+            assert_eq!(duration_seconds, Some(600));
+            assert_eq!(reason.as_deref(), Some("abuse"));
+        }
+        other => panic!("Expected banned event, got {:?}", other),
+    }
+    match next_message(&mut moderator).await {
+        ServerMessage::UserLeft {
+            user_id, reason, ..
+        } => {
+            assert_eq!(user_id, 2);
+            assert_eq!(reason, Some(LeaveReason::Banned));
+        }
+        other => panic!("Expected user-left, got {:?}", other),
+    }
+
+    send(
+        &mut alice,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    match next_message(&mut alice).await {
+        ServerMessage::Error { message, code } => {
+            assert_eq!(code, Some(403));
+            assert!(message.contains("banned"));
+        }
+        other => panic!("Expected error, got {:?}", other),
+    }
+}
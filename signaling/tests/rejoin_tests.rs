@@ -0,0 +1,193 @@
+mod common;
+
+use futures_util::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+
+use common::{
+    connect_client, create_test_participant, create_test_token, find_available_port, next_message,
+    received, send, Client, TEST_OFFER_SDP,
+};
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::room::{
+    JoinError, JoinOutcome, LocalRoomManager, RoomManager, RoomManagerTrait, RoomParticipant,
+    CLOSE_CODE_REPLACED,
+};
+use webrtc_signaling::room_config::{RejoinPolicy, RoomConfig, RoomConfigStore};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+fn replacing_manager() -> LocalRoomManager {
+    let config = RoomConfig {
+        rejoin: RejoinPolicy::Replace,
+        ..RoomConfig::default()
+    };
+    LocalRoomManager::with_room_configs(Arc::new(RoomConfigStore::new(config)))
+}
+
+async fn join(
+    manager: &LocalRoomManager,
+    participant: RoomParticipant,
+) -> Result<JoinOutcome, JoinError> {
+    manager
+        .join_room_with("room".to_string(), participant, Default::default())
+        .await
+}
+
+/// Close code of the frame the connection was sent, if any
+fn close_code(rx: &mut mpsc::Receiver<Message>) -> Option<CloseCode> {
+    while let Ok(message) = rx.try_recv() {
+        if let Message::Close(Some(frame)) = message {
+            return Some(frame.code);
+        }
+    }
+    None
+}
+
+#[tokio::test]
+async fn test_rejoin_replaces_the_stale_connection() {
+    let manager = replacing_manager();
+    let (alice, mut stale_rx) = create_test_participant(1, "alice");
+    let (bob, mut bob_rx) = create_test_participant(2, "bob");
+    join(&manager, alice.clone()).await.unwrap();
+    join(&manager, bob).await.unwrap();
+    received(&mut bob_rx);
+
+    let (alice_again, mut alice_rx) = create_test_participant(1, "alice");
+    let outcome = join(&manager, alice_again.clone()).await.unwrap();
+    assert!(matches!(
+        outcome,
+        JoinOutcome::Joined(participants)
+            if participants.len() == 1 && participants[0].user_id == 2
+    ));
+
+    // Peers see one event, not a leave followed by a join
+    assert!(matches!(
+        received(&mut bob_rx).as_slice(),
+        [ServerMessage::ParticipantReplaced { user_id: 1, .. }]
+    ));
+    assert_eq!(
+        close_code(&mut stale_rx),
+        Some(CloseCode::from(CLOSE_CODE_REPLACED))
+    );
+
+    {
+        let rooms = manager.get_rooms();
+        let rooms = rooms.read().await;
+        let room = rooms.get("room").unwrap();
+        assert_eq!(
+            room.participants[&1].connection_id,
+            alice_again.connection_id
+        );
+        // The first joiner stays host
+        assert_eq!(room.host, Some(1));
+    }
+
+    // The stale connection closing does not take the new one out of the room
+    manager
+        .remove_user_from_all_rooms(1, alice.connection_id)
+        .await;
+    assert!(manager.user_in_room("room", 1).await);
+    assert!(received(&mut bob_rx).is_empty());
+
+    // Joining twice from the same connection is still refused
+    assert_eq!(
+        join(&manager, alice_again).await.unwrap_err(),
+        JoinError::AlreadyInRoom
+    );
+    assert!(received(&mut alice_rx).is_empty());
+}
+
+#[tokio::test]
+async fn test_rejoin_is_refused_by_default() {
+    let manager = LocalRoomManager::new();
+    let (alice, mut alice_rx) = create_test_participant(1, "alice");
+    join(&manager, alice).await.unwrap();
+
+    let (alice_again, _rx) = create_test_participant(1, "alice");
+    assert_eq!(
+        join(&manager, alice_again).await.unwrap_err(),
+        JoinError::AlreadyInRoom
+    );
+    assert_eq!(close_code(&mut alice_rx), None);
+}
+
+async fn join_room(client: &mut Client) -> ServerMessage {
+    send(
+        client,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    next_message(client).await
+}
+
+#[tokio::test]
+async fn test_rejoin_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::with_implementation(Box::new(replacing_manager())),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut stale = connect_client(port, create_test_token(jwt_secret, 1, "alice")).await;
+    let mut bob = connect_client(port, create_test_token(jwt_secret, 2, "bob")).await;
+    join_room(&mut stale).await;
+    join_room(&mut bob).await;
+    assert!(matches!(
+        next_message(&mut stale).await,
+        ServerMessage::UserJoined { .. }
+    ));
+
+    // A page refresh opens a new socket while the old one still looks alive
+    let mut alice = connect_client(port, create_test_token(jwt_secret, 1, "alice")).await;
+    assert!(matches!(
+        join_room(&mut alice).await,
+        ServerMessage::RoomJoined { participants, .. }
+            if participants.len() == 1 && participants[0].user_id == 2
+    ));
+    assert!(matches!(
+        next_message(&mut bob).await,
+        ServerMessage::ParticipantReplaced { user_id: 1, .. }
+    ));
+    let frame = tokio::time::timeout(Duration::from_secs(2), stale.next())
+        .await
+        .expect("Timed out waiting for close frame");
+    match frame {
+        Some(Ok(Message::Close(Some(frame)))) => {
+            assert_eq!(frame.code, CloseCode::from(CLOSE_CODE_REPLACED))
+        }
+        other => panic!("Expected close frame, got {:?}", other),
+    }
+
+    // Signaling reaches the new connection
+    send(
+        &mut bob,
+        ClientMessage::Offer {
+            room_name: "room".to_string(),
+            target_user_id: Some(1),
+            sdp: TEST_OFFER_SDP.to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::Offer {
+            from_user_id: 2,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,321 @@
+mod common;
+
+use argon2::password_hash::{PasswordHasher, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::connect_async;
+use uuid::Uuid;
+
+use common::{create_test_token, find_available_port, next_message, send};
+use webrtc_signaling::access::{self, LockoutPolicy};
+use webrtc_signaling::auth::AuthenticatedUser;
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::room::{
+    outbound_channel, JoinError, JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait,
+    RoomParticipant,
+};
+use webrtc_signaling::room_config::{RoomConfig, RoomConfigStore};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+const PASSWORD: &str = "correct horse battery staple";
+
+/// Hash with minimal Argon2 cost so tests that verify many times stay fast
+fn cheap_hash(password: &str) -> String {
+    let params = Params::new(Params::MIN_M_COST, 1, 1, None).unwrap();
+    let salt = SaltString::from_b64("c2lnbmFsaW5nc2FsdA").unwrap();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string()
+}
+
+fn create_test_participant(user_id: u32, username: &str) -> RoomParticipant {
+    let (tx, _rx) = outbound_channel();
+    RoomParticipant {
+        user: AuthenticatedUser {
+            user_id,
+            username: username.to_string(),
+            tenant: None,
+        },
+        connection_id: Uuid::new_v4(),
+        sender: tx,
+    }
+}
+
+fn request(password: Option<&str>, client_ip: &str) -> JoinRequest {
+    JoinRequest {
+        password: password.map(str::to_string),
+        client_ip: Some(client_ip.parse::<IpAddr>().unwrap()),
+        ..JoinRequest::default()
+    }
+}
+
+async fn protected_room_manager(policy: LockoutPolicy) -> LocalRoomManager {
+    let store = RoomConfigStore::default();
+    store
+        .set(
+            "private",
+            RoomConfig {
+                password_hash: Some(cheap_hash(PASSWORD)),
+                ..RoomConfig::default()
+            },
+        )
+        .await;
+    LocalRoomManager::with_room_configs(Arc::new(store)).with_lockout_policy(policy)
+}
+
+#[test]
+fn test_hash_and_verify_password() {
+    let hash = access::hash_password(PASSWORD).unwrap();
+    assert!(hash.starts_with("$argon2id$"));
+    assert!(!hash.contains(PASSWORD));
+
+    assert!(access::verify_password(&hash, PASSWORD));
+    assert!(!access::verify_password(&hash, "wrong"));
+    assert!(!access::verify_password("not a hash", PASSWORD));
+
+    // Salts are random, so equal passwords produce different hashes
+    assert_ne!(hash, access::hash_password(PASSWORD).unwrap());
+}
+
+#[tokio::test]
+async fn test_protected_room_requires_correct_password() {
+    let manager = protected_room_manager(LockoutPolicy::default()).await;
+
+    let result = manager
+        .join_room_with(
+            "private".to_string(),
+            create_test_participant(1, "alice"),
+            JoinRequest::default(),
+        )
+        .await;
+    assert_eq!(result.unwrap_err(), JoinError::PasswordRequired);
+
+    let result = manager
+        .join_room_with(
+            "private".to_string(),
+            create_test_participant(1, "alice"),
+            request(Some("guess"), "192.0.2.1"),
+        )
+        .await;
+    assert_eq!(result.unwrap_err(), JoinError::InvalidPassword);
+
+    let result = manager
+        .join_room_with(
+            "private".to_string(),
+            create_test_participant(1, "alice"),
+            request(Some(PASSWORD), "192.0.2.1"),
+        )
+        .await;
+    assert!(result.is_ok());
+    assert!(manager.user_in_room("private", 1).await);
+
+    // The credential-less join refuses protected rooms but leaves open rooms open
+    let result = manager
+        .join_room("private".to_string(), create_test_participant(2, "bob"))
+        .await;
+    assert_eq!(result.unwrap_err(), "Room requires a password".to_string());
+    assert!(manager
+        .join_room("public".to_string(), create_test_participant(2, "bob"))
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_repeated_failures_lock_out_user() {
+    let policy = LockoutPolicy {
+        max_failures: 3,
+        ..LockoutPolicy::default()
+    };
+    let manager = protected_room_manager(policy).await;
+
+    for _ in 0..3 {
+        let result = manager
+            .join_room_with(
+                "private".to_string(),
+                create_test_participant(1, "alice"),
+                request(Some("guess"), "192.0.2.1"),
+            )
+            .await;
+        assert_eq!(result.unwrap_err(), JoinError::InvalidPassword);
+    }
+
+    // Even the right password is refused while locked out, from any address
+    let result = manager
+        .join_room_with(
+            "private".to_string(),
+            create_test_participant(1, "alice"),
+            request(Some(PASSWORD), "198.51.100.1"),
+        )
+        .await;
+    match result {
+        Err(JoinError::LockedOut { retry_after }) => {
+            assert!(retry_after > Duration::from_secs(60))
+        }
+        other => panic!("Expected lockout, got {:?}", other),
+    }
+    assert!(!manager.user_in_room("private", 1).await);
+}
+
+#[tokio::test]
+async fn test_repeated_failures_lock_out_address() {
+    let policy = LockoutPolicy {
+        max_failures: 2,
+        ..LockoutPolicy::default()
+    };
+    let manager = protected_room_manager(policy).await;
+
+    // Rotating accounts from one address does not evade the lockout
+    for user_id in [1, 2] {
+        let result = manager
+            .join_room_with(
+                "private".to_string(),
+                create_test_participant(user_id, "guesser"),
+                request(Some("guess"), "192.0.2.1"),
+            )
+            .await;
+        assert_eq!(result.unwrap_err(), JoinError::InvalidPassword);
+    }
+
+    let result = manager
+        .join_room_with(
+            "private".to_string(),
+            create_test_participant(3, "guesser"),
+            request(Some(PASSWORD), "192.0.2.1"),
+        )
+        .await;
+    assert!(matches!(result, Err(JoinError::LockedOut { .. })));
+
+    // Other addresses are unaffected
+    let result = manager
+        .join_room_with(
+            "private".to_string(),
+            create_test_participant(4, "carol"),
+            request(Some(PASSWORD), "203.0.113.5"),
+        )
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_successful_join_resets_failures() {
+    let policy = LockoutPolicy {
+        max_failures: 2,
+        ..LockoutPolicy::default()
+    };
+    let manager = protected_room_manager(policy).await;
+
+    let join = |user_id: u32, password: &'static str, client_ip: &'static str| {
+        manager.join_room_with(
+            "private".to_string(),
+            create_test_participant(user_id, "guest"),
+            request(Some(password), client_ip),
+        )
+    };
+
+    assert_eq!(
+        join(1, "typo", "192.0.2.1").await.unwrap_err(),
+        JoinError::InvalidPassword
+    );
+    assert!(join(1, PASSWORD, "192.0.2.1").await.is_ok());
+    manager.leave_room("private", 1).await.unwrap();
+
+    // The user's earlier failure no longer counts
+    assert_eq!(
+        join(1, "typo", "203.0.113.5").await.unwrap_err(),
+        JoinError::InvalidPassword
+    );
+    assert!(join(1, PASSWORD, "203.0.113.5").await.is_ok());
+
+    // The address's does: one known password does not reset guessing from it
+    assert_eq!(
+        join(2, "guess", "192.0.2.1").await.unwrap_err(),
+        JoinError::InvalidPassword
+    );
+    assert!(matches!(
+        join(2, PASSWORD, "192.0.2.1").await,
+        Err(JoinError::LockedOut { .. })
+    ));
+}
+
+#[test]
+fn test_password_hash_from_room_config_file() {
+    let hash = cheap_hash(PASSWORD);
+    let store = RoomConfigStore::from_json(&format!(
+        r#"{{ "rooms": {{ "private": {{ "passwordHash": "{}" }} }} }}"#,
+        hash
+    ))
+    .unwrap();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let config = runtime.block_on(store.get("private"));
+    assert_eq!(config.password_hash, Some(hash));
+    assert_eq!(runtime.block_on(store.get("public")).password_hash, None);
+}
+
+#[tokio::test]
+async fn test_join_room_password_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+    let manager = protected_room_manager(LockoutPolicy::default()).await;
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::with_implementation(Box::new(manager)),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    let mut ws_stream = ws_stream;
+
+    send(
+        &mut ws_stream,
+        ClientMessage::Auth {
+            token: create_test_token(jwt_secret, 5, "alice"),
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "private".to_string(),
+            password: Some("guess".to_string()),
+        },
+    )
+    .await;
+    match next_message(&mut ws_stream).await {
+        ServerMessage::Error { message, code } => {
+            assert_eq!(code, Some(403));
+            assert!(message.contains("Invalid room password"));
+        }
+        other => panic!("Expected Error message, got {:?}", other),
+    }
+
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "private".to_string(),
+            password: Some(PASSWORD.to_string()),
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::RoomJoined { .. }
+    ));
+}
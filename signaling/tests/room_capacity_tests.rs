@@ -0,0 +1,277 @@
+mod common;
+
+use futures_util::{SinkExt, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{create_test_participant, create_test_token, find_available_port, received};
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::room::{
+    JoinError, JoinOutcome, JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait,
+    RoomParticipant,
+};
+use webrtc_signaling::room_config::{OverflowPolicy, RoomConfig, RoomConfigStore};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+fn manager_with_capacity(max_participants: usize, overflow: OverflowPolicy) -> LocalRoomManager {
+    let store = RoomConfigStore::new(RoomConfig {
+        max_participants: Some(max_participants),
+        overflow,
+        ..RoomConfig::default()
+    });
+    LocalRoomManager::with_room_configs(Arc::new(store))
+}
+
+async fn join(
+    manager: &LocalRoomManager,
+    participant: RoomParticipant,
+) -> Result<JoinOutcome, JoinError> {
+    manager
+        .join_room_with("room".to_string(), participant, JoinRequest::default())
+        .await
+}
+
+#[tokio::test]
+async fn test_full_room_rejects_join() {
+    let manager = manager_with_capacity(2, OverflowPolicy::Reject);
+
+    for user_id in [1, 2] {
+        let (participant, _rx) = create_test_participant(user_id, "member");
+        assert!(matches!(
+            join(&manager, participant).await,
+            Ok(JoinOutcome::Joined(_))
+        ));
+    }
+
+    let (participant, _rx) = create_test_participant(3, "late");
+    assert_eq!(
+        join(&manager, participant).await.unwrap_err(),
+        JoinError::RoomFull { capacity: 2 }
+    );
+    assert_eq!(manager.get_room_participants("room").await.len(), 2);
+
+    // A slot opens once someone leaves
+    manager.leave_room("room", 1).await.unwrap();
+    let (participant, _rx) = create_test_participant(3, "late");
+    assert!(join(&manager, participant).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_default_capacity_applies_to_unlisted_rooms() {
+    let store = RoomConfigStore::from_json(
+        r#"{
+            "default": { "maxParticipants": 1 },
+            "rooms": { "large": { "maxParticipants": 50 } }
+        }"#,
+    )
+    .unwrap();
+    let manager = LocalRoomManager::with_room_configs(Arc::new(store));
+
+    let (alice, _rx) = create_test_participant(1, "alice");
+    let (bob, _rx) = create_test_participant(2, "bob");
+    assert!(manager.join_room("room".to_string(), alice).await.is_ok());
+    assert_eq!(
+        manager
+            .join_room("room".to_string(), bob)
+            .await
+            .unwrap_err(),
+        "Room is full (1 participants)"
+    );
+
+    let (alice, _rx) = create_test_participant(1, "alice");
+    let (bob, _rx) = create_test_participant(2, "bob");
+    assert!(manager.join_room("large".to_string(), alice).await.is_ok());
+    assert!(manager.join_room("large".to_string(), bob).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_concurrent_joins_respect_capacity() {
+    let manager = Arc::new(manager_with_capacity(5, OverflowPolicy::Reject));
+
+    let handles: Vec<_> = (1..=20)
+        .map(|user_id| {
+            let manager = Arc::clone(&manager);
+            tokio::spawn(async move {
+                let (participant, _rx) = create_test_participant(user_id, "racer");
+                join(&manager, participant).await
+            })
+        })
+        .collect();
+
+    let mut joined = 0;
+    for handle in handles {
+        if handle.await.unwrap().is_ok() {
+            joined += 1;
+        }
+    }
+    assert_eq!(joined, 5);
+    assert_eq!(manager.get_room_participants("room").await.len(), 5);
+}
+
+#[tokio::test]
+async fn test_waitlist_admits_in_order() {
+    let manager = manager_with_capacity(1, OverflowPolicy::Waitlist);
+
+    let (alice, _alice_rx) = create_test_participant(1, "alice");
+    let (bob, mut bob_rx) = create_test_participant(2, "bob");
+    let (carol, mut carol_rx) = create_test_participant(3, "carol");
+    join(&manager, alice).await.unwrap();
+
+    assert!(matches!(
+        join(&manager, bob).await,
+        Ok(JoinOutcome::Waitlisted { position: 1 })
+    ));
+    assert!(matches!(
+        join(&manager, carol).await,
+        Ok(JoinOutcome::Waitlisted { position: 2 })
+    ));
+    // Waiting users are not in the room and cannot queue twice
+    assert!(!manager.user_in_room("room", 2).await);
+    let (bob_again, _rx) = create_test_participant(2, "bob");
+    assert_eq!(
+        join(&manager, bob_again).await.unwrap_err(),
+        JoinError::AlreadyInRoom
+    );
+
+    manager.leave_room("room", 1).await.unwrap();
+
+    assert!(manager.user_in_room("room", 2).await);
+    assert!(!manager.user_in_room("room", 3).await);
+    match received(&mut bob_rx).as_slice() {
+        [ServerMessage::RoomJoined {
+            participants,
+            spectator,
+            ..
+        }] => {
+            assert!(participants.is_empty());
+            assert!(!spectator);
+        }
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+    match received(&mut carol_rx).as_slice() {
+        [ServerMessage::Waitlisted { position, .. }] => assert_eq!(*position, 1),
+        other => panic!("Expected waitlisted, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_disconnect_leaves_waitlist() {
+    let manager = manager_with_capacity(1, OverflowPolicy::Waitlist);
+
+    let (alice, _alice_rx) = create_test_participant(1, "alice");
+    let (bob, _bob_rx) = create_test_participant(2, "bob");
+    let (carol, _carol_rx) = create_test_participant(3, "carol");
+    let bob_connection = bob.connection_id;
+    join(&manager, alice).await.unwrap();
+    join(&manager, bob).await.unwrap();
+    join(&manager, carol).await.unwrap();
+
+    manager.remove_user_from_all_rooms(2, bob_connection).await;
+    manager.leave_room("room", 1).await.unwrap();
+
+    assert!(!manager.user_in_room("room", 2).await);
+    assert!(manager.user_in_room("room", 3).await);
+
+    // Leaving the waitlist explicitly also works
+    let (dave, _dave_rx) = create_test_participant(4, "dave");
+    join(&manager, dave).await.unwrap();
+    assert!(manager.leave_room("room", 4).await.is_ok());
+    let rooms = manager.get_rooms();
+    assert!(rooms.read().await["room"].waitlist.is_empty());
+}
+
+#[tokio::test]
+async fn test_spectators_do_not_count_towards_capacity() {
+    let manager = manager_with_capacity(1, OverflowPolicy::Spectator);
+
+    let (alice, _alice_rx) = create_test_participant(1, "alice");
+    let (bob, _bob_rx) = create_test_participant(2, "bob");
+    let (carol, _carol_rx) = create_test_participant(3, "carol");
+    assert!(matches!(
+        join(&manager, alice).await,
+        Ok(JoinOutcome::Joined(_))
+    ));
+    assert!(matches!(
+        join(&manager, bob).await,
+        Ok(JoinOutcome::Spectating(_))
+    ));
+    assert!(matches!(
+        join(&manager, carol).await,
+        Ok(JoinOutcome::Spectating(_))
+    ));
+
+    {
+        let rooms = manager.get_rooms();
+        let rooms = rooms.read().await;
+        assert_eq!(rooms["room"].participants.len(), 3);
+        assert_eq!(rooms["room"].active_count(), 1);
+    }
+
+    // Once the participant leaves, the next join takes the free slot
+    manager.leave_room("room", 1).await.unwrap();
+    let (dave, _dave_rx) = create_test_participant(4, "dave");
+    assert!(matches!(
+        join(&manager, dave).await,
+        Ok(JoinOutcome::Joined(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_room_full_event_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+    let manager = manager_with_capacity(1, OverflowPolicy::Reject);
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::with_implementation(Box::new(manager)),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut responses = Vec::new();
+    let mut clients = Vec::new();
+    for (user_id, username) in [(1, "alice"), (2, "bob")] {
+        let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+            .await
+            .expect("Failed to connect");
+        for msg in [
+            ClientMessage::Auth {
+                token: create_test_token(jwt_secret, user_id, username),
+            },
+            ClientMessage::JoinRoom {
+                room_name: "room".to_string(),
+                password: None,
+            },
+        ] {
+            let text = serde_json::to_string(&msg).unwrap();
+            ws_stream.send(Message::Text(text)).await.unwrap();
+            let response = tokio::time::timeout(Duration::from_secs(2), ws_stream.next())
+                .await
+                .expect("Timed out waiting for message")
+                .unwrap()
+                .unwrap();
+            responses
+                .push(serde_json::from_str::<ServerMessage>(response.to_text().unwrap()).unwrap());
+        }
+        clients.push(ws_stream);
+    }
+
+    assert!(matches!(responses[1], ServerMessage::RoomJoined { .. }));
+    match &responses[3] {
+        ServerMessage::RoomFull {
+            room_name,
+            capacity,
+        } => {
+            assert_eq!(room_name, "room");
+            assert_eq!(*capacity, 1);
+        }
+        other => panic!("Expected room-full, got {:?}", other),
+    }
+}
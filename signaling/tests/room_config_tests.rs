@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use webrtc_signaling::room::{LocalRoomManager, RoomManager};
+use webrtc_signaling::room_config::{RoomConfig, RoomConfigStore};
+use webrtc_signaling::sdp::SdpPolicy;
+
+#[tokio::test]
+async fn test_room_config_from_json() {
+    let store = RoomConfigStore::from_json(
+        r#"{
+            "default": { "sdpPolicy": { "maxVideoBitrateKbps": 2500 } },
+            "rooms": {
+                "webinar": {
+                    "sdpPolicy": {
+                        "preferredCodecs": ["VP9", "VP8"],
+                        "allowedCodecs": ["opus", "VP9", "VP8"]
+                    }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let webinar = store.get("webinar").await;
+    assert_eq!(webinar.sdp_policy.preferred_codecs, vec!["VP9", "VP8"]);
+    assert_eq!(webinar.sdp_policy.max_video_bitrate_kbps, None);
+
+    let other = store.get("standup").await;
+    assert_eq!(other.sdp_policy.max_video_bitrate_kbps, Some(2500));
+}
+
+#[tokio::test]
+async fn test_room_config_rejects_invalid_json() {
+    let result = RoomConfigStore::from_json(r#"{ "rooms": { "a": { "sdpPolicy": 5 } } }"#);
+    assert!(result.is_err());
+    assert!(result
+        .unwrap_err()
+        .starts_with("Invalid room configuration"));
+}
+
+#[tokio::test]
+async fn test_room_manager_exposes_room_config() {
+    let store = Arc::new(RoomConfigStore::default());
+    let policy = SdpPolicy {
+        max_audio_bitrate_kbps: Some(32),
+        ..SdpPolicy::default()
+    };
+    store
+        .set(
+            "quiet_room",
+            RoomConfig {
+                sdp_policy: policy.clone(),
+                ..RoomConfig::default()
+            },
+        )
+        .await;
+
+    let manager =
+        RoomManager::with_implementation(Box::new(LocalRoomManager::with_room_configs(store)));
+
+    assert_eq!(manager.room_config("quiet_room").await.sdp_policy, policy);
+    assert!(manager.room_config("other").await.sdp_policy.is_empty());
+}
+
+#[tokio::test]
+async fn test_persistent_rooms_from_json() {
+    let store = RoomConfigStore::from_json(
+        r#"{
+            "default": { "emptyTtlSecs": 60 },
+            "rooms": {
+                "standup": { "persistent": true },
+                "adhoc": {}
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let rooms = store.persistent_rooms().await;
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(rooms[0].0, "standup");
+    assert_eq!(
+        store.get("other").await.empty_ttl(),
+        Some(std::time::Duration::from_secs(60))
+    );
+    assert_eq!(store.get("adhoc").await.empty_ttl(), None);
+}
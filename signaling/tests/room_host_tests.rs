@@ -0,0 +1,272 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{
+    create_test_participant, create_test_token, find_available_port, next_message, received, send,
+    Client,
+};
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::moderation::{ModerationError, ModeratorAction, RoomRole};
+use webrtc_signaling::room::{
+    JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait, RoomOwnership,
+};
+use webrtc_signaling::room_config::{HostTransferPolicy, RoomConfig, RoomConfigStore};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+async fn join_as(
+    manager: &LocalRoomManager,
+    user_id: u32,
+    username: &str,
+    role: RoomRole,
+) -> mpsc::Receiver<Message> {
+    let (participant, rx) = create_test_participant(user_id, username);
+    let request = JoinRequest {
+        role,
+        ..JoinRequest::default()
+    };
+    manager
+        .join_room_with("room".to_string(), participant, request)
+        .await
+        .unwrap();
+    rx
+}
+
+async fn join(manager: &LocalRoomManager, user_id: u32, username: &str) -> mpsc::Receiver<Message> {
+    join_as(manager, user_id, username, RoomRole::Participant).await
+}
+
+fn host_changes(messages: &[ServerMessage]) -> Vec<(Option<u32>, Option<u32>)> {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            ServerMessage::HostChanged {
+                host_user_id,
+                previous_host_user_id,
+                ..
+            } => Some((*host_user_id, *previous_host_user_id)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_first_joiner_owns_and_hosts_room() {
+    let manager = LocalRoomManager::new();
+    let _alice_rx = join(&manager, 1, "alice").await;
+    let mut bob_rx = join(&manager, 2, "bob").await;
+
+    assert_eq!(
+        manager.room_ownership("room").await,
+        RoomOwnership {
+            owner: Some(1),
+            host: Some(1),
+        }
+    );
+    assert!(host_changes(&received(&mut bob_rx)).is_empty());
+    assert_eq!(
+        manager.room_ownership("missing").await,
+        RoomOwnership::default()
+    );
+}
+
+#[tokio::test]
+async fn test_host_claim_takes_over_room() {
+    let manager = LocalRoomManager::new();
+    let mut alice_rx = join(&manager, 1, "alice").await;
+    let _bob_rx = join_as(&manager, 2, "bob", RoomRole::Host).await;
+
+    assert_eq!(
+        manager.room_ownership("room").await,
+        RoomOwnership {
+            owner: Some(2),
+            host: Some(2),
+        }
+    );
+    assert_eq!(
+        host_changes(&received(&mut alice_rx)),
+        vec![(Some(2), Some(1))]
+    );
+}
+
+#[tokio::test]
+async fn test_transfer_host_requires_host() {
+    let manager = LocalRoomManager::new();
+    let mut alice_rx = join(&manager, 1, "alice").await;
+    let mut bob_rx = join(&manager, 2, "bob").await;
+    received(&mut alice_rx);
+
+    assert_eq!(
+        manager
+            .moderate("room", 2, ModeratorAction::TransferHost { target: 1 })
+            .await
+            .unwrap_err(),
+        ModerationError::NotHost
+    );
+    assert_eq!(
+        manager
+            .moderate("room", 1, ModeratorAction::TransferHost { target: 9 })
+            .await
+            .unwrap_err(),
+        ModerationError::TargetNotFound
+    );
+
+    manager
+        .moderate("room", 1, ModeratorAction::TransferHost { target: 2 })
+        .await
+        .unwrap();
+    for rx in [&mut alice_rx, &mut bob_rx] {
+        assert_eq!(host_changes(&received(rx)), vec![(Some(2), Some(1))]);
+    }
+
+    // The owner stays the same and the new host may hand the role back
+    assert_eq!(manager.room_ownership("room").await.owner, Some(1));
+    assert!(manager
+        .moderate("room", 2, ModeratorAction::TransferHost { target: 1 })
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_host_leaving_promotes_longest_present() {
+    let manager = LocalRoomManager::new();
+    let _alice_rx = join(&manager, 1, "alice").await;
+    let mut bob_rx = join(&manager, 3, "bob").await;
+    let mut carol_rx = join(&manager, 2, "carol").await;
+    received(&mut bob_rx);
+
+    manager.leave_room("room", 1).await.unwrap();
+
+    for rx in [&mut bob_rx, &mut carol_rx] {
+        assert_eq!(host_changes(&received(rx)), vec![(Some(3), Some(1))]);
+    }
+    assert_eq!(
+        manager.room_ownership("room").await,
+        RoomOwnership {
+            owner: Some(1),
+            host: Some(3),
+        }
+    );
+
+    // Non-hosts leaving do not move the role
+    manager.leave_room("room", 2).await.unwrap();
+    assert!(host_changes(&received(&mut bob_rx)).is_empty());
+}
+
+#[tokio::test]
+async fn test_vacant_policy_waits_for_owner() {
+    let store = RoomConfigStore::new(RoomConfig {
+        host_transfer: HostTransferPolicy::Vacant,
+        ..RoomConfig::default()
+    });
+    let manager = LocalRoomManager::with_room_configs(Arc::new(store));
+    let _alice_rx = join(&manager, 1, "alice").await;
+    let mut bob_rx = join(&manager, 2, "bob").await;
+
+    manager.leave_room("room", 1).await.unwrap();
+    assert_eq!(host_changes(&received(&mut bob_rx)), vec![(None, Some(1))]);
+
+    // Other joiners do not take the vacant role, the returning owner does
+    let _carol_rx = join(&manager, 3, "carol").await;
+    assert_eq!(manager.room_ownership("room").await.host, None);
+
+    let _alice_rx = join(&manager, 1, "alice").await;
+    assert_eq!(host_changes(&received(&mut bob_rx)), vec![(Some(1), None)]);
+    assert_eq!(manager.room_ownership("room").await.host, Some(1));
+}
+
+async fn connect_and_join(port: u16, token: String) -> (Client, ServerMessage) {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    let joined = next_message(&mut ws_stream).await;
+    (ws_stream, joined)
+}
+
+#[tokio::test]
+async fn test_host_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (mut alice, _) = connect_and_join(port, create_test_token(jwt_secret, 1, "alice")).await;
+    let (mut bob, joined) = connect_and_join(port, create_test_token(jwt_secret, 2, "bob")).await;
+    match joined {
+        ServerMessage::RoomJoined {
+            owner_user_id,
+            host_user_id,
+            ..
+        } => {
+            assert_eq!(owner_user_id, Some(1));
+            assert_eq!(host_user_id, Some(1));
+        }
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::UserJoined { .. }
+    ));
+
+    send(
+        &mut alice,
+        ClientMessage::TransferHost {
+            room_name: "room".to_string(),
+            target_user_id: 2,
+        },
+    )
+    .await;
+    for ws_stream in [&mut alice, &mut bob] {
+        match next_message(ws_stream).await {
+            ServerMessage::HostChanged {
+                host_user_id,
+                previous_host_user_id,
+                ..
+            } => {
+                assert_eq!(host_user_id, Some(2));
+                assert_eq!(previous_host_user_id, Some(1));
+            }
+            other => panic!("Expected host-changed, got {:?}", other),
+        }
+    }
+
+    // Only the host may hand the role on
+    send(
+        &mut alice,
+        ClientMessage::TransferHost {
+            room_name: "room".to_string(),
+            target_user_id: 1,
+        },
+    )
+    .await;
+    match next_message(&mut alice).await {
+        ServerMessage::Error { code, .. } => assert_eq!(code, Some(403)),
+        other => panic!("Expected error, got {:?}", other),
+    }
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use common::create_test_participant;
+use webrtc_signaling::moderation::ModeratorAction;
+use webrtc_signaling::room::{JoinError, JoinRequest, LocalRoomManager, RoomManagerTrait};
+use webrtc_signaling::room_config::{RoomConfig, RoomConfigStore};
+
+async fn join(
+    manager: &LocalRoomManager,
+    room_name: &str,
+    user_id: u32,
+) -> Result<mpsc::Receiver<Message>, JoinError> {
+    let (participant, rx) = create_test_participant(user_id, &format!("user{}", user_id));
+    manager
+        .join_room_with(room_name.to_string(), participant, JoinRequest::default())
+        .await?;
+    Ok(rx)
+}
+
+async fn room_exists(manager: &LocalRoomManager, room_name: &str) -> bool {
+    manager.get_rooms().read().await.contains_key(room_name)
+}
+
+#[tokio::test]
+async fn test_ad_hoc_room_removed_when_empty() {
+    let manager = LocalRoomManager::new();
+    let _rx = join(&manager, "adhoc", 1).await.unwrap();
+    assert!(room_exists(&manager, "adhoc").await);
+
+    manager.leave_room("adhoc", 1).await.unwrap();
+    assert!(!room_exists(&manager, "adhoc").await);
+}
+
+#[tokio::test]
+async fn test_persistent_room_keeps_state_while_empty() {
+    let manager = LocalRoomManager::new();
+    manager
+        .create_room("standup", RoomConfig::default())
+        .await
+        .unwrap();
+    assert!(room_exists(&manager, "standup").await);
+    assert!(manager.room_config("standup").await.persistent);
+
+    let _rx = join(&manager, "standup", 1).await.unwrap();
+    manager
+        .moderate("standup", 1, ModeratorAction::Lock { locked: true })
+        .await
+        .unwrap();
+    manager.leave_room("standup", 1).await.unwrap();
+
+    // The lock and owner outlive the last participant
+    assert!(room_exists(&manager, "standup").await);
+    assert_eq!(manager.room_ownership("standup").await.owner, Some(1));
+    assert_eq!(
+        join(&manager, "standup", 2).await.unwrap_err(),
+        JoinError::RoomLocked
+    );
+}
+
+#[tokio::test]
+async fn test_delete_room_removes_definition() {
+    let manager = LocalRoomManager::new();
+    manager
+        .create_room("standup", RoomConfig::default())
+        .await
+        .unwrap();
+    let _rx = join(&manager, "standup", 1).await.unwrap();
+
+    // Occupied rooms stay until the last participant leaves
+    manager.delete_room("standup").await.unwrap();
+    assert!(room_exists(&manager, "standup").await);
+    assert!(!manager.room_config("standup").await.persistent);
+
+    manager.leave_room("standup", 1).await.unwrap();
+    assert!(!room_exists(&manager, "standup").await);
+    assert!(manager.delete_room("standup").await.is_err());
+    assert!(manager
+        .create_room("", RoomConfig::default())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_empty_room_ttl() {
+    let store = RoomConfigStore::new(RoomConfig {
+        empty_ttl_secs: Some(1),
+        ..RoomConfig::default()
+    });
+    let manager = LocalRoomManager::with_room_configs(Arc::new(store));
+
+    let _rx = join(&manager, "adhoc", 1).await.unwrap();
+    manager.leave_room("adhoc", 1).await.unwrap();
+    assert!(room_exists(&manager, "adhoc").await);
+
+    // Using the room again restarts the countdown
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    let _rx = join(&manager, "adhoc", 2).await.unwrap();
+    manager.leave_room("adhoc", 2).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert!(room_exists(&manager, "adhoc").await);
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert!(!room_exists(&manager, "adhoc").await);
+}
@@ -0,0 +1,225 @@
+mod common;
+
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::time::Duration;
+use tokio_tungstenite::connect_async;
+
+use common::{find_available_port, next_message, send, Client};
+use webrtc_signaling::messages::{BreakoutAssignment, ClientMessage, ServerMessage};
+use webrtc_signaling::room::RoomManager;
+use webrtc_signaling::room_name::{RoomNameCharset, RoomNameError, RoomNamePolicy};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+#[test]
+fn test_default_policy() {
+    let policy = RoomNamePolicy::default();
+
+    assert_eq!(policy.apply("standup").unwrap(), "standup");
+    assert_eq!(policy.apply("Team-1.eu/a_b").unwrap(), "Team-1.eu/a_b");
+    assert_eq!(policy.apply(""), Err(RoomNameError::Empty));
+    assert_eq!(
+        policy.apply("stand up"),
+        Err(RoomNameError::InvalidCharacter(' '))
+    );
+    assert_eq!(
+        policy.apply("rooms:1"),
+        Err(RoomNameError::InvalidCharacter(':'))
+    );
+    assert_eq!(
+        policy.apply("caf\u{e9}"),
+        Err(RoomNameError::InvalidCharacter('\u{e9}'))
+    );
+    assert_eq!(policy.apply("/standup"), Err(RoomNameError::EmptySegment));
+    assert_eq!(policy.apply("a//b"), Err(RoomNameError::EmptySegment));
+    assert_eq!(policy.validate_join("standup"), Ok(()));
+    assert_eq!(
+        policy.validate_join("standup/a"),
+        Err(RoomNameError::BreakoutRoom)
+    );
+    assert_eq!(
+        policy.apply(&"a".repeat(129)),
+        Err(RoomNameError::TooLong { max: 128 })
+    );
+    assert_eq!(
+        policy.apply(&"a".repeat(4 * 1024 * 1024)),
+        Err(RoomNameError::TooLong { max: 128 })
+    );
+}
+
+#[test]
+fn test_reserved_prefixes() {
+    let policy = RoomNamePolicy {
+        reserved_prefixes: vec!["system".to_string(), "_".to_string()],
+        ..RoomNamePolicy::default()
+    };
+
+    assert_eq!(
+        policy.apply("system-status"),
+        Err(RoomNameError::ReservedPrefix("system".to_string()))
+    );
+    assert_eq!(
+        policy.apply("_hidden"),
+        Err(RoomNameError::ReservedPrefix("_".to_string()))
+    );
+    assert!(policy.apply("my_system").is_ok());
+    assert_eq!(RoomNameError::Empty.code(), 400);
+}
+
+#[test]
+fn test_normalization() {
+    let policy = RoomNamePolicy {
+        charset: RoomNameCharset::Unicode,
+        normalize_nfc: true,
+        case_fold: true,
+        ..RoomNamePolicy::default()
+    };
+
+    // A decomposed accent composes to the same room as the precomposed one
+    assert_eq!(policy.apply("Cafe\u{301}").unwrap(), "caf\u{e9}");
+    assert_eq!(policy.apply("CAF\u{c9}").unwrap(), "caf\u{e9}");
+    assert_eq!(
+        policy.apply("\u{41f}\u{43b}\u{430}\u{43d}").unwrap(),
+        "\u{43f}\u{43b}\u{430}\u{43d}"
+    );
+    assert_eq!(
+        policy.apply("caf\u{e9} bar"),
+        Err(RoomNameError::InvalidCharacter(' '))
+    );
+
+    let unnormalized = RoomNamePolicy {
+        charset: RoomNameCharset::Unicode,
+        ..RoomNamePolicy::default()
+    };
+    // Without composing, the combining accent is not a letter of its own
+    assert_eq!(
+        unnormalized.apply("Cafe\u{301}"),
+        Err(RoomNameError::InvalidCharacter('\u{301}'))
+    );
+}
+
+fn create_test_token(secret: &str, user_id: u32, username: &str) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+
+    let claims = serde_json::json!({
+        "sub": user_id,
+        "username": username,
+        "iat": now,
+        "exp": now + 3600,
+        "room_roles": {"*": "host"},
+    });
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap()
+}
+
+async fn connect_and_join(port: u16, token: String, room_name: &str) -> (Client, ServerMessage) {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: room_name.to_string(),
+            password: None,
+        },
+    )
+    .await;
+    let reply = next_message(&mut ws_stream).await;
+    (ws_stream, reply)
+}
+
+#[tokio::test]
+async fn test_room_names_checked_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    let config = ServerConfig {
+        room_names: RoomNamePolicy {
+            case_fold: true,
+            ..RoomNamePolicy::default()
+        },
+        ..ServerConfig::default()
+    };
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            config,
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (_, reply) =
+        connect_and_join(port, create_test_token(jwt_secret, 1, "alice"), "stand up").await;
+    match reply {
+        ServerMessage::Error { message, code } => {
+            assert_eq!(code, Some(400));
+            assert!(message.contains("invalid character"));
+        }
+        other => panic!("Expected error, got {:?}", other),
+    }
+
+    // Breakout rooms are not joined by name
+    let (_, reply) =
+        connect_and_join(port, create_test_token(jwt_secret, 1, "alice"), "standup/a").await;
+    match reply {
+        ServerMessage::Error { message, code } => {
+            assert_eq!(code, Some(403));
+            assert!(message.contains("Breakout rooms"));
+        }
+        other => panic!("Expected error, got {:?}", other),
+    }
+
+    // Both spellings reach the same room
+    let (mut alice, reply) =
+        connect_and_join(port, create_test_token(jwt_secret, 1, "alice"), "Standup").await;
+    match reply {
+        ServerMessage::RoomJoined { room_name, .. } => assert_eq!(room_name, "standup"),
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+    let (_bob, reply) =
+        connect_and_join(port, create_test_token(jwt_secret, 2, "bob"), "STANDUP").await;
+    match reply {
+        ServerMessage::RoomJoined { participants, .. } => assert_eq!(participants.len(), 1),
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::UserJoined { .. }
+    ));
+
+    // Breakout names follow the same rules
+    send(
+        &mut alice,
+        ClientMessage::CreateBreakouts {
+            room_name: "standup".to_string(),
+            breakouts: vec![BreakoutAssignment {
+                name: "Bad Name".to_string(),
+                user_ids: vec![2],
+            }],
+        },
+    )
+    .await;
+    loop {
+        if let ServerMessage::Error { message, code } = next_message(&mut alice).await {
+            assert_eq!(code, Some(400));
+            assert!(message.starts_with("Invalid room name"));
+            break;
+        }
+    }
+}
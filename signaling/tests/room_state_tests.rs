@@ -0,0 +1,270 @@
+mod common;
+
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{
+    create_test_participant, create_test_token, find_available_port, next_message, received, send,
+    Client,
+};
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::room::{JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait};
+use webrtc_signaling::room_config::{OverflowPolicy, RoomConfig, RoomConfigStore};
+use webrtc_signaling::room_state::{RoomStateError, RoomStateUpdate, MAX_KEYS};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+async fn join(manager: &LocalRoomManager, user_id: u32, username: &str) -> mpsc::Receiver<Message> {
+    let (participant, rx) = create_test_participant(user_id, username);
+    manager
+        .join_room("room".to_string(), participant)
+        .await
+        .unwrap();
+    rx
+}
+
+fn set(key: &str, value: serde_json::Value, expected_version: Option<u64>) -> RoomStateUpdate {
+    RoomStateUpdate {
+        key: key.to_string(),
+        value: Some(value),
+        expected_version,
+    }
+}
+
+fn delete(key: &str, expected_version: Option<u64>) -> RoomStateUpdate {
+    RoomStateUpdate {
+        key: key.to_string(),
+        value: None,
+        expected_version,
+    }
+}
+
+#[tokio::test]
+async fn test_set_broadcasts_to_room() {
+    let manager = LocalRoomManager::new();
+    let mut alice_rx = join(&manager, 1, "alice").await;
+    let mut bob_rx = join(&manager, 2, "bob").await;
+    received(&mut alice_rx);
+    received(&mut bob_rx);
+
+    let version = manager
+        .update_room_state("room", 1, set("slide", json!({"page": 3}), None))
+        .await
+        .unwrap();
+    assert_eq!(version, 1);
+
+    for rx in [&mut alice_rx, &mut bob_rx] {
+        match received(rx).as_slice() {
+            [ServerMessage::RoomStateChanged {
+                key,
+                value,
+                version,
+                by_user_id,
+                ..
+            }] => {
+                assert_eq!(key, "slide");
+                assert_eq!(value, &Some(json!({"page": 3})));
+                assert_eq!(*version, 1);
+                assert_eq!(*by_user_id, 1);
+            }
+            other => panic!("Expected room-state-changed, got {:?}", other),
+        }
+    }
+    let state = manager.room_state("room").await;
+    assert_eq!(state["slide"].value, json!({"page": 3}));
+    assert_eq!(state["slide"].version, 1);
+}
+
+#[tokio::test]
+async fn test_stale_version_is_rejected() {
+    let manager = LocalRoomManager::new();
+    let mut alice_rx = join(&manager, 1, "alice").await;
+    let _bob_rx = join(&manager, 2, "bob").await;
+
+    // Both start from an absent key; only the first write lands
+    manager
+        .update_room_state("room", 1, set("slide", json!(1), Some(0)))
+        .await
+        .unwrap();
+    received(&mut alice_rx);
+    let result = manager
+        .update_room_state("room", 2, set("slide", json!(2), Some(0)))
+        .await;
+    assert_eq!(result, Err(RoomStateError::VersionConflict { current: 1 }));
+    assert_eq!(result.unwrap_err().code(), 409);
+    assert!(received(&mut alice_rx).is_empty());
+
+    let version = manager
+        .update_room_state("room", 2, set("slide", json!(2), Some(1)))
+        .await
+        .unwrap();
+    assert_eq!(version, 2);
+    assert_eq!(manager.room_state("room").await["slide"].value, json!(2));
+}
+
+#[tokio::test]
+async fn test_delete_and_limits() {
+    let manager = LocalRoomManager::new();
+    let _alice_rx = join(&manager, 1, "alice").await;
+
+    assert_eq!(
+        manager
+            .update_room_state("room", 1, delete("slide", None))
+            .await,
+        Err(RoomStateError::KeyNotFound)
+    );
+    manager
+        .update_room_state("room", 1, set("slide", json!(1), None))
+        .await
+        .unwrap();
+    // Versions keep counting across deletes so a re-set key never reuses one
+    assert_eq!(
+        manager
+            .update_room_state("room", 1, delete("slide", Some(1)))
+            .await,
+        Ok(2)
+    );
+    assert_eq!(
+        manager
+            .update_room_state("room", 1, set("slide", json!(1), Some(0)))
+            .await,
+        Ok(3)
+    );
+
+    assert!(matches!(
+        manager
+            .update_room_state("room", 1, set("", json!(1), None))
+            .await,
+        Err(RoomStateError::Invalid(_))
+    ));
+    assert_eq!(
+        manager
+            .update_room_state("room", 2, set("slide", json!(1), None))
+            .await,
+        Err(RoomStateError::NotInRoom)
+    );
+
+    for index in 1..MAX_KEYS {
+        manager
+            .update_room_state("room", 1, set(&format!("key{}", index), json!(index), None))
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        manager
+            .update_room_state("room", 1, set("one-too-many", json!(true), None))
+            .await,
+        Err(RoomStateError::TooManyKeys)
+    );
+}
+
+#[tokio::test]
+async fn test_admitted_joiner_receives_state() {
+    let store = RoomConfigStore::new(RoomConfig {
+        max_participants: Some(1),
+        overflow: OverflowPolicy::Waitlist,
+        ..RoomConfig::default()
+    });
+    let manager = LocalRoomManager::with_room_configs(Arc::new(store));
+    let _alice_rx = join(&manager, 1, "alice").await;
+    manager
+        .update_room_state("room", 1, set("slide", json!({"page": 3}), None))
+        .await
+        .unwrap();
+
+    // Bob waits for a slot and gets the state along with it
+    let (bob, mut bob_rx) = create_test_participant(2, "bob");
+    manager
+        .join_room_with("room".to_string(), bob, JoinRequest::default())
+        .await
+        .unwrap();
+    manager.leave_room("room", 1).await.unwrap();
+    match received(&mut bob_rx).last() {
+        Some(ServerMessage::RoomJoined { state, .. }) => {
+            assert_eq!(state.len(), 1);
+            assert_eq!(state["slide"].value, json!({"page": 3}));
+            assert_eq!(state["slide"].version, 1);
+        }
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+}
+
+async fn connect_and_join(port: u16, token: String) -> (Client, ServerMessage) {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    let joined = next_message(&mut ws_stream).await;
+    (ws_stream, joined)
+}
+
+#[tokio::test]
+async fn test_room_state_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (mut alice, _) = connect_and_join(port, create_test_token(jwt_secret, 1, "alice")).await;
+    send(
+        &mut alice,
+        ClientMessage::SetRoomState {
+            room_name: "room".to_string(),
+            key: "slide".to_string(),
+            value: json!(3),
+            expected_version: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::RoomStateChanged { version: 1, .. }
+    ));
+
+    let (mut bob, joined) = connect_and_join(port, create_test_token(jwt_secret, 2, "bob")).await;
+    match joined {
+        ServerMessage::RoomJoined { state, .. } => assert_eq!(state["slide"].value, json!(3)),
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+
+    send(
+        &mut bob,
+        ClientMessage::DeleteRoomState {
+            room_name: "room".to_string(),
+            key: "slide".to_string(),
+            expected_version: Some(0),
+        },
+    )
+    .await;
+    match next_message(&mut bob).await {
+        ServerMessage::Error { message, code } => {
+            assert_eq!(code, Some(409));
+            assert!(message.contains("version 1"));
+        }
+        other => panic!("Expected error, got {:?}", other),
+    }
+}
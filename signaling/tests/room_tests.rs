@@ -1,19 +1,18 @@
-use tokio::sync::mpsc;
-use tokio_tungstenite::tungstenite::Message;
 use uuid::Uuid;
 use webrtc_signaling::auth::AuthenticatedUser;
-use webrtc_signaling::room::{Room, RoomManager, RoomParticipant};
+use webrtc_signaling::room::{outbound_channel, Room, RoomManager, RoomParticipant};
 use webrtc_signaling::messages::ServerMessage;
 
 fn create_test_user(user_id: u32, username: &str) -> AuthenticatedUser {
     AuthenticatedUser {
         user_id,
         username: username.to_string(),
+        tenant: None,
     }
 }
 
 fn create_test_participant(user_id: u32, username: &str) -> RoomParticipant {
-    let (tx, _rx) = mpsc::unbounded_channel::<Message>();
+    let (tx, _rx) = outbound_channel();
     RoomParticipant {
         user: create_test_user(user_id, username),
         connection_id: Uuid::new_v4(),
@@ -285,7 +284,7 @@ async fn test_remove_user_from_all_rooms() {
 #[test]
 fn test_participant_creation() {
     let user = create_test_user(123, "testuser");
-    let (tx, _rx) = mpsc::unbounded_channel::<Message>();
+    let (tx, _rx) = outbound_channel();
     let connection_id = Uuid::new_v4();
 
     let participant = RoomParticipant {
@@ -0,0 +1,242 @@
+mod common;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use futures_util::StreamExt;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{create_test_participant, find_available_port, received, send, Client};
+use webrtc_signaling::messages::{ClientMessage, RoomCloseReason, ServerMessage};
+use webrtc_signaling::room::{
+    JoinError, JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait,
+};
+use webrtc_signaling::room_config::{RoomConfig, RoomConfigStore};
+use webrtc_signaling::schedule::RoomDeadline;
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+fn manager_with(config: RoomConfig) -> LocalRoomManager {
+    LocalRoomManager::with_room_configs(Arc::new(RoomConfigStore::new(config)))
+}
+
+async fn join(
+    manager: &LocalRoomManager,
+    user_id: u32,
+    request: JoinRequest,
+) -> (Result<(), JoinError>, mpsc::Receiver<Message>) {
+    let (participant, rx) = create_test_participant(user_id, &format!("user{}", user_id));
+    let result = manager
+        .join_room_with("room".to_string(), participant, request)
+        .await
+        .map(|_| ());
+    (result, rx)
+}
+
+#[tokio::test]
+async fn test_joins_are_refused_outside_the_window() {
+    let now = Utc::now();
+    let not_yet_open = manager_with(RoomConfig {
+        opens_at: Some(now + ChronoDuration::minutes(10)),
+        ..RoomConfig::default()
+    });
+    match join(&not_yet_open, 1, JoinRequest::default()).await.0 {
+        Err(JoinError::RoomNotOpen { opens_in }) => {
+            assert!(opens_in > Duration::from_secs(590) && opens_in <= Duration::from_secs(600))
+        }
+        other => panic!("Expected room-not-open, got {:?}", other),
+    }
+
+    let ended = manager_with(RoomConfig {
+        opens_at: Some(now - ChronoDuration::hours(2)),
+        closes_at: Some(now - ChronoDuration::hours(1)),
+        ..RoomConfig::default()
+    });
+    assert_eq!(
+        join(&ended, 1, JoinRequest::default()).await.0,
+        Err(JoinError::RoomClosed)
+    );
+
+    let open = manager_with(RoomConfig {
+        opens_at: Some(now - ChronoDuration::minutes(1)),
+        closes_at: Some(now + ChronoDuration::hours(1)),
+        ..RoomConfig::default()
+    });
+    assert_eq!(join(&open, 1, JoinRequest::default()).await.0, Ok(()));
+}
+
+#[test]
+fn test_deadline_is_the_earliest_limit() {
+    let now = Utc::now();
+    let config = RoomConfig {
+        closes_at: Some(now + ChronoDuration::minutes(30)),
+        max_duration_secs: Some(3600),
+        ..RoomConfig::default()
+    };
+    let deadline = RoomDeadline::for_session(&config, None, now).unwrap();
+    assert_eq!(deadline.at, now + ChronoDuration::minutes(30));
+    assert_eq!(deadline.reason, RoomCloseReason::ScheduledEnd);
+
+    // A free-tier token caps the session further
+    let deadline =
+        RoomDeadline::for_session(&config, Some(Duration::from_secs(40 * 60)), now).unwrap();
+    assert_eq!(deadline.at, now + ChronoDuration::minutes(30));
+    let deadline =
+        RoomDeadline::for_session(&config, Some(Duration::from_secs(20 * 60)), now).unwrap();
+    assert_eq!(deadline.at, now + ChronoDuration::minutes(20));
+    assert_eq!(deadline.reason, RoomCloseReason::MaxDuration);
+
+    assert_eq!(
+        RoomDeadline::for_session(&RoomConfig::default(), None, now),
+        None
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_room_is_warned_then_closed_at_its_maximum_duration() {
+    let manager = manager_with(RoomConfig {
+        max_duration_secs: Some(600),
+        ..RoomConfig::default()
+    });
+    let (result, mut alice_rx) = join(&manager, 1, JoinRequest::default()).await;
+    result.unwrap();
+    // A later joiner's token does not restart or shorten the session
+    let request = JoinRequest {
+        max_duration: Some(Duration::from_secs(1)),
+        ..JoinRequest::default()
+    };
+    let (result, mut bob_rx) = join(&manager, 2, request).await;
+    result.unwrap();
+    received(&mut alice_rx);
+
+    tokio::time::sleep(Duration::from_secs(1200)).await;
+
+    let expected = [
+        ServerMessage::RoomClosing {
+            room_name: "room".to_string(),
+            closes_in_seconds: 300,
+            reason: RoomCloseReason::MaxDuration,
+        },
+        ServerMessage::RoomClosing {
+            room_name: "room".to_string(),
+            closes_in_seconds: 60,
+            reason: RoomCloseReason::MaxDuration,
+        },
+        ServerMessage::RoomClosed {
+            room_name: "room".to_string(),
+            reason: RoomCloseReason::MaxDuration,
+        },
+    ];
+    for rx in [&mut alice_rx, &mut bob_rx] {
+        let messages = received(rx);
+        let messages: Vec<String> = messages
+            .iter()
+            .filter(|message| !matches!(message, ServerMessage::RoomJoined { .. }))
+            .map(|message| serde_json::to_string(message).unwrap())
+            .collect();
+        let expected: Vec<String> = expected
+            .iter()
+            .map(|message| serde_json::to_string(message).unwrap())
+            .collect();
+        assert_eq!(messages, expected);
+    }
+    assert!(!manager.user_in_room("room", 1).await);
+    assert!(!manager.user_in_room("room", 2).await);
+    assert!(manager.get_rooms().read().await.is_empty());
+
+    // The next session starts afresh
+    let (result, _rx) = join(&manager, 1, JoinRequest::default()).await;
+    assert_eq!(result, Ok(()));
+}
+
+fn create_test_token(secret: &str, user_id: u32, username: &str) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+
+    let claims = serde_json::json!({
+        "sub": user_id,
+        "username": username,
+        "iat": now,
+        "exp": now + 3600,
+        "max_duration_secs": 1,
+    });
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap()
+}
+
+async fn next_message(ws_stream: &mut Client) -> ServerMessage {
+    let msg = tokio::time::timeout(Duration::from_secs(3), ws_stream.next())
+        .await
+        .expect("Timed out waiting for message")
+        .unwrap()
+        .unwrap();
+    serde_json::from_str(msg.to_text().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_token_caps_the_call_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (mut alice, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    let token = create_test_token(jwt_secret, 1, "alice");
+    send(&mut alice, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut alice,
+        ClientMessage::JoinRoom {
+            room_name: "room".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::RoomJoined { .. }
+    ));
+
+    // Too short for any warning
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::RoomClosed {
+            reason: RoomCloseReason::MaxDuration,
+            ..
+        }
+    ));
+    send(
+        &mut alice,
+        ClientMessage::LeaveRoom {
+            room_name: "room".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut alice).await,
+        ServerMessage::Error { .. }
+    ));
+}
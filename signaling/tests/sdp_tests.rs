@@ -0,0 +1,326 @@
+use webrtc_signaling::candidate::{CandidateFilterStats, CandidatePolicy};
+use webrtc_signaling::sdp::{
+    prepare_for_relay, Direction, SdpError, SdpLimits, SdpPolicy, SessionDescription,
+};
+
+const CHROME_OFFER: &str = "v=0\r
+o=- 4611731400430051336 2 IN IP4 127.0.0.1\r
+s=-\r
+t=0 0\r
+a=group:BUNDLE 0 1\r
+a=msid-semantic: WMS stream\r
+m=audio 9 UDP/TLS/RTP/SAVPF 111 0 8\r
+c=IN IP4 0.0.0.0\r
+a=mid:0\r
+a=sendrecv\r
+a=rtpmap:111 opus/48000/2\r
+a=fmtp:111 minptime=10;useinbandfec=1\r
+m=video 9 UDP/TLS/RTP/SAVPF 96 98\r
+c=IN IP4 0.0.0.0\r
+a=mid:1\r
+a=recvonly\r
+a=rtpmap:96 VP8/90000\r
+a=rtpmap:98 VP9/90000\r
+a=fmtp:98 profile-id=0\r
+";
+
+/// Relay `sdp` through a room whose only rewrite rules are `policy`
+fn relay(sdp: &str, limits: &SdpLimits, policy: &SdpPolicy) -> Result<String, SdpError> {
+    prepare_for_relay(
+        sdp,
+        limits,
+        policy,
+        &CandidatePolicy::default(),
+        &CandidateFilterStats::default(),
+    )
+}
+
+#[test]
+fn test_parse_valid_offer() {
+    let description = SessionDescription::parse(CHROME_OFFER, &SdpLimits::default()).unwrap();
+
+    assert_eq!(description.session_name, "-");
+    assert_eq!(description.direction, None);
+    assert_eq!(description.media.len(), 2);
+
+    let audio = &description.media[0];
+    assert_eq!(audio.kind, "audio");
+    assert_eq!(audio.port, 9);
+    assert_eq!(audio.protocol, "UDP/TLS/RTP/SAVPF");
+    assert_eq!(audio.mid.as_deref(), Some("0"));
+    assert_eq!(audio.direction, Direction::SendRecv);
+    assert_eq!(audio.lines()[0], "m=audio 9 UDP/TLS/RTP/SAVPF 111 0 8");
+}
+
+#[test]
+fn test_parse_exposes_codecs_in_preference_order() {
+    let description = SessionDescription::parse(CHROME_OFFER, &SdpLimits::default()).unwrap();
+
+    let audio_codecs: Vec<&str> = description.media[0]
+        .codecs
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert_eq!(audio_codecs, vec!["opus", "PCMU", "PCMA"]);
+
+    let opus = &description.media[0].codecs[0];
+    assert_eq!(opus.payload_type, 111);
+    assert_eq!(opus.clock_rate, 48000);
+    assert_eq!(opus.channels, Some(2));
+    assert_eq!(opus.fmtp.as_deref(), Some("minptime=10;useinbandfec=1"));
+
+    let video = &description.media[1];
+    assert_eq!(video.direction, Direction::RecvOnly);
+    assert_eq!(video.codecs[1].name, "VP9");
+    assert_eq!(video.codecs[1].fmtp.as_deref(), Some("profile-id=0"));
+}
+
+#[test]
+fn test_session_direction_is_inherited() {
+    let offer = "v=0\no=- 1 1 IN IP4 0.0.0.0\ns=-\nt=0 0\na=sendonly\nm=audio 9 RTP/AVP 0\n";
+
+    let description = SessionDescription::parse(offer, &SdpLimits::default()).unwrap();
+    assert_eq!(description.direction, Some(Direction::SendOnly));
+    assert_eq!(description.media[0].direction, Direction::SendOnly);
+}
+
+#[test]
+fn test_roundtrip_preserves_lines() {
+    let description = SessionDescription::parse(CHROME_OFFER, &SdpLimits::default()).unwrap();
+    assert_eq!(description.to_string(), CHROME_OFFER);
+}
+
+#[test]
+fn test_reject_oversized_sdp() {
+    let limits = SdpLimits {
+        max_size: 64,
+        ..SdpLimits::default()
+    };
+
+    let result = relay(CHROME_OFFER, &limits, &SdpPolicy::default());
+    assert_eq!(
+        result,
+        Err(SdpError::TooLarge {
+            size: CHROME_OFFER.len(),
+            max: 64
+        })
+    );
+}
+
+#[test]
+fn test_reject_too_many_media_sections() {
+    let limits = SdpLimits {
+        max_media_sections: 1,
+        ..SdpLimits::default()
+    };
+
+    let result = relay(CHROME_OFFER, &limits, &SdpPolicy::default());
+    assert_eq!(
+        result,
+        Err(SdpError::TooManyMediaSections { count: 2, max: 1 })
+    );
+}
+
+#[test]
+fn test_reject_malformed_payloads() {
+    let limits = SdpLimits::default();
+
+    assert_eq!(
+        relay("", &limits, &SdpPolicy::default()),
+        Err(SdpError::Empty)
+    );
+    assert_eq!(
+        relay("test_offer_sdp", &limits, &SdpPolicy::default()),
+        Err(SdpError::InvalidVersion)
+    );
+    assert_eq!(
+        relay(
+            "v=0\r\no=- 1 1 IN IP4 0.0.0.0\r\nnot a line\r\n",
+            &limits,
+            &SdpPolicy::default()
+        ),
+        Err(SdpError::MalformedLine { line: 3 })
+    );
+    assert_eq!(
+        relay(
+            "v=0\r\no=- 1 1 IN IP4 0.0.0.0\r\nt=0 0\r\n",
+            &limits,
+            &SdpPolicy::default()
+        ),
+        Err(SdpError::MissingField('s'))
+    );
+    assert_eq!(
+        relay(
+            "v=0\r\no=- 1 1 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\nm=audio nine RTP/AVP 0\r\n",
+            &limits,
+            &SdpPolicy::default()
+        ),
+        Err(SdpError::InvalidMediaLine { line: 5 })
+    );
+    assert_eq!(
+        relay(
+            "v=0\r\no=- 1 1 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\nm=audio 9 RTP/AVP 111\r\na=rtpmap:111 opus\r\n",
+            &limits,
+            &SdpPolicy::default()
+        ),
+        Err(SdpError::InvalidAttribute {
+            line: 6,
+            attribute: "rtpmap".to_string()
+        })
+    );
+}
+
+#[test]
+fn test_reject_long_lines() {
+    let limits = SdpLimits {
+        max_line_length: 16,
+        ..SdpLimits::default()
+    };
+
+    assert_eq!(
+        relay(CHROME_OFFER, &limits, &SdpPolicy::default()),
+        Err(SdpError::LineTooLong { line: 2, max: 16 })
+    );
+}
+
+#[test]
+fn test_error_messages_are_readable() {
+    let error = SdpError::TooManyMediaSections { count: 40, max: 32 };
+    assert_eq!(
+        error.to_string(),
+        "SDP has 40 media sections, maximum is 32"
+    );
+}
+
+const VIDEO_OFFER: &str = "v=0\r
+o=- 1 2 IN IP4 127.0.0.1\r
+s=-\r
+t=0 0\r
+m=audio 9 UDP/TLS/RTP/SAVPF 111 0\r
+c=IN IP4 0.0.0.0\r
+a=mid:0\r
+a=rtpmap:111 opus/48000/2\r
+m=video 9 UDP/TLS/RTP/SAVPF 96 97 98 99\r
+c=IN IP4 0.0.0.0\r
+b=AS:800\r
+a=mid:1\r
+a=rtpmap:96 VP8/90000\r
+a=rtcp-fb:96 nack\r
+a=rtpmap:97 rtx/90000\r
+a=fmtp:97 apt=96\r
+a=rtpmap:98 VP9/90000\r
+a=rtpmap:99 rtx/90000\r
+a=fmtp:99 apt=98\r
+";
+
+#[test]
+fn test_policy_prefers_codecs() {
+    let policy = SdpPolicy {
+        preferred_codecs: vec!["vp9".to_string(), "VP8".to_string()],
+        ..SdpPolicy::default()
+    };
+
+    let rewritten = relay(VIDEO_OFFER, &SdpLimits::default(), &policy).unwrap();
+    assert!(rewritten.contains("m=video 9 UDP/TLS/RTP/SAVPF 98 96 97 99\r\n"));
+    // Sections without a preferred codec keep their order
+    assert!(rewritten.contains("m=audio 9 UDP/TLS/RTP/SAVPF 111 0\r\n"));
+
+    let description = SessionDescription::parse(&rewritten, &SdpLimits::default()).unwrap();
+    assert_eq!(description.media[1].codecs[0].name, "VP9");
+}
+
+#[test]
+fn test_policy_strips_disallowed_codecs() {
+    let policy = SdpPolicy {
+        allowed_codecs: vec!["opus".to_string(), "VP9".to_string()],
+        ..SdpPolicy::default()
+    };
+
+    let rewritten = relay(VIDEO_OFFER, &SdpLimits::default(), &policy).unwrap();
+    assert!(rewritten.contains("m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n"));
+    assert!(rewritten.contains("m=video 9 UDP/TLS/RTP/SAVPF 98 99\r\n"));
+    // Attributes of stripped payload types go with them, including VP8's RTX stream
+    assert!(!rewritten.contains("a=rtpmap:96"));
+    assert!(!rewritten.contains("a=rtcp-fb:96"));
+    assert!(!rewritten.contains("a=fmtp:97"));
+    assert!(rewritten.contains("a=fmtp:99 apt=98\r\n"));
+}
+
+#[test]
+fn test_policy_rejects_sections_without_allowed_codecs() {
+    let policy = SdpPolicy {
+        allowed_codecs: vec!["opus".to_string()],
+        ..SdpPolicy::default()
+    };
+
+    let result = relay(VIDEO_OFFER, &SdpLimits::default(), &policy);
+    assert_eq!(
+        result,
+        Err(SdpError::NoAllowedCodecs {
+            media: "video".to_string()
+        })
+    );
+}
+
+#[test]
+fn test_policy_caps_bandwidth() {
+    let policy = SdpPolicy {
+        max_audio_bitrate_kbps: Some(64),
+        max_video_bitrate_kbps: Some(1500),
+        ..SdpPolicy::default()
+    };
+
+    let rewritten = relay(VIDEO_OFFER, &SdpLimits::default(), &policy).unwrap();
+    assert!(rewritten.contains("c=IN IP4 0.0.0.0\r\nb=AS:64\r\nb=TIAS:64000\r\na=mid:0\r\n"));
+    // An existing lower cap is kept
+    assert!(rewritten.contains("c=IN IP4 0.0.0.0\r\nb=AS:800\r\nb=TIAS:800000\r\na=mid:1\r\n"));
+    assert_eq!(rewritten.matches("b=AS:").count(), 2);
+}
+
+#[test]
+fn test_empty_policy_leaves_sdp_untouched() {
+    let offer = VIDEO_OFFER.replace("\r\n", "\n");
+    let rewritten = relay(&offer, &SdpLimits::default(), &SdpPolicy::default()).unwrap();
+    assert_eq!(rewritten, offer);
+}
+
+#[test]
+fn test_relay_applies_sdp_and_candidate_policies_together() {
+    let offer = format!(
+        "{}a=candidate:1 1 udp 2122260223 192.168.1.20 54321 typ host\r\n\
+         a=candidate:2 1 udp 41885439 198.51.100.9 3478 typ relay raddr 192.168.1.20 rport 54321\r\n",
+        CHROME_OFFER
+    );
+    let policy = SdpPolicy {
+        allowed_codecs: vec!["opus".to_string(), "VP8".to_string()],
+        ..SdpPolicy::default()
+    };
+    let candidates = CandidatePolicy {
+        relay_only: true,
+        ..CandidatePolicy::default()
+    };
+    let stats = CandidateFilterStats::default();
+
+    let relayed =
+        prepare_for_relay(&offer, &SdpLimits::default(), &policy, &candidates, &stats).unwrap();
+    assert!(!relayed.contains("VP9"));
+    assert!(!relayed.contains("typ host"));
+    assert!(relayed.contains("typ relay raddr 0.0.0.0 rport 0"));
+    assert_eq!(stats.snapshot().not_relay, 1);
+
+    // Limits still apply when there is nothing to rewrite
+    let limits = SdpLimits {
+        max_size: 64,
+        ..SdpLimits::default()
+    };
+    assert!(matches!(
+        prepare_for_relay(
+            &offer,
+            &limits,
+            &SdpPolicy::default(),
+            &CandidatePolicy::default(),
+            &stats
+        ),
+        Err(SdpError::TooLarge { .. })
+    ));
+}
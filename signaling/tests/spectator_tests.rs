@@ -0,0 +1,396 @@
+mod common;
+
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{create_test_participant, find_available_port, next_message, received, send, Client};
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::moderation::{ModerationError, ModeratorAction, RoomRole};
+use webrtc_signaling::room::{
+    JoinError, JoinOutcome, JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait,
+};
+use webrtc_signaling::room_config::{OverflowPolicy, RoomConfig, RoomConfigStore};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+
+const HOST: u32 = 1;
+
+fn manager_with(config: RoomConfig) -> LocalRoomManager {
+    LocalRoomManager::with_room_configs(Arc::new(RoomConfigStore::new(config)))
+}
+
+async fn join_as(
+    manager: &LocalRoomManager,
+    user_id: u32,
+    username: &str,
+    role: RoomRole,
+) -> (Result<JoinOutcome, JoinError>, mpsc::Receiver<Message>) {
+    let (participant, rx) = create_test_participant(user_id, username);
+    let request = JoinRequest {
+        role,
+        ..JoinRequest::default()
+    };
+    let result = manager
+        .join_room_with("room".to_string(), participant, request)
+        .await;
+    (result, rx)
+}
+
+fn user_ids(outcome: &JoinOutcome) -> Vec<u32> {
+    let mut ids: Vec<u32> = outcome
+        .participants()
+        .unwrap()
+        .iter()
+        .map(|p| p.user_id)
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn offer_from(user_id: u32) -> ServerMessage {
+    ServerMessage::Offer {
+        room_name: "room".to_string(),
+        from_user_id: user_id,
+        sdp: "v=0".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_spectators_join_unannounced() {
+    let manager = LocalRoomManager::new();
+    let (_, mut host_rx) = join_as(&manager, HOST, "host", RoomRole::Host).await;
+    let (_, mut presenter_rx) = join_as(&manager, 2, "presenter", RoomRole::Participant).await;
+    received(&mut host_rx);
+
+    let (result, _viewer_rx) = join_as(&manager, 3, "viewer", RoomRole::Spectator).await;
+    let outcome = result.unwrap();
+    assert!(matches!(outcome, JoinOutcome::Spectating(_)));
+    assert_eq!(user_ids(&outcome), vec![HOST, 2]);
+    assert!(received(&mut host_rx).is_empty());
+    assert!(received(&mut presenter_rx).is_empty());
+
+    // Other spectators are not listed either
+    let (result, _) = join_as(&manager, 4, "viewer2", RoomRole::Spectator).await;
+    assert_eq!(user_ids(&result.unwrap()), vec![HOST, 2]);
+    let mut listed: Vec<u32> = manager
+        .get_room_participants("room")
+        .await
+        .iter()
+        .map(|p| p.user_id)
+        .collect();
+    listed.sort_unstable();
+    assert_eq!(listed, vec![HOST, 2]);
+    assert_eq!(manager.room_role("room", 3).await, RoomRole::Spectator);
+
+    // Nor is their departure
+    manager.leave_room("room", 3).await.unwrap();
+    assert!(received(&mut host_rx).is_empty());
+    assert!(received(&mut presenter_rx).is_empty());
+
+    // Presenters are still announced to spectators
+    let (_, mut viewer_rx) = join_as(&manager, 5, "viewer3", RoomRole::Spectator).await;
+    let (_, _late_rx) = join_as(&manager, 6, "late", RoomRole::Participant).await;
+    assert!(matches!(
+        received(&mut viewer_rx).as_slice(),
+        [ServerMessage::UserJoined { user, .. }] if user.user_id == 6
+    ));
+}
+
+#[tokio::test]
+async fn test_spectators_are_counted_separately() {
+    let manager = manager_with(RoomConfig {
+        max_participants: Some(2),
+        overflow: OverflowPolicy::Reject,
+        ..RoomConfig::default()
+    });
+    let (_, _host_rx) = join_as(&manager, HOST, "host", RoomRole::Host).await;
+    for user_id in 10..15 {
+        let (result, _) = join_as(&manager, user_id, "viewer", RoomRole::Spectator).await;
+        assert!(matches!(result, Ok(JoinOutcome::Spectating(_))));
+    }
+    let (result, _) = join_as(&manager, 2, "presenter", RoomRole::Participant).await;
+    assert!(matches!(result, Ok(JoinOutcome::Joined(_))));
+
+    let (result, _) = join_as(&manager, 3, "extra", RoomRole::Participant).await;
+    assert_eq!(result.unwrap_err(), JoinError::RoomFull { capacity: 2 });
+
+    let rooms = manager.get_rooms();
+    let rooms = rooms.read().await;
+    let room = rooms.get("room").unwrap();
+    assert_eq!(room.participants.len(), 7);
+    assert_eq!(room.spectators.len(), 5);
+    assert_eq!(room.active_count(), 2);
+}
+
+#[tokio::test]
+async fn test_broadcast_signals_reach_presenters_only() {
+    let manager = LocalRoomManager::new();
+    let (_, mut host_rx) = join_as(&manager, HOST, "host", RoomRole::Host).await;
+    let (_, mut presenter_rx) = join_as(&manager, 2, "presenter", RoomRole::Participant).await;
+    let (_, mut viewer_rx) = join_as(&manager, 3, "viewer", RoomRole::Spectator).await;
+    let (_, mut other_viewer_rx) = join_as(&manager, 4, "viewer2", RoomRole::Spectator).await;
+    received(&mut host_rx);
+
+    manager
+        .broadcast_to_room("room", HOST, offer_from(HOST))
+        .await
+        .unwrap();
+    assert_eq!(received(&mut presenter_rx).len(), 1);
+    assert!(received(&mut viewer_rx).is_empty());
+
+    manager
+        .broadcast_to_room("room", 3, offer_from(3))
+        .await
+        .unwrap();
+    assert_eq!(received(&mut host_rx).len(), 1);
+    assert_eq!(received(&mut presenter_rx).len(), 1);
+    assert!(received(&mut other_viewer_rx).is_empty());
+}
+
+#[tokio::test]
+async fn test_host_promotes_spectator() {
+    let manager = LocalRoomManager::new();
+    let (_, mut host_rx) = join_as(&manager, HOST, "host", RoomRole::Host).await;
+    let (_, mut presenter_rx) = join_as(&manager, 2, "presenter", RoomRole::Moderator).await;
+    let (_, mut viewer_rx) = join_as(&manager, 3, "viewer", RoomRole::Spectator).await;
+    received(&mut host_rx);
+
+    let promote = ModeratorAction::Promote { target: 3 };
+    assert_eq!(
+        manager.moderate("room", 2, promote.clone()).await,
+        Err(ModerationError::NotHost)
+    );
+    assert_eq!(
+        manager
+            .moderate("room", HOST, ModeratorAction::Promote { target: 9 })
+            .await,
+        Err(ModerationError::TargetNotFound)
+    );
+
+    manager
+        .moderate("room", HOST, promote.clone())
+        .await
+        .unwrap();
+    assert!(matches!(
+        received(&mut viewer_rx).as_slice(),
+        [ServerMessage::Promoted {
+            by_user_id: HOST,
+            ..
+        }]
+    ));
+    for rx in [&mut host_rx, &mut presenter_rx] {
+        assert!(matches!(
+            received(rx).as_slice(),
+            [ServerMessage::UserJoined { user, .. }] if user.user_id == 3
+        ));
+    }
+    assert_eq!(manager.room_role("room", 3).await, RoomRole::Participant);
+
+    // Promoting a presenter is refused
+    assert!(matches!(
+        manager.moderate("room", HOST, promote).await,
+        Err(ModerationError::Invalid(_))
+    ));
+
+    // Once promoted they receive broadcasts and are announced when leaving
+    manager
+        .broadcast_to_room("room", HOST, offer_from(HOST))
+        .await
+        .unwrap();
+    assert_eq!(received(&mut viewer_rx).len(), 1);
+    manager.leave_room("room", 3).await.unwrap();
+    assert!(matches!(
+        received(&mut host_rx).as_slice(),
+        [ServerMessage::UserLeft { user_id: 3, .. }]
+    ));
+}
+
+#[tokio::test]
+async fn test_spectators_never_become_host() {
+    let manager = LocalRoomManager::new();
+    let (_, _viewer_rx) = join_as(&manager, 3, "viewer", RoomRole::Spectator).await;
+    let (_, _host_rx) = join_as(&manager, HOST, "host", RoomRole::Participant).await;
+    assert_eq!(manager.room_ownership("room").await.host, Some(HOST));
+
+    manager.leave_room("room", HOST).await.unwrap();
+    assert_eq!(manager.room_ownership("room").await.host, None);
+}
+
+#[tokio::test]
+async fn test_spectator_admitted_from_lobby_keeps_role() {
+    let manager = manager_with(RoomConfig {
+        lobby: true,
+        ..RoomConfig::default()
+    });
+    let (_, _host_rx) = join_as(&manager, HOST, "host", RoomRole::Host).await;
+    let (result, mut viewer_rx) = join_as(&manager, 3, "viewer", RoomRole::Spectator).await;
+    assert!(matches!(result, Ok(JoinOutcome::InLobby)));
+
+    manager
+        .moderate("room", HOST, ModeratorAction::Admit { target: 3 })
+        .await
+        .unwrap();
+    assert!(matches!(
+        received(&mut viewer_rx).as_slice(),
+        [ServerMessage::RoomJoined {
+            spectator: true,
+            ..
+        }]
+    ));
+    assert_eq!(manager.room_role("room", 3).await, RoomRole::Spectator);
+}
+
+fn create_test_token(secret: &str, user_id: u32, username: &str, role: &str) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+
+    let claims = serde_json::json!({
+        "sub": user_id,
+        "username": username,
+        "iat": now,
+        "exp": now + 3600,
+        "room_roles": { "webinar": role },
+    });
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap()
+}
+
+async fn connect_and_join(port: u16, token: String) -> (Client, ServerMessage) {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: "webinar".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    let reply = next_message(&mut ws_stream).await;
+    (ws_stream, reply)
+}
+
+fn offer_to(target_user_id: u32) -> ClientMessage {
+    ClientMessage::Offer {
+        room_name: "webinar".to_string(),
+        sdp: "v=0\r\no=- 1 1 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\n".to_string(),
+        target_user_id: Some(target_user_id),
+    }
+}
+
+#[tokio::test]
+async fn test_spectator_signaling_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (mut host, _) =
+        connect_and_join(port, create_test_token(jwt_secret, 1, "host", "host")).await;
+    let (mut viewer, reply) = connect_and_join(
+        port,
+        create_test_token(jwt_secret, 2, "viewer", "spectator"),
+    )
+    .await;
+    assert!(matches!(
+        reply,
+        ServerMessage::RoomJoined {
+            spectator: true,
+            ..
+        }
+    ));
+    let (mut other_viewer, _) = connect_and_join(
+        port,
+        create_test_token(jwt_secret, 3, "viewer2", "spectator"),
+    )
+    .await;
+
+    // A spectator may not signal another spectator
+    send(&mut viewer, offer_to(3)).await;
+    match next_message(&mut viewer).await {
+        ServerMessage::Error { code, .. } => assert_eq!(code, Some(403)),
+        other => panic!("Expected error, got {:?}", other),
+    }
+
+    // ...but may signal a presenter, who may answer
+    send(&mut viewer, offer_to(1)).await;
+    assert!(matches!(
+        next_message(&mut host).await,
+        ServerMessage::Offer {
+            from_user_id: 2,
+            ..
+        }
+    ));
+    send(
+        &mut host,
+        ClientMessage::Answer {
+            room_name: "webinar".to_string(),
+            sdp: "v=0\r\no=- 1 1 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\n".to_string(),
+            target_user_id: 2,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut viewer).await,
+        ServerMessage::Answer {
+            from_user_id: 1,
+            ..
+        }
+    ));
+
+    // The host promotes a spectator, who is announced to the room
+    send(
+        &mut host,
+        ClientMessage::PromoteSpectator {
+            room_name: "webinar".to_string(),
+            target_user_id: 3,
+        },
+    )
+    .await;
+    assert!(matches!(
+        next_message(&mut other_viewer).await,
+        ServerMessage::Promoted { by_user_id: 1, .. }
+    ));
+    assert!(matches!(
+        next_message(&mut host).await,
+        ServerMessage::UserJoined { user, .. } if user.user_id == 3
+    ));
+    assert!(matches!(
+        next_message(&mut viewer).await,
+        ServerMessage::UserJoined { user, .. } if user.user_id == 3
+    ));
+
+    send(&mut viewer, offer_to(3)).await;
+    assert!(matches!(
+        next_message(&mut other_viewer).await,
+        ServerMessage::Offer {
+            from_user_id: 2,
+            ..
+        }
+    ));
+}
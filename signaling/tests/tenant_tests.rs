@@ -0,0 +1,282 @@
+mod common;
+
+use futures_util::StreamExt;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::time::Duration;
+use tokio_tungstenite::connect_async;
+
+use common::{create_tenant_participant, find_available_port, next_message, send, Client};
+use webrtc_signaling::auth::JwtValidator;
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::room::{
+    JoinError, JoinRequest, LocalRoomManager, RoomManager, RoomManagerTrait,
+};
+use webrtc_signaling::room_config::{RoomConfig, RoomConfigStore};
+use webrtc_signaling::server::{start_server_with_config, ServerConfig};
+use webrtc_signaling::tenant::{
+    local_room_name, localize_message, qualify_room_name, tenant_of, validate_tenant_id,
+};
+
+#[test]
+fn test_room_names_are_qualified_by_tenant() {
+    assert_eq!(
+        qualify_room_name(Some("acme"), "standup").unwrap(),
+        "acme::standup"
+    );
+    assert_eq!(qualify_room_name(None, "standup").unwrap(), "standup");
+    // Nobody can spell out another tenant's room
+    assert!(qualify_room_name(None, "acme::standup").is_err());
+    assert!(qualify_room_name(Some("evil"), "acme::standup").is_err());
+
+    assert_eq!(tenant_of("acme::standup/a"), Some("acme"));
+    assert_eq!(tenant_of("standup"), None);
+    assert_eq!(
+        local_room_name(Some("acme"), "acme::standup/a"),
+        "standup/a"
+    );
+    assert_eq!(local_room_name(None, "standup"), "standup");
+}
+
+#[test]
+fn test_tenant_id_validation() {
+    assert!(validate_tenant_id("acme-prod_2").is_ok());
+    assert!(validate_tenant_id("").is_err());
+    assert!(validate_tenant_id("a::b").is_err());
+    assert!(validate_tenant_id(&"a".repeat(65)).is_err());
+}
+
+#[test]
+fn test_localize_message() {
+    let moved = r#"{"type":"moved-to-room","fromRoomName":"acme::standup","roomName":"acme::standup/a","participants":[]}"#;
+    let localized: serde_json::Value =
+        serde_json::from_str(&localize_message("acme", moved).unwrap()).unwrap();
+    assert_eq!(localized["fromRoomName"], "standup");
+    assert_eq!(localized["roomName"], "standup/a");
+
+    assert_eq!(
+        localize_message("acme", r#"{"type":"room-left","roomName":"standup"}"#),
+        None
+    );
+
+    let error = r#"{"type":"error","message":"Room acme::standup is not defined","code":null}"#;
+    let localized: serde_json::Value =
+        serde_json::from_str(&localize_message("acme", error).unwrap()).unwrap();
+    assert_eq!(localized["message"], "Room standup is not defined");
+}
+
+#[tokio::test]
+async fn test_cross_tenant_join_is_refused() {
+    let manager = LocalRoomManager::new();
+
+    let (outsider, _rx) = create_tenant_participant(1, Some("globex"));
+    assert_eq!(
+        manager
+            .join_room_with(
+                "acme::standup".to_string(),
+                outsider,
+                JoinRequest::default()
+            )
+            .await
+            .unwrap_err(),
+        JoinError::WrongTenant
+    );
+    let (untenanted, _rx) = create_tenant_participant(1, None);
+    assert_eq!(
+        manager
+            .join_room_with(
+                "acme::standup".to_string(),
+                untenanted,
+                JoinRequest::default(),
+            )
+            .await
+            .unwrap_err(),
+        JoinError::WrongTenant
+    );
+    let (tenant_user, _rx) = create_tenant_participant(1, Some("acme"));
+    assert_eq!(
+        manager
+            .join_room_with("standup".to_string(), tenant_user, JoinRequest::default())
+            .await
+            .unwrap_err(),
+        JoinError::WrongTenant
+    );
+
+    let (member, _rx) = create_tenant_participant(1, Some("acme"));
+    manager
+        .join_room("acme::standup".to_string(), member)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_tenant_default_configuration() {
+    let store = RoomConfigStore::from_json(
+        r#"{
+            "default": {"maxParticipants": 10},
+            "tenants": {"acme": {"maxParticipants": 2}},
+            "rooms": {"acme::all-hands": {"maxParticipants": 500}}
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(store.get("acme::standup").await.max_participants, Some(2));
+    assert_eq!(
+        store.get("acme::all-hands").await.max_participants,
+        Some(500)
+    );
+    assert_eq!(
+        store.get("globex::standup").await.max_participants,
+        Some(10)
+    );
+    assert_eq!(store.get("standup").await.max_participants, Some(10));
+
+    let store = RoomConfigStore::default().with_tenant_default(
+        "acme",
+        RoomConfig {
+            max_participants: Some(3),
+            ..RoomConfig::default()
+        },
+    );
+    assert_eq!(store.get("acme::standup").await.max_participants, Some(3));
+}
+
+fn create_test_token(secret: &str, user_id: u32, tenant: Option<&str>) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+
+    let claims = serde_json::json!({
+        "sub": user_id,
+        "username": format!("user{}", user_id),
+        "iat": now,
+        "exp": now + 3600,
+        "tenant": tenant,
+    });
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_tenant_claim() {
+    let validator = JwtValidator::new("secret");
+
+    let user = validator
+        .validate_token(&create_test_token("secret", 1, Some("acme")))
+        .unwrap();
+    assert_eq!(user.tenant.as_deref(), Some("acme"));
+    let user = validator
+        .validate_token(&create_test_token("secret", 1, None))
+        .unwrap();
+    assert_eq!(user.tenant, None);
+    assert!(validator
+        .validate_token(&create_test_token("secret", 1, Some("a::b")))
+        .is_err());
+}
+
+async fn connect_and_join(port: u16, token: String, room_name: &str) -> (Client, ServerMessage) {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+    send(&mut ws_stream, ClientMessage::Auth { token }).await;
+    assert!(matches!(
+        next_message(&mut ws_stream).await,
+        ServerMessage::Authenticated { .. }
+    ));
+    send(
+        &mut ws_stream,
+        ClientMessage::JoinRoom {
+            room_name: room_name.to_string(),
+            password: None,
+        },
+    )
+    .await;
+    let reply = next_message(&mut ws_stream).await;
+    (ws_stream, reply)
+}
+
+#[tokio::test]
+async fn test_tenants_are_isolated_over_websocket() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            jwt_secret.to_string(),
+            RoomManager::new(),
+            ServerConfig::default(),
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (mut acme, joined) = connect_and_join(
+        port,
+        create_test_token(jwt_secret, 1, Some("acme")),
+        "standup",
+    )
+    .await;
+    match joined {
+        ServerMessage::RoomJoined { room_name, .. } => assert_eq!(room_name, "standup"),
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+
+    // Same user id and room name, different tenant: a separate room
+    let (mut globex, joined) = connect_and_join(
+        port,
+        create_test_token(jwt_secret, 1, Some("globex")),
+        "standup",
+    )
+    .await;
+    match joined {
+        ServerMessage::RoomJoined {
+            room_name,
+            participants,
+            ..
+        } => {
+            assert_eq!(room_name, "standup");
+            assert!(participants.is_empty());
+        }
+        other => panic!("Expected room-joined, got {:?}", other),
+    }
+
+    // Signaling stays inside the tenant
+    send(
+        &mut globex,
+        ClientMessage::IceCandidate {
+            room_name: "standup".to_string(),
+            candidate: "candidate:1 1 udp 2122260223 192.0.2.1 54400 typ host".to_string(),
+            sdp_mid: None,
+            sdp_mline_index: None,
+            target_user_id: None,
+        },
+    )
+    .await;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), acme.next())
+            .await
+            .is_err(),
+        "Nothing should cross tenants"
+    );
+
+    // Spelling out another tenant's room is refused
+    send(
+        &mut globex,
+        ClientMessage::JoinRoom {
+            room_name: "acme::standup".to_string(),
+            password: None,
+        },
+    )
+    .await;
+    match next_message(&mut globex).await {
+        ServerMessage::Error { code, .. } => assert_eq!(code, Some(400)),
+        other => panic!("Expected error, got {:?}", other),
+    }
+}
@@ -0,0 +1,299 @@
+mod common;
+
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+use common::create_test_participant;
+use webrtc_signaling::room::{LocalRoomManager, RoomManagerTrait};
+use webrtc_signaling::webhook::{
+    sign, WebhookConfig, WebhookDispatcher, WebhookEvent, WebhookPayload,
+};
+
+/// A request captured by the stub endpoint
+struct StubRequest {
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+impl StubRequest {
+    fn payload(&self) -> WebhookPayload {
+        serde_json::from_str(&self.body).unwrap()
+    }
+}
+
+/// Serve webhook requests on `listener`, answering with `statuses` in order and
+/// 200 once they run out
+fn serve_stub(listener: TcpListener, statuses: Vec<u16>) -> mpsc::UnboundedReceiver<StubRequest> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut statuses = statuses.into_iter();
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut data = Vec::new();
+            let mut buf = [0u8; 1024];
+            let header_end = loop {
+                let read = stream.read(&mut buf).await.unwrap();
+                data.extend_from_slice(&buf[..read]);
+                if let Some(index) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break index;
+                }
+            };
+
+            let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+            let headers: HashMap<String, String> = head
+                .lines()
+                .skip(1)
+                .filter_map(|line| line.split_once(": "))
+                .map(|(name, value)| (name.to_ascii_lowercase(), value.to_string()))
+                .collect();
+            let length: usize = headers["content-length"].parse().unwrap();
+            while data.len() < header_end + 4 + length {
+                let read = stream.read(&mut buf).await.unwrap();
+                data.extend_from_slice(&buf[..read]);
+            }
+            let body = String::from_utf8_lossy(&data[header_end + 4..]).to_string();
+
+            let status = statuses.next().unwrap_or(200);
+            let response = format!("HTTP/1.1 {} Stub\r\nContent-Length: 0\r\n\r\n", status);
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = tx.send(StubRequest { headers, body });
+        }
+    });
+    rx
+}
+
+async fn start_stub(statuses: Vec<u16>) -> (String, mpsc::UnboundedReceiver<StubRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+    (url, serve_stub(listener, statuses))
+}
+
+async fn next_request(rx: &mut mpsc::UnboundedReceiver<StubRequest>) -> StubRequest {
+    tokio::time::timeout(Duration::from_secs(2), rx.recv())
+        .await
+        .expect("Timed out waiting for a webhook")
+        .unwrap()
+}
+
+fn test_config(url: String) -> WebhookConfig {
+    WebhookConfig {
+        urls: vec![url],
+        secret: Some("webhook_secret".to_string()),
+        initial_backoff: Duration::from_millis(20),
+        max_backoff: Duration::from_millis(100),
+        timeout: Duration::from_secs(1),
+        ..WebhookConfig::default()
+    }
+}
+
+fn room_created(room_name: &str) -> WebhookEvent {
+    WebhookEvent::RoomCreated {
+        room_name: room_name.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_delivers_signed_payload() {
+    let (url, mut requests) = start_stub(vec![]).await;
+    let dispatcher = WebhookDispatcher::start(test_config(url)).unwrap();
+
+    dispatcher.emit(room_created("standup"));
+    let request = next_request(&mut requests).await;
+
+    let timestamp: i64 = request.headers["x-webhook-timestamp"].parse().unwrap();
+    assert_eq!(
+        request.headers["x-webhook-signature"],
+        format!(
+            "sha256={}",
+            sign("webhook_secret", timestamp, &request.body)
+        )
+    );
+    assert_eq!(request.headers["content-type"], "application/json");
+    assert!(request.body.contains(r#""type":"room-created""#));
+    assert_eq!(request.payload().event, room_created("standup"));
+}
+
+#[tokio::test]
+async fn test_retries_failed_delivery() {
+    let (url, mut requests) = start_stub(vec![500, 503]).await;
+    let dispatcher = WebhookDispatcher::start(test_config(url)).unwrap();
+
+    dispatcher.emit(room_created("standup"));
+    let attempts = [
+        next_request(&mut requests).await,
+        next_request(&mut requests).await,
+        next_request(&mut requests).await,
+    ];
+
+    // Every attempt carries the same delivery id and payload
+    let delivery_id = &attempts[0].headers["x-webhook-id"];
+    for attempt in &attempts {
+        assert_eq!(&attempt.headers["x-webhook-id"], delivery_id);
+        assert_eq!(attempt.body, attempts[0].body);
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(dispatcher.pending(), 0);
+}
+
+#[tokio::test]
+async fn test_gives_up_after_max_attempts() {
+    let (url, mut requests) = start_stub(vec![500; 10]).await;
+    let dispatcher = WebhookDispatcher::start(WebhookConfig {
+        max_attempts: 2,
+        ..test_config(url)
+    })
+    .unwrap();
+
+    dispatcher.emit(room_created("standup"));
+    next_request(&mut requests).await;
+    next_request(&mut requests).await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(dispatcher.pending(), 0);
+    assert!(requests.try_recv().is_err());
+}
+
+#[test]
+fn test_backoff_doubles_up_to_limit() {
+    let config = WebhookConfig {
+        initial_backoff: Duration::from_secs(1),
+        max_backoff: Duration::from_secs(5),
+        ..WebhookConfig::default()
+    };
+    assert_eq!(config.backoff(1), Duration::from_secs(1));
+    assert_eq!(config.backoff(2), Duration::from_secs(2));
+    assert_eq!(config.backoff(3), Duration::from_secs(4));
+    assert_eq!(config.backoff(4), Duration::from_secs(5));
+    assert_eq!(config.backoff(40), Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_rejects_unsupported_urls() {
+    let result = WebhookDispatcher::start(WebhookConfig {
+        urls: vec!["https://example.com/hooks".to_string()],
+        ..WebhookConfig::default()
+    });
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_queue_is_bounded_and_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let queue_path = dir.path().join("webhooks.jsonl");
+
+    // Reserve a port, then leave it closed so deliveries fail
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+
+    let config = WebhookConfig {
+        queue_path: Some(queue_path.clone()),
+        queue_capacity: 2,
+        initial_backoff: Duration::from_secs(3600),
+        max_backoff: Duration::from_secs(3600),
+        ..test_config(format!("http://{}/hooks", address))
+    };
+    let dispatcher = WebhookDispatcher::start(config.clone()).unwrap();
+    for room_name in ["a", "b", "c"] {
+        dispatcher.emit(room_created(room_name));
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // The oldest event was dropped to stay within capacity
+    assert_eq!(dispatcher.pending(), 2);
+
+    // A restarted server delivers what was queued once the endpoint is back
+    let mut requests = serve_stub(TcpListener::bind(address).await.unwrap(), vec![]);
+    let _restarted = WebhookDispatcher::start(config).unwrap();
+    let mut delivered = vec![
+        next_request(&mut requests).await.payload().event,
+        next_request(&mut requests).await.payload().event,
+    ];
+    delivered.sort_by_key(|event| format!("{:?}", event));
+    assert_eq!(delivered, vec![room_created("b"), room_created("c")]);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(requests.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_queue_journal_is_compacted_on_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let queue_path = dir.path().join("webhooks.jsonl");
+    let (url, mut requests) = start_stub(vec![]).await;
+    let config = WebhookConfig {
+        queue_path: Some(queue_path.clone()),
+        ..test_config(url)
+    };
+
+    let dispatcher = WebhookDispatcher::start(config.clone()).unwrap();
+    dispatcher.emit(room_created("room"));
+    next_request(&mut requests).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(dispatcher.pending(), 0);
+
+    // The delivery and its acknowledgement were appended, not rewritten
+    let journal = std::fs::read_to_string(&queue_path).unwrap();
+    assert_eq!(journal.lines().count(), 2);
+
+    // Nothing is left to deliver after a restart, and the file holds only what is pending
+    let _restarted = WebhookDispatcher::start(config).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(requests.try_recv().is_err());
+    assert_eq!(std::fs::read_to_string(&queue_path).unwrap(), "");
+}
+
+#[tokio::test]
+async fn test_room_manager_reports_membership() {
+    let (url, mut requests) = start_stub(vec![]).await;
+    let dispatcher = WebhookDispatcher::start(test_config(url)).unwrap();
+    let manager = LocalRoomManager::new().with_webhooks(dispatcher);
+
+    let (alice, _alice_rx) = create_test_participant(1, "alice");
+    let (bob, _bob_rx) = create_test_participant(2, "bob");
+    manager.join_room("room".to_string(), alice).await.unwrap();
+    manager.join_room("room".to_string(), bob).await.unwrap();
+    manager.leave_room("room", 1).await.unwrap();
+    manager.leave_room("room", 2).await.unwrap();
+
+    let mut events = Vec::new();
+    for _ in 0..6 {
+        events.push(next_request(&mut requests).await.payload().event);
+    }
+    assert_eq!(
+        events,
+        vec![
+            room_created("room"),
+            WebhookEvent::UserJoined {
+                room_name: "room".to_string(),
+                user_id: 1,
+                username: "alice".to_string(),
+                participant_count: 1,
+                spectator_count: 0,
+            },
+            WebhookEvent::UserJoined {
+                room_name: "room".to_string(),
+                user_id: 2,
+                username: "bob".to_string(),
+                participant_count: 2,
+                spectator_count: 0,
+            },
+            WebhookEvent::UserLeft {
+                room_name: "room".to_string(),
+                user_id: 1,
+                participant_count: 1,
+                spectator_count: 0,
+            },
+            WebhookEvent::UserLeft {
+                room_name: "room".to_string(),
+                user_id: 2,
+                participant_count: 0,
+                spectator_count: 0,
+            },
+            WebhookEvent::RoomEmptied {
+                room_name: "room".to_string(),
+            },
+        ]
+    );
+}
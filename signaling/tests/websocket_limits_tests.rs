@@ -0,0 +1,177 @@
+mod common;
+
+use futures_util::{SinkExt, StreamExt};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use common::{create_test_token, find_available_port, Client};
+use webrtc_signaling::messages::{ClientMessage, ServerMessage};
+use webrtc_signaling::room::RoomManager;
+use webrtc_signaling::server::{start_server_with_config, ServerConfig, WebSocketLimits};
+
+fn small_limits() -> WebSocketLimits {
+    WebSocketLimits {
+        max_message_size: 1024,
+        max_frame_size: 4096,
+        max_send_queue: None,
+        max_oversized_messages: 2,
+    }
+}
+
+async fn start_limited_server(limits: WebSocketLimits) -> u16 {
+    let port = find_available_port().await;
+    let config = ServerConfig {
+        websocket: limits,
+        ..ServerConfig::default()
+    };
+    tokio::spawn(async move {
+        start_server_with_config(
+            "127.0.0.1".to_string(),
+            port,
+            "test_secret_key".to_string(),
+            RoomManager::new(),
+            config,
+        )
+        .await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    port
+}
+
+async fn connect_and_authenticate(port: u16) -> Client {
+    let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+        .await
+        .expect("Failed to connect");
+
+    let auth_msg = ClientMessage::Auth {
+        token: create_test_token("test_secret_key", 7, "sender"),
+    };
+    ws_stream
+        .send(Message::Text(serde_json::to_string(&auth_msg).unwrap()))
+        .await
+        .unwrap();
+    match next_server_message(&mut ws_stream).await {
+        Some(ServerMessage::Authenticated { user_id, .. }) => assert_eq!(user_id, 7),
+        other => panic!("Expected Authenticated message, got {:?}", other),
+    }
+    ws_stream
+}
+
+async fn next_server_message(ws_stream: &mut Client) -> Option<ServerMessage> {
+    let msg = tokio::time::timeout(Duration::from_secs(2), ws_stream.next())
+        .await
+        .expect("Timed out waiting for message")?;
+    match msg {
+        Ok(Message::Text(text)) => Some(serde_json::from_str(&text).unwrap()),
+        _ => None,
+    }
+}
+
+/// Wait for the close frame, skipping any other messages
+async fn expect_close_code(ws_stream: &mut Client) -> CloseCode {
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(2), ws_stream.next())
+            .await
+            .expect("Timed out waiting for close frame");
+        match msg {
+            Some(Ok(Message::Close(Some(frame)))) => return frame.code,
+            Some(Ok(_)) => continue,
+            other => panic!("Expected close frame, got {:?}", other),
+        }
+    }
+}
+
+fn oversized_join(size: usize) -> String {
+    let msg = ClientMessage::JoinRoom {
+        room_name: "x".repeat(size),
+        password: None,
+    };
+    serde_json::to_string(&msg).unwrap()
+}
+
+#[test]
+fn test_websocket_config_uses_larger_limit_as_hard_cap() {
+    let config = small_limits().websocket_config();
+    assert_eq!(config.max_message_size, Some(4096));
+    assert_eq!(config.max_frame_size, Some(4096));
+    assert!(!config.accept_unmasked_frames);
+
+    let inverted = WebSocketLimits {
+        max_message_size: 8192,
+        ..small_limits()
+    };
+    assert_eq!(inverted.websocket_config().max_frame_size, Some(8192));
+}
+
+#[tokio::test]
+async fn test_oversized_message_gets_error_then_close() {
+    let port = start_limited_server(small_limits()).await;
+    let mut ws_stream = connect_and_authenticate(port).await;
+
+    ws_stream
+        .send(Message::Text(oversized_join(2000)))
+        .await
+        .unwrap();
+    match next_server_message(&mut ws_stream).await {
+        Some(ServerMessage::Error { message, code }) => {
+            assert_eq!(code, Some(413));
+            assert!(message.contains("maximum 1024"));
+        }
+        other => panic!("Expected Error message, got {:?}", other),
+    }
+
+    // The connection stays usable after a single oversized message
+    let join_msg = ClientMessage::JoinRoom {
+        room_name: "small".to_string(),
+        password: None,
+    };
+    ws_stream
+        .send(Message::Text(serde_json::to_string(&join_msg).unwrap()))
+        .await
+        .unwrap();
+    match next_server_message(&mut ws_stream).await {
+        Some(ServerMessage::RoomJoined { room_name, .. }) => assert_eq!(room_name, "small"),
+        other => panic!("Expected RoomJoined message, got {:?}", other),
+    }
+
+    // The second strike closes the connection with 1009
+    ws_stream
+        .send(Message::Text(oversized_join(2000)))
+        .await
+        .unwrap();
+    assert_eq!(expect_close_code(&mut ws_stream).await, CloseCode::Size);
+}
+
+#[tokio::test]
+async fn test_message_over_frame_limit_closes_connection() {
+    let port = start_limited_server(small_limits()).await;
+    let mut ws_stream = connect_and_authenticate(port).await;
+
+    ws_stream
+        .send(Message::Text(oversized_join(10_000)))
+        .await
+        .unwrap();
+
+    // The server stops reading mid-frame, so the client may see a reset before the
+    // close frame; either way the connection must end and never with another code
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(2), ws_stream.next())
+            .await
+            .expect("Timed out waiting for the connection to close");
+        match msg {
+            Some(Ok(Message::Text(text))) => {
+                match serde_json::from_str::<ServerMessage>(&text).unwrap() {
+                    ServerMessage::Error { code, .. } => assert_eq!(code, Some(413)),
+                    other => panic!("Expected Error message, got {:?}", other),
+                }
+            }
+            Some(Ok(Message::Close(Some(frame)))) => {
+                assert_eq!(frame.code, CloseCode::Size);
+                break;
+            }
+            Some(Ok(other)) => panic!("Unexpected message {:?}", other),
+            Some(Err(_)) | None => break,
+        }
+    }
+}
@@ -1,42 +1,14 @@
-use tokio::net::TcpListener;
+mod common;
+
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use futures_util::{SinkExt, StreamExt};
 use std::time::Duration;
 
+use common::{create_test_token, find_available_port, TEST_OFFER_SDP};
 use webrtc_signaling::messages::{ClientMessage, ServerMessage};
 use webrtc_signaling::server::start_server;
-use jsonwebtoken::{encode, EncodingKey, Header};
-
-// Helper function to create a test JWT token
-fn create_test_token(secret: &str, user_id: u32, username: &str) -> String {
-    use webrtc_signaling::auth::Claims;
-
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as usize;
-
-    let claims = Claims {
-        sub: user_id,
-        username: username.to_string(),
-        iat: now,
-        exp: now + 3600, // Valid for 1 hour
-    };
-
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_ref()),
-    ).unwrap()
-}
 
-// Helper function to find an available port
-async fn find_available_port() -> u16 {
-    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let port = listener.local_addr().unwrap().port();
-    drop(listener);
-    port
-}
+const TEST_ANSWER_SDP: &str = "v=0\r\no=- 1876359312442051237 2 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\nc=IN IP4 0.0.0.0\r\na=mid:0\r\na=sendrecv\r\na=rtpmap:111 opus/48000/2\r\n";
 
 #[tokio::test]
 async fn test_websocket_authentication_success() {
@@ -160,7 +132,7 @@ async fn test_room_join_and_leave_flow() {
     if let Some(Ok(Message::Text(response))) = ws_receiver.next().await {
         let server_msg: ServerMessage = serde_json::from_str(&response).unwrap();
         match server_msg {
-            ServerMessage::RoomJoined { room_name, user_id, participants } => {
+            ServerMessage::RoomJoined { room_name, user_id, participants, .. } => {
                 assert_eq!(room_name, "test_room");
                 assert_eq!(user_id, 123);
                 assert!(participants.is_empty()); // No other participants
@@ -180,7 +152,9 @@ async fn test_room_join_and_leave_flow() {
     if let Some(Ok(Message::Text(response))) = ws_receiver.next().await {
         let server_msg: ServerMessage = serde_json::from_str(&response).unwrap();
         match server_msg {
-            ServerMessage::RoomLeft { room_name, user_id } => {
+            ServerMessage::RoomLeft {
+                room_name, user_id, ..
+            } => {
                 assert_eq!(room_name, "test_room");
                 assert_eq!(user_id, 123);
             },
@@ -249,7 +223,7 @@ async fn test_multiple_users_in_room() {
     if let Some(Ok(Message::Text(response))) = ws_receiver2.next().await {
         let server_msg: ServerMessage = serde_json::from_str(&response).unwrap();
         match server_msg {
-            ServerMessage::RoomJoined { room_name, user_id, participants } => {
+            ServerMessage::RoomJoined { room_name, user_id, participants, .. } => {
                 assert_eq!(room_name, "test_room");
                 assert_eq!(user_id, 456);
                 assert_eq!(participants.len(), 1);
@@ -321,7 +295,7 @@ async fn test_webrtc_signaling_flow() {
     // Test WebRTC offer
     let offer_msg = ClientMessage::Offer {
         room_name: "test_room".to_string(),
-        sdp: "test_offer_sdp".to_string(),
+        sdp: TEST_OFFER_SDP.to_string(),
         target_user_id: Some(456),
     };
     ws_sender1.send(Message::Text(serde_json::to_string(&offer_msg).unwrap())).await.unwrap();
@@ -333,7 +307,7 @@ async fn test_webrtc_signaling_flow() {
             ServerMessage::Offer { room_name, from_user_id, sdp } => {
                 assert_eq!(room_name, "test_room");
                 assert_eq!(from_user_id, 123);
-                assert_eq!(sdp, "test_offer_sdp");
+                assert_eq!(sdp, TEST_OFFER_SDP);
             },
             _ => panic!("Expected offer message, got: {:?}", server_msg),
         }
@@ -342,7 +316,7 @@ async fn test_webrtc_signaling_flow() {
     // Test WebRTC answer
     let answer_msg = ClientMessage::Answer {
         room_name: "test_room".to_string(),
-        sdp: "test_answer_sdp".to_string(),
+        sdp: TEST_ANSWER_SDP.to_string(),
         target_user_id: 123,
     };
     ws_sender2.send(Message::Text(serde_json::to_string(&answer_msg).unwrap())).await.unwrap();
@@ -354,7 +328,7 @@ async fn test_webrtc_signaling_flow() {
             ServerMessage::Answer { room_name, from_user_id, sdp } => {
                 assert_eq!(room_name, "test_room");
                 assert_eq!(from_user_id, 456);
-                assert_eq!(sdp, "test_answer_sdp");
+                assert_eq!(sdp, TEST_ANSWER_SDP);
             },
             _ => panic!("Expected answer message, got: {:?}", server_msg),
         }
@@ -388,3 +362,66 @@ async fn test_webrtc_signaling_flow() {
     // Clean up
     server_handle.abort();
 }
+
+#[tokio::test]
+async fn test_invalid_sdp_is_rejected() {
+    let port = find_available_port().await;
+    let jwt_secret = "test_secret_key";
+    let token1 = create_test_token(jwt_secret, 123, "user1");
+    let token2 = create_test_token(jwt_secret, 456, "user2");
+
+    let server_handle = tokio::spawn(async move {
+        start_server("127.0.0.1".to_string(), port, jwt_secret.to_string()).await
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let ws_url = format!("ws://127.0.0.1:{}", port);
+
+    let (ws_stream1, _) = connect_async(&ws_url).await.expect("Failed to connect user1");
+    let (mut ws_sender1, mut ws_receiver1) = ws_stream1.split();
+
+    let (ws_stream2, _) = connect_async(&ws_url).await.expect("Failed to connect user2");
+    let (mut ws_sender2, mut ws_receiver2) = ws_stream2.split();
+
+    let auth_msg1 = ClientMessage::Auth { token: token1 };
+    ws_sender1.send(Message::Text(serde_json::to_string(&auth_msg1).unwrap())).await.unwrap();
+    let _auth_response1 = ws_receiver1.next().await;
+
+    let auth_msg2 = ClientMessage::Auth { token: token2 };
+    ws_sender2.send(Message::Text(serde_json::to_string(&auth_msg2).unwrap())).await.unwrap();
+    let _auth_response2 = ws_receiver2.next().await;
+
+    let join_msg1 = ClientMessage::JoinRoom { room_name: "test_room".to_string(), password: None };
+    ws_sender1.send(Message::Text(serde_json::to_string(&join_msg1).unwrap())).await.unwrap();
+    let _join_response1 = ws_receiver1.next().await;
+
+    let join_msg2 = ClientMessage::JoinRoom { room_name: "test_room".to_string(), password: None };
+    ws_sender2.send(Message::Text(serde_json::to_string(&join_msg2).unwrap())).await.unwrap();
+    let _join_response2 = ws_receiver2.next().await;
+    let _user_joined = ws_receiver1.next().await;
+
+    // Garbage SDP is answered with an error and never reaches the peer
+    let offer_msg = ClientMessage::Offer {
+        room_name: "test_room".to_string(),
+        sdp: "not an sdp".to_string(),
+        target_user_id: Some(456),
+    };
+    ws_sender1.send(Message::Text(serde_json::to_string(&offer_msg).unwrap())).await.unwrap();
+
+    if let Some(Ok(Message::Text(response))) = ws_receiver1.next().await {
+        let server_msg: ServerMessage = serde_json::from_str(&response).unwrap();
+        match server_msg {
+            ServerMessage::Error { message, code } => {
+                assert!(message.starts_with("Invalid SDP"));
+                assert_eq!(code, Some(400));
+            },
+            _ => panic!("Expected error message, got: {:?}", server_msg),
+        }
+    }
+
+    let nothing = tokio::time::timeout(Duration::from_millis(200), ws_receiver2.next()).await;
+    assert!(nothing.is_err(), "Invalid offer should not be relayed");
+
+    server_handle.abort();
+}